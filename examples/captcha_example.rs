@@ -15,7 +15,7 @@ use neocrates::axum::{
     response::{IntoResponse, Json, Response},
     routing::{get, post},
 };
-use neocrates::captcha::{CaptchaData, CaptchaService};
+use neocrates::captcha::{CaptchaData, CaptchaOptions, CaptchaService};
 use neocrates::rediscache::RedisPool;
 use neocrates::serde::{Deserialize, Serialize};
 use neocrates::tokio;
@@ -93,6 +93,7 @@ async fn generate_numeric_captcha(
         &payload.account,
         Some(payload.length),
         Some(300),
+        CaptchaOptions::default(),
     )
     .await
     {
@@ -129,6 +130,7 @@ async fn generate_alphanumeric_captcha(
         &payload.account,
         Some(payload.length),
         Some(300),
+        CaptchaOptions::default(),
     )
     .await
     {
@@ -307,14 +309,7 @@ async fn main() {
 
     println!("Connecting to Redis at: {}", redis_url);
 
-    let redis_config = neocrates::rediscache::RedisConfig {
-        url: redis_url.clone(),
-        max_size: 10,
-        min_idle: Some(1),
-        connection_timeout: std::time::Duration::from_secs(5),
-        idle_timeout: Some(std::time::Duration::from_secs(600)),
-        max_lifetime: Some(std::time::Duration::from_secs(3600)),
-    };
+    let redis_config = neocrates::rediscache::RedisConfig::new(redis_url.clone());
 
     let redis_pool = match RedisPool::new(redis_config).await {
         Ok(pool) => Arc::new(pool),
@@ -39,9 +39,11 @@
 
 use std::{env, sync::Arc};
 
+use neocrates::helper::core::regex::CN_MOBILE_REGEX;
 use neocrates::rediscache::{RedisConfig, RedisPool};
 use neocrates::sms::sms_service::{
-    AliyunSmsConfig, SmsConfig, SmsProviderConfig, SmsService, TencentSmsConfig,
+    AliyunSmsConfig, CaptchaCodeConfig, SmsConfig, SmsLimitConfig, SmsProviderConfig, SmsService,
+    TencentSmsConfig,
 };
 use neocrates::sms::tencent::Region;
 
@@ -67,14 +69,7 @@ async fn main() {
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
     println!("Connecting to Redis at: {}", redis_url);
 
-    let redis_config = RedisConfig {
-        url: redis_url,
-        max_size: 10,
-        min_idle: Some(1),
-        connection_timeout: std::time::Duration::from_secs(5),
-        idle_timeout: Some(std::time::Duration::from_secs(600)),
-        max_lifetime: Some(std::time::Duration::from_secs(3600)),
-    };
+    let redis_config = RedisConfig::new(redis_url);
 
     let redis_pool = match RedisPool::new(redis_config).await {
         Ok(pool) => Arc::new(pool),
@@ -105,6 +100,8 @@ async fn main() {
             SmsConfig {
                 debug,
                 provider: SmsProviderConfig::Aliyun(aliyun),
+                code: CaptchaCodeConfig::default(),
+                limit: SmsLimitConfig::default(),
             }
         }
         "tencent" => {
@@ -123,6 +120,8 @@ async fn main() {
             SmsConfig {
                 debug,
                 provider: SmsProviderConfig::Tencent(tencent),
+                code: CaptchaCodeConfig::default(),
+                limit: SmsLimitConfig::default(),
             }
         }
         other => {
@@ -140,9 +139,8 @@ async fn main() {
     // Redis key prefix for captcha codes
     let redis_key_prefix = "captcha:sms:";
 
-    // A simple China mainland mobile regex (11 digits, starts with 1)
-    // You can replace this with your project's MOBILE_REGEX constant if you have one.
-    let mobile_regex = regex::Regex::new(r"^1\d{10}$").expect("invalid mobile regex");
+    // Shared, precompiled China mainland mobile regex (11 digits, starts with 1[3-9])
+    let mobile_regex = &*CN_MOBILE_REGEX;
 
     // Target mobile number (for demo)
     // - In real projects you receive this from user input.
@@ -165,7 +163,7 @@ async fn main() {
         &redis_pool,
         &mobile,
         redis_key_prefix,
-        &mobile_regex,
+        mobile_regex,
     )
     .await;
 
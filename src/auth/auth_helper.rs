@@ -1,9 +1,12 @@
 use std::sync::Arc;
 
-use crate::helper::core::utils::Utils;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::helper::core::{ulid::generate_ulid, utils::Utils};
 use crate::middlewares::models::{
-    AuthModel, AuthTokenResult, CACHE_AUTH_FP_UID, CACHE_AUTH_REFRESH_TOKEN, CACHE_AUTH_TOKEN,
-    CACHE_AUTH_UID, CACHE_AUTH_UID_FP,
+    AuthModel, AuthTokenResult, CACHE_AUTH_FP_UID, CACHE_AUTH_REFRESH_TOKEN, CACHE_AUTH_SESSIONS,
+    CACHE_AUTH_TOKEN, CACHE_AUTH_UID, CACHE_AUTH_UID_FP,
 };
 use crate::rediscache::RedisPool;
 use crate::response::error::{AppError, AppResult};
@@ -21,40 +24,62 @@ impl AuthHelper {
         Utils::generate_token()
     }
 
-    /// Delete token and associated data from Redis for a specific user.
-    pub async fn delete_token(rdpool: &Arc<RedisPool>, prefix: &str, uid: i64) -> AppResult<()> {
-        let auth_uid_key = format!("{}{}{}", prefix, CACHE_AUTH_UID, uid);
-        let auth_result_str: Option<String> = rdpool
-            .get::<_, String>(&auth_uid_key)
-            .await
-            .map_err(|e| AppError::RedisError(e.to_string()))?;
+    /// Log out one session of a user: delete its access/refresh tokens and drop it from the
+    /// user's session list. Other sessions (devices) stay logged in.
+    pub async fn delete_token(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        uid: i64,
+        access_token: &str,
+    ) -> AppResult<()> {
+        let sessions = Self::load_sessions(rdpool, prefix, uid).await?;
+        if let Some(session) = sessions
+            .iter()
+            .find(|session| session.stored.token.access_token == access_token)
+        {
+            Self::remove_session(rdpool, prefix, uid, session).await?;
+        }
+        Ok(())
+    }
 
-        let auth_result: Option<AuthTokenResult> = if let Some(s) = auth_result_str {
-            match serde_json::from_str(&s) {
-                Ok(v) => Some(v),
-                Err(e) => {
-                    tracing::error!("Failed to deserialize AuthTokenResult: {}", e);
-                    None
-                }
+    /// Log out the session with the given [`SessionInfo::id`], e.g. from an "active devices"
+    /// page. Returns false if the user has no such session.
+    pub async fn revoke_session(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        uid: i64,
+        session_id: &str,
+    ) -> AppResult<bool> {
+        let sessions = Self::load_sessions(rdpool, prefix, uid).await?;
+        match sessions
+            .iter()
+            .find(|session| session.stored.meta.id == session_id)
+        {
+            Some(session) => {
+                Self::remove_session(rdpool, prefix, uid, session).await?;
+                Ok(true)
             }
-        } else {
-            None
-        };
+            None => Ok(false),
+        }
+    }
 
-        if let Some(auth_result) = auth_result {
-            let token_key = format!("{}{}{}", prefix, CACHE_AUTH_TOKEN, auth_result.access_token);
-            let refresh_token_key = format!(
-                "{}{}{}",
-                prefix, CACHE_AUTH_REFRESH_TOKEN, auth_result.refresh_token
-            );
-            rdpool
-                .del(token_key)
-                .await
-                .map_err(|e| AppError::RedisError(e.to_string()))?;
-            rdpool
-                .del(refresh_token_key)
-                .await
-                .map_err(|e| AppError::RedisError(e.to_string()))?;
+    /// Log out every session of a user, including a pre-multi-session `CACHE_AUTH_UID` slot.
+    pub async fn delete_all_sessions(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        uid: i64,
+    ) -> AppResult<()> {
+        for session in Self::load_sessions(rdpool, prefix, uid).await? {
+            Self::delete_token_pair(rdpool, prefix, &session.stored.token).await?;
+        }
+        rdpool
+            .del(Self::sessions_key(prefix, uid))
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+
+        let auth_uid_key = format!("{}{}{}", prefix, CACHE_AUTH_UID, uid);
+        if let Ok(legacy) = Self::get_auth_token_result(rdpool, &auth_uid_key).await {
+            Self::delete_token_pair(rdpool, prefix, &legacy).await?;
         }
         rdpool
             .del(auth_uid_key)
@@ -193,18 +218,254 @@ impl AuthHelper {
         Ok(())
     }
 
-    /// Store authentication tokens and model in Redis.
+    /// Store authentication tokens and model in Redis, and add the pair to the user's sessions
+    /// as a new session without a fingerprint.
     pub async fn store_token(
         rdpool: &Arc<RedisPool>,
         prefix: &str,
         auth_model: &AuthModel,
         auth_token: &AuthTokenResult,
+    ) -> AppResult<()> {
+        let now = Utc::now().timestamp_millis();
+        let meta = SessionMeta {
+            id: generate_ulid(),
+            fingerprint: None,
+            created_at: now,
+        };
+        Self::store_session(rdpool, prefix, auth_model, auth_token, meta, now).await
+    }
+
+    /// Generate and store new authentication tokens for the given AuthModel.
+    ///
+    /// This method is independent of database models (Users/Spaces).
+    /// A user may hold up to `max_sessions` sessions (e.g. phone and laptop); logging in once
+    /// more logs out the oldest ones. Pass `1` for the old one-session-per-user behavior.
+    pub async fn generate_auth_token(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        expires_at: u64,
+        refresh_expires_at: u64,
+        max_sessions: usize,
+        auth_model: AuthModel,
+    ) -> AppResult<AuthTokenResult> {
+        Self::generate_auth_token_with_fingerprint(
+            rdpool,
+            prefix,
+            expires_at,
+            refresh_expires_at,
+            max_sessions,
+            None,
+            auth_model,
+        )
+        .await
+    }
+
+    /// Like [`generate_auth_token`](Self::generate_auth_token), and records the client
+    /// `fingerprint` on the session so [`list_sessions`](Self::list_sessions) can show which
+    /// device it belongs to.
+    pub async fn generate_auth_token_with_fingerprint(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        expires_at: u64,
+        refresh_expires_at: u64,
+        max_sessions: usize,
+        fingerprint: Option<&str>,
+        auth_model: AuthModel,
+    ) -> AppResult<AuthTokenResult> {
+        let uid = auth_model.uid;
+        let now = Utc::now().timestamp_millis();
+        let meta = SessionMeta {
+            id: generate_ulid(),
+            fingerprint: fingerprint.filter(|fp| !fp.is_empty()).map(str::to_string),
+            created_at: now,
+        };
+        let auth_token = Self::issue_token(
+            rdpool,
+            prefix,
+            expires_at,
+            refresh_expires_at,
+            auth_model,
+            meta,
+        )
+        .await?;
+
+        let sessions = Self::load_sessions(rdpool, prefix, uid).await?;
+        for session in sessions_to_evict(&sessions, max_sessions) {
+            tracing::info!("Evicting oldest session for uid: {}", uid);
+            Self::remove_session(rdpool, prefix, uid, session).await?;
+        }
+
+        tracing::info!("Auth token generated successfully for uid: {}", uid);
+        Ok(auth_token)
+    }
+
+    /// Refresh the authentication token.
+    ///
+    /// Validates access_token and refresh_token against the user's sessions in Redis.
+    /// If valid, rotates that session's tokens using the existing AuthModel in Redis; the
+    /// session keeps its id, fingerprint and creation time, and the user's other sessions
+    /// are untouched.
+    /// The refresh token is consumed with an atomic compare-and-delete, so when two refreshes
+    /// race with the same pair only one succeeds; the other gets `AppError::Unauthorized`.
+    /// Note: This does not refresh user data from the database.
+    pub async fn refresh_auth(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        expires_at: u64,
+        refresh_expires_at: u64,
+        access_token: &str,
+        refresh_token: &str,
+    ) -> AppResult<AuthTokenResult> {
+        let refresh_token_key = format!("{}{}{}", prefix, CACHE_AUTH_REFRESH_TOKEN, refresh_token);
+        let auth_str = match rdpool.get::<_, String>(&refresh_token_key).await {
+            Ok(Some(s)) => s,
+            Ok(None) => return Err(AppError::Unauthorized),
+            Err(e) => {
+                tracing::warn!("Failed to get token from redis error: {}", e);
+                return Err(AppError::TokenExpired);
+            }
+        };
+        let auth_model: AuthModel = serde_json::from_str(&auth_str).map_err(|e| {
+            tracing::error!("Failed to deserialize AuthModel: {}", e);
+            AppError::TokenExpired
+        })?;
+        let uid = auth_model.uid;
+
+        let sessions = Self::load_sessions(rdpool, prefix, uid).await?;
+        let session = sessions
+            .iter()
+            .find(|session| session.stored.token.refresh_token == refresh_token);
+        let legacy_key = format!("{}{}{}", prefix, CACHE_AUTH_UID, uid);
+        let current_access_token = match session {
+            Some(session) => session.stored.token.access_token.clone(),
+            // Tokens issued before multi-session support live in the legacy slot
+            None => Self::get_auth_token_result(rdpool, &legacy_key)
+                .await
+                .ok()
+                .filter(|legacy| legacy.refresh_token == refresh_token)
+                .map(|legacy| legacy.access_token)
+                .ok_or_else(|| {
+                    tracing::error!("Refresh token mismatch for uid {}", uid);
+                    AppError::Unauthorized
+                })?,
+        };
+        if current_access_token != access_token {
+            tracing::error!("Access token mismatch for uid {}", uid);
+            return Err(AppError::Unauthorized);
+        }
+
+        // Consume the refresh token; losing the race means another refresh already rotated it
+        let consumed = rdpool
+            .compare_and_delete(&refresh_token_key, &auth_str)
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        if !consumed {
+            tracing::warn!("Refresh token already used for uid {}", uid);
+            return Err(AppError::Unauthorized);
+        }
+
+        let meta = match session {
+            Some(session) => {
+                Self::remove_session(rdpool, prefix, uid, session).await?;
+                session.stored.meta.clone()
+            }
+            None => {
+                let token_key = format!("{}{}{}", prefix, CACHE_AUTH_TOKEN, access_token);
+                let _ = rdpool.del(token_key).await;
+                let _ = rdpool.del(legacy_key).await;
+                SessionMeta {
+                    id: generate_ulid(),
+                    fingerprint: None,
+                    created_at: Utc::now().timestamp_millis(),
+                }
+            }
+        };
+
+        // Rotating replaces the session, so the session count does not change
+        Self::issue_token(
+            rdpool,
+            prefix,
+            expires_at,
+            refresh_expires_at,
+            auth_model,
+            meta,
+        )
+        .await
+    }
+
+    /// The user's sessions, oldest first, for an "active devices" page.
+    ///
+    /// Tokens are masked to their last few characters; use [`SessionInfo::id`] with
+    /// [`revoke_session`](Self::revoke_session) to log one out.
+    pub async fn list_sessions(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        uid: i64,
+    ) -> AppResult<Vec<SessionInfo>> {
+        Ok(Self::load_sessions(rdpool, prefix, uid)
+            .await?
+            .iter()
+            .map(SessionEntry::info)
+            .collect())
+    }
+
+    /// The user's live sessions, oldest first. Sessions whose refresh token has expired are
+    /// removed from the set on the way, so they neither show up nor count toward
+    /// `max_sessions`.
+    async fn load_sessions(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        uid: i64,
+    ) -> AppResult<Vec<SessionEntry>> {
+        let sessions_key = Self::sessions_key(prefix, uid);
+        let mut conn = rdpool
+            .get_connection()
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        let members: Vec<String> = redis::cmd("ZRANGE")
+            .arg(&sessions_key)
+            .arg(0)
+            .arg(-1)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+
+        let now = Utc::now().timestamp_millis();
+        let mut live = Vec::with_capacity(members.len());
+        let mut expired = Vec::new();
+        for raw in members {
+            match SessionEntry::parse(raw) {
+                Some(entry) if entry.refresh_expires_at_millis() <= now => expired.push(entry.raw),
+                Some(entry) => live.push(entry),
+                None => {}
+            }
+        }
+        if !expired.is_empty() {
+            tracing::info!(
+                "Pruning {} expired sessions for uid: {}",
+                expired.len(),
+                uid
+            );
+            let _: i64 = redis::cmd("ZREM")
+                .arg(&sessions_key)
+                .arg(&expired)
+                .query_async(&mut *conn)
+                .await
+                .map_err(|e| AppError::RedisError(e.to_string()))?;
+        }
+        Ok(live)
+    }
+
+    async fn store_session(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        auth_model: &AuthModel,
+        auth_token: &AuthTokenResult,
+        meta: SessionMeta,
+        token_issued_at: i64,
     ) -> AppResult<()> {
         let auth_str =
             serde_json::to_string(&auth_model).map_err(|e| AppError::ClientError(e.to_string()))?;
-        let auth_result_str =
-            serde_json::to_string(&auth_token).map_err(|e| AppError::ClientError(e.to_string()))?;
-        let auth_uid_key = format!("{}{}{}", prefix, CACHE_AUTH_UID, auth_model.uid);
         let token_key = format!("{}{}{}", prefix, CACHE_AUTH_TOKEN, auth_token.access_token);
         let refresh_token_key = format!(
             "{}{}{}",
@@ -219,76 +480,295 @@ impl AuthHelper {
             .setex(refresh_token_key, &auth_str, auth_token.refresh_expires_at)
             .await
             .map_err(|e| AppError::RedisError(e.to_string()))?;
-        rdpool
-            .set(auth_uid_key, auth_result_str)
+
+        let created_at = meta.created_at;
+        let stored = StoredSession {
+            meta,
+            token: AuthTokenResult {
+                access_token: auth_token.access_token.clone(),
+                expires_at: auth_token.expires_at,
+                refresh_token: auth_token.refresh_token.clone(),
+                refresh_expires_at: auth_token.refresh_expires_at,
+            },
+            token_issued_at,
+        };
+        let stored_str =
+            serde_json::to_string(&stored).map_err(|e| AppError::ClientError(e.to_string()))?;
+
+        let sessions_key = Self::sessions_key(prefix, auth_model.uid);
+        let mut conn = rdpool
+            .get_connection()
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        let _: () = redis::pipe()
+            .cmd("ZADD")
+            .arg(&sessions_key)
+            .arg(created_at)
+            .arg(&stored_str)
+            .ignore()
+            // The list lives as long as its newest refresh token
+            .cmd("EXPIRE")
+            .arg(&sessions_key)
+            .arg(auth_token.refresh_expires_at)
+            .ignore()
+            .query_async(&mut *conn)
             .await
             .map_err(|e| AppError::RedisError(e.to_string()))?;
         Ok(())
     }
 
-    /// Generate and store new authentication tokens for the given AuthModel.
-    ///
-    /// This method is independent of database models (Users/Spaces).
-    /// It cleans up old tokens for the user before creating new ones.
-    pub async fn generate_auth_token(
+    async fn issue_token(
         rdpool: &Arc<RedisPool>,
         prefix: &str,
         expires_at: u64,
         refresh_expires_at: u64,
         auth_model: AuthModel,
+        meta: SessionMeta,
     ) -> AppResult<AuthTokenResult> {
-        // Delete previous token information for this user
-        Self::delete_token(rdpool, prefix, auth_model.uid).await?;
-
-        let token = Self::generate_token();
-        let refresh_token = Self::generate_refresh_token();
         let auth_token = AuthTokenResult {
-            access_token: token,
-            refresh_token,
+            access_token: Self::generate_token(),
+            refresh_token: Self::generate_refresh_token(),
             expires_at,
             refresh_expires_at,
         };
-
-        Self::store_token(rdpool, prefix, &auth_model, &auth_token).await?;
-
-        tracing::info!(
-            "Auth token generated successfully for uid: {}",
-            auth_model.uid
-        );
+        let now = Utc::now().timestamp_millis();
+        Self::store_session(rdpool, prefix, &auth_model, &auth_token, meta, now).await?;
         Ok(auth_token)
     }
 
-    /// Refresh the authentication token.
-    ///
-    /// Validates access_token and refresh_token against Redis records.
-    /// If valid, rotates the tokens using the existing AuthModel in Redis.
-    /// Note: This does not refresh user data from the database.
-    pub async fn refresh_auth(
+    async fn remove_session(
         rdpool: &Arc<RedisPool>,
         prefix: &str,
-        expires_at: u64,
-        refresh_expires_at: u64,
-        access_token: &str,
-        refresh_token: &str,
-    ) -> AppResult<AuthTokenResult> {
-        let refresh_token_key = format!("{}{}{}", prefix, CACHE_AUTH_REFRESH_TOKEN, refresh_token);
-        let auth_model: AuthModel =
-            Self::get_auth_model(rdpool, refresh_token_key.as_str()).await?;
+        uid: i64,
+        session: &SessionEntry,
+    ) -> AppResult<()> {
+        Self::delete_token_pair(rdpool, prefix, &session.stored.token).await?;
+        let mut conn = rdpool
+            .get_connection()
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        let _: i64 = redis::cmd("ZREM")
+            .arg(Self::sessions_key(prefix, uid))
+            .arg(&session.raw)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn delete_token_pair(
+        rdpool: &Arc<RedisPool>,
+        prefix: &str,
+        token: &AuthTokenResult,
+    ) -> AppResult<()> {
+        let token_key = format!("{}{}{}", prefix, CACHE_AUTH_TOKEN, token.access_token);
+        let refresh_token_key = format!(
+            "{}{}{}",
+            prefix, CACHE_AUTH_REFRESH_TOKEN, token.refresh_token
+        );
+        rdpool
+            .del(token_key)
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        rdpool
+            .del(refresh_token_key)
+            .await
+            .map_err(|e| AppError::RedisError(e.to_string()))?;
+        Ok(())
+    }
 
-        let auth_uid_key = format!("{}{}{}", prefix, CACHE_AUTH_UID, auth_model.uid);
-        let auth_result: AuthTokenResult =
-            Self::get_auth_token_result(rdpool, auth_uid_key.as_str()).await?;
+    fn sessions_key(prefix: &str, uid: i64) -> String {
+        format!("{}{}{}", prefix, CACHE_AUTH_SESSIONS, uid)
+    }
+}
 
-        if auth_result.access_token != access_token {
-            tracing::error!("Access token mismatch for uid {}", auth_model.uid);
-            return Err(AppError::Unauthorized);
+/// A user's session as shown to that user: safe to return to a UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    /// Stable id of the session across token refreshes; pass it to `revoke_session`.
+    pub id: String,
+    /// Last characters of the current access token, e.g. `****9f3a`.
+    pub token_hint: String,
+    pub fingerprint: Option<String>,
+    /// Login time.
+    pub created_at: DateTime<Utc>,
+    pub access_expires_at: DateTime<Utc>,
+    /// When the session ends unless refreshed.
+    pub refresh_expires_at: DateTime<Utc>,
+}
+
+/// Identity of a session, kept when its tokens are rotated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionMeta {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fingerprint: Option<String>,
+    /// Unix millis; also the member's score in the sessions set.
+    created_at: i64,
+}
+
+/// Member of the per-user sessions sorted set.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredSession {
+    #[serde(flatten)]
+    meta: SessionMeta,
+    token: AuthTokenResult,
+    /// Unix millis when the current token pair was issued.
+    token_issued_at: i64,
+}
+
+/// A stored session plus its exact JSON, needed to remove that sorted-set member.
+struct SessionEntry {
+    stored: StoredSession,
+    raw: String,
+}
+
+impl SessionEntry {
+    /// Parse a sorted-set member.
+    fn parse(raw: String) -> Option<Self> {
+        match serde_json::from_str::<StoredSession>(&raw) {
+            Ok(stored) => Some(Self { stored, raw }),
+            Err(e) => {
+                tracing::error!("Failed to deserialize session: {}", e);
+                None
+            }
         }
-        if auth_result.refresh_token != refresh_token {
-            tracing::error!("Refresh token mismatch for uid {}", auth_model.uid);
-            return Err(AppError::Unauthorized);
+    }
+
+    /// Unix millis when the session's refresh token expires.
+    fn refresh_expires_at_millis(&self) -> i64 {
+        secs_after(
+            self.stored.token_issued_at,
+            self.stored.token.refresh_expires_at,
+        )
+    }
+
+    fn info(&self) -> SessionInfo {
+        let stored = &self.stored;
+        let after = |secs: u64| millis_to_datetime(secs_after(stored.token_issued_at, secs));
+        SessionInfo {
+            id: stored.meta.id.clone(),
+            token_hint: mask_token(&stored.token.access_token),
+            fingerprint: stored.meta.fingerprint.clone(),
+            created_at: millis_to_datetime(stored.meta.created_at),
+            access_expires_at: after(stored.token.expires_at),
+            refresh_expires_at: after(stored.token.refresh_expires_at),
         }
+    }
+}
+
+/// `millis` plus `secs` seconds, saturating.
+fn secs_after(millis: i64, secs: u64) -> i64 {
+    millis.saturating_add(i64::try_from(secs.saturating_mul(1000)).unwrap_or(i64::MAX))
+}
+
+fn millis_to_datetime(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Keep only the last 4 characters, and only if the token is long enough that they reveal
+/// little.
+fn mask_token(token: &str) -> String {
+    const VISIBLE: usize = 4;
+    let len = token.chars().count();
+    if len < VISIBLE * 4 {
+        return "****".to_string();
+    }
+    let tail: String = token.chars().skip(len - VISIBLE).collect();
+    format!("****{}", tail)
+}
+
+/// The sessions to log out so at most `max_sessions` remain, given sessions oldest first.
+/// At least one (the newest) session is always kept.
+fn sessions_to_evict<T>(oldest_first: &[T], max_sessions: usize) -> &[T] {
+    let excess = oldest_first.len().saturating_sub(max_sessions.max(1));
+    &oldest_first[..excess]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_sessions_first() {
+        let sessions = ["first", "second", "third", "fourth"];
+        assert_eq!(sessions_to_evict(&sessions, 2), ["first", "second"]);
+        assert_eq!(sessions_to_evict(&sessions, 3), ["first"]);
+    }
+
+    #[test]
+    fn keeps_everything_within_the_limit() {
+        let sessions = ["first", "second"];
+        assert!(sessions_to_evict(&sessions, 2).is_empty());
+        assert!(sessions_to_evict(&sessions, 5).is_empty());
+        assert!(sessions_to_evict::<&str>(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn zero_limit_still_keeps_the_newest_session() {
+        let sessions = ["old", "new"];
+        assert_eq!(sessions_to_evict(&sessions, 0), ["old"]);
+        assert_eq!(sessions_to_evict(&sessions, 1), ["old"]);
+    }
+
+    fn entry(access_token: &str, created_at: i64, token_issued_at: i64) -> SessionEntry {
+        let stored = StoredSession {
+            meta: SessionMeta {
+                id: "01J8Z3NDEKTSV4RRFFQ69G5FAV".to_string(),
+                fingerprint: Some("fp-laptop".to_string()),
+                created_at,
+            },
+            token: AuthTokenResult {
+                access_token: access_token.to_string(),
+                expires_at: 300,
+                refresh_token: "refresh-secret".to_string(),
+                refresh_expires_at: 86_400,
+            },
+            token_issued_at,
+        };
+        let raw = serde_json::to_string(&stored).unwrap();
+        SessionEntry { stored, raw }
+    }
+
+    #[test]
+    fn session_info_masks_tokens_and_computes_expiry() {
+        let token = "0f8e9d3c-1b2a-4c5d-8e7f-9a0b1c2d9f3a";
+        let info = entry(token, 1_700_000_000_000, 1_700_000_060_000).info();
+
+        assert_eq!(info.token_hint, "****9f3a");
+        assert_eq!(info.fingerprint.as_deref(), Some("fp-laptop"));
+        assert_eq!(info.created_at.timestamp(), 1_700_000_000);
+        assert_eq!(info.access_expires_at.timestamp(), 1_700_000_060 + 300);
+        assert_eq!(info.refresh_expires_at.timestamp(), 1_700_000_060 + 86_400);
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(!json.contains(token));
+        assert!(!json.contains("refresh-secret"));
+    }
+
+    #[test]
+    fn short_tokens_are_fully_masked() {
+        assert_eq!(mask_token("abc"), "****");
+        assert_eq!(mask_token("0123456789abcde"), "****");
+        assert_eq!(mask_token("0123456789abcdef"), "****cdef");
+    }
+
+    #[test]
+    fn stored_session_round_trips_through_json() {
+        let session = entry("token-value-long-enough", 1, 2);
+        let parsed: StoredSession = serde_json::from_str(&session.raw).unwrap();
+        assert_eq!(parsed.meta.id, session.stored.meta.id);
+        assert_eq!(parsed.meta.created_at, 1);
+        assert_eq!(parsed.token.access_token, "token-value-long-enough");
+    }
+
+    #[test]
+    fn parses_session_members() {
+        let current = entry("token-value-long-enough", 1_000, 2_000);
+        let parsed = SessionEntry::parse(current.raw.clone()).unwrap();
+        assert_eq!(parsed.stored.meta.id, current.stored.meta.id);
+        assert_eq!(parsed.refresh_expires_at_millis(), 2_000 + 86_400_000);
 
-        // Generate auth token using existing model
-        Self::generate_auth_token(rdpool, prefix, expires_at, refresh_expires_at, auth_model).await
+        assert!(SessionEntry::parse("not json".to_string()).is_none());
     }
 }
@@ -1,5 +1,6 @@
 use once_cell::sync::OnceCell;
 use std::{sync::Arc, time::Duration};
+use tokio::sync::RwLock;
 
 use crate::{
     aws::sts_service::AwsConfig,
@@ -18,6 +19,10 @@ pub struct OssConfig {
 
 static OSS_CONFIG: OnceCell<OssConfig> = OnceCell::new();
 
+/// Shared client built from `OSS_CONFIG` on first use. `OssConfig` only carries static
+/// keys, so the client stays valid until `invalidate_client` drops it.
+static OSS_CLIENT: RwLock<Option<Arc<AwsClient>>> = RwLock::const_new(None);
+
 impl OssConfig {
     /// Create an OssConfig instance from the provided AwsConfig
     ///
@@ -74,8 +79,7 @@ impl AwsService {
     /// * `AppResult<Vec<u8>>` - Result containing the downloaded data or an error
     ///
     pub async fn download_object(path: &str) -> AppResult<Vec<u8>> {
-        let cfg = OSS_CONFIG.get().expect("OSS_CONFIG not initialized");
-        let client = match Self::build_client(cfg).await {
+        let client = match Self::client().await {
             Ok(client) => client,
             Err(err) => {
                 tracing::error!("「download_object」Failed to create AWS client: {}", err);
@@ -106,8 +110,7 @@ impl AwsService {
     /// * `AppResult<()>` - Result indicating success or failure
     ///
     pub async fn put_object(path: &str, data: Vec<u8>) -> AppResult<()> {
-        let cfg = OSS_CONFIG.get().expect("OSS_CONFIG not initialized");
-        let client = match Self::build_client(cfg).await {
+        let client = match Self::client().await {
             Ok(client) => client,
             Err(err) => {
                 tracing::error!("「put_object」Failed to create AWS client: {}", err);
@@ -138,8 +141,7 @@ impl AwsService {
     /// * `AppResult<String>` - The signed URL or an error
     ///
     pub async fn get_signed_url(path: &str, expires_in: u64) -> AppResult<String> {
-        let cfg = OSS_CONFIG.get().expect("OSS_CONFIG not initialized");
-        let client = match Self::build_client(cfg).await {
+        let client = match Self::client().await {
             Ok(client) => client,
             Err(err) => {
                 tracing::error!("「get_signed_url」Failed to create AWS client: {}", err);
@@ -164,8 +166,7 @@ impl AwsService {
 
     /// Get a signed PUT URL for uploading an object.
     pub async fn get_signed_put_url(path: &str, expires_in: u64) -> AppResult<String> {
-        let cfg = OSS_CONFIG.get().expect("OSS_CONFIG not initialized");
-        let client = match Self::build_client(cfg).await {
+        let client = match Self::client().await {
             Ok(client) => client,
             Err(err) => {
                 tracing::error!("「get_signed_put_url」Failed to create AWS client: {}", err);
@@ -259,6 +260,30 @@ impl AwsService {
         Ok(())
     }
 
+    /// Drop the cached client so the next call rebuilds it from `OSS_CONFIG`, e.g. when the
+    /// connection pool has gone bad.
+    pub async fn invalidate_client() {
+        OSS_CLIENT.write().await.take();
+    }
+
+    /// Return the shared client, building it on first use.
+    async fn client() -> AppResult<Arc<AwsClient>> {
+        if let Some(client) = OSS_CLIENT.read().await.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let mut guard = OSS_CLIENT.write().await;
+        // Another task may have built it while we waited for the write lock
+        if let Some(client) = guard.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let cfg = OSS_CONFIG.get().expect("OSS_CONFIG not initialized");
+        let client = Arc::new(Self::build_client(cfg).await?);
+        *guard = Some(client.clone());
+        Ok(client)
+    }
+
     fn redact_url(url: &str) -> String {
         url.split('?').next().unwrap_or(url).to_string()
     }
@@ -276,3 +301,30 @@ impl AwsService {
         .map_err(|e| AppError::ClientError(e.to_string()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn client_is_built_once_and_reused() {
+        let _ = OSS_CONFIG.set(OssConfig {
+            bucket: "bucket".to_string(),
+            region: "us-east-1".to_string(),
+            endpoint: "http://127.0.0.1:9000".to_string(),
+            access_key: "ak".to_string(),
+            secret_key: "sk".to_string(),
+            force_path_style: true,
+        });
+
+        let first = AwsService::client().await.unwrap();
+        for _ in 0..100 {
+            let again = AwsService::client().await.unwrap();
+            assert!(Arc::ptr_eq(&first, &again));
+        }
+
+        AwsService::invalidate_client().await;
+        let rebuilt = AwsService::client().await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+    }
+}
@@ -178,7 +178,7 @@ impl CosService {
                     .setex(
                         redis_key,
                         serde_json::to_string(&sts).expect("Failed to serialize AliyunStsVo"),
-                        config.aliyun_expiration as u64 - 60,
+                        u64::from(config.aliyun_expiration).saturating_sub(60),
                     )
                     .await
                     .map_err(|e| AppError::RedisError(e.to_string()))?;
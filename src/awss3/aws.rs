@@ -40,6 +40,13 @@ impl AwsClient {
         })
     }
 
+    ///
+    /// Create a client, picking the URL style from the endpoint.
+    ///
+    /// Path-style addressing is enabled unless the endpoint is a provider known to serve
+    /// virtual-hosted buckets (see [`default_path_style`]), so MinIO and RustFS work without
+    /// extra setup. Use `new_with_options` to choose explicitly.
+    ///
     pub async fn new(
         bucket: &str,
         region: &str,
@@ -47,7 +54,15 @@ impl AwsClient {
         access_key: &str,
         secret_key: &str,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        Self::new_with_options(bucket, region, endpoint, access_key, secret_key, false).await
+        Self::new_with_options(
+            bucket,
+            region,
+            endpoint,
+            access_key,
+            secret_key,
+            default_path_style(endpoint),
+        )
+        .await
     }
 
     ///
@@ -84,6 +99,33 @@ impl AwsClient {
         Ok(data)
     }
 
+    ///
+    /// Get an object from the bucket as a stream, without buffering the body in memory.
+    ///
+    /// Prefer this over `get_object` for large files. The returned `ByteStream` can be
+    /// read chunk by chunk (`next().await`), turned into an `AsyncRead` with
+    /// `into_async_read()`, or forwarded as an HTTP response body.
+    ///
+    /// ```rust,ignore
+    /// let mut reader = client.get_object_stream("exports/big.csv").await?.into_async_read();
+    /// let mut file = tokio::fs::File::create("/tmp/big.csv").await?;
+    /// tokio::io::copy(&mut reader, &mut file).await?;
+    /// ```
+    ///
+    pub async fn get_object_stream(
+        &self,
+        key: &str,
+    ) -> Result<ByteStream, Box<dyn std::error::Error>> {
+        let resp = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await?;
+        Ok(resp.body)
+    }
+
     ///
     /// Get a presigned URL for an object in the bucket.
     ///
@@ -110,6 +152,21 @@ impl AwsClient {
         &self,
         key: &str,
         expires_in: Duration,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        self.put_presigned_url(key, expires_in, None).await
+    }
+
+    ///
+    /// Get a presigned PUT URL so a browser can upload directly to the bucket.
+    ///
+    /// When `content_type` is set it is part of the signature, so the upload must send
+    /// exactly that `Content-Type` header or the backend rejects it.
+    ///
+    pub async fn put_presigned_url(
+        &self,
+        key: &str,
+        expires_in: Duration,
+        content_type: Option<&str>,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let presign_config = PresigningConfig::expires_in(expires_in)?;
         let presigned_req = self
@@ -117,6 +174,7 @@ impl AwsClient {
             .put_object()
             .bucket(&self.bucket)
             .key(key)
+            .set_content_type(content_type.map(str::to_owned))
             .presigned(presign_config)
             .await?;
         Ok(presigned_req.uri().to_string())
@@ -156,28 +214,202 @@ impl AwsClient {
     ///
     /// List the Objects
     ///
+    /// Follows continuation tokens until every page has been read, so prefixes with more
+    /// than 1000 keys are returned in full. `limit` caps the number of keys collected and
+    /// stops paging early once reached.
+    ///
     pub async fn list_objects(
         &self,
         prefix: Option<&str>,
+        limit: Option<usize>,
     ) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let mut req = self.client.list_objects_v2().bucket(&self.bucket);
-        if let Some(p) = prefix {
-            req = req.prefix(p);
+        collect_pages(limit, |token| {
+            let req = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .set_prefix(prefix.map(str::to_owned))
+                .set_continuation_token(token);
+            async move {
+                let resp = req.send().await?;
+                let keys = resp
+                    .contents()
+                    .iter()
+                    .filter_map(|obj| obj.key().map(|k| k.to_string()))
+                    .collect();
+                let next_token = if resp.is_truncated().unwrap_or(false) {
+                    resp.next_continuation_token().map(str::to_owned)
+                } else {
+                    None
+                };
+                Ok(KeyPage { keys, next_token })
+            }
+        })
+        .await
+    }
+}
+
+/// Hosts that address buckets virtual-hosted style (`bucket.host`); Aliyun OSS rejects
+/// path-style requests outright.
+const VIRTUAL_HOSTED_SUFFIXES: [&str; 3] = ["amazonaws.com", "aliyuncs.com", "myqcloud.com"];
+
+/// Whether `AwsClient::new` forces path-style URLs for `endpoint`: true for everything except
+/// AWS, Aliyun OSS and Tencent COS, which covers MinIO, RustFS and bare IPs.
+pub fn default_path_style(endpoint: &str) -> bool {
+    let host = endpoint
+        .split_once("://")
+        .map_or(endpoint, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    !VIRTUAL_HOSTED_SUFFIXES
+        .iter()
+        .any(|suffix| host == *suffix || host.ends_with(&format!(".{suffix}")))
+}
+
+/// One page of a listing and the token for the next page, if there is one.
+struct KeyPage {
+    keys: Vec<String>,
+    next_token: Option<String>,
+}
+
+/// Fetch pages in order until there is no continuation token or `limit` keys are collected.
+async fn collect_pages<F, Fut>(
+    limit: Option<usize>,
+    mut fetch: F,
+) -> Result<Vec<String>, Box<dyn std::error::Error>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<KeyPage, Box<dyn std::error::Error>>>,
+{
+    let mut keys = Vec::new();
+    let mut token = None;
+    loop {
+        let page = fetch(token).await?;
+        keys.extend(page.keys);
+        if let Some(limit) = limit
+            && keys.len() >= limit
+        {
+            keys.truncate(limit);
+            break;
+        }
+        match page.next_token {
+            Some(next) => token = Some(next),
+            None => break,
         }
-        let resp = req.send().await?;
-        let keys = resp
-            .contents()
-            .iter()
-            .filter_map(|obj| obj.key().map(|k| k.to_string()))
-            .collect();
-        Ok(keys)
     }
+    Ok(keys)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn mock_pages() -> Vec<KeyPage> {
+        vec![
+            KeyPage {
+                keys: vec!["a/1".into(), "a/2".into()],
+                next_token: Some("t1".into()),
+            },
+            KeyPage {
+                keys: vec!["a/3".into(), "a/4".into()],
+                next_token: Some("t2".into()),
+            },
+            KeyPage {
+                keys: vec!["a/5".into()],
+                next_token: None,
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn collect_pages_follows_continuation_tokens_in_order() {
+        let mut pages = mock_pages().into_iter();
+        let mut seen_tokens = Vec::new();
+        let keys = collect_pages(None, |token| {
+            seen_tokens.push(token);
+            let page = pages.next().expect("requested past the last page");
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(keys, vec!["a/1", "a/2", "a/3", "a/4", "a/5"]);
+        assert_eq!(
+            seen_tokens,
+            vec![None, Some("t1".to_string()), Some("t2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn collect_pages_stops_at_limit() {
+        let mut pages = mock_pages().into_iter();
+        let mut calls = 0;
+        let keys = collect_pages(Some(3), |_| {
+            calls += 1;
+            let page = pages.next().unwrap();
+            async move { Ok(page) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(keys, vec!["a/1", "a/2", "a/3"]);
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn collect_pages_propagates_errors() {
+        let result = collect_pages(None, |_| async { Err("boom".into()) }).await;
+        assert_eq!(result.unwrap_err().to_string(), "boom");
+    }
+
+    #[test]
+    fn path_style_defaults_by_endpoint() {
+        for endpoint in [
+            "http://127.0.0.1:9000",
+            "http://minio:9000/",
+            "https://rustfs.internal.example.com",
+            "localhost:9000",
+        ] {
+            assert!(default_path_style(endpoint), "{endpoint}");
+        }
+        for endpoint in [
+            "https://s3.amazonaws.com",
+            "https://s3.us-east-1.amazonaws.com",
+            "https://oss-cn-hangzhou.aliyuncs.com",
+            "https://cos.ap-guangzhou.myqcloud.com:443",
+            "HTTPS://S3.AMAZONAWS.COM.",
+        ] {
+            assert!(!default_path_style(endpoint), "{endpoint}");
+        }
+    }
+
+    /// Needs a local MinIO, e.g.
+    /// `docker run -p 9000:9000 minio/minio server /data` plus a `neocrates-test` bucket.
+    #[tokio::test]
+    #[ignore = "requires a MinIO server at MINIO_ENDPOINT"]
+    async fn minio_round_trip_with_path_style() {
+        let endpoint =
+            std::env::var("MINIO_ENDPOINT").unwrap_or_else(|_| "http://127.0.0.1:9000".into());
+        let bucket = std::env::var("MINIO_BUCKET").unwrap_or_else(|_| "neocrates-test".into());
+        let client = AwsClient::new(&bucket, "us-east-1", &endpoint, "minioadmin", "minioadmin")
+            .await
+            .unwrap();
+
+        let key = format!(
+            "path-style/{}.txt",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        client.put_object(&key, b"hello".to_vec()).await.unwrap();
+        assert_eq!(client.get_object(&key).await.unwrap(), b"hello");
+        client.delete_object(&key).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_get_object_from_oss() {
         let bucket = "xxxxxx";
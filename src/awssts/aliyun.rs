@@ -1,14 +1,13 @@
 use anyhow::Result;
-use base64::{Engine, engine::general_purpose::STANDARD};
 use chrono::{DateTime, Utc};
-use hmac::{Hmac, Mac};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use sha1::Sha1;
 use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
+use super::common::{StsProviderError, hmac_sha1_base64, url_encode};
+
 // Constants
 const STS_SIGN_VERSION: &str = "1.0";
 const STS_API_VERSION: &str = "2015-04-01";
@@ -44,6 +43,19 @@ pub enum StsError {
     SignatureError(String),
 }
 
+impl StsProviderError for StsError {
+    fn service_code(&self) -> Option<&str> {
+        match self {
+            StsError::ServiceError { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    fn is_request_error(&self) -> bool {
+        matches!(self, StsError::RequestError(_))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Credentials {
     #[serde(rename = "AccessKeyId")]
@@ -100,23 +112,60 @@ pub struct StsClient {
     access_key_secret: String,
     role_arn: String,
     session_name: String,
+    client: reqwest::Client,
 }
 
 impl StsClient {
+    /// Create a client that verifies the STS endpoint's TLS certificate.
     pub fn new(
         access_key_id: impl Into<String>,
         access_key_secret: impl Into<String>,
         role_arn: impl Into<String>,
         session_name: impl Into<String>,
     ) -> Self {
+        Self::new_with_options(
+            access_key_id,
+            access_key_secret,
+            role_arn,
+            session_name,
+            false,
+        )
+    }
+
+    /// Like `new`, but `accept_invalid_certs` disables TLS certificate verification.
+    ///
+    /// Only for local proxies or test endpoints: with it on, anyone on the network path
+    /// can impersonate the STS endpoint and read the issued credentials.
+    pub fn new_with_options(
+        access_key_id: impl Into<String>,
+        access_key_secret: impl Into<String>,
+        role_arn: impl Into<String>,
+        session_name: impl Into<String>,
+        accept_invalid_certs: bool,
+    ) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .danger_accept_invalid_certs(accept_invalid_certs)
+            .build()
+            .expect("Failed to build reqwest client for Aliyun STS");
+
         Self {
             access_key_id: access_key_id.into(),
             access_key_secret: access_key_secret.into(),
             role_arn: role_arn.into(),
             session_name: session_name.into(),
+            client,
         }
     }
 
+    pub fn role_arn(&self) -> &str {
+        &self.role_arn
+    }
+
+    pub fn session_name(&self) -> &str {
+        &self.session_name
+    }
+
     pub async fn assume_role(&self, expired_time_seconds: u32) -> Result<Response, StsError> {
         let url = self.generate_signed_url(expired_time_seconds)?;
         let (body, status) = self.send_request(&url).await?;
@@ -163,7 +212,7 @@ impl StsClient {
 
         // Generate signature
         let key = format!("{}&", self.access_key_secret);
-        let signature = sign_string_hmac_sha1(&string_to_sign, &key)?;
+        let signature = hmac_sha1_base64(key.as_bytes(), &string_to_sign);
 
         // Build the final URL
         let assume_url = format!(
@@ -177,12 +226,7 @@ impl StsClient {
     }
 
     async fn send_request(&self, url: &str) -> Result<(Vec<u8>, StatusCode), StsError> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .danger_accept_invalid_certs(true)
-            .build()?;
-
-        let resp = client.get(url).send().await?;
+        let resp = self.client.get(url).send().await?;
         let status = resp.status();
         let body = resp.bytes().await?.to_vec();
 
@@ -226,36 +270,6 @@ impl StsClient {
     }
 }
 
-// Utility functions
-fn url_encode(s: &str) -> String {
-    let mut result = String::new();
-    for b in s.bytes() {
-        match b {
-            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
-                result.push(b as char)
-            }
-            _ => {
-                result.push_str(&format!("%{:02X}", b));
-            }
-        }
-    }
-    result
-}
-
-fn sign_string_hmac_sha1(string_to_sign: &str, key: &str) -> Result<String, StsError> {
-    type HmacSha1 = Hmac<Sha1>;
-
-    let mut mac = HmacSha1::new_from_slice(key.as_bytes())
-        .map_err(|e| StsError::SignatureError(format!("HMAC error: {}", e)))?;
-
-    mac.update(string_to_sign.as_bytes());
-
-    let result = mac.finalize();
-    let code_bytes = result.into_bytes();
-
-    Ok(STANDARD.encode(code_bytes))
-}
-
 // Helper functions to convert between time formats
 pub fn parse_iso8601_to_datetime(s: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
     let dt = DateTime::parse_from_rfc3339(s)?;
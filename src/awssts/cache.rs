@@ -0,0 +1,386 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, de::DeserializeOwned};
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::{
+    awssts::{aliyun, common, tencent},
+    middlewares::token_store::{TokenStore, TokenStoreError, store_get, store_set},
+    rediscache::RedisPool,
+};
+
+/// Refresh credentials this long before they expire, unless overridden.
+const DEFAULT_REFRESH_SKEW: Duration = Duration::from_secs(60);
+/// How long a refresh may hold the distributed lock before it is considered abandoned.
+const REFRESH_LOCK_TTL: Duration = Duration::from_secs(30);
+/// While another instance refreshes, re-check the cache this often, up to this many times.
+const REFRESH_WAIT_INTERVAL: Duration = Duration::from_millis(100);
+const REFRESH_WAIT_ATTEMPTS: u32 = 50;
+
+#[derive(Error, Debug)]
+pub enum StsCacheError<E> {
+    #[error("Credential store error: {0}")]
+    Store(#[from] TokenStoreError),
+
+    #[error("STS provider error: {0}")]
+    Provider(E),
+}
+
+/// Temporary credentials that know when they stop working.
+pub trait ExpiringCredentials: Serialize + DeserializeOwned + Send + Sync {
+    fn expires_at(&self) -> DateTime<Utc>;
+}
+
+impl ExpiringCredentials for aliyun::Response {
+    /// An unparseable `Expiration` is treated as already expired, so it is never cached.
+    fn expires_at(&self) -> DateTime<Utc> {
+        aliyun::parse_iso8601_to_datetime(&self.credentials.expiration)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+}
+
+impl ExpiringCredentials for tencent::StsCredential {
+    fn expires_at(&self) -> DateTime<Utc> {
+        self.expiration
+    }
+}
+
+/// Cache for assumed-role credentials, shared by the Aliyun and Tencent STS clients.
+///
+/// Credentials are stored in a `TokenStore` under a role/session key and fetched again once
+/// they are within `refresh_skew` of expiry. Refreshes are single-flighted: within a process
+/// through a per-key mutex, and across instances through a Redis lock when one is configured,
+/// so a burst of requests triggers one AssumeRole call instead of N.
+///
+/// # Example
+/// ```rust,ignore
+/// let cache = StsCredentialCache::new(store).with_redis_lock(redis_pool);
+/// let creds = sts_client.assume_role_cached(&cache, 3600).await?;
+/// ```
+pub struct StsCredentialCache {
+    store: Arc<dyn TokenStore>,
+    redis_lock: Option<Arc<RedisPool>>,
+    refresh_skew: Duration,
+    inflight: crate::dashmap::DashMap<String, Arc<Mutex<()>>>,
+}
+
+/// A clone of the per-key refresh mutex; the last one out removes the map entry, so
+/// `inflight` only holds keys with a refresh in progress.
+struct InflightEntry<'a> {
+    map: &'a crate::dashmap::DashMap<String, Arc<Mutex<()>>>,
+    key: &'a str,
+    lock: Arc<Mutex<()>>,
+}
+
+impl<'a> InflightEntry<'a> {
+    fn join(map: &'a crate::dashmap::DashMap<String, Arc<Mutex<()>>>, key: &'a str) -> Self {
+        let lock = map.entry(key.to_string()).or_default().clone();
+        Self { map, key, lock }
+    }
+}
+
+impl Drop for InflightEntry<'_> {
+    fn drop(&mut self) {
+        // Clones are only taken under the shard lock, which `remove_if` also holds, so
+        // a count of 2 (the map and us) cannot race with a new waiter.
+        self.map.remove_if(self.key, |_, lock| {
+            Arc::ptr_eq(lock, &self.lock) && Arc::strong_count(lock) == 2
+        });
+    }
+}
+
+impl StsCredentialCache {
+    pub fn new(store: Arc<dyn TokenStore>) -> Self {
+        Self {
+            store,
+            redis_lock: None,
+            refresh_skew: DEFAULT_REFRESH_SKEW,
+            inflight: crate::dashmap::DashMap::new(),
+        }
+    }
+
+    /// Refresh credentials once they are within `skew` of expiry (default 60s).
+    pub fn with_refresh_skew(mut self, skew: Duration) -> Self {
+        self.refresh_skew = skew;
+        self
+    }
+
+    /// Single-flight refreshes across instances with a Redis lock.
+    pub fn with_redis_lock(mut self, pool: Arc<RedisPool>) -> Self {
+        self.redis_lock = Some(pool);
+        self
+    }
+
+    /// Build the cache key for one provider/role/session combination.
+    pub fn cache_key(provider: &str, role: &str, session: &str) -> String {
+        format!("sts:{}:{}:{}", provider, role, session)
+    }
+
+    /// Return cached credentials for `key`, or call `fetch` and cache the result.
+    ///
+    /// `fetch` runs at most once per process per refresh; with a Redis lock configured,
+    /// other instances wait for the cache to be filled instead of calling STS themselves.
+    /// If the lock holder does not finish in time, this instance fetches on its own rather
+    /// than failing the request.
+    pub async fn get_or_refresh<T, E, F, Fut>(
+        &self,
+        key: &str,
+        fetch: F,
+    ) -> Result<T, StsCacheError<E>>
+    where
+        T: ExpiringCredentials,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(creds) = self.get_fresh::<T>(key).await? {
+            return Ok(creds);
+        }
+
+        let local = InflightEntry::join(&self.inflight, key);
+        let _local_guard = local.lock.lock().await;
+        // Another task may have refreshed while we waited
+        if let Some(creds) = self.get_fresh::<T>(key).await? {
+            return Ok(creds);
+        }
+
+        let lock = match self.acquire_refresh_lock(key).await {
+            RefreshLock::Filled => {
+                if let Some(creds) = self.get_fresh::<T>(key).await? {
+                    return Ok(creds);
+                }
+                None
+            }
+            RefreshLock::Held(lock) => lock,
+        };
+
+        let result = self.fetch_and_store(key, fetch).await;
+
+        if let (Some(pool), Some((lock_key, token))) = (&self.redis_lock, lock) {
+            pool.release_lock_if(&lock_key, Some(&token)).await;
+        }
+        result
+    }
+
+    async fn get_fresh<T: ExpiringCredentials>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, TokenStoreError> {
+        let creds = store_get::<T>(self.store.as_ref(), key).await?;
+        Ok(creds.filter(|c| remaining_ttl(c.expires_at(), self.refresh_skew).is_some()))
+    }
+
+    async fn fetch_and_store<T, E, F, Fut>(
+        &self,
+        key: &str,
+        fetch: F,
+    ) -> Result<T, StsCacheError<E>>
+    where
+        T: ExpiringCredentials,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let creds = fetch().await.map_err(StsCacheError::Provider)?;
+        match remaining_ttl(creds.expires_at(), self.refresh_skew) {
+            Some(ttl) => store_set(self.store.as_ref(), key, &creds, Some(ttl.as_secs())).await?,
+            None => tracing::warn!(
+                "「sts_cache」Credentials for {} expire within the refresh skew, not caching",
+                key
+            ),
+        }
+        Ok(creds)
+    }
+
+    /// Take the distributed refresh lock, or wait until whoever holds it fills the cache.
+    async fn acquire_refresh_lock(&self, key: &str) -> RefreshLock {
+        let Some(pool) = &self.redis_lock else {
+            return RefreshLock::Held(None);
+        };
+        let lock_key = RedisPool::lock_key("sts", key);
+
+        for _ in 0..REFRESH_WAIT_ATTEMPTS {
+            match pool.acquire_lock(&lock_key, REFRESH_LOCK_TTL, None).await {
+                Ok(Some(token)) => return RefreshLock::Held(Some((lock_key, token))),
+                Ok(None) => {
+                    tokio::time::sleep(REFRESH_WAIT_INTERVAL).await;
+                    // A cache hit here is re-read by the caller
+                    if let Ok(Some(_)) = self.store.get_raw(key).await {
+                        return RefreshLock::Filled;
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "「sts_cache」Failed to acquire refresh lock {}: {}",
+                        lock_key,
+                        err
+                    );
+                    return RefreshLock::Held(None);
+                }
+            }
+        }
+
+        tracing::warn!(
+            "「sts_cache」Timed out waiting for refresh lock {}, refreshing without it",
+            lock_key
+        );
+        RefreshLock::Held(None)
+    }
+}
+
+enum RefreshLock {
+    /// We may refresh; holds `(lock_key, token)` when a Redis lock was taken.
+    Held(Option<(String, String)>),
+    /// Another instance refreshed while we waited.
+    Filled,
+}
+
+/// Time the credentials can still be served from cache, or None once inside `skew` of expiry.
+fn remaining_ttl(expires_at: DateTime<Utc>, skew: Duration) -> Option<Duration> {
+    let remaining = (expires_at - Utc::now()).to_std().ok()?;
+    remaining.checked_sub(skew).filter(|ttl| ttl.as_secs() > 0)
+}
+
+impl aliyun::StsClient {
+    /// `assume_role` through `cache`, keyed by this client's role ARN and session name.
+    pub async fn assume_role_cached(
+        &self,
+        cache: &StsCredentialCache,
+        expired_time_seconds: u32,
+    ) -> Result<aliyun::Response, StsCacheError<aliyun::StsError>> {
+        let key = StsCredentialCache::cache_key("aliyun", self.role_arn(), self.session_name());
+        cache
+            .get_or_refresh(&key, || self.assume_role(expired_time_seconds))
+            .await
+    }
+}
+
+impl tencent::StsClient {
+    /// `get_temp_credentials` through `cache`, keyed by region, session `name` and `policy`.
+    ///
+    /// The cache's refresh skew doubles as the clock-skew safety margin: credentials are
+    /// replaced once `StsCredential::is_expired(skew)` would report them expired, so set it
+    /// comfortably above the longest expected upload.
+    pub async fn get_temp_credentials_cached(
+        &self,
+        cache: &StsCredentialCache,
+        name: &str,
+        policy: Option<&str>,
+        duration_seconds: Option<u32>,
+    ) -> Result<tencent::StsCredential, StsCacheError<tencent::StsError>> {
+        let session = match policy {
+            Some(policy) => format!("{}:{}", name, common::sha256_hex(policy)),
+            None => name.to_string(),
+        };
+        let key = StsCredentialCache::cache_key("tencent", &self.region, &session);
+        cache
+            .get_or_refresh(&key, || {
+                self.get_temp_credentials(name, policy, duration_seconds)
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middlewares::token_store::InMemoryTokenStore;
+    use serde::Deserialize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct FakeCreds {
+        id: usize,
+        expires_at: DateTime<Utc>,
+    }
+
+    impl ExpiringCredentials for FakeCreds {
+        fn expires_at(&self) -> DateTime<Utc> {
+            self.expires_at
+        }
+    }
+
+    fn expiring_in(id: usize, secs: i64) -> FakeCreds {
+        FakeCreds {
+            id,
+            expires_at: Utc::now() + chrono::Duration::seconds(secs),
+        }
+    }
+
+    #[test]
+    fn remaining_ttl_subtracts_skew() {
+        let skew = Duration::from_secs(60);
+        let ttl = remaining_ttl(Utc::now() + chrono::Duration::seconds(600), skew).unwrap();
+        assert!(ttl <= Duration::from_secs(540) && ttl >= Duration::from_secs(530));
+        assert_eq!(
+            remaining_ttl(Utc::now() + chrono::Duration::seconds(30), skew),
+            None
+        );
+        assert_eq!(
+            remaining_ttl(Utc::now() - chrono::Duration::seconds(30), skew),
+            None
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_misses_fetch_once() {
+        let cache = Arc::new(StsCredentialCache::new(Arc::new(InMemoryTokenStore::new())));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..16 {
+            let cache = cache.clone();
+            let calls = calls.clone();
+            handles.push(tokio::spawn(async move {
+                cache
+                    .get_or_refresh("sts:test:role:session", || async {
+                        let id = calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Ok::<_, String>(expiring_in(id, 3600))
+                    })
+                    .await
+                    .unwrap()
+            }));
+        }
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().id, 0);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.inflight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refreshes_inside_skew_and_reports_provider_errors() {
+        let cache = StsCredentialCache::new(Arc::new(InMemoryTokenStore::new()))
+            .with_refresh_skew(Duration::from_secs(120));
+
+        // Expires within the skew: returned but not cached
+        let first = cache
+            .get_or_refresh("k", || async { Ok::<_, String>(expiring_in(1, 60)) })
+            .await
+            .unwrap();
+        assert_eq!(first.id, 1);
+
+        let second = cache
+            .get_or_refresh("k", || async { Ok::<_, String>(expiring_in(2, 3600)) })
+            .await
+            .unwrap();
+        assert_eq!(second.id, 2);
+
+        let cached = cache
+            .get_or_refresh("k", || async { Err::<FakeCreds, _>("unused".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(cached, second);
+
+        let err = cache
+            .get_or_refresh("other", || async {
+                Err::<FakeCreds, _>("denied".to_string())
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, StsCacheError::Provider(ref e) if e == "denied"));
+        assert!(cache.inflight.is_empty());
+    }
+}
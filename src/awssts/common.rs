@@ -0,0 +1,109 @@
+//! Signing helpers and error behavior shared by the Aliyun and Tencent STS clients.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use hmac::{Hmac, KeyInit, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::crypto::core::Crypto;
+
+/// Behavior shared by the providers' `StsError` types, so callers can handle either
+/// provider's failures without matching on its variants.
+pub trait StsProviderError: std::error::Error + Send + Sync + 'static {
+    /// Error code returned by the STS service, when the service rejected the request.
+    fn service_code(&self) -> Option<&str>;
+
+    /// Whether the request failed before the service answered (connect, TLS, timeout).
+    fn is_request_error(&self) -> bool;
+}
+
+/// RFC 3986 percent-encoding: everything but `A-Z a-z 0-9 - _ . ~` becomes `%XX`
+/// (uppercase hex, UTF-8 bytes). Both providers' signatures require exactly this.
+pub fn url_encode(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                result.push(b as char)
+            }
+            _ => {
+                result.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    result
+}
+
+/// Lowercase hex SHA-256 of `data`.
+pub fn sha256_hex(data: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+pub fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    Crypto::hmac_sha256(key, data.as_bytes())
+}
+
+pub fn hmac_sha256_hex(key: &[u8], data: &str) -> String {
+    Crypto::hmac_sha256_hex(key, data.as_bytes())
+}
+
+/// Base64 HMAC-SHA1 of `data`, as used by Aliyun's signature version 1.0.
+pub fn hmac_sha1_base64(key: &[u8], data: &str) -> String {
+    let mut mac =
+        <Hmac<Sha1> as KeyInit>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    STANDARD.encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_encode_rfc3986() {
+        assert_eq!(url_encode("AZaz09-_.~"), "AZaz09-_.~");
+        assert_eq!(url_encode("a b+c/d=e&f*"), "a%20b%2Bc%2Fd%3De%26f%2A");
+        assert_eq!(url_encode("中"), "%E4%B8%AD");
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha1_base64() {
+        // de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9
+        assert_eq!(
+            hmac_sha1_base64(b"key", "The quick brown fox jumps over the lazy dog"),
+            "3nybhbi3iqa8ino29wqQcBydtNk="
+        );
+    }
+
+    #[test]
+    fn test_provider_errors_share_service_code() {
+        let aliyun_err = crate::awssts::aliyun::StsError::ServiceError {
+            status_code: 403,
+            code: "NoPermission".to_string(),
+            message: "denied".to_string(),
+            request_id: "req".to_string(),
+            host_id: None,
+            raw_message: String::new(),
+        };
+        let tencent_err = crate::awssts::tencent::StsError::ApiError {
+            code: "AuthFailure".to_string(),
+            message: "denied".to_string(),
+        };
+        let signature_err = crate::awssts::tencent::StsError::SignatureError("bad".to_string());
+
+        let errors: [&dyn StsProviderError; 3] = [&aliyun_err, &tencent_err, &signature_err];
+        let codes: Vec<_> = errors.iter().map(|e| e.service_code()).collect();
+        assert_eq!(codes, vec![Some("NoPermission"), Some("AuthFailure"), None]);
+        assert!(errors.iter().all(|e| !e.is_request_error()));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod aliyun;
+#[cfg(any(feature = "full", all(feature = "web", feature = "redis")))]
+pub mod cache;
+pub mod common;
 pub mod tencent;
 
 // pub use StsClient as TencentStsClient;
@@ -1,12 +1,13 @@
 use anyhow::Result;
 use chrono::{DateTime, TimeZone, Utc};
-use hmac::{Hmac, Mac};
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, HOST, HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use thiserror::Error;
 
+use super::common::{StsProviderError, hmac_sha256, hmac_sha256_hex, sha256_hex, url_encode};
+use crate::helper::core::cast::to_i64;
+
 #[derive(Error, Debug)]
 pub enum StsError {
     #[error("HTTP request error: {0}")]
@@ -25,6 +26,19 @@ pub enum StsError {
     SignatureError(String),
 }
 
+impl StsProviderError for StsError {
+    fn service_code(&self) -> Option<&str> {
+        match self {
+            StsError::ApiError { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    fn is_request_error(&self) -> bool {
+        matches!(self, StsError::RequestError(_))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StsCredential {
     pub tmp_secret_id: String,
@@ -33,6 +47,19 @@ pub struct StsCredential {
     pub expiration: DateTime<Utc>,
 }
 
+impl StsCredential {
+    /// Whether the credentials expire within `skew` from now.
+    ///
+    /// Pass a safety margin rather than zero: our clock and Tencent's can drift apart, and a
+    /// long upload started just before `expiration` would otherwise fail halfway through.
+    pub fn is_expired(&self, skew: std::time::Duration) -> bool {
+        let skew = chrono::Duration::from_std(skew).unwrap_or(chrono::Duration::MAX);
+        Utc::now()
+            .checked_add_signed(skew)
+            .is_none_or(|deadline| deadline >= self.expiration)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StsResponse {
     #[serde(rename = "Response")]
@@ -142,11 +169,18 @@ impl StsClient {
                 message: "No credentials found in the response".to_string(),
             })?;
 
-        // Convert expiration to DateTime<Utc>
-        let expiration = Utc
-            .timestamp_opt(credentials.expired_time as i64, 0)
-            .single()
-            .ok_or_else(|| StsError::SignatureError("Invalid expiration timestamp".to_string()))?;
+        // Convert expiration to DateTime<Utc>; an out-of-range value is a malformed response
+        let invalid_expiration = || StsError::ApiError {
+            code: "InvalidExpiration".to_string(),
+            message: format!(
+                "Invalid ExpiredTime in the response: {}",
+                credentials.expired_time
+            ),
+        };
+        let expiration = to_i64(credentials.expired_time)
+            .ok()
+            .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+            .ok_or_else(invalid_expiration)?;
 
         Ok(StsCredential {
             tmp_secret_id: credentials.tmp_secret_id,
@@ -179,7 +213,7 @@ impl StsClient {
             http_request_method, canonical_uri, canonical_params, host
         );
 
-        let canonical_request_hash = hash_sha256(&canonical_request);
+        let canonical_request_hash = sha256_hex(&canonical_request);
 
         let string_to_sign = format!(
             "TC3-HMAC-SHA256\n{}\n{}\n{}",
@@ -240,60 +274,36 @@ impl StsClient {
     }
 
     fn calculate_signature(&self, date: &str, string_to_sign: &str) -> Result<String, StsError> {
-        let secret_date = hmac_sha256(format!("TC3{}", self.secret_key).as_bytes(), date)?;
+        let secret_date = hmac_sha256(format!("TC3{}", self.secret_key).as_bytes(), date);
 
-        let secret_service = hmac_sha256(&secret_date, &self.service)?;
+        let secret_service = hmac_sha256(&secret_date, &self.service);
 
-        let secret_signing = hmac_sha256(&secret_service, "tc3_request")?;
+        let secret_signing = hmac_sha256(&secret_service, "tc3_request");
 
-        let signature = hmac_sha256_hex(&secret_signing, string_to_sign)?;
+        let signature = hmac_sha256_hex(&secret_signing, string_to_sign);
 
         Ok(signature)
     }
 }
 
-fn url_encode(s: &str) -> String {
-    let mut result = String::new();
-    for b in s.bytes() {
-        match b {
-            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
-                result.push(b as char)
-            }
-            _ => {
-                result.push_str(&format!("%{:02X}", b));
-            }
-        }
-    }
-    result
-}
-
-fn hash_sha256(data: &str) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(data.as_bytes());
-    hex::encode(hasher.finalize())
-}
-
-fn hmac_sha256(key: &[u8], data: &str) -> Result<Vec<u8>, StsError> {
-    type HmacSha256 = Hmac<Sha256>;
-
-    let mut mac = HmacSha256::new_from_slice(key)
-        .map_err(|e| StsError::SignatureError(format!("HMAC error: {}", e)))?;
-
-    mac.update(data.as_bytes());
-
-    let result = mac.finalize();
-    Ok(result.into_bytes().to_vec())
-}
-
-fn hmac_sha256_hex(key: &[u8], data: &str) -> Result<String, StsError> {
-    let hash = hmac_sha256(key, data)?;
-    Ok(hex::encode(hash))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_expired_with_skew() {
+        let credential = StsCredential {
+            tmp_secret_id: "id".to_string(),
+            tmp_secret_key: "key".to_string(),
+            token: "token".to_string(),
+            expiration: Utc::now() + chrono::Duration::seconds(120),
+        };
+        assert!(!credential.is_expired(std::time::Duration::ZERO));
+        assert!(!credential.is_expired(std::time::Duration::from_secs(60)));
+        assert!(credential.is_expired(std::time::Duration::from_secs(300)));
+        assert!(credential.is_expired(std::time::Duration::MAX));
+    }
+
     #[tokio::test]
     async fn test_generate_sts() {
         let client = StsClient::new("your_secret_id", "your_secret_key", "ap-guangzhou");
@@ -7,6 +7,7 @@
 
 use std::sync::Arc;
 
+use super::glyphs;
 #[cfg(any(feature = "redis", feature = "full"))]
 use crate::rediscache::RedisPool;
 use crate::response::error::{AppError, AppResult};
@@ -23,16 +24,45 @@ pub enum CaptchaType {
 }
 
 /// Captcha generation result
+///
+/// Safe to return to the client as-is: `code` is only filled in when
+/// [`CaptchaOptions::include_code`] is set, and `None` fields are left out of the JSON.
 #[derive(Debug, Clone, crate::serde::Serialize, crate::serde::Deserialize)]
 pub struct CaptchaData {
     /// Captcha ID for validation
     pub id: String,
-    /// Captcha code (for validation, may be hidden for security)
-    pub code: String,
+    /// Raw captcha code, only present when requested via [`CaptchaOptions::include_code`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// SVG image of the code for the client to display
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub svg: Option<String>,
     /// Expiration time in seconds
     pub expires_in: u64,
 }
 
+/// What the numeric/alphanumeric generators put into [`CaptchaData`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptchaOptions {
+    /// Return the raw code (e.g. to send it by SMS or email). Default: `false`
+    pub include_code: bool,
+    /// Render the code with [`CaptchaService::render_svg`]. Default: `true`
+    pub svg: bool,
+    /// Alphanumeric only: mix upper and lower case and require the exact case on
+    /// validation. Default: `false`
+    pub case_sensitive: bool,
+}
+
+impl Default for CaptchaOptions {
+    fn default() -> Self {
+        Self {
+            include_code: false,
+            svg: true,
+            case_sensitive: false,
+        }
+    }
+}
+
 /// Captcha service for generating and validating various types of captchas
 pub struct CaptchaService;
 
@@ -41,6 +71,15 @@ impl CaptchaService {
     const CACHE_PREFIX_NUMERIC: &'static str = ":captcha:numeric:";
     const CACHE_PREFIX_ALPHA: &'static str = ":captcha:alpha:";
 
+    /// Marks a stored alphanumeric code as case-sensitive; `:` never appears in a code
+    const CASE_SENSITIVE_MARKER: &'static str = "cs:";
+
+    /// Alphanumeric charsets, excluding confusing characters (0, O, o, I, i, l, 1)
+    const CHARSET_UPPER: &'static [u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    /// Case-sensitive codes also leave out lower case letters drawn like their capitals
+    /// (c k p s u v w x z), so the case of every letter is visible.
+    const CHARSET_MIXED: &'static [u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabdefghjmnqrty23456789";
+
     /// Default expiration time (2 minutes)
     const DEFAULT_EXPIRATION: u64 = 120;
 
@@ -121,6 +160,8 @@ impl CaptchaService {
 
         match result {
             Some(stored_code) => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, true);
                 let hashed_input = Self::hash_code(code);
                 if stored_code != hashed_input {
                     return Err(AppError::ClientError(
@@ -130,6 +171,8 @@ impl CaptchaService {
                 }
             }
             None => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, false);
                 return Err(AppError::ClientError(
                     "Captcha expired or not found".to_string(),
                 ));
@@ -175,9 +218,10 @@ impl CaptchaService {
     /// * `redis_pool` - Redis connection pool
     /// * `account` - Account identifier
     /// * `length` - Length of the numeric code (default: 6)
+    /// * `options` - Whether to return the SVG image and/or the raw code
     ///
     /// # Returns
-    /// * `Ok(CaptchaData)` containing the captcha ID and code
+    /// * `Ok(CaptchaData)` containing the captcha ID and, per `options`, the SVG and code
     ///
     /// # Example
     /// ```rust,ignore
@@ -186,12 +230,15 @@ impl CaptchaService {
     /// async fn example(redis_pool: Arc<RedisPool>) {
     ///     let captcha = CaptchaService::gen_numeric_captcha(
     ///         &redis_pool,
+    ///         "app:",
     ///         "user@example.com",
-    ///         Some(6)
+    ///         Some(6),
+    ///         None,
+    ///         CaptchaOptions::default(),
     ///     ).await.unwrap();
     ///
     ///     println!("Captcha ID: {}", captcha.id);
-    ///     println!("Captcha Code: {}", captcha.code);
+    ///     println!("Captcha SVG: {}", captcha.svg.unwrap());
     /// }
     /// ```
     #[cfg(any(feature = "redis", feature = "full"))]
@@ -201,6 +248,7 @@ impl CaptchaService {
         account: &str,
         length: Option<usize>,
         expires_in: Option<u64>,
+        options: CaptchaOptions,
     ) -> AppResult<CaptchaData> {
         let len = length.unwrap_or(6).clamp(4, 8);
 
@@ -226,11 +274,7 @@ impl CaptchaService {
             id
         );
 
-        Ok(CaptchaData {
-            id,
-            code,
-            expires_in: seconds,
-        })
+        Ok(Self::captcha_data(id, &code, seconds, options))
     }
 
     /// Validate numeric captcha
@@ -256,6 +300,8 @@ impl CaptchaService {
 
         match result {
             Some(stored_code) => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, true);
                 if stored_code != code {
                     return Err(AppError::ClientError(
                         "Numeric captcha verification failed".to_string(),
@@ -263,6 +309,8 @@ impl CaptchaService {
                 }
             }
             None => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, false);
                 return Err(AppError::ClientError(
                     "Captcha expired or not found".to_string(),
                 ));
@@ -288,22 +336,27 @@ impl CaptchaService {
     /// * `redis_pool` - Redis connection pool
     /// * `account` - Account identifier
     /// * `length` - Length of the code (default: 6)
+    /// * `options` - Whether to return the SVG image and/or the raw code
     ///
     /// # Returns
-    /// * `Ok(CaptchaData)` containing the captcha ID and code
+    /// * `Ok(CaptchaData)` containing the captcha ID and, per `options`, the SVG and code
     ///
     /// # Example
     /// ```rust,ignore
     /// use neocrates::captcha::CaptchaService;
     ///
     /// async fn example(redis_pool: Arc<RedisPool>) {
+    ///     let options = CaptchaOptions { include_code: true, ..Default::default() };
     ///     let captcha = CaptchaService::gen_alphanumeric_captcha(
     ///         &redis_pool,
+    ///         "app:",
     ///         "user@example.com",
-    ///         Some(6)
+    ///         Some(6),
+    ///         None,
+    ///         options,
     ///     ).await.unwrap();
     ///
-    ///     println!("Captcha Code: {}", captcha.code); // e.g., "A3K7M9"
+    ///     println!("Captcha Code: {:?}", captcha.code); // e.g., Some("A3K7M9")
     /// }
     /// ```
     #[cfg(any(feature = "redis", feature = "full"))]
@@ -313,28 +366,18 @@ impl CaptchaService {
         account: &str,
         length: Option<usize>,
         expires_in: Option<u64>,
+        options: CaptchaOptions,
     ) -> AppResult<CaptchaData> {
         let len = length.unwrap_or(6).clamp(4, 10);
-
-        // Generate random alphanumeric code (excluding confusing characters: 0, O, I, l, 1)
-        let charset = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
-
-        // Use uuid for randomness (Send-safe)
-        let uuid = crate::uuid::Uuid::new_v4();
-        let uuid_bytes = uuid.as_bytes();
-        let code: String = (0..len)
-            .map(|i| {
-                let idx = (uuid_bytes[i % 16] as usize) % charset.len();
-                charset[idx] as char
-            })
-            .collect();
+        let code = Self::alphanumeric_code(len, options.case_sensitive);
 
         let id = crate::uuid::Uuid::new_v4().to_string();
         let key = format!("{}{}{}", prefix, Self::CACHE_PREFIX_ALPHA, id);
         let seconds = expires_in.unwrap_or(Self::DEFAULT_EXPIRATION);
+        let stored = Self::stored_alphanumeric(&code, options.case_sensitive);
 
         redis_pool
-            .setex(&key, code.clone(), seconds)
+            .setex(&key, stored, seconds)
             .await
             .map_err(|e| AppError::RedisError(e.to_string()))?;
 
@@ -344,14 +387,13 @@ impl CaptchaService {
             id
         );
 
-        Ok(CaptchaData {
-            id,
-            code,
-            expires_in: seconds,
-        })
+        Ok(Self::captcha_data(id, &code, seconds, options))
     }
 
-    /// Validate alphanumeric captcha (case-insensitive)
+    /// Validate alphanumeric captcha
+    ///
+    /// Case-insensitive unless the captcha was generated with
+    /// [`CaptchaOptions::case_sensitive`].
     ///
     /// # Arguments
     /// * `redis_pool` - Redis connection pool
@@ -374,13 +416,17 @@ impl CaptchaService {
 
         match result {
             Some(stored_code) => {
-                if stored_code.to_uppercase() != code.to_uppercase() {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, true);
+                if !Self::alphanumeric_matches(&stored_code, code) {
                     return Err(AppError::ClientError(
                         "Captcha verification failed".to_string(),
                     ));
                 }
             }
             None => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_CAPTCHA, false);
                 return Err(AppError::ClientError(
                     "Captcha expired or not found".to_string(),
                 ));
@@ -398,8 +444,153 @@ impl CaptchaService {
         Ok(())
     }
 
+    // ==================== SVG Rendering ====================
+
+    /// Render `code` as a self-contained SVG image
+    ///
+    /// Each character is drawn as `<path>` strokes from a built-in outline font (`0-9`,
+    /// `A-Z`, `a-z`), with a random scale, rotation, offset, color and per-point jitter,
+    /// over a few noise lines and dots. The characters themselves never appear in the
+    /// markup; any character the font lacks is left blank. This deters naive scraping
+    /// only; it is not meant to beat OCR.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let svg = CaptchaService::render_svg("A3K7M9");
+    /// let img = format!("<img src=\"data:image/svg+xml;utf8,{}\">", urlencoding::encode(&svg));
+    /// ```
+    pub fn render_svg(code: &str) -> String {
+        use rand::RngExt;
+        use std::fmt::Write;
+
+        const CHAR_WIDTH: u32 = 28;
+        const HEIGHT: u32 = 48;
+        let mut rng = rand::rng();
+        let width = CHAR_WIDTH * code.chars().count() as u32 + 20;
+
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{HEIGHT}" viewBox="0 0 {width} {HEIGHT}"><rect width="100%" height="100%" fill="#f4f4f4"/>"##
+        );
+        for _ in 0..rng.random_range(4..=6) {
+            let _ = write!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                rng.random_range(0..width / 3),
+                rng.random_range(0..HEIGHT),
+                rng.random_range(width * 2 / 3..width),
+                rng.random_range(0..HEIGHT),
+                Self::random_color(&mut rng),
+                rng.random_range(1..=2),
+            );
+        }
+        for _ in 0..width / 4 {
+            let _ = write!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="1" fill="{}"/>"#,
+                rng.random_range(0..width),
+                rng.random_range(0..HEIGHT),
+                Self::random_color(&mut rng),
+            );
+        }
+        for (i, ch) in code.chars().enumerate() {
+            let Some(strokes) = glyphs::glyph(ch) else {
+                continue;
+            };
+            let cx =
+                (10 + CHAR_WIDTH * i as u32 + CHAR_WIDTH / 2) as f32 + rng.random_range(-2.0..=2.0);
+            let cy = (HEIGHT / 2) as f32 + rng.random_range(-3.0..=3.0);
+            let scale: f32 = rng.random_range(4.0..=5.0);
+            let (sin, cos) = rng.random_range(-25.0f32..=25.0).to_radians().sin_cos();
+
+            let mut d = String::new();
+            for stroke in strokes {
+                for (j, &(gx, gy)) in stroke.iter().enumerate() {
+                    let x = (gx - glyphs::GRID_WIDTH / 2.0) * scale + rng.random_range(-0.6..=0.6);
+                    let y = (gy - glyphs::GRID_HEIGHT / 2.0) * scale + rng.random_range(-0.6..=0.6);
+                    let _ = write!(
+                        d,
+                        "{}{:.1} {:.1}",
+                        if j == 0 { "M" } else { "L" },
+                        cx + x * cos - y * sin,
+                        cy + x * sin + y * cos,
+                    );
+                }
+            }
+            let _ = write!(
+                svg,
+                r#"<path d="{d}" fill="none" stroke="{}" stroke-width="{:.1}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                Self::random_color(&mut rng),
+                rng.random_range(2.0f32..=3.0),
+            );
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// A dark-ish color so glyphs stay readable on the light background
+    fn random_color(rng: &mut impl rand::RngExt) -> String {
+        format!(
+            "rgb({},{},{})",
+            rng.random_range(20..160),
+            rng.random_range(20..160),
+            rng.random_range(20..160)
+        )
+    }
+
     // ==================== Helper Functions ====================
 
+    /// Random alphanumeric code; upper case only unless `case_sensitive`
+    fn alphanumeric_code(len: usize, case_sensitive: bool) -> String {
+        let charset = if case_sensitive {
+            Self::CHARSET_MIXED
+        } else {
+            Self::CHARSET_UPPER
+        };
+
+        use rand::RngExt;
+
+        // The rng lives only inside this sync fn, so callers stay Send.
+        let mut rng = rand::rng();
+        (0..len)
+            .map(|_| charset[rng.random_range(0..charset.len())] as char)
+            .collect()
+    }
+
+    /// Redis value for an alphanumeric code, recording how it must be compared
+    fn stored_alphanumeric(code: &str, case_sensitive: bool) -> String {
+        if case_sensitive {
+            format!("{}{}", Self::CASE_SENSITIVE_MARKER, code)
+        } else {
+            code.to_string()
+        }
+    }
+
+    /// Compare user input against a value written by [`Self::stored_alphanumeric`]
+    ///
+    /// Unmarked values (including those stored before the marker existed) compare
+    /// case-insensitively.
+    fn alphanumeric_matches(stored: &str, input: &str) -> bool {
+        match stored.strip_prefix(Self::CASE_SENSITIVE_MARKER) {
+            Some(code) => code == input,
+            None => stored.eq_ignore_ascii_case(input),
+        }
+    }
+
+    /// Build the generation result for `code`, keeping it out unless asked for
+    fn captcha_data(
+        id: String,
+        code: &str,
+        expires_in: u64,
+        options: CaptchaOptions,
+    ) -> CaptchaData {
+        CaptchaData {
+            id,
+            code: options.include_code.then(|| code.to_string()),
+            svg: options.svg.then(|| Self::render_svg(code)),
+            expires_in,
+        }
+    }
+
     /// Hash a code using MD5 (for simple obfuscation, not cryptographic security)
     fn hash_code(code: &str) -> String {
         use crate::md5;
@@ -426,4 +617,98 @@ mod tests {
         assert_eq!(hash1, hash2);
         assert_ne!(hash1, hash3);
     }
+
+    #[test]
+    fn test_render_svg_draws_every_char() {
+        let svg = CaptchaService::render_svg("A3K7");
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<path ").count(), 4);
+        assert_ne!(svg, CaptchaService::render_svg("A3K7"));
+    }
+
+    #[test]
+    fn test_render_svg_keeps_code_out_of_markup() {
+        let code = "AHKNRWXY";
+        let svg = CaptchaService::render_svg(code);
+        assert!(!svg.contains("<text"));
+        for ch in code.chars() {
+            assert!(!svg.contains(ch), "{} found in svg", ch);
+        }
+    }
+
+    #[test]
+    fn test_font_covers_every_charset() {
+        let charsets = [
+            CaptchaService::CHARSET_UPPER,
+            CaptchaService::CHARSET_MIXED,
+            b"0123456789",
+        ];
+        for b in charsets.concat() {
+            assert!(glyphs::glyph(b as char).is_some(), "{}", b as char);
+        }
+    }
+
+    #[test]
+    fn test_captcha_data_hides_code_by_default() {
+        let data = CaptchaService::captcha_data(
+            "id-1".to_string(),
+            "123456",
+            120,
+            CaptchaOptions::default(),
+        );
+        assert!(data.code.is_none());
+        assert!(data.svg.as_deref().unwrap().contains("<path "));
+        let json = serde_json::to_value(&data).unwrap();
+        assert!(json.get("code").is_none());
+
+        let options = CaptchaOptions {
+            include_code: true,
+            svg: false,
+            ..Default::default()
+        };
+        let data = CaptchaService::captcha_data("id-2".to_string(), "123456", 120, options);
+        assert_eq!(data.code.as_deref(), Some("123456"));
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(json["code"], "123456");
+        assert!(json.get("svg").is_none());
+    }
+
+    #[test]
+    fn test_alphanumeric_case_insensitive_by_default() {
+        let code = CaptchaService::alphanumeric_code(10, false);
+        assert_eq!(code.len(), 10);
+        assert!(
+            code.bytes()
+                .all(|b| CaptchaService::CHARSET_UPPER.contains(&b))
+        );
+
+        let stored = CaptchaService::stored_alphanumeric("A3K7M9", false);
+        assert_eq!(stored, "A3K7M9");
+        assert!(CaptchaService::alphanumeric_matches(&stored, "A3K7M9"));
+        assert!(CaptchaService::alphanumeric_matches(&stored, "a3k7m9"));
+        assert!(!CaptchaService::alphanumeric_matches(&stored, "A3K7M8"));
+    }
+
+    #[test]
+    fn test_alphanumeric_case_sensitive() {
+        let code = CaptchaService::alphanumeric_code(10, true);
+        assert!(
+            code.bytes()
+                .all(|b| CaptchaService::CHARSET_MIXED.contains(&b))
+        );
+
+        let stored = CaptchaService::stored_alphanumeric("aB3hM9", true);
+        assert!(CaptchaService::alphanumeric_matches(&stored, "aB3hM9"));
+        assert!(!CaptchaService::alphanumeric_matches(&stored, "AB3HM9"));
+        assert!(!CaptchaService::alphanumeric_matches(&stored, "ab3hm9"));
+        assert!(!CaptchaService::alphanumeric_matches(&stored, "cs:aB3hM9"));
+    }
+
+    #[test]
+    fn test_mixed_charset_has_no_look_alike_case_pairs() {
+        for look_alike in b"ckpsuvwxz" {
+            assert!(!CaptchaService::CHARSET_MIXED.contains(look_alike));
+        }
+    }
 }
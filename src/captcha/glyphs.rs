@@ -0,0 +1,368 @@
+//! Built-in stroke font used by [`CaptchaService::render_svg`](super::CaptchaService::render_svg)
+//!
+//! Glyphs are polylines on a 4 x 8 unit grid: capitals and digits span `y = 0..6`,
+//! lower case letters have their x-height at `y = 2` and descenders reach `y = 8`.
+//! Drawing codes as strokes keeps the characters themselves out of the SVG markup.
+
+/// One polyline of a glyph, in grid units
+pub(crate) type Stroke = &'static [(f32, f32)];
+
+/// Width of the glyph grid
+pub(crate) const GRID_WIDTH: f32 = 4.0;
+/// Height of the glyph grid, including descenders
+pub(crate) const GRID_HEIGHT: f32 = 8.0;
+
+const RING: Stroke = &[
+    (1.0, 0.0),
+    (3.0, 0.0),
+    (4.0, 1.0),
+    (4.0, 5.0),
+    (3.0, 6.0),
+    (1.0, 6.0),
+    (0.0, 5.0),
+    (0.0, 1.0),
+    (1.0, 0.0),
+];
+const P_BOWL: Stroke = &[
+    (0.0, 6.0),
+    (0.0, 0.0),
+    (3.0, 0.0),
+    (4.0, 1.0),
+    (4.0, 2.0),
+    (3.0, 3.0),
+    (0.0, 3.0),
+];
+const LOWER_BOWL_RIGHT: Stroke = &[
+    (0.0, 3.0),
+    (1.0, 2.0),
+    (3.0, 2.0),
+    (4.0, 3.0),
+    (4.0, 5.0),
+    (3.0, 6.0),
+    (1.0, 6.0),
+    (0.0, 5.0),
+];
+const LOWER_BOWL_LEFT: Stroke = &[
+    (4.0, 3.0),
+    (3.0, 2.0),
+    (1.0, 2.0),
+    (0.0, 3.0),
+    (0.0, 5.0),
+    (1.0, 6.0),
+    (3.0, 6.0),
+    (4.0, 5.0),
+];
+const LOWER_ARCH: Stroke = &[(0.0, 3.0), (1.0, 2.0), (3.0, 2.0), (4.0, 3.0), (4.0, 6.0)];
+
+/// Strokes for `ch`, or `None` if the font has no glyph for it
+///
+/// Covers `0-9`, `A-Z` and `a-z`.
+pub(crate) fn glyph(ch: char) -> Option<&'static [Stroke]> {
+    let strokes: &'static [Stroke] = match ch {
+        '0' => &[RING, &[(3.0, 1.0), (1.0, 5.0)]],
+        '1' => &[
+            &[(1.0, 1.0), (2.0, 0.0), (2.0, 6.0)],
+            &[(1.0, 6.0), (3.0, 6.0)],
+        ],
+        '2' => &[&[
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 2.0),
+            (0.0, 6.0),
+            (4.0, 6.0),
+        ]],
+        '3' => &[&[
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (2.0, 2.5),
+            (3.0, 2.5),
+            (4.0, 3.5),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (1.0, 6.0),
+            (0.0, 5.0),
+        ]],
+        '4' => &[&[(3.0, 6.0), (3.0, 0.0), (0.0, 4.0), (4.0, 4.0)]],
+        '5' => &[&[
+            (4.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 2.5),
+            (3.0, 2.5),
+            (4.0, 3.5),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (0.0, 6.0),
+        ]],
+        '6' => &[&[
+            (3.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+            (4.0, 3.5),
+            (3.0, 2.5),
+            (0.0, 2.5),
+        ]],
+        '7' => &[&[(0.0, 0.0), (4.0, 0.0), (1.5, 6.0)]],
+        '8' => &[
+            &[
+                (1.0, 0.0),
+                (3.0, 0.0),
+                (4.0, 1.0),
+                (4.0, 2.0),
+                (3.0, 3.0),
+                (1.0, 3.0),
+                (0.0, 4.0),
+                (0.0, 5.0),
+                (1.0, 6.0),
+                (3.0, 6.0),
+                (4.0, 5.0),
+                (4.0, 4.0),
+                (3.0, 3.0),
+            ],
+            &[(1.0, 3.0), (0.0, 2.0), (0.0, 1.0), (1.0, 0.0)],
+        ],
+        '9' => &[&[
+            (4.0, 3.5),
+            (1.0, 3.5),
+            (0.0, 2.5),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (3.0, 0.0),
+            (4.0, 1.0),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (1.0, 6.0),
+        ]],
+
+        'A' => &[
+            &[(0.0, 6.0), (2.0, 0.0), (4.0, 6.0)],
+            &[(0.7, 4.0), (3.3, 4.0)],
+        ],
+        'B' => &[
+            &[
+                (0.0, 0.0),
+                (0.0, 6.0),
+                (3.0, 6.0),
+                (4.0, 5.0),
+                (4.0, 4.0),
+                (3.0, 3.0),
+                (0.0, 3.0),
+            ],
+            &[(0.0, 0.0), (3.0, 0.0), (4.0, 1.0), (4.0, 2.0), (3.0, 3.0)],
+        ],
+        'C' => &[&[
+            (4.0, 1.0),
+            (3.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+        ]],
+        'D' => &[&[
+            (0.0, 0.0),
+            (0.0, 6.0),
+            (2.5, 6.0),
+            (4.0, 4.5),
+            (4.0, 1.5),
+            (2.5, 0.0),
+            (0.0, 0.0),
+        ]],
+        'E' => &[
+            &[(4.0, 0.0), (0.0, 0.0), (0.0, 6.0), (4.0, 6.0)],
+            &[(0.0, 3.0), (3.0, 3.0)],
+        ],
+        'F' => &[
+            &[(4.0, 0.0), (0.0, 0.0), (0.0, 6.0)],
+            &[(0.0, 3.0), (3.0, 3.0)],
+        ],
+        'G' => &[&[
+            (4.0, 1.0),
+            (3.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+            (4.0, 3.5),
+            (2.0, 3.5),
+        ]],
+        'H' => &[
+            &[(0.0, 0.0), (0.0, 6.0)],
+            &[(4.0, 0.0), (4.0, 6.0)],
+            &[(0.0, 3.0), (4.0, 3.0)],
+        ],
+        'I' => &[
+            &[(1.0, 0.0), (3.0, 0.0)],
+            &[(2.0, 0.0), (2.0, 6.0)],
+            &[(1.0, 6.0), (3.0, 6.0)],
+        ],
+        'J' => &[&[(4.0, 0.0), (4.0, 5.0), (3.0, 6.0), (1.0, 6.0), (0.0, 5.0)]],
+        'K' => &[
+            &[(0.0, 0.0), (0.0, 6.0)],
+            &[(4.0, 0.0), (0.0, 3.5)],
+            &[(1.3, 2.5), (4.0, 6.0)],
+        ],
+        'L' => &[&[(0.0, 0.0), (0.0, 6.0), (4.0, 6.0)]],
+        'M' => &[&[(0.0, 6.0), (0.0, 0.0), (2.0, 3.0), (4.0, 0.0), (4.0, 6.0)]],
+        'N' => &[&[(0.0, 6.0), (0.0, 0.0), (4.0, 6.0), (4.0, 0.0)]],
+        'O' => &[RING],
+        'P' => &[P_BOWL],
+        'Q' => &[RING, &[(2.5, 4.5), (4.0, 6.5)]],
+        'R' => &[P_BOWL, &[(2.0, 3.0), (4.0, 6.0)]],
+        'S' => &[&[
+            (4.0, 1.0),
+            (3.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 2.0),
+            (1.0, 3.0),
+            (3.0, 3.0),
+            (4.0, 4.0),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (1.0, 6.0),
+            (0.0, 5.0),
+        ]],
+        'T' => &[&[(0.0, 0.0), (4.0, 0.0)], &[(2.0, 0.0), (2.0, 6.0)]],
+        'U' => &[&[
+            (0.0, 0.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.0, 6.0),
+            (4.0, 5.0),
+            (4.0, 0.0),
+        ]],
+        'V' => &[&[(0.0, 0.0), (2.0, 6.0), (4.0, 0.0)]],
+        'W' => &[&[(0.0, 0.0), (1.0, 6.0), (2.0, 2.0), (3.0, 6.0), (4.0, 0.0)]],
+        'X' => &[&[(0.0, 0.0), (4.0, 6.0)], &[(4.0, 0.0), (0.0, 6.0)]],
+        'Y' => &[
+            &[(0.0, 0.0), (2.0, 3.0), (4.0, 0.0)],
+            &[(2.0, 3.0), (2.0, 6.0)],
+        ],
+        'Z' => &[&[(0.0, 0.0), (4.0, 0.0), (0.0, 6.0), (4.0, 6.0)]],
+
+        'a' => &[
+            &[(0.5, 2.0), (3.0, 2.0), (4.0, 3.0), (4.0, 6.0)],
+            &[
+                (4.0, 4.0),
+                (1.0, 4.0),
+                (0.0, 4.8),
+                (1.0, 6.0),
+                (3.0, 6.0),
+                (4.0, 5.0),
+            ],
+        ],
+        'b' => &[&[(0.0, 0.0), (0.0, 6.0)], LOWER_BOWL_RIGHT],
+        'c' => &[&[
+            (4.0, 2.5),
+            (3.5, 2.0),
+            (1.0, 2.0),
+            (0.0, 3.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.5, 6.0),
+            (4.0, 5.5),
+        ]],
+        'd' => &[&[(4.0, 0.0), (4.0, 6.0)], LOWER_BOWL_LEFT],
+        'e' => &[&[
+            (0.0, 4.0),
+            (4.0, 4.0),
+            (4.0, 3.0),
+            (3.0, 2.0),
+            (1.0, 2.0),
+            (0.0, 3.0),
+            (0.0, 5.0),
+            (1.0, 6.0),
+            (3.5, 6.0),
+        ]],
+        'f' => &[
+            &[(3.5, 0.0), (2.5, 0.0), (1.5, 1.0), (1.5, 6.0)],
+            &[(0.0, 2.5), (3.0, 2.5)],
+        ],
+        'g' => &[
+            &[(4.0, 2.0), (4.0, 7.0), (3.0, 8.0), (1.0, 8.0), (0.5, 7.5)],
+            &[
+                (4.0, 3.0),
+                (3.0, 2.0),
+                (1.0, 2.0),
+                (0.0, 3.0),
+                (0.0, 4.5),
+                (1.0, 5.5),
+                (3.0, 5.5),
+                (4.0, 4.5),
+            ],
+        ],
+        'h' => &[&[(0.0, 0.0), (0.0, 6.0)], LOWER_ARCH],
+        'i' => &[&[(2.0, 2.0), (2.0, 6.0)], &[(2.0, 0.6), (2.0, 0.9)]],
+        'j' => &[
+            &[(2.5, 2.0), (2.5, 7.0), (1.5, 8.0), (0.5, 8.0)],
+            &[(2.5, 0.6), (2.5, 0.9)],
+        ],
+        'k' => &[
+            &[(0.0, 0.0), (0.0, 6.0)],
+            &[(3.5, 2.0), (0.0, 4.5)],
+            &[(1.2, 3.7), (4.0, 6.0)],
+        ],
+        'l' => &[&[(1.5, 0.0), (1.5, 5.0), (2.5, 6.0)]],
+        'm' => &[
+            &[(0.0, 6.0), (0.0, 2.0)],
+            &[(0.0, 3.0), (1.0, 2.0), (2.0, 3.0), (2.0, 6.0)],
+            &[(2.0, 3.0), (3.0, 2.0), (4.0, 3.0), (4.0, 6.0)],
+        ],
+        'n' => &[&[(0.0, 6.0), (0.0, 2.0)], LOWER_ARCH],
+        'o' => &[&[
+            (1.0, 2.0),
+            (3.0, 2.0),
+            (4.0, 3.0),
+            (4.0, 5.0),
+            (3.0, 6.0),
+            (1.0, 6.0),
+            (0.0, 5.0),
+            (0.0, 3.0),
+            (1.0, 2.0),
+        ]],
+        'p' => &[&[(0.0, 2.0), (0.0, 8.0)], LOWER_BOWL_RIGHT],
+        'q' => &[&[(4.0, 2.0), (4.0, 8.0)], LOWER_BOWL_LEFT],
+        'r' => &[
+            &[(0.0, 2.0), (0.0, 6.0)],
+            &[(0.0, 3.5), (1.5, 2.0), (3.5, 2.0)],
+        ],
+        's' => &[&[
+            (4.0, 2.5),
+            (3.5, 2.0),
+            (0.5, 2.0),
+            (0.0, 2.5),
+            (0.0, 3.5),
+            (0.5, 4.0),
+            (3.5, 4.0),
+            (4.0, 4.5),
+            (4.0, 5.5),
+            (3.5, 6.0),
+            (0.5, 6.0),
+            (0.0, 5.5),
+        ]],
+        't' => &[
+            &[(1.5, 0.5), (1.5, 5.0), (2.5, 6.0), (3.5, 6.0)],
+            &[(0.0, 2.0), (3.5, 2.0)],
+        ],
+        'u' => &[
+            &[(0.0, 2.0), (0.0, 5.0), (1.0, 6.0), (3.0, 6.0), (4.0, 5.0)],
+            &[(4.0, 2.0), (4.0, 6.0)],
+        ],
+        'v' => &[&[(0.0, 2.0), (2.0, 6.0), (4.0, 2.0)]],
+        'w' => &[&[(0.0, 2.0), (1.0, 6.0), (2.0, 3.5), (3.0, 6.0), (4.0, 2.0)]],
+        'x' => &[&[(0.0, 2.0), (4.0, 6.0)], &[(4.0, 2.0), (0.0, 6.0)]],
+        'y' => &[&[(0.0, 2.0), (2.0, 6.0)], &[(4.0, 2.0), (1.5, 8.0)]],
+        'z' => &[&[(0.0, 2.0), (4.0, 2.0), (0.0, 6.0), (4.0, 6.0)]],
+        _ => return None,
+    };
+    Some(strokes)
+}
@@ -4,8 +4,9 @@
 //! - Slider captcha
 //! - Numeric captcha
 //! - Alphanumeric captcha
-//! - Image captcha
+//! - SVG rendering for numeric/alphanumeric codes
 
 pub mod captcha_service;
+mod glyphs;
 
-pub use captcha_service::{CaptchaData, CaptchaService, CaptchaType};
+pub use captcha_service::{CaptchaData, CaptchaOptions, CaptchaService, CaptchaType};
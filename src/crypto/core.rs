@@ -1,12 +1,15 @@
 use argon2::{
-    Argon2,
+    Algorithm, Argon2, Params, Version,
     password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
 };
 
-use anyhow::Error;
+use anyhow::{Error, anyhow};
 use base64::{Engine as _, engine::general_purpose};
 use hex::encode;
+use hmac::{Hmac, KeyInit, Mac};
 use rand::RngExt;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use sha2::Sha256;
 use tracing::warn;
 
 pub struct Crypto;
@@ -34,14 +37,66 @@ impl Crypto {
     /// * `Ok(String)` - On success, returns the PHC format hash string.
     /// * `Err(password_hash::Error)` - On failure, returns an error.
     pub fn hash_password(password: &str) -> Result<String, password_hash::Error> {
+        // Argon2::default() is Argon2id with m=19 MiB, t=2, p=1 (the OWASP baseline).
+        Self::hash_with(&Argon2::default(), password)
+    }
+
+    /// Hashes a password using Argon2id with explicit cost parameters.
+    ///
+    /// The costs are stored in the PHC string, so `verify_password` needs no extra input.
+    /// OWASP-recommended starting points (pick one, all offer equivalent protection):
+    ///
+    /// | m_cost (KiB) | t_cost | p_cost |
+    /// |---|---|---|
+    /// | 47104 (46 MiB) | 1 | 1 |
+    /// | 19456 (19 MiB) | 2 | 1 |
+    /// | 12288 (12 MiB) | 3 | 1 |
+    /// | 9216 (9 MiB) | 4 | 1 |
+    /// | 7168 (7 MiB) | 5 | 1 |
+    ///
+    /// Trade memory for iterations on memory-constrained hosts, then tune until one hash
+    /// takes well under your login latency budget.
+    ///
+    /// # Arguments
+    /// * `password` - The plaintext password to hash.
+    /// * `params` - Argon2 cost parameters (memory KiB, iterations, parallelism).
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let params = Crypto::argon2_params(47_104, 1, 1)?;
+    /// let hash = Crypto::hash_password_with_params("secret", &params)?;
+    /// ```
+    ///
+    /// # Returns
+    /// * `Ok(String)` - On success, returns the PHC format hash string.
+    /// * `Err(password_hash::Error)` - On failure, returns an error.
+    pub fn hash_password_with_params(
+        password: &str,
+        params: &Params,
+    ) -> Result<String, password_hash::Error> {
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone());
+        Self::hash_with(&argon2, password)
+    }
+
+    /// Build Argon2 cost parameters.
+    ///
+    /// # Arguments
+    /// * `m_cost` - Memory in KiB.
+    /// * `t_cost` - Iterations.
+    /// * `p_cost` - Parallelism (lanes).
+    ///
+    /// # Returns
+    /// * `Err(argon2::Error)` - When a cost is outside Argon2's allowed range.
+    pub fn argon2_params(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Params, argon2::Error> {
+        Params::new(m_cost, t_cost, p_cost, None)
+    }
+
+    fn hash_with(argon2: &Argon2, password: &str) -> Result<String, password_hash::Error> {
         let mut salt_bytes = [0u8; 16];
         let mut rng = rand::rng();
         rng.fill(&mut salt_bytes);
         let salt = SaltString::encode_b64(&salt_bytes)?;
 
-        // Argon2::default() uses the recommended secure parameters.
-        let argon2 = Argon2::default();
-
         // Perform the hash calculation.
         let password_hash = argon2
             .hash_password(password.as_bytes(), &salt)?
@@ -49,6 +104,78 @@ impl Crypto {
         Ok(password_hash)
     }
 
+    /// Verifies a password and, when the stored hash is weaker than `target_params`,
+    /// returns a fresh hash for the caller to persist (transparent upgrade on login).
+    ///
+    /// A stored hash is considered weaker when it is not Argon2id v0x13, or when any of
+    /// its memory / iteration / parallelism costs are below the target.
+    ///
+    /// # Arguments
+    /// * `password` - The plaintext password to verify.
+    /// * `stored_hash` - The PHC format hash currently stored for the user.
+    /// * `target_params` - The cost parameters new hashes should use.
+    ///
+    /// # Returns
+    /// * `(false, None)` - The password does not match (or the hash is malformed).
+    /// * `(true, None)` - The password matches and the stored hash is up to date.
+    /// * `(true, Some(new_hash))` - The password matches and should be re-stored as `new_hash`.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let (valid, new_hash) = Crypto::verify_and_maybe_rehash(&password, &user.password, &params);
+    /// if !valid {
+    ///     return Err(AppError::Unauthorized);
+    /// }
+    /// if let Some(new_hash) = new_hash {
+    ///     user_repo.update_password_hash(user.id, &new_hash).await?;
+    /// }
+    /// ```
+    pub fn verify_and_maybe_rehash(
+        password: &str,
+        stored_hash: &str,
+        target_params: &Params,
+    ) -> (bool, Option<String>) {
+        if !Self::verify_password(password, stored_hash) {
+            return (false, None);
+        }
+
+        if !Self::needs_rehash(stored_hash, target_params) {
+            return (true, None);
+        }
+
+        match Self::hash_password_with_params(password, target_params) {
+            Ok(new_hash) => (true, Some(new_hash)),
+            Err(e) => {
+                // The login itself succeeded; keep the old hash and try again next time.
+                warn!("...「verify_and_maybe_rehash」rehash failed: {} ...", e);
+                (true, None)
+            }
+        }
+    }
+
+    /// Returns true if `hash` was produced with a weaker algorithm or lower costs than `target_params`.
+    pub fn needs_rehash(hash: &str, target_params: &Params) -> bool {
+        let parsed_hash = match PasswordHash::new(hash) {
+            Ok(hash) => hash,
+            Err(_) => return true,
+        };
+
+        if parsed_hash.algorithm != Algorithm::Argon2id.ident()
+            || parsed_hash.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+
+        match Params::try_from(&parsed_hash) {
+            Ok(params) => {
+                params.m_cost() < target_params.m_cost()
+                    || params.t_cost() < target_params.t_cost()
+                    || params.p_cost() < target_params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+
     /// Verifies if a password matches a given hash.
     pub fn verify_password(password: &str, hash: &str) -> bool {
         // Parse the hash string into a PasswordHash struct.
@@ -63,11 +190,19 @@ impl Crypto {
             .is_ok()
     }
 
+    /// Double-base64 encoding of `key`. This is obfuscation only: anyone can decode it.
+    #[deprecated(
+        note = "encoding only, not a keyed scheme; use `Crypto::sign_basic_auth_key` / `verify_basic_auth_key`"
+    )]
     pub fn generate_basic_auth_key(key: &str) -> String {
         let first_encode = general_purpose::STANDARD.encode(key.as_bytes());
         general_purpose::STANDARD.encode(first_encode.as_bytes())
     }
 
+    /// Reverse of [`Crypto::generate_basic_auth_key`].
+    #[deprecated(
+        note = "encoding only, not a keyed scheme; use `Crypto::sign_basic_auth_key` / `verify_basic_auth_key`"
+    )]
     pub fn decode_basic_auth_key(encoded_key: &str) -> Result<String, Error> {
         warn!(
             "...「decode_basic_auth_key」encoded_key: {} ...",
@@ -78,12 +213,57 @@ impl Crypto {
         String::from_utf8(second_decode).map_err(Error::from)
     }
 
-    // zstd_compress
-    pub fn zstd_compress(data: &[u8]) -> Result<Vec<u8>, Error> {
-        let compressed = zstd::stream::encode_all(data, 0)?;
+    /// Issue a keyed BASIC auth token for `key` (e.g. a service name), signed with `secret`.
+    ///
+    /// Format: `base64url(key).hex(hmac_sha256(secret, key))`. Unlike the deprecated
+    /// double-base64 helper, a token cannot be forged without the secret; it is still a
+    /// long-lived bearer credential, so rotate `secret` to revoke all tokens at once.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let token = Crypto::sign_basic_auth_key("billing-service", &secret);
+    /// // client sends `Authorization: Basic <token>`
+    /// assert_eq!(
+    ///     Crypto::verify_basic_auth_key(&token, &secret).as_deref(),
+    ///     Some("billing-service")
+    /// );
+    /// ```
+    pub fn sign_basic_auth_key(key: &str, secret: &str) -> String {
+        format!(
+            "{}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode(key.as_bytes()),
+            Self::hmac_sha256_hex(secret.as_bytes(), key.as_bytes())
+        )
+    }
+
+    /// Verify a token from [`Crypto::sign_basic_auth_key`] and return the signed key.
+    ///
+    /// Returns None when the token is malformed or the signature does not match `secret`.
+    pub fn verify_basic_auth_key(token: &str, secret: &str) -> Option<String> {
+        let (encoded_key, signature) = token.trim().rsplit_once('.')?;
+        let key = general_purpose::URL_SAFE_NO_PAD.decode(encoded_key).ok()?;
+        if !Self::hmac_sha256_verify(secret.as_bytes(), &key, signature) {
+            return None;
+        }
+        String::from_utf8(key).ok()
+    }
+
+    /// Compress `data` with zstd.
+    ///
+    /// # Arguments
+    /// data - Bytes to compress
+    /// level - Compression level, 1 (fastest) to 22 (smallest); 0 selects zstd's default (3)
+    pub fn zstd_compress(data: &[u8], level: i32) -> Result<Vec<u8>, Error> {
+        let compressed = zstd::stream::encode_all(data, level)?;
         Ok(compressed)
     }
 
+    /// Decompress data produced by [`Crypto::zstd_compress`] (or any zstd frame).
+    pub fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+        let decompressed = zstd::stream::decode_all(data)?;
+        Ok(decompressed)
+    }
+
     // Generate a random 32-character AES key in hexadecimal format.
     pub fn generate_aes_key() -> String {
         let mut key = [0u8; 32];
@@ -97,6 +277,124 @@ impl Crypto {
         };
         hex_string
     }
+
+    /// HMAC-SHA256 of `data` under `key`, as raw bytes.
+    ///
+    /// Use this when the MAC feeds another derivation step (e.g. TC3 / SigV4 signing keys).
+    pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// HMAC-SHA256 of `data` under `key`, as lowercase hex (e.g. webhook signatures).
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let signature = Crypto::hmac_sha256_hex(secret.as_bytes(), body);
+    /// ```
+    pub fn hmac_sha256_hex(key: &[u8], data: &[u8]) -> String {
+        encode(Self::hmac_sha256(key, data))
+    }
+
+    /// Verify a hex HMAC-SHA256 signature in constant time.
+    ///
+    /// # Arguments
+    /// key - HMAC key
+    /// data - Signed payload
+    /// expected_hex - Signature to check, hex encoded (case-insensitive)
+    ///
+    /// # Returns
+    /// true if the signature matches; false on mismatch or invalid hex.
+    pub fn hmac_sha256_verify(key: &[u8], data: &[u8], expected_hex: &str) -> bool {
+        let Ok(expected) = hex::decode(expected_hex.trim()) else {
+            return false;
+        };
+        let mut mac = <Hmac<Sha256> as KeyInit>::new_from_slice(key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(data);
+        // `verify_slice` compares in constant time (ring's `constant_time` is deprecated).
+        mac.verify_slice(&expected).is_ok()
+    }
+
+    /// Encrypt `plaintext` with AES-256-GCM.
+    ///
+    /// A random 96-bit nonce is generated per call, so encrypting the same plaintext twice
+    /// yields different outputs.
+    ///
+    /// # Arguments
+    /// key - 32 raw key bytes, e.g. a 64-character hex secret decoded with `hex::decode`
+    /// plaintext - Data to encrypt
+    ///
+    /// # Returns
+    /// `nonce (12 bytes) || ciphertext || tag (16 bytes)`
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// // 64 hex characters = 32 bytes, e.g. generated once with `openssl rand -hex 32`
+    /// let key = hex::decode(std::env::var("DATA_KEY")?)?;
+    /// let sealed = Crypto::aes_gcm_encrypt(&key, b"secret")?;
+    /// let opened = Crypto::aes_gcm_decrypt(&key, &sealed)?;
+    /// assert_eq!(opened, b"secret");
+    /// ```
+    pub fn aes_gcm_encrypt(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = Self::aes_gcm_key(key)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut nonce)
+            .map_err(|_| anyhow!("failed to generate AES-GCM nonce"))?;
+
+        let mut in_out = plaintext.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut in_out,
+        )
+        .map_err(|_| anyhow!("AES-GCM encryption failed"))?;
+
+        let mut sealed = Vec::with_capacity(NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    /// Decrypt data produced by [`Crypto::aes_gcm_encrypt`].
+    ///
+    /// # Arguments
+    /// key - The 32-byte key used for encryption
+    /// sealed - `nonce || ciphertext || tag`
+    ///
+    /// # Returns
+    /// The plaintext, or an error if the input is truncated, the key is wrong or the data
+    /// was tampered with.
+    pub fn aes_gcm_decrypt(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        let key = Self::aes_gcm_key(key)?;
+        if sealed.len() < NONCE_LEN + AES_256_GCM.tag_len() {
+            return Err(anyhow!("AES-GCM input too short: {} bytes", sealed.len()));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| anyhow!("invalid AES-GCM nonce"))?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| anyhow!("AES-GCM decryption failed: wrong key or tampered data"))?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(in_out)
+    }
+
+    fn aes_gcm_key(key: &[u8]) -> Result<LessSafeKey, Error> {
+        let unbound = UnboundKey::new(&AES_256_GCM, key).map_err(|_| {
+            anyhow!(
+                "AES-256-GCM key must be {} bytes, got {}",
+                AES_256_GCM.key_len(),
+                key.len()
+            )
+        })?;
+        Ok(LessSafeKey::new(unbound))
+    }
 }
 
 // fn main() {
@@ -130,6 +428,7 @@ impl Crypto {
 mod tests {
     use super::*;
     #[test]
+    #[allow(deprecated)]
     fn test_generate_basic_auth_key() {
         let key = "topedu::auth";
         let encoded_key = Crypto::generate_basic_auth_key(key);
@@ -138,6 +437,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_decode_basic_auth_key() {
         let key = "topedu::auth";
         let encoded_key = Crypto::generate_basic_auth_key(key);
@@ -148,6 +448,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_new() {
         assert!(Crypto::generate_basic_auth_key("test").len() > 0);
     }
@@ -158,6 +459,219 @@ mod tests {
         println!("the aes_key :{}", key)
     }
 
+    #[test]
+    fn test_sign_and_verify_basic_auth_key() {
+        let token = Crypto::sign_basic_auth_key("topedu::auth", "s3cret");
+        assert_eq!(
+            Crypto::verify_basic_auth_key(&token, "s3cret").as_deref(),
+            Some("topedu::auth")
+        );
+        assert_eq!(Crypto::verify_basic_auth_key(&token, "other"), None);
+
+        // Swapping in another key keeps the old signature, which no longer matches
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let forged = format!(
+            "{}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode("admin"),
+            signature
+        );
+        assert_eq!(Crypto::verify_basic_auth_key(&forged, "s3cret"), None);
+        assert_eq!(Crypto::verify_basic_auth_key("no-dot", "s3cret"), None);
+
+        #[allow(deprecated)]
+        let legacy = Crypto::generate_basic_auth_key("topedu::auth");
+        assert_eq!(Crypto::verify_basic_auth_key(&legacy, "s3cret"), None);
+    }
+
+    #[test]
+    fn test_zstd_round_trip() {
+        let data = "neocrates zstd round trip; ".repeat(200).into_bytes();
+        assert!(data.len() > 4096);
+        for level in [0, 1, 19] {
+            let compressed = Crypto::zstd_compress(&data, level).unwrap();
+            assert!(
+                compressed.len() * 20 < data.len(),
+                "level {} compressed {} -> {}",
+                level,
+                data.len(),
+                compressed.len()
+            );
+            assert_eq!(Crypto::zstd_decompress(&compressed).unwrap(), data);
+        }
+        assert!(Crypto::zstd_decompress(b"not zstd").is_err());
+    }
+
+    // RFC 4231 test cases 1, 2, 4 and 6 (key longer than the block size).
+    #[test]
+    fn test_hmac_sha256_rfc4231_vectors() {
+        let cases: [(Vec<u8>, &[u8], &str); 4] = [
+            (
+                vec![0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe".to_vec(),
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                (1u8..=25).collect(),
+                &[0xcd; 50],
+                "82558a389a443c0ea4cc819899f2083a85f0faa3e578f8077a2e3ff46729665b",
+            ),
+            (
+                vec![0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for (key, data, expected) in cases {
+            assert_eq!(Crypto::hmac_sha256_hex(&key, data), expected);
+            assert!(Crypto::hmac_sha256_verify(&key, data, expected));
+            assert!(Crypto::hmac_sha256_verify(
+                &key,
+                data,
+                &expected.to_uppercase()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_hmac_sha256_verify_rejects_mismatch() {
+        let signature = Crypto::hmac_sha256_hex(b"secret", b"payload");
+        assert!(!Crypto::hmac_sha256_verify(
+            b"secret",
+            b"payload!",
+            &signature
+        ));
+        assert!(!Crypto::hmac_sha256_verify(
+            b"other", b"payload", &signature
+        ));
+        assert!(!Crypto::hmac_sha256_verify(
+            b"secret",
+            b"payload",
+            &signature[..62]
+        ));
+        assert!(!Crypto::hmac_sha256_verify(
+            b"secret", b"payload", "not hex"
+        ));
+    }
+
+    #[test]
+    fn test_aes_gcm_round_trip() {
+        // Keys are 64-hex-character secrets decoded to 32 raw bytes, as in the docs
+        let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")
+            .unwrap();
+        assert_eq!(key.len(), 32);
+        let sealed = Crypto::aes_gcm_encrypt(&key, b"hello neocrates").unwrap();
+        assert_eq!(sealed.len(), 12 + b"hello neocrates".len() + 16);
+        assert_eq!(
+            Crypto::aes_gcm_decrypt(&key, &sealed).unwrap(),
+            b"hello neocrates"
+        );
+
+        // Fresh nonce per call
+        let again = Crypto::aes_gcm_encrypt(&key, b"hello neocrates").unwrap();
+        assert_ne!(sealed, again);
+
+        let empty = Crypto::aes_gcm_encrypt(&key, b"").unwrap();
+        assert!(Crypto::aes_gcm_decrypt(&key, &empty).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_aes_gcm_detects_tampering() {
+        let key = [7u8; 32];
+        let sealed = Crypto::aes_gcm_encrypt(&key, b"transfer 100").unwrap();
+
+        for i in [0, 12, sealed.len() - 1] {
+            let mut tampered = sealed.clone();
+            tampered[i] ^= 0x01;
+            assert!(Crypto::aes_gcm_decrypt(&key, &tampered).is_err());
+        }
+        assert!(Crypto::aes_gcm_decrypt(&[8u8; 32], &sealed).is_err());
+        assert!(Crypto::aes_gcm_decrypt(&key, &sealed[..20]).is_err());
+        assert!(Crypto::aes_gcm_encrypt(&key[..16], b"x").is_err());
+    }
+
+    // Small costs keep the tests fast; only the relative strength matters here.
+    fn params(m_cost: u32, t_cost: u32, p_cost: u32) -> Params {
+        Params::new(m_cost, t_cost, p_cost, None).expect("valid argon2 params")
+    }
+
+    #[test]
+    fn test_hash_with_custom_params_verifies() {
+        let custom = Crypto::argon2_params(4096, 3, 2).unwrap();
+        let hash = Crypto::hash_password_with_params("secret", &custom).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=4096,t=3,p=2$"));
+        assert!(Crypto::verify_password("secret", &hash));
+        assert!(!Crypto::verify_password("Secret", &hash));
+        assert!(Crypto::argon2_params(1, 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_verify_and_maybe_rehash_upgrades_weaker_hash() {
+        let old = params(1024, 1, 1);
+        let target = params(2048, 2, 1);
+        let stored = Crypto::hash_password_with_params("secret", &old).unwrap();
+
+        let (valid, new_hash) = Crypto::verify_and_maybe_rehash("secret", &stored, &target);
+        assert!(valid);
+        let new_hash = new_hash.expect("weaker hash should be upgraded");
+        assert!(Crypto::verify_password("secret", &new_hash));
+        assert!(!Crypto::needs_rehash(&new_hash, &target));
+
+        // Once upgraded, logging in again does not rehash.
+        assert_eq!(
+            Crypto::verify_and_maybe_rehash("secret", &new_hash, &target),
+            (true, None)
+        );
+    }
+
+    #[test]
+    fn test_verify_and_maybe_rehash_keeps_current_or_stronger_hash() {
+        let target = params(1024, 1, 1);
+        let same = Crypto::hash_password_with_params("secret", &target).unwrap();
+        let stronger = Crypto::hash_password_with_params("secret", &params(2048, 2, 1)).unwrap();
+
+        assert_eq!(
+            Crypto::verify_and_maybe_rehash("secret", &same, &target),
+            (true, None)
+        );
+        assert_eq!(
+            Crypto::verify_and_maybe_rehash("secret", &stronger, &target),
+            (true, None)
+        );
+    }
+
+    #[test]
+    fn test_verify_and_maybe_rehash_rejects_wrong_password() {
+        let old = params(1024, 1, 1);
+        let target = params(2048, 2, 1);
+        let stored = Crypto::hash_password_with_params("secret", &old).unwrap();
+
+        assert_eq!(
+            Crypto::verify_and_maybe_rehash("wrong", &stored, &target),
+            (false, None)
+        );
+        assert_eq!(
+            Crypto::verify_and_maybe_rehash("secret", "not-a-hash", &target),
+            (false, None)
+        );
+    }
+
+    #[test]
+    fn test_needs_rehash_for_other_argon2_variants() {
+        let target = params(1024, 1, 1);
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, target.clone());
+        let stored = Crypto::hash_with(&argon2i, "secret").unwrap();
+
+        assert!(Crypto::needs_rehash(&stored, &target));
+        let (valid, new_hash) = Crypto::verify_and_maybe_rehash("secret", &stored, &target);
+        assert!(valid);
+        assert!(new_hash.unwrap().starts_with("$argon2id$"));
+    }
+
     #[test]
     fn test_md5_string() {
         let data = "hello world";
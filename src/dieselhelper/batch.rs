@@ -0,0 +1,363 @@
+// Bulk insert / upsert helpers for Postgres.
+//
+// Postgres limits a single statement to 65535 bind parameters. A multi-row
+// `INSERT ... VALUES (...), (...)` binds `rows * columns` parameters, so large
+// batches must be split before they reach the server, otherwise the query
+// fails with "too many bind parameters".
+//
+// Diesel's insert types are too generic to wrap in a plain function, so the
+// entry points are macros that expand at the call site (like `diesel_execute!`):
+//
+//   // ON CONFLICT (email) DO NOTHING
+//   let counts = diesel_insert_batch!(conn, users::table, &new_users, 4, users::email)?;
+//
+//   // ON CONFLICT (email) DO UPDATE SET name = excluded.name, age = excluded.age
+//   let counts = diesel_upsert_batch!(
+//       conn, users::table, &new_users, 4, users::email, [users::name, users::age]
+//   )?;
+//
+// The `columns_per_row` argument is the number of columns each record binds,
+// which is what the chunk size is derived from.
+//
+// All chunks of one call run in a single transaction (a savepoint when the caller
+// already has one open), so a failing chunk rolls back the chunks before it and the
+// call either writes every record or none.
+
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of bind parameters Postgres accepts in a single statement.
+pub const PG_MAX_BIND_PARAMS: usize = 65535;
+
+/// Outcome of a bulk insert / upsert.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpsertCounts {
+    /// Rows that did not exist before and were inserted.
+    pub inserted: usize,
+    /// Rows that conflicted and were updated (`DO UPDATE`).
+    pub updated: usize,
+    /// Rows that conflicted and were left untouched (`DO NOTHING`).
+    pub skipped: usize,
+}
+
+impl UpsertCounts {
+    /// Total number of records processed.
+    pub fn total(&self) -> usize {
+        self.inserted + self.updated + self.skipped
+    }
+}
+
+/// Compute how many rows fit into one statement without exceeding
+/// [`PG_MAX_BIND_PARAMS`].
+///
+/// # Arguments
+/// * `columns_per_row` - Number of bind parameters each row contributes
+///
+/// # Returns
+/// The number of rows per chunk (always at least 1)
+pub fn batch_chunk_size(columns_per_row: usize) -> usize {
+    (PG_MAX_BIND_PARAMS / columns_per_row.max(1)).max(1)
+}
+
+/// Bulk insert with `ON CONFLICT (...) DO NOTHING`, chunked under the bind-parameter limit.
+///
+/// Returns `QueryResult<UpsertCounts>` where conflicting rows are counted as `skipped`.
+/// All chunks run in one transaction: on `Err` nothing was written.
+///
+/// Usage:
+///   let counts = diesel_insert_batch!(conn, users::table, &records, 4, users::email)?;
+#[macro_export]
+macro_rules! diesel_insert_batch {
+    ($conn:expr, $table:expr, $records:expr, $columns_per_row:expr, $target:expr) => {{
+        use diesel::{Connection, RunQueryDsl};
+        let __records = $records;
+        let __chunk_size = $crate::dieselhelper::batch::batch_chunk_size($columns_per_row);
+        (&mut *$conn).transaction(
+            |__conn| -> diesel::QueryResult<$crate::dieselhelper::batch::UpsertCounts> {
+                let mut __counts = $crate::dieselhelper::batch::UpsertCounts::default();
+                for __chunk in __records.chunks(__chunk_size) {
+                    let __diesel_q = diesel::insert_into($table)
+                        .values(__chunk)
+                        .on_conflict($target)
+                        .do_nothing();
+                    $crate::dieselhelper::logging::log_query(&__diesel_q);
+                    let __inserted = __diesel_q.execute(__conn)?;
+                    __counts.inserted += __inserted;
+                    __counts.skipped += __chunk.len().saturating_sub(__inserted);
+                }
+                Ok(__counts)
+            },
+        )
+    }};
+}
+
+/// Bulk upsert with `ON CONFLICT (...) DO UPDATE SET col = excluded.col, ...`,
+/// chunked under the bind-parameter limit.
+///
+/// Inserted vs. updated rows are told apart with `RETURNING (xmax = 0)`, which is
+/// true only for freshly inserted tuples. All chunks run in one transaction: on `Err`
+/// nothing was written.
+///
+/// `records` must not contain two records with the same conflict key: Postgres rejects
+/// the statement with "ON CONFLICT DO UPDATE command cannot affect row a second time"
+/// (and records in different chunks would silently apply last-wins). Dedupe by key
+/// before calling, keeping the record that should win.
+///
+/// Usage:
+///   let counts = diesel_upsert_batch!(
+///       conn, users::table, &records, 4, users::email, [users::name, users::age]
+///   )?;
+#[macro_export]
+macro_rules! diesel_upsert_batch {
+    ($conn:expr, $table:expr, $records:expr, $columns_per_row:expr, $target:expr, [$($col:expr),+ $(,)?]) => {{
+        use diesel::{Connection, ExpressionMethods, RunQueryDsl};
+        let __records = $records;
+        let __chunk_size = $crate::dieselhelper::batch::batch_chunk_size($columns_per_row);
+        (&mut *$conn).transaction(
+            |__conn| -> diesel::QueryResult<$crate::dieselhelper::batch::UpsertCounts> {
+                let mut __counts = $crate::dieselhelper::batch::UpsertCounts::default();
+                for __chunk in __records.chunks(__chunk_size) {
+                    let __diesel_q = diesel::insert_into($table)
+                        .values(__chunk)
+                        .on_conflict($target)
+                        .do_update()
+                        .set(($($col.eq(diesel::upsert::excluded($col)),)+))
+                        .returning(diesel::dsl::sql::<diesel::sql_types::Bool>("xmax = 0"));
+                    $crate::dieselhelper::logging::log_query(&__diesel_q);
+                    let __flags: Vec<bool> = __diesel_q.get_results(__conn)?;
+                    let __inserted = __flags.iter().filter(|f| **f).count();
+                    __counts.inserted += __inserted;
+                    __counts.updated += __flags.len() - __inserted;
+                }
+                Ok(__counts)
+            },
+        )
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    diesel::table! {
+        batch_items (id) {
+            id -> Int8,
+            name -> Text,
+            qty -> Int4,
+        }
+    }
+
+    #[derive(diesel::Insertable, Clone)]
+    #[diesel(table_name = batch_items)]
+    struct NewItem {
+        id: i64,
+        name: String,
+        qty: i32,
+    }
+
+    // Compile-only: makes sure both macros expand to well-typed diesel queries.
+    #[allow(dead_code)]
+    fn macros_typecheck(
+        conn: &mut diesel::PgConnection,
+        items: &[NewItem],
+    ) -> diesel::QueryResult<UpsertCounts> {
+        let ignored =
+            crate::diesel_insert_batch!(conn, batch_items::table, items, 3, batch_items::id)?;
+        let upserted = crate::diesel_upsert_batch!(
+            conn,
+            batch_items::table,
+            items,
+            3,
+            batch_items::id,
+            [batch_items::name, batch_items::qty]
+        )?;
+        Ok(UpsertCounts {
+            inserted: ignored.inserted + upserted.inserted,
+            updated: upserted.updated,
+            skipped: ignored.skipped,
+        })
+    }
+
+    #[test]
+    fn test_batch_chunk_size_stays_under_limit() {
+        assert_eq!(batch_chunk_size(1), PG_MAX_BIND_PARAMS);
+        assert_eq!(batch_chunk_size(4), 16383);
+        assert!(batch_chunk_size(7) * 7 <= PG_MAX_BIND_PARAMS);
+        assert!((batch_chunk_size(7) + 1) * 7 > PG_MAX_BIND_PARAMS);
+    }
+
+    #[test]
+    fn test_batch_chunk_size_degenerate_inputs() {
+        assert_eq!(batch_chunk_size(0), PG_MAX_BIND_PARAMS);
+        assert_eq!(batch_chunk_size(PG_MAX_BIND_PARAMS * 2), 1);
+    }
+
+    fn items(ids: std::ops::Range<i64>, qty: i32) -> Vec<NewItem> {
+        ids.map(|id| NewItem {
+            id,
+            name: format!("item-{id}"),
+            qty,
+        })
+        .collect()
+    }
+
+    /// Connection with an empty `batch_items` temp table (dropped with the connection).
+    fn test_conn() -> diesel::PgConnection {
+        use diesel::{Connection, RunQueryDsl};
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let mut conn = diesel::PgConnection::establish(&url).unwrap();
+        diesel::sql_query(
+            "CREATE TEMPORARY TABLE batch_items (\
+             id INT8 PRIMARY KEY, name TEXT NOT NULL, qty INT4 NOT NULL CHECK (qty >= 0))",
+        )
+        .execute(&mut conn)
+        .unwrap();
+        conn
+    }
+
+    fn row_count(conn: &mut diesel::PgConnection) -> i64 {
+        use diesel::{QueryDsl, RunQueryDsl};
+        batch_items::table.count().get_result(conn).unwrap()
+    }
+
+    // 3 columns per row: 21845 rows per chunk, so 30k rows take two statements.
+    const MULTI_CHUNK_ROWS: i64 = 30_000;
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_insert_batch_counts_across_chunks() {
+        let mut conn = test_conn();
+        assert!(MULTI_CHUNK_ROWS as usize > batch_chunk_size(3));
+        let first = items(0..MULTI_CHUNK_ROWS, 1);
+        let counts =
+            crate::diesel_insert_batch!(&mut conn, batch_items::table, &first, 3, batch_items::id)
+                .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 30_000,
+                updated: 0,
+                skipped: 0
+            }
+        );
+
+        // Half of these already exist and are skipped by DO NOTHING.
+        let second = items(15_000..45_000, 2);
+        let counts =
+            crate::diesel_insert_batch!(&mut conn, batch_items::table, &second, 3, batch_items::id)
+                .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 15_000,
+                updated: 0,
+                skipped: 15_000
+            }
+        );
+        assert_eq!(row_count(&mut conn), 45_000);
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_upsert_batch_tells_inserted_from_updated() {
+        use diesel::{QueryDsl, RunQueryDsl};
+
+        let mut conn = test_conn();
+        let existing = items(0..10_000, 1);
+        crate::diesel_insert_batch!(&mut conn, batch_items::table, &existing, 3, batch_items::id)
+            .unwrap();
+
+        let records = items(5_000..MULTI_CHUNK_ROWS + 5_000, 7);
+        let counts = crate::diesel_upsert_batch!(
+            &mut conn,
+            batch_items::table,
+            &records,
+            3,
+            batch_items::id,
+            [batch_items::name, batch_items::qty]
+        )
+        .unwrap();
+        assert_eq!(
+            counts,
+            UpsertCounts {
+                inserted: 25_000,
+                updated: 5_000,
+                skipped: 0
+            }
+        );
+        let qty: i32 = batch_items::table
+            .find(5_000i64)
+            .select(batch_items::qty)
+            .first(&mut conn)
+            .unwrap();
+        assert_eq!(qty, 7);
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_failing_chunk_rolls_back_earlier_chunks() {
+        let mut conn = test_conn();
+        let mut records = items(0..MULTI_CHUNK_ROWS, 1);
+        // The last row lands in the second chunk and violates `qty >= 0`.
+        records.last_mut().unwrap().qty = -1;
+
+        assert!(
+            crate::diesel_insert_batch!(
+                &mut conn,
+                batch_items::table,
+                &records,
+                3,
+                batch_items::id
+            )
+            .is_err()
+        );
+        assert!(
+            crate::diesel_upsert_batch!(
+                &mut conn,
+                batch_items::table,
+                &records,
+                3,
+                batch_items::id,
+                [batch_items::qty]
+            )
+            .is_err()
+        );
+        assert_eq!(row_count(&mut conn), 0);
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn test_upsert_batch_rejects_duplicate_keys_in_one_chunk() {
+        let mut conn = test_conn();
+        let mut records = items(0..3, 1);
+        records.push(records[0].clone());
+
+        let err = crate::diesel_upsert_batch!(
+            &mut conn,
+            batch_items::table,
+            &records,
+            3,
+            batch_items::id,
+            [batch_items::qty]
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("cannot affect row a second time"),
+            "{err}"
+        );
+        assert_eq!(row_count(&mut conn), 0);
+    }
+
+    #[test]
+    fn test_upsert_counts_total() {
+        let counts = UpsertCounts {
+            inserted: 3,
+            updated: 2,
+            skipped: 1,
+        };
+        assert_eq!(counts.total(), 6);
+    }
+}
@@ -1,2 +1,3 @@
+pub mod batch;
 pub mod logging;
 pub mod pool;
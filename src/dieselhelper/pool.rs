@@ -1,17 +1,34 @@
-use deadpool_diesel::postgres::{Manager, Pool, Runtime};
+use deadpool_diesel::postgres::{Hook, HookError, Manager, Object, Pool, Runtime};
 use diesel::connection::Connection as DieselConnection;
-use diesel::{PgConnection, QueryableByName, RunQueryDsl, sql_query, sql_types::Text};
+use diesel::query_builder::InsertStatement;
+use diesel::query_dsl::methods::ExecuteDsl;
+use diesel::result::DatabaseErrorKind;
+use diesel::{
+    Insertable, PgConnection, QueryableByName, RunQueryDsl, Table, sql_query, sql_types::Text,
+};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use url::Url;
 
+use super::batch::batch_chunk_size;
+use crate::helper::core::retry::{RetryPolicy, retry_async_with};
+
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("Database connection error: {0}")]
     ConnectionError(#[from] deadpool_diesel::PoolError),
 
     #[error("Database query error: {0}")]
-    QueryError(#[from] diesel::result::Error),
+    QueryError(diesel::result::Error),
+
+    /// The statement ran longer than `DieselPoolConfig::statement_timeout` and Postgres
+    /// cancelled it (SQLSTATE `57014`).
+    #[error("Database statement timed out: {0}")]
+    Timeout(diesel::result::Error),
 
     #[error("Database URL parse error: {0}")]
     UrlParseError(#[from] url::ParseError),
@@ -25,19 +42,58 @@ pub enum DatabaseError {
     #[error("Database initialization error: {0}")]
     InitializationError(String),
 
+    #[error("Database pool exhausted: waited {waited:?}, {in_use}/{max_size} connections in use")]
+    PoolTimeout {
+        waited: Duration,
+        max_size: usize,
+        in_use: usize,
+    },
+
     #[error(transparent)]
     UserError(#[from] anyhow::Error),
 }
 
 pub type DatabaseResult<T> = Result<T, DatabaseError>;
 
+impl From<diesel::result::Error> for DatabaseError {
+    fn from(err: diesel::result::Error) -> Self {
+        if is_statement_timeout(&err) {
+            DatabaseError::Timeout(err)
+        } else {
+            DatabaseError::QueryError(err)
+        }
+    }
+}
+
+/// Whether Postgres cancelled the statement because of `statement_timeout` (`57014`).
+///
+/// Diesel reports `57014` as `DatabaseErrorKind::Unknown` and its `DatabaseErrorInformation`
+/// has no accessor for the SQLSTATE, so it is recognized by the server message. A cancel
+/// requested by a client (`pg_cancel_backend`) has the same SQLSTATE but another message and
+/// stays a `QueryError`.
+///
+/// Limitation: the match is on the English message text. On a server whose `lc_messages`
+/// is localized (e.g. `zh_CN.UTF-8`) a statement timeout is reported as `QueryError`.
+fn is_statement_timeout(err: &diesel::result::Error) -> bool {
+    matches!(
+        err,
+        diesel::result::Error::DatabaseError(DatabaseErrorKind::Unknown, info)
+            if info.message().contains("canceling statement due to statement timeout")
+    )
+}
+
 #[derive(QueryableByName)]
 pub struct DbRow {
     #[diesel(sql_type = Text)]
     pub datname: String,
 }
 
-async fn ensure_database_exists(database_url: &str) -> DatabaseResult<()> {
+/// Split a database URL into the target database name and the URL of the maintenance
+/// database used to create it.
+fn maintenance_target(
+    database_url: &str,
+    maintenance_db: &str,
+) -> DatabaseResult<(String, String)> {
     let parsed = Url::parse(database_url)?;
     let db_name = parsed
         .path_segments()
@@ -47,8 +103,12 @@ async fn ensure_database_exists(database_url: &str) -> DatabaseResult<()> {
         .ok_or(DatabaseError::DatabaseNameMissing)?;
 
     let mut default_url = parsed.clone();
-    default_url.set_path("/postgres");
-    let default_url_string = default_url.to_string();
+    default_url.set_path(&format!("/{}", maintenance_db));
+    Ok((db_name, default_url.to_string()))
+}
+
+async fn ensure_database_exists(database_url: &str, maintenance_db: &str) -> DatabaseResult<()> {
+    let (db_name, default_url_string) = maintenance_target(database_url, maintenance_db)?;
     let sanitized_db_name = db_name.replace('"', "\"\"");
 
     tokio::task::spawn_blocking(move || -> DatabaseResult<()> {
@@ -81,38 +141,280 @@ async fn ensure_database_exists(database_url: &str) -> DatabaseResult<()> {
     })?
 }
 
+/// Postgres catalog unique indexes hit when two sessions race on the same
+/// `CREATE ... IF NOT EXISTS` (extensions, relations/indexes, types, schemas).
+const CATALOG_UNIQUE_INDEXES: &[&str] = &[
+    "pg_extension_name_index",
+    "pg_class_relname_nsp_index",
+    "pg_type_typname_nsp_index",
+    "pg_namespace_nspname_index",
+];
+
+/// Whether a setup statement failed only because another session created the same object
+/// concurrently.
+///
+/// Only unique violations on the Postgres catalog indexes count. Diesel does not expose
+/// the SQLSTATE, so `duplicate_object`/`duplicate_table` (42710/42P07) cannot be told apart
+/// from other errors reliably; statements should use `IF NOT EXISTS`, and anything else is
+/// propagated.
+fn is_already_exists(err: &diesel::result::Error) -> bool {
+    match err {
+        diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => info
+            .constraint_name()
+            .is_some_and(|name| CATALOG_UNIQUE_INDEXES.contains(&name)),
+        _ => false,
+    }
+}
+
+/// Session settings applied once per physical connection.
+///
+/// They run in the pool's `post_create` hook, so checkouts from the pool pay no extra
+/// round trips.
+#[derive(Debug, Clone)]
+pub struct DieselPoolConfig {
+    /// `SET TIME ZONE 'UTC'` on every new connection (default `true`).
+    pub utc_timezone: bool,
+    /// `SET log_statement = 'all'` on every new connection (default `false`).
+    pub log_statement_all: bool,
+    /// Create the database on startup when it does not exist (default `true`).
+    ///
+    /// Disable it where the role cannot `CREATE DATABASE` (e.g. managed RDS); the
+    /// existence check is then skipped entirely.
+    pub create_if_missing: bool,
+    /// Database connected to for the existence check / `CREATE DATABASE`
+    /// (default `"postgres"`).
+    pub maintenance_db: String,
+    /// How long `connection()` waits for a free connection before failing with
+    /// `DatabaseError::PoolTimeout` (default `None`: wait indefinitely).
+    pub wait_timeout: Option<Duration>,
+    /// `SET statement_timeout` on every new connection (default `None`: no limit).
+    ///
+    /// Statements running longer are cancelled by Postgres and fail with
+    /// `DatabaseError::Timeout` (only with English server messages, see
+    /// `is_statement_timeout`), so a runaway query cannot hold a connection indefinitely.
+    /// Rounded up to whole milliseconds.
+    pub statement_timeout: Option<Duration>,
+}
+
+impl Default for DieselPoolConfig {
+    fn default() -> Self {
+        Self {
+            utc_timezone: true,
+            log_statement_all: false,
+            create_if_missing: true,
+            maintenance_db: "postgres".to_string(),
+            wait_timeout: None,
+            statement_timeout: None,
+        }
+    }
+}
+
+impl DieselPoolConfig {
+    /// Statements executed right after a physical connection is established.
+    pub fn session_statements(&self) -> Vec<String> {
+        let mut statements = Vec::new();
+        if self.utc_timezone {
+            statements.push("SET TIME ZONE 'UTC'".to_string());
+        }
+        if self.log_statement_all {
+            statements.push("SET log_statement = 'all'".to_string());
+        }
+        if let Some(timeout) = self.statement_timeout {
+            // 0 would disable the timeout; never round a tiny timeout down to it.
+            let millis = timeout.as_nanos().div_ceil(1_000_000).max(1);
+            statements.push(format!("SET statement_timeout = {}", millis));
+        }
+        statements
+    }
+}
+
+/// Whether a transaction failed with a serialization failure or deadlock and can be replayed.
+///
+/// Diesel reports `40001` as `DatabaseErrorKind::SerializationFailure`; deadlocks (`40P01`)
+/// have no dedicated kind, so they are recognized by the server message.
+fn is_retryable_transaction_error(err: &DatabaseError) -> bool {
+    match err {
+        DatabaseError::QueryError(diesel::result::Error::DatabaseError(kind, info)) => {
+            matches!(kind, DatabaseErrorKind::SerializationFailure)
+                || info.message().contains("deadlock detected")
+        }
+        _ => false,
+    }
+}
+
+/// Split `values` into owned chunks of at most `chunk_size` elements, preserving order.
+fn split_into_chunks<V>(mut values: Vec<V>, chunk_size: usize) -> Vec<Vec<V>> {
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = Vec::with_capacity(values.len().div_ceil(chunk_size));
+    while values.len() > chunk_size {
+        let rest = values.split_off(chunk_size);
+        chunks.push(std::mem::replace(&mut values, rest));
+    }
+    if !values.is_empty() {
+        chunks.push(values);
+    }
+    chunks
+}
+
+/// Check a connection out of `pool`, reporting a wait timeout as `PoolTimeout`.
+async fn checkout(pool: &Pool, replica: bool) -> DatabaseResult<Object> {
+    let started = Instant::now();
+    let conn = pool.get().await;
+    #[cfg(any(feature = "metrics", feature = "full"))]
+    record_metrics(pool, replica, &conn);
+    #[cfg(not(any(feature = "metrics", feature = "full")))]
+    let _ = replica;
+    conn.map_err(|e| match e {
+        deadpool_diesel::PoolError::Timeout(_) => {
+            let status = pool.status();
+            let err = DatabaseError::PoolTimeout {
+                waited: started.elapsed(),
+                max_size: status.max_size,
+                in_use: status.size.saturating_sub(status.available),
+            };
+            warn!("{} (waiting: {})", err, status.waiting);
+            err
+        }
+        other => DatabaseError::ConnectionError(other),
+    })
+}
+
+#[cfg(any(feature = "metrics", feature = "full"))]
+fn record_metrics(pool: &Pool, replica: bool, conn: &Result<Object, deadpool_diesel::PoolError>) {
+    use crate::metrics::{
+        POOL_DIESEL_PRIMARY, POOL_DIESEL_REPLICA, record_pool, record_pool_timeout,
+    };
+    let name = if replica {
+        POOL_DIESEL_REPLICA
+    } else {
+        POOL_DIESEL_PRIMARY
+    };
+    if matches!(conn, Err(deadpool_diesel::PoolError::Timeout(_))) {
+        record_pool_timeout(name);
+    }
+    let status = pool.status();
+    record_pool(
+        name,
+        status.size.saturating_sub(status.available),
+        status.available,
+        status.max_size,
+    );
+}
+
+/// Pool usage snapshot returned by [`DieselPool::metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolMetrics {
+    /// Configured maximum number of connections.
+    pub max_size: usize,
+    /// Connections currently open (idle + in use).
+    pub size: usize,
+    /// Idle connections ready to be handed out.
+    pub available: usize,
+    /// Callers waiting for a connection.
+    pub waiting: usize,
+}
+
+impl PoolMetrics {
+    /// Connections currently checked out.
+    pub fn in_use(&self) -> usize {
+        self.size.saturating_sub(self.available)
+    }
+}
+
+/// Postgres connection pool, optionally paired with a read replica.
+///
+/// With a replica, [`Self::read_connection`] / [`Self::interact_read`] use it and everything
+/// else (including [`Self::interact`], transactions and migrations) uses the primary.
+/// Replicas lag behind the primary: a read right after a write may not see it yet, so
+/// routing such reads to the primary is the caller's responsibility.
 #[derive(Clone)]
 pub struct DieselPool {
     pool: Pool,
+    replica: Option<Pool>,
 }
 
 impl DieselPool {
-    /// Create and initialize a new DieselPool.
+    /// Create and initialize a new DieselPool with the default [`DieselPoolConfig`]
+    /// (UTC session time zone).
     pub async fn new(url: impl Into<String>, max_size: usize) -> DatabaseResult<Self> {
+        Self::with_config(url, max_size, DieselPoolConfig::default()).await
+    }
+
+    /// Create and initialize a new DieselPool with explicit session settings.
+    pub async fn with_config(
+        url: impl Into<String>,
+        max_size: usize,
+        config: DieselPoolConfig,
+    ) -> DatabaseResult<Self> {
         let url = url.into();
-        ensure_database_exists(&url).await?;
+        if config.create_if_missing {
+            ensure_database_exists(&url, &config.maintenance_db).await?;
+        }
 
+        let statements = config.session_statements();
         let manager = Manager::new(url.clone(), Runtime::Tokio1);
-        let pool = Pool::builder(manager)
+        let mut builder = Pool::builder(manager)
             .max_size(max_size)
-            .build()
-            .map_err(|e| {
-                DatabaseError::InitializationError(format!("Failed to build pool: {}", e))
-            })?;
-
-        // Set the timezone to UTC for all connections
-        let conn = pool.get().await.map_err(DatabaseError::ConnectionError)?;
-        conn.interact(|conn| sql_query("SET TIME ZONE 'UTC'").execute(conn))
-            .await
-            .map_err(DatabaseError::InteractionError)?
-            .map_err(|e| {
-                DatabaseError::InitializationError(format!(
-                    "Failed to execute timezone query: {}",
-                    e
-                ))
-            })?;
+            .wait_timeout(config.wait_timeout)
+            .runtime(Runtime::Tokio1);
+        if !statements.is_empty() {
+            builder = builder.post_create(Hook::async_fn(move |conn, _metrics| {
+                let statements = statements.clone();
+                Box::pin(async move {
+                    conn.interact(move |conn| {
+                        for sql in &statements {
+                            sql_query(sql).execute(conn)?;
+                        }
+                        Ok::<_, diesel::result::Error>(())
+                    })
+                    .await
+                    .map_err(|e| HookError::Message(format!("Session setup failed: {}", e).into()))?
+                    .map_err(|e| HookError::Message(format!("Session setup failed: {}", e).into()))
+                })
+            }));
+        }
+        let pool = builder.build().map_err(|e| {
+            DatabaseError::InitializationError(format!("Failed to build pool: {}", e))
+        })?;
+
+        // Establish the first connection eagerly so bad settings fail at startup.
+        pool.get().await.map_err(DatabaseError::ConnectionError)?;
+
+        Ok(Self {
+            pool,
+            replica: None,
+        })
+    }
+
+    /// Create a primary pool and a read-replica pool, both with the session settings of
+    /// `config` (UTC time zone etc.).
+    ///
+    /// The database is only created on the primary; the replica must already have it.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let pool = DieselPool::with_read_replica(primary_url, replica_url, 10, DieselPoolConfig::default()).await?;
+    /// let users = pool.interact_read(|conn| users::table.load::<User>(conn)).await?;
+    /// ```
+    pub async fn with_read_replica(
+        primary_url: impl Into<String>,
+        replica_url: impl Into<String>,
+        max_size: usize,
+        config: DieselPoolConfig,
+    ) -> DatabaseResult<Self> {
+        let replica_config = DieselPoolConfig {
+            create_if_missing: false,
+            ..config.clone()
+        };
+        let primary = Self::with_config(primary_url, max_size, config).await?;
+        let replica = Self::with_config(replica_url, max_size, replica_config).await?;
+        Ok(primary.with_replica(replica))
+    }
 
-        Ok(Self { pool })
+    /// Route reads to `replica` (an already-built pool; its own replica, if any, is ignored).
+    pub fn with_replica(mut self, replica: DieselPool) -> Self {
+        self.replica = Some(replica.pool);
+        self
     }
 
     /// Get the underlying Pool reference.
@@ -120,12 +422,43 @@ impl DieselPool {
         &self.pool
     }
 
+    /// The read-replica pool, if one is configured.
+    pub fn replica(&self) -> Option<&Pool> {
+        self.replica.as_ref()
+    }
+
+    fn read_pool(&self) -> &Pool {
+        self.replica.as_ref().unwrap_or(&self.pool)
+    }
+
     /// Get a connection object from the pool.
-    pub async fn connection(&self) -> DatabaseResult<deadpool::managed::Object<Manager>> {
-        self.pool
-            .get()
-            .await
-            .map_err(DatabaseError::ConnectionError)
+    ///
+    /// A wait that exceeds `DieselPoolConfig::wait_timeout` is reported as
+    /// `DatabaseError::PoolTimeout` (pool saturated) rather than `ConnectionError`.
+    pub async fn connection(&self) -> DatabaseResult<Object> {
+        checkout(&self.pool, false).await
+    }
+
+    /// Connection for writes: always from the primary. Same as [`Self::connection`].
+    pub async fn write_connection(&self) -> DatabaseResult<Object> {
+        checkout(&self.pool, false).await
+    }
+
+    /// Connection for reads: from the replica when configured, otherwise the primary.
+    pub async fn read_connection(&self) -> DatabaseResult<Object> {
+        checkout(self.read_pool(), self.replica.is_some()).await
+    }
+
+    /// Snapshot of pool usage, e.g. for dashboards; sustained `waiting > 0` means the
+    /// pool is too small for the load.
+    pub fn metrics(&self) -> PoolMetrics {
+        let status = self.pool.status();
+        PoolMetrics {
+            max_size: status.max_size,
+            size: status.size,
+            available: status.available,
+            waiting: status.waiting,
+        }
     }
 
     /// Check the status of the database connection.
@@ -165,6 +498,33 @@ impl DieselPool {
             .map_err(Into::into)
     }
 
+    /// Run `f` on a read connection (replica when configured, otherwise the primary).
+    ///
+    /// Use it for queries that tolerate replication lag; see the type-level docs.
+    pub async fn interact_read<F, T, E>(&self, f: F) -> DatabaseResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: Send + 'static + Into<DatabaseError>,
+    {
+        let conn = self.read_connection().await?;
+        conn.interact(f)
+            .await
+            .map_err(DatabaseError::InteractionError)?
+            .map_err(Into::into)
+    }
+
+    /// Run `f` on the primary. Same as [`Self::interact`], named for symmetry with
+    /// [`Self::interact_read`].
+    pub async fn interact_write<F, T, E>(&self, f: F) -> DatabaseResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: Send + 'static + Into<DatabaseError>,
+    {
+        self.interact(f).await
+    }
+
     /// The transaction handler
     pub async fn transaction<F, T>(&self, f: F) -> DatabaseResult<T>
     where
@@ -175,6 +535,84 @@ impl DieselPool {
             .await
     }
 
+    /// Run `f` in a transaction, replaying it when Postgres aborts with a serialization
+    /// failure (`40001`) or deadlock (`40P01`), up to `retries` extra attempts with
+    /// jittered exponential backoff.
+    ///
+    /// The closure is `Fn` because it may run several times: anything it does outside the
+    /// database (HTTP calls, sending messages, mutating captured state) can happen more than
+    /// once. Only the database work is rolled back between attempts.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let balance = pool
+    ///     .transaction_with_retry(3, move |conn| {
+    ///         sql_query("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE").execute(conn)?;
+    ///         transfer(conn, from, to, amount)
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn transaction_with_retry<F, T>(&self, retries: u32, f: F) -> DatabaseResult<T>
+    where
+        F: Fn(&mut PgConnection) -> diesel::result::QueryResult<T> + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let f = Arc::new(f);
+        let policy = RetryPolicy {
+            max_retries: retries,
+            base_delay_ms: 20,
+            max_delay_ms: 1_000,
+            jitter: 0.5,
+        };
+        retry_async_with(
+            &policy,
+            "diesel_transaction",
+            is_retryable_transaction_error,
+            || {
+                let f = Arc::clone(&f);
+                self.transaction(move |conn| f(conn))
+            },
+        )
+        .await
+    }
+
+    /// Insert `values` into `table`, split into as many statements as needed to stay under
+    /// Postgres' 65535 bind-parameter limit, and return the total inserted row count.
+    ///
+    /// `columns_per_row` is the number of columns each value binds; the chunk size is
+    /// derived from it (see [`batch_chunk_size`]). All chunks run in one transaction, so
+    /// either every row is inserted or none.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let inserted = pool.bulk_insert(users::table, 4, new_users).await?;
+    /// ```
+    pub async fn bulk_insert<T, V>(
+        &self,
+        table: T,
+        columns_per_row: usize,
+        values: Vec<V>,
+    ) -> DatabaseResult<usize>
+    where
+        T: Table + Copy + Send + 'static,
+        V: Send + 'static,
+        Vec<V>: Insertable<T>,
+        InsertStatement<T, <Vec<V> as Insertable<T>>::Values>: ExecuteDsl<PgConnection>,
+    {
+        if values.is_empty() {
+            return Ok(0);
+        }
+        let chunks = split_into_chunks(values, batch_chunk_size(columns_per_row));
+        self.transaction(move |conn| {
+            let mut inserted = 0;
+            for chunk in chunks {
+                inserted += diesel::insert_into(table).values(chunk).execute(conn)?;
+            }
+            Ok(inserted)
+        })
+        .await
+    }
+
     /// Execute a function with a database connection (non-transactional).
     pub async fn run<F, T, E>(&self, f: F) -> DatabaseResult<T>
     where
@@ -184,4 +622,434 @@ impl DieselPool {
     {
         self.interact(f).await
     }
+
+    /// Run all pending embedded migrations and return the versions that were applied.
+    ///
+    /// Idempotent: returns an empty list when the schema is already up to date.
+    /// Migration failures surface as `DatabaseError::InitializationError`.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use neocrates::diesel_migrations::{EmbeddedMigrations, embed_migrations};
+    ///
+    /// pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+    ///
+    /// let applied = pool.run_pending_migrations(MIGRATIONS).await?;
+    /// ```
+    pub async fn run_pending_migrations(
+        &self,
+        migrations: EmbeddedMigrations,
+    ) -> DatabaseResult<Vec<String>> {
+        let applied = self
+            .interact(move |conn| {
+                conn.run_pending_migrations(migrations)
+                    .map(|versions| {
+                        versions
+                            .iter()
+                            .map(|v| v.to_string())
+                            .collect::<Vec<String>>()
+                    })
+                    .map_err(|e| {
+                        DatabaseError::InitializationError(format!(
+                            "Failed to run migrations: {}",
+                            e
+                        ))
+                    })
+            })
+            .await?;
+        if applied.is_empty() {
+            info!("Database schema is up to date, no pending migrations");
+        } else {
+            info!("Applied migrations: {}", applied.join(", "));
+        }
+        Ok(applied)
+    }
+
+    /// Ensure the given Postgres extensions are installed (`CREATE EXTENSION IF NOT EXISTS`).
+    ///
+    /// Intended for startup, before migrations run.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// pool.ensure_extensions(&["pg_trgm", "uuid-ossp"]).await?;
+    /// ```
+    pub async fn ensure_extensions(&self, extensions: &[&str]) -> DatabaseResult<()> {
+        for extension in extensions {
+            let sanitized = extension.replace('"', "\"\"");
+            self.ensure_statement_idempotent(format!(
+                "CREATE EXTENSION IF NOT EXISTS \"{}\"",
+                sanitized
+            ))
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Run an idempotent setup statement (e.g. `CREATE INDEX IF NOT EXISTS ...`).
+    ///
+    /// Losing a race with another instance creating the same object is logged and treated
+    /// as success; any other failure is returned.
+    pub async fn ensure_statement_idempotent(&self, sql: impl Into<String>) -> DatabaseResult<()> {
+        let sql = sql.into();
+        self.interact(move |conn| match sql_query(&sql).execute(conn) {
+            Ok(_) => {
+                info!("Setup statement applied: {}", sql);
+                Ok(())
+            }
+            Err(e) if is_already_exists(&e) => {
+                warn!(
+                    "Setup statement skipped, object already exists: {} ({})",
+                    sql, e
+                );
+                Ok(())
+            }
+            Err(e) => {
+                error!("Setup statement failed: {} ({})", sql, e);
+                Err(DatabaseError::from(e))
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db_error(kind: DatabaseErrorKind, message: &str) -> diesel::result::Error {
+        diesel::result::Error::DatabaseError(kind, Box::new(message.to_string()))
+    }
+
+    #[test]
+    fn test_session_statements() {
+        assert_eq!(
+            DieselPoolConfig::default().session_statements(),
+            vec!["SET TIME ZONE 'UTC'"]
+        );
+        let config = DieselPoolConfig {
+            utc_timezone: false,
+            log_statement_all: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.session_statements(),
+            vec!["SET log_statement = 'all'"]
+        );
+        let config = DieselPoolConfig {
+            statement_timeout: Some(Duration::from_micros(1500)),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.session_statements(),
+            vec!["SET TIME ZONE 'UTC'", "SET statement_timeout = 2"]
+        );
+    }
+
+    #[test]
+    fn test_statement_timeout_maps_to_timeout_variant() {
+        let err = DatabaseError::from(db_error(
+            DatabaseErrorKind::Unknown,
+            "canceling statement due to statement timeout",
+        ));
+        assert!(matches!(err, DatabaseError::Timeout(_)));
+
+        let err = DatabaseError::from(db_error(
+            DatabaseErrorKind::Unknown,
+            "canceling statement due to user request",
+        ));
+        assert!(matches!(err, DatabaseError::QueryError(_)));
+        assert!(matches!(
+            DatabaseError::from(diesel::result::Error::NotFound),
+            DatabaseError::QueryError(_)
+        ));
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_statement_timeout_cancels_slow_query() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let config = DieselPoolConfig {
+            statement_timeout: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let pool = DieselPool::with_config(url, 1, config).await.unwrap();
+        let started = Instant::now();
+        let result = pool
+            .interact(|conn| sql_query("SELECT pg_sleep(2)").execute(conn))
+            .await;
+        assert!(
+            matches!(result, Err(DatabaseError::Timeout(_))),
+            "{:?}",
+            result
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The connection is still usable afterwards.
+        pool.health_check().await.unwrap();
+    }
+
+    #[derive(QueryableByName)]
+    struct TimeZoneRow {
+        #[diesel(sql_type = Text)]
+        #[diesel(column_name = "TimeZone")]
+        time_zone: String,
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_session_settings_run_once_per_physical_connection() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let pool = DieselPool::new(url, 1).await.unwrap();
+        for _ in 0..20 {
+            let tz = pool
+                .interact(|conn| sql_query("SHOW TIME ZONE").get_result::<TimeZoneRow>(conn))
+                .await
+                .unwrap();
+            assert_eq!(tz.time_zone, "UTC");
+        }
+        // Twenty checkouts, one physical connection: the hook ran once.
+        assert_eq!(pool.status().size, 1);
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_reads_use_replica_and_writes_use_primary() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let pool = DieselPool::with_read_replica(url.clone(), url, 2, Default::default())
+            .await
+            .unwrap();
+        let replica = pool.replica().unwrap().clone();
+
+        // Hold a read connection: only the replica has one checked out.
+        let read = pool.read_connection().await.unwrap();
+        assert_eq!(replica.status().available, 0);
+        assert_eq!(pool.pool().status().available, 1);
+        drop(read);
+
+        let write = pool.write_connection().await.unwrap();
+        assert_eq!(pool.pool().status().available, 0);
+        assert_eq!(replica.status().available, 1);
+        drop(write);
+
+        // Session setup ran on the replica too.
+        let tz = pool
+            .interact_read(|conn| sql_query("SHOW TIME ZONE").get_result::<TimeZoneRow>(conn))
+            .await
+            .unwrap();
+        assert_eq!(tz.time_zone, "UTC");
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_reads_fall_back_to_primary_without_replica() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        let pool = DieselPool::new(url, 1).await.unwrap();
+        assert!(pool.replica().is_none());
+        let read = pool.read_connection().await.unwrap();
+        assert_eq!(pool.pool().status().available, 0);
+        drop(read);
+    }
+
+    #[test]
+    fn test_is_retryable_transaction_error() {
+        let query = |kind, message| DatabaseError::QueryError(db_error(kind, message));
+        assert!(is_retryable_transaction_error(&query(
+            DatabaseErrorKind::SerializationFailure,
+            "could not serialize access due to concurrent update"
+        )));
+        assert!(is_retryable_transaction_error(&query(
+            DatabaseErrorKind::Unknown,
+            "deadlock detected"
+        )));
+        assert!(!is_retryable_transaction_error(&query(
+            DatabaseErrorKind::UniqueViolation,
+            "duplicate key value violates unique constraint"
+        )));
+        assert!(!is_retryable_transaction_error(
+            &DatabaseError::DatabaseNameMissing
+        ));
+    }
+
+    diesel::table! {
+        bulk_items (id) {
+            id -> Int8,
+            qty -> Int4,
+        }
+    }
+
+    #[derive(Insertable)]
+    #[diesel(table_name = bulk_items)]
+    struct NewBulkItem {
+        id: i64,
+        qty: i32,
+    }
+
+    /// Needs a running Postgres: `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_bulk_insert_spans_chunks_and_rolls_back_on_failure() {
+        use diesel::QueryDsl;
+
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+        // One connection, so the temp table is visible to every checkout.
+        let pool = DieselPool::new(url, 1).await.unwrap();
+        pool.interact(|conn| {
+            sql_query(
+                "CREATE TEMPORARY TABLE IF NOT EXISTS bulk_items \
+                 (id INT8 PRIMARY KEY, qty INT4 NOT NULL CHECK (qty >= 0))",
+            )
+            .execute(conn)
+        })
+        .await
+        .unwrap();
+        let count = || pool.interact(|conn| bulk_items::table.count().get_result::<i64>(conn));
+
+        // 2 columns per row: 40k rows need two statements.
+        let rows = 40_000usize;
+        assert!(rows > batch_chunk_size(2));
+        let values = |start: i64, bad_last: bool| -> Vec<NewBulkItem> {
+            (start..start + rows as i64)
+                .map(|id| NewBulkItem {
+                    id,
+                    qty: if bad_last && id == start + rows as i64 - 1 {
+                        -1
+                    } else {
+                        1
+                    },
+                })
+                .collect()
+        };
+
+        let inserted = pool
+            .bulk_insert(bulk_items::table, 2, values(0, false))
+            .await
+            .unwrap();
+        assert_eq!(inserted, rows);
+        assert_eq!(count().await.unwrap(), rows as i64);
+
+        // The last row sits in the second chunk and violates the CHECK: the first chunk
+        // is rolled back with it.
+        assert!(
+            pool.bulk_insert(bulk_items::table, 2, values(100_000, true))
+                .await
+                .is_err()
+        );
+        assert_eq!(count().await.unwrap(), rows as i64);
+    }
+
+    #[test]
+    fn test_split_into_chunks_forces_multiple_statements() {
+        // 10k rows x 8 columns = 80k binds, above the 65535 limit.
+        let rows: Vec<u32> = (0..10_000).collect();
+        let chunk_size = batch_chunk_size(8);
+        let chunks = split_into_chunks(rows, chunk_size);
+        assert_eq!(chunks.len(), 2);
+        assert!(
+            chunks
+                .iter()
+                .all(|c| c.len() * 8 <= crate::dieselhelper::batch::PG_MAX_BIND_PARAMS)
+        );
+        assert_eq!(chunks.iter().map(Vec::len).sum::<usize>(), 10_000);
+        assert_eq!(chunks.concat(), (0..10_000).collect::<Vec<u32>>());
+
+        assert!(split_into_chunks(Vec::<u32>::new(), 10).is_empty());
+        assert_eq!(split_into_chunks(vec![1, 2, 3], 3), vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_pool_metrics_and_timeout_message() {
+        let metrics = PoolMetrics {
+            max_size: 10,
+            size: 10,
+            available: 2,
+            waiting: 3,
+        };
+        assert_eq!(metrics.in_use(), 8);
+
+        let err = DatabaseError::PoolTimeout {
+            waited: Duration::from_millis(500),
+            max_size: 10,
+            in_use: 10,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Database pool exhausted: waited 500ms, 10/10 connections in use"
+        );
+    }
+
+    #[test]
+    fn test_maintenance_target() {
+        let (db, url) =
+            maintenance_target("postgres://u:p@localhost:5432/app", "postgres").unwrap();
+        assert_eq!(db, "app");
+        assert_eq!(url, "postgres://u:p@localhost:5432/postgres");
+
+        let (_, url) =
+            maintenance_target("postgres://u:p@db.internal/app?sslmode=require", "rdsadmin")
+                .unwrap();
+        assert_eq!(url, "postgres://u:p@db.internal/rdsadmin?sslmode=require");
+
+        assert!(matches!(
+            maintenance_target("postgres://u:p@localhost/", "postgres"),
+            Err(DatabaseError::DatabaseNameMissing)
+        ));
+    }
+
+    #[derive(Debug)]
+    struct ConstraintViolation(&'static str);
+
+    impl diesel::result::DatabaseErrorInformation for ConstraintViolation {
+        fn message(&self) -> &str {
+            "duplicate key value violates unique constraint"
+        }
+        fn details(&self) -> Option<&str> {
+            None
+        }
+        fn hint(&self) -> Option<&str> {
+            None
+        }
+        fn table_name(&self) -> Option<&str> {
+            None
+        }
+        fn column_name(&self) -> Option<&str> {
+            None
+        }
+        fn constraint_name(&self) -> Option<&str> {
+            Some(self.0)
+        }
+        fn statement_position(&self) -> Option<i32> {
+            None
+        }
+    }
+
+    fn unique_violation(constraint: &'static str) -> diesel::result::Error {
+        diesel::result::Error::DatabaseError(
+            DatabaseErrorKind::UniqueViolation,
+            Box::new(ConstraintViolation(constraint)),
+        )
+    }
+
+    #[test]
+    fn test_is_already_exists() {
+        assert!(is_already_exists(&unique_violation(
+            "pg_extension_name_index"
+        )));
+        assert!(is_already_exists(&unique_violation(
+            "pg_class_relname_nsp_index"
+        )));
+        assert!(!is_already_exists(&unique_violation("users_email_key")));
+        assert!(!is_already_exists(&db_error(
+            DatabaseErrorKind::UniqueViolation,
+            "Key (email)=(a@b.c) already exists"
+        )));
+        assert!(!is_already_exists(&db_error(
+            DatabaseErrorKind::Unknown,
+            "extension \"pg_trgm\" already exists"
+        )));
+        assert!(!is_already_exists(&diesel::result::Error::NotFound));
+    }
 }
@@ -7,6 +7,12 @@
 //!
 //! - [`LoggedJson`]: Logs deserialization errors and returns a generic error response
 //! - [`DetailedJson`]: Provides detailed, structured error responses for different error types
+//! - [`ValidatedJson`]: `DetailedJson` plus `validator::Validate`, failing with `AppError`'s 422 response
+//! - [`DetailedQuery`]: Query-string counterpart of `DetailedJson`, with the same error response shape
+//! - [`LimitedJson`]: `AppJson` with an explicit per-extractor body cap, answering 413 when exceeded
+//! - [`AppJson`]: Reports failures through `AppError`, i.e. the app-wide `{code, message, data}`
+//!   envelope with business codes. **Preferred** for new code; the extractors above keep their
+//!   ad-hoc `{error, message}` body for compatibility.
 //!
 //! # Usage
 //!
@@ -32,12 +38,19 @@
 
 use crate::axum::{
     Json,
-    extract::{FromRequest, Request, rejection::JsonRejection},
+    body::to_bytes,
+    extract::{
+        FromRequest, FromRequestParts, Query, Request,
+        rejection::{BytesRejection, FailedToBufferBody, JsonRejection, QueryRejection},
+    },
     http::StatusCode,
+    http::request::Parts,
     response::{IntoResponse, Response},
 };
-use crate::serde::de::DeserializeOwned;
+use crate::response::error::AppError;
+use crate::serde::{Serialize, de::DeserializeOwned};
 use crate::{serde_json, tracing};
+use validator::Validate;
 
 /// 自定义 JSON extractor，用于打印反序列化错误
 ///
@@ -125,7 +138,7 @@ where
 /// - `JsonDataError`: 422 Unprocessable Entity - 数据格式不匹配（如类型错误、缺失字段等）
 /// - `JsonSyntaxError`: 400 Bad Request - JSON 语法错误
 /// - `MissingJsonContentType`: 415 Unsupported Media Type - 缺少正确的 Content-Type 头
-/// - `BytesRejection`: 500 Internal Server Error - 无法读取请求体
+/// - `BytesRejection`: 413 Payload Too Large - 请求体超过 body limit；其他读取失败为 400 Bad Request
 /// - 其他错误: 400 Bad Request - 未知错误
 ///
 /// # Response Format
@@ -196,8 +209,15 @@ where
                         "missing_content_type",
                         format!("Missing Content-Type: application/json header: {}", err),
                     ),
+                    JsonRejection::BytesRejection(BytesRejection::FailedToBufferBody(
+                        FailedToBufferBody::LengthLimitError(err),
+                    )) => (
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "payload_too_large",
+                        format!("Request body too large: {}", err),
+                    ),
                     JsonRejection::BytesRejection(err) => (
-                        StatusCode::INTERNAL_SERVER_ERROR,
+                        StatusCode::BAD_REQUEST,
                         "bytes_rejection",
                         format!("Failed to read request body: {}", err),
                     ),
@@ -210,18 +230,574 @@ where
 
                 tracing::error!("JSON extraction failed [{}]: {}", error_type, message);
 
-                let response = (
-                    status,
-                    Json(serde_json::json!({
-                        "error": error_type,
-                        "message": message,
-                        "status": status.as_u16(),
-                    })),
-                )
-                    .into_response();
+                Err(detailed_error_response(status, error_type, message))
+            }
+        }
+    }
+}
 
-                Err(response)
+/// The `{ "error", "message", "status" }` body shared by `DetailedJson` and `DetailedQuery`.
+fn detailed_error_response(status: StatusCode, error_type: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": error_type,
+            "message": message,
+            "status": status.as_u16(),
+        })),
+    )
+        .into_response()
+}
+
+/// 自定义 JSON extractor，错误统一通过 `AppError` 返回（推荐）
+///
+/// `LoggedJson` / `DetailedJson` 返回 `{"error", "message"}` 格式，与应用其他地方使用的
+/// `AppError` 响应 `{"code", "message", "data"}` 不一致。`AppJson` 将 `JsonRejection`
+/// 转换为 `AppError`（见 `impl From<JsonRejection> for AppError`），保证全应用错误格式和业务码统一。
+///
+/// 同时实现了 `IntoResponse`，可以直接作为 handler 返回值，等同于 `Json<T>`。
+///
+/// # Response Format
+///
+/// ```json
+/// {
+///   "code": 410100,
+///   "message": "JSON syntax error: ..."
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use neocrates::axum::{Router, routing::post};
+/// use neocrates::helper::core::axum_extractor::AppJson;
+/// use neocrates::response::error::AppResult;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize, Serialize)]
+/// struct CreateUser {
+///     name: String,
+/// }
+///
+/// async fn create_user(AppJson(payload): AppJson<CreateUser>) -> AppResult<AppJson<CreateUser>> {
+///     Ok(AppJson(payload))
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// ```
+pub struct AppJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(AppJson(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for AppJson<T> {
+    fn into_response(self) -> Response {
+        Json(self.0).into_response()
+    }
+}
+
+/// 带显式大小上限的 JSON extractor
+///
+/// 与 `AppJson` 相同（错误通过 `AppError` 返回），但请求体上限由类型参数 `MAX`（字节）指定，
+/// 而不是依赖 axum 的默认 body limit。超过上限时返回 413 `AppError::PayloadTooLarge`，
+/// 与真正的服务端错误区分开：
+/// 1. `Content-Length` 声明超过 `MAX` 时直接拒绝，不读取请求体
+/// 2. 否则流式读取，累计超过 `MAX` 时立即停止（分块传输同样受限）
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use neocrates::axum::{Router, routing::post};
+/// use neocrates::helper::core::axum_extractor::LimitedJson;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Comment {
+///     body: String,
+/// }
+///
+/// // At most 16 KiB
+/// async fn create_comment(LimitedJson(comment): LimitedJson<Comment, 16_384>) -> String {
+///     comment.body
+/// }
+///
+/// let app = Router::new().route("/comments", post(create_comment));
+/// ```
+pub struct LimitedJson<T, const MAX: usize>(pub T);
+
+impl<S, T, const MAX: usize> FromRequest<S> for LimitedJson<T, MAX>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let too_large = || {
+            tracing::warn!("LimitedJson body exceeds {} bytes", MAX);
+            AppError::PayloadTooLarge(format!("request body exceeds {} bytes", MAX))
+        };
+        let declared_len = req
+            .headers()
+            .get(crate::axum::http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<usize>().ok());
+        if declared_len.is_some_and(|len| len > MAX) {
+            return Err(too_large());
+        }
+
+        if !has_json_content_type(&req) {
+            return Err(JsonRejection::MissingJsonContentType(Default::default()).into());
+        }
+
+        // Buffer here rather than through `Json::from_request`, so `MAX` replaces axum's
+        // `DefaultBodyLimit` instead of only ever lowering it.
+        let buf = to_bytes(req.into_body(), MAX).await.map_err(|e| {
+            let e = e.into_inner();
+            if e.is::<http_body_util::LengthLimitError>() {
+                too_large()
+            } else {
+                AppError::ClientError(format!("Failed to read request body: {}", e))
+            }
+        })?;
+        let Json(value) = Json::<T>::from_bytes(&buf)?;
+        Ok(LimitedJson(value))
+    }
+}
+
+/// `application/json` or an `application/*+json` type, as axum's `Json` requires.
+fn has_json_content_type(req: &Request) -> bool {
+    req.headers()
+        .get(crate::axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase())
+        .is_some_and(|mime| {
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+}
+
+/// 自定义 JSON extractor，反序列化后自动执行 `validator` 校验
+///
+/// 反序列化阶段与 `DetailedJson` 完全一致（相同的错误类型、状态码和响应格式）；
+/// 反序列化成功后调用 `T::validate()`，校验失败时将 `ValidationErrors` 转换为
+/// `AppError::FieldValidation`，返回 422 以及逐字段的错误列表，handler 中无需再手动 `.validate()?`。
+///
+/// # Response Format
+///
+/// 校验失败时返回 `AppError` 的统一格式：
+/// ```json
+/// {
+///   "code": 400001,
+///   "message": "Parameter validation failed: email: invalid email",
+///   "data": [{ "field": "email", "code": "email", "message": "invalid email" }]
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use neocrates::axum::{Router, routing::post};
+/// use neocrates::helper::core::axum_extractor::ValidatedJson;
+/// use serde::Deserialize;
+/// use validator::Validate;
+///
+/// #[derive(Deserialize, Validate)]
+/// struct CreateUser {
+///     #[validate(length(min = 1, max = 32))]
+///     name: String,
+///     #[validate(email(message = "invalid email"))]
+///     email: String,
+/// }
+///
+/// async fn create_user(ValidatedJson(payload): ValidatedJson<CreateUser>) -> String {
+///     format!("Created user: {}", payload.name)
+/// }
+///
+/// let app = Router::new().route("/users", post(create_user));
+/// ```
+pub struct ValidatedJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let DetailedJson(value) = DetailedJson::<T>::from_request(req, state).await?;
+        if let Err(errors) = value.validate() {
+            tracing::warn!("JSON validation failed: {}", errors);
+            return Err(AppError::from(errors).into_response());
+        }
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// 自定义 Query extractor，错误处理与 `DetailedJson` 一致
+///
+/// 包装 `axum::extract::Query`，反序列化失败时按错误类型分类、通过 `tracing` 记录日志，
+/// 并返回与 `DetailedJson` 相同结构的 JSON 响应，便于前端统一处理 query 与 body 的错误。
+///
+/// # Error Types and Status Codes
+///
+/// - `query_missing_field`: 400 Bad Request - 缺少必填参数
+/// - `query_invalid_value`: 400 Bad Request - 参数类型或取值不合法（如 `size=abc`）
+/// - `query_deserialize_error`: 400 Bad Request - 其他反序列化错误
+///
+/// # Response Format
+///
+/// ```json
+/// {
+///   "error": "query_invalid_value",
+///   "message": "Invalid query parameter: invalid digit found in string",
+///   "status": 400
+/// }
+/// ```
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use neocrates::axum::{Router, routing::get};
+/// use neocrates::helper::core::axum_extractor::DetailedQuery;
+/// use neocrates::helper::core::serde_helpers::{
+///     normalize_current, normalize_order, normalize_page_size,
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct ListUsers {
+///     #[serde(default, deserialize_with = "normalize_current")]
+///     current: Option<i64>,
+///     #[serde(default, deserialize_with = "normalize_page_size")]
+///     size: Option<i64>,
+///     #[serde(default, deserialize_with = "normalize_order")]
+///     order: Option<String>,
+/// }
+///
+/// async fn list_users(DetailedQuery(query): DetailedQuery<ListUsers>) -> String {
+///     format!("page {:?} size {:?}", query.current, query.size)
+/// }
+///
+/// let app = Router::new().route("/users", get(list_users));
+/// ```
+pub struct DetailedQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for DetailedQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Query::<T>::from_request_parts(parts, state).await {
+            Ok(Query(value)) => Ok(DetailedQuery(value)),
+            Err(rejection) => {
+                let (error_type, message) = classify_query_rejection(&rejection);
+                tracing::error!("Query extraction failed [{}]: {}", error_type, message);
+                Err(detailed_error_response(
+                    rejection.status(),
+                    error_type,
+                    message,
+                ))
+            }
+        }
+    }
+}
+
+/// Map a query rejection to an error type and message.
+///
+/// serde_urlencoded only reports errors as text, so the type is derived from the message.
+fn classify_query_rejection(rejection: &QueryRejection) -> (&'static str, String) {
+    let detail = match rejection {
+        QueryRejection::FailedToDeserializeQueryString(err) => err.body_text(),
+        _ => rejection.body_text(),
+    };
+    // Strip axum's "Failed to deserialize query string: " prefix
+    let detail = detail
+        .split_once(": ")
+        .map(|(_, rest)| rest.to_string())
+        .unwrap_or(detail);
+    if detail.contains("missing field") {
+        (
+            "query_missing_field",
+            format!("Missing query parameter: {}", detail),
+        )
+    } else if detail.contains("invalid") || detail.contains("unknown variant") {
+        (
+            "query_invalid_value",
+            format!("Invalid query parameter: {}", detail),
+        )
+    } else {
+        (
+            "query_deserialize_error",
+            format!("Failed to deserialize query string: {}", detail),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::axum::body::{Body, Bytes};
+    use serde::Deserialize;
+    use validator::{ValidationError, ValidationErrors};
+
+    #[derive(Deserialize)]
+    struct CreateUser {
+        name: String,
+    }
+
+    impl Validate for CreateUser {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::new();
+            if self.name.is_empty() {
+                errors.add(
+                    "name",
+                    ValidationError::new("length").with_message("name is required".into()),
+                );
+            }
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
             }
         }
     }
+
+    fn json_request(body: &'static str) -> Request {
+        Request::builder()
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = crate::axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_accepts_valid_payload() {
+        let ValidatedJson(user) =
+            ValidatedJson::<CreateUser>::from_request(json_request(r#"{"name":"neo"}"#), &())
+                .await
+                .ok()
+                .unwrap();
+        assert_eq!(user.name, "neo");
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_rejects_invalid_payload_with_field_errors() {
+        let response =
+            ValidatedJson::<CreateUser>::from_request(json_request(r#"{"name":""}"#), &())
+                .await
+                .err()
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let json = body_json(response).await;
+        assert_eq!(json["data"][0]["field"], "name");
+        assert_eq!(json["data"][0]["message"], "name is required");
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ListQuery {
+        #[serde(
+            default,
+            deserialize_with = "crate::helper::core::serde_helpers::normalize_current"
+        )]
+        current: Option<i64>,
+        #[serde(
+            default,
+            deserialize_with = "crate::helper::core::serde_helpers::normalize_page_size"
+        )]
+        size: Option<i64>,
+        keyword: String,
+    }
+
+    async fn extract_query(uri: &str) -> Result<ListQuery, Response> {
+        let (mut parts, _) = Request::builder()
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+            .into_parts();
+        DetailedQuery::<ListQuery>::from_request_parts(&mut parts, &())
+            .await
+            .map(|DetailedQuery(query)| query)
+    }
+
+    #[tokio::test]
+    async fn test_detailed_query_applies_normalizers() {
+        let query = extract_query("/users?current=0&size=7&keyword=neo")
+            .await
+            .unwrap();
+        assert_eq!(query.current, Some(1));
+        assert_eq!(
+            query.size,
+            Some(crate::helper::core::serde_helpers::DEFAULT_PAGE_SIZE)
+        );
+        assert_eq!(query.keyword, "neo");
+    }
+
+    #[tokio::test]
+    async fn test_detailed_query_errors_use_detailed_shape() {
+        let response = extract_query("/users?current=1").await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["error"], "query_missing_field");
+        assert_eq!(json["status"], 400);
+        assert!(json["message"].as_str().unwrap().contains("keyword"));
+
+        let response = extract_query("/users?current=abc&keyword=neo")
+            .await
+            .unwrap_err();
+        assert_eq!(body_json(response).await["error"], "query_invalid_value");
+    }
+
+    #[tokio::test]
+    async fn test_app_json_errors_use_app_error_envelope() {
+        let response = AppJson::<CreateUser>::from_request(json_request("{"), &())
+            .await
+            .err()
+            .unwrap()
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let json = body_json(response).await;
+        assert_eq!(json["code"], AppError::BIZ_JSON_ERROR);
+        assert!(
+            json["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("JSON syntax error")
+        );
+        assert!(json.get("error").is_none());
+
+        let response = AppJson::<CreateUser>::from_request(json_request(r#"{"nick":"x"}"#), &())
+            .await
+            .err()
+            .unwrap()
+            .into_response();
+        assert_eq!(body_json(response).await["code"], 400001);
+
+        let AppJson(user) =
+            AppJson::<CreateUser>::from_request(json_request(r#"{"name":"neo"}"#), &())
+                .await
+                .ok()
+                .unwrap();
+        assert_eq!(user.name, "neo");
+    }
+
+    #[tokio::test]
+    async fn test_limited_json_rejects_oversized_body_with_413() {
+        let body = r#"{"name":"a name that is clearly longer than sixteen bytes"}"#;
+        let err = LimitedJson::<CreateUser, 16>::from_request(json_request(body), &())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::PayloadTooLarge(_)));
+        assert_eq!(err.into_response().status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Chunked body without Content-Length is capped while streaming
+        let chunked = Request::builder()
+            .method("POST")
+            .header("content-type", "application/json")
+            .body(Body::from_stream(futures::stream::iter(vec![
+                Ok::<_, std::io::Error>(Bytes::from_static(br#"{"name":"#)),
+                Ok(Bytes::from_static(br#""0123456789abcdef"}"#)),
+            ])))
+            .unwrap();
+        let err = LimitedJson::<CreateUser, 16>::from_request(chunked, &())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, AppError::PayloadTooLarge(_)));
+
+        let LimitedJson(user) =
+            LimitedJson::<CreateUser, 64>::from_request(json_request(body), &())
+                .await
+                .ok()
+                .unwrap();
+        assert!(user.name.starts_with("a name"));
+    }
+
+    #[tokio::test]
+    async fn test_limited_json_max_overrides_default_body_limit() {
+        use crate::axum::{Router, extract::DefaultBodyLimit, routing::post};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/users",
+                post(|LimitedJson(user): LimitedJson<CreateUser, 64>| async move { user.name }),
+            )
+            .layer(DefaultBodyLimit::max(8));
+        let request = Request::builder()
+            .method("POST")
+            .uri("/users")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"longer than eight"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let err = LimitedJson::<CreateUser, 64>::from_request(
+            Request::builder()
+                .method("POST")
+                .body(Body::from(r#"{"name":"neo"}"#))
+                .unwrap(),
+            &(),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(matches!(err, AppError::ValidationError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_detailed_json_maps_body_limit_to_413() {
+        use crate::axum::{Router, extract::DefaultBodyLimit, routing::post};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/users",
+                post(|DetailedJson(user): DetailedJson<CreateUser>| async move { user.name }),
+            )
+            .layer(DefaultBodyLimit::max(8));
+        let request = Request::builder()
+            .method("POST")
+            .uri("/users")
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"name":"longer than eight"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body_json(response).await["error"], "payload_too_large");
+    }
+
+    #[tokio::test]
+    async fn test_validated_json_keeps_detailed_deserialize_errors() {
+        let response = ValidatedJson::<CreateUser>::from_request(json_request("{"), &())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body_json(response).await["error"], "json_syntax_error");
+    }
 }
@@ -0,0 +1,79 @@
+//! Checked integer conversions.
+//!
+//! `as` casts between signed and unsigned integers silently wrap: a negative TTL becomes a
+//! huge `u64`, an oversized `u64` count becomes negative. These helpers return an error
+//! instead, so the caller decides how to surface it.
+//!
+//! ```rust,ignore
+//! use neocrates::helper::core::cast::{to_i64, to_u64};
+//!
+//! let seconds = to_i64(ttl_secs)?;     // u64 -> i64
+//! total += to_u64(deleted)?;           // i64 -> u64
+//! ```
+
+use thiserror::Error;
+
+/// Value did not fit into the target integer type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("integer {value} is out of range for {target}")]
+pub struct CastError {
+    /// The original value, widened to `i128` so any source type fits.
+    pub value: i128,
+    /// Name of the target type, e.g. `"u64"`.
+    pub target: &'static str,
+}
+
+/// Convert `i64` to `u64`, failing on negative values.
+pub fn to_u64(value: i64) -> Result<u64, CastError> {
+    u64::try_from(value).map_err(|_| CastError {
+        value: value as i128,
+        target: "u64",
+    })
+}
+
+/// Convert `u64` to `i64`, failing on values above `i64::MAX`.
+pub fn to_i64(value: u64) -> Result<i64, CastError> {
+    i64::try_from(value).map_err(|_| CastError {
+        value: value as i128,
+        target: "i64",
+    })
+}
+
+/// Convert `i64` to `usize`, failing on negative values (or values above `usize::MAX`
+/// on 32-bit targets).
+pub fn to_usize(value: i64) -> Result<usize, CastError> {
+    usize::try_from(value).map_err(|_| CastError {
+        value: value as i128,
+        target: "usize",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_range_values_convert() {
+        assert_eq!(to_u64(0), Ok(0));
+        assert_eq!(to_u64(i64::MAX), Ok(i64::MAX as u64));
+        assert_eq!(to_i64(i64::MAX as u64), Ok(i64::MAX));
+        assert_eq!(to_usize(42), Ok(42));
+    }
+
+    #[test]
+    fn test_out_of_range_values_fail() {
+        assert_eq!(
+            to_u64(-1),
+            Err(CastError {
+                value: -1,
+                target: "u64"
+            })
+        );
+        assert!(to_i64(u64::MAX).is_err());
+        assert!(to_usize(-5).is_err());
+        assert_eq!(
+            to_i64(u64::MAX).unwrap_err().to_string(),
+            "integer 18446744073709551615 is out of range for i64"
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Circuit breaker for calls to external services.
+//!
+//! When a downstream keeps failing, retrying every request only adds load and latency. A
+//! [`CircuitBreaker`] counts consecutive failures; past `failure_threshold` it *opens* and
+//! rejects calls immediately with [`CircuitError::CircuitOpen`] for `cool_down`. It then
+//! lets a few trial calls through (*half-open*): if they succeed it *closes* again, if one
+//! fails it re-opens for another cool-down.
+//!
+//! ```rust,ignore
+//! use neocrates::helper::core::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+//!
+//! static SMS: Lazy<CircuitBreaker> =
+//!     Lazy::new(|| CircuitBreaker::new("sms", CircuitBreakerConfig::default()));
+//!
+//! // AppError counts as a failure when `is_retryable()` (5xx-like errors), so a 404 or a
+//! // validation error from the downstream does not open the circuit.
+//! let receipt = SMS.call(send_sms(&mobile, &code)).await?;
+//! ```
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// Errors that can tell whether they mean the downstream is unhealthy.
+pub trait CircuitFailure {
+    /// `true` if this error should count towards opening the circuit.
+    fn trips_circuit(&self) -> bool;
+}
+
+#[cfg(any(feature = "web", feature = "full"))]
+impl CircuitFailure for crate::response::error::AppError {
+    /// Retryable errors (database, Redis, MQ, external, internal) trip the circuit; client
+    /// errors such as `NotFound` or `ValidationError` do not.
+    fn trips_circuit(&self) -> bool {
+        self.is_retryable()
+    }
+}
+
+/// Configuration for a [`CircuitBreaker`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit (at least 1).
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before allowing trial calls.
+    pub cool_down: Duration,
+    /// Trial calls allowed while half-open; this many successes close the circuit
+    /// (at least 1).
+    pub half_open_max_calls: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+            half_open_max_calls: 1,
+        }
+    }
+}
+
+/// Observable state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls pass through; failures are counted.
+    Closed,
+    /// Calls are rejected until the cool-down has elapsed.
+    Open,
+    /// A limited number of trial calls decide whether to close or re-open.
+    HalfOpen,
+}
+
+/// Error returned by [`CircuitBreaker::call`].
+#[derive(Debug, Error)]
+pub enum CircuitError<E> {
+    /// The call was not attempted because the circuit is open (or all half-open trial
+    /// slots are taken).
+    #[error("circuit '{name}' is open, retry in {retry_after:?}")]
+    CircuitOpen { name: String, retry_after: Duration },
+    /// The call ran and failed.
+    #[error("{0}")]
+    Failed(E),
+}
+
+impl<E> CircuitError<E> {
+    /// The operation's own error, or `None` if the call was short-circuited.
+    pub fn into_inner(self) -> Option<E> {
+        match self {
+            CircuitError::CircuitOpen { .. } => None,
+            CircuitError::Failed(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(any(feature = "web", feature = "full"))]
+impl From<CircuitError<crate::response::error::AppError>> for crate::response::error::AppError {
+    fn from(err: CircuitError<crate::response::error::AppError>) -> Self {
+        match err {
+            CircuitError::CircuitOpen { retry_after, .. } => {
+                crate::response::error::AppError::ServiceUnavailable {
+                    msg: err.to_string(),
+                    retry_after: Some(retry_after),
+                }
+            }
+            CircuitError::Failed(err) => err,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    /// Bumped on every transition, so results of calls admitted under an earlier state
+    /// are ignored.
+    generation: u64,
+}
+
+/// A closed/open/half-open circuit breaker; share it (e.g. in a `static` or `Arc`) between
+/// all callers of one downstream.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config: CircuitBreakerConfig {
+                failure_threshold: config.failure_threshold.max(1),
+                half_open_max_calls: config.half_open_max_calls.max(1),
+                ..config
+            },
+            inner: Mutex::new(Inner {
+                state: State::Closed { failures: 0 },
+                generation: 0,
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Current state; an open circuit whose cool-down has elapsed reports `HalfOpen`.
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        match inner.state {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { until } if Instant::now() < until => CircuitState::Open,
+            State::Open { .. } | State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Run `op` through the breaker; errors count as failures per [`CircuitFailure`].
+    pub async fn call<T, E, F>(&self, op: F) -> Result<T, CircuitError<E>>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: CircuitFailure,
+    {
+        self.call_with(E::trips_circuit, op).await
+    }
+
+    /// Like [`Self::call`], with `is_failure` deciding which errors count as failures.
+    /// Errors that do not count are returned as-is and treated as a healthy response.
+    pub async fn call_with<T, E, F, P>(&self, is_failure: P, op: F) -> Result<T, CircuitError<E>>
+    where
+        F: Future<Output = Result<T, E>>,
+        P: FnOnce(&E) -> bool,
+    {
+        let mut permit = self.acquire()?;
+        let result = op.await;
+        let failed = match &result {
+            Ok(_) => false,
+            Err(err) => is_failure(err),
+        };
+        permit.finish(failed);
+        result.map_err(CircuitError::Failed)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn acquire<E>(&self) -> Result<Permit<'_>, CircuitError<E>> {
+        let mut inner = self.lock();
+        let now = Instant::now();
+        if let State::Open { until } = inner.state {
+            if now < until {
+                return Err(self.open_error(until - now));
+            }
+            self.transition(
+                &mut inner,
+                State::HalfOpen {
+                    in_flight: 0,
+                    successes: 0,
+                },
+            );
+        }
+        let trial = match &mut inner.state {
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } => {
+                if *in_flight + *successes >= self.config.half_open_max_calls {
+                    return Err(self.open_error(Duration::ZERO));
+                }
+                *in_flight += 1;
+                true
+            }
+            _ => false,
+        };
+        Ok(Permit {
+            breaker: self,
+            generation: inner.generation,
+            trial,
+            finished: false,
+        })
+    }
+
+    fn open_error<E>(&self, retry_after: Duration) -> CircuitError<E> {
+        CircuitError::CircuitOpen {
+            name: self.name.clone(),
+            retry_after,
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, state: State) {
+        match state {
+            State::Open { .. } => tracing::warn!(
+                "circuit breaker opened: name={} cool_down_ms={}",
+                self.name,
+                self.config.cool_down.as_millis()
+            ),
+            State::HalfOpen { .. } => {
+                tracing::info!("circuit breaker half-open: name={}", self.name)
+            }
+            State::Closed { .. } => tracing::info!("circuit breaker closed: name={}", self.name),
+        }
+        inner.state = state;
+        inner.generation += 1;
+    }
+
+    fn record(&self, generation: u64, outcome: Outcome) {
+        let mut inner = self.lock();
+        if inner.generation != generation {
+            return;
+        }
+        let open = State::Open {
+            until: Instant::now() + self.config.cool_down,
+        };
+        match (&mut inner.state, outcome) {
+            (State::Closed { failures }, Outcome::Success) => *failures = 0,
+            (State::Closed { failures }, Outcome::Failure) => {
+                *failures += 1;
+                if *failures >= self.config.failure_threshold {
+                    self.transition(&mut inner, open);
+                }
+            }
+            (
+                State::HalfOpen {
+                    in_flight,
+                    successes,
+                },
+                Outcome::Success,
+            ) => {
+                *in_flight -= 1;
+                *successes += 1;
+                if *successes >= self.config.half_open_max_calls {
+                    self.transition(&mut inner, State::Closed { failures: 0 });
+                }
+            }
+            (State::HalfOpen { .. }, Outcome::Failure) => self.transition(&mut inner, open),
+            (State::HalfOpen { in_flight, .. }, Outcome::Cancelled) => *in_flight -= 1,
+            (State::Closed { .. }, Outcome::Cancelled) | (State::Open { .. }, _) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Outcome {
+    Success,
+    Failure,
+    /// The caller dropped the future before it completed.
+    Cancelled,
+}
+
+/// An admitted call; frees its half-open trial slot even if the call is cancelled.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    generation: u64,
+    trial: bool,
+    finished: bool,
+}
+
+impl Permit<'_> {
+    fn finish(&mut self, failed: bool) {
+        self.finished = true;
+        let outcome = if failed {
+            Outcome::Failure
+        } else {
+            Outcome::Success
+        };
+        self.breaker.record(self.generation, outcome);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if !self.finished && self.trial {
+            self.breaker.record(self.generation, Outcome::Cancelled);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Down,
+        NotFound,
+    }
+
+    impl CircuitFailure for TestError {
+        fn trips_circuit(&self) -> bool {
+            matches!(self, TestError::Down)
+        }
+    }
+
+    fn breaker(threshold: u32, cool_down_ms: u64) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "test",
+            CircuitBreakerConfig {
+                failure_threshold: threshold,
+                cool_down: Duration::from_millis(cool_down_ms),
+                half_open_max_calls: 1,
+            },
+        )
+    }
+
+    async fn fail(cb: &CircuitBreaker) -> Result<(), CircuitError<TestError>> {
+        cb.call(async { Err::<(), _>(TestError::Down) }).await
+    }
+
+    async fn succeed(cb: &CircuitBreaker) -> Result<u32, CircuitError<TestError>> {
+        cb.call(async { Ok::<_, TestError>(7) }).await
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_threshold_and_short_circuits() {
+        let cb = breaker(3, 10_000);
+        for _ in 0..3 {
+            assert!(matches!(
+                fail(&cb).await,
+                Err(CircuitError::Failed(TestError::Down))
+            ));
+        }
+        assert_eq!(cb.state(), CircuitState::Open);
+
+        let ran = std::sync::atomic::AtomicBool::new(false);
+        let result = cb
+            .call(async {
+                ran.store(true, std::sync::atomic::Ordering::SeqCst);
+                Ok::<_, TestError>(())
+            })
+            .await;
+        match result {
+            Err(CircuitError::CircuitOpen { name, retry_after }) => {
+                assert_eq!(name, "test");
+                assert!(retry_after > Duration::from_secs(9));
+            }
+            other => panic!("expected CircuitOpen, got {:?}", other),
+        }
+        assert!(!ran.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_success_resets_failure_count() {
+        let cb = breaker(2, 10_000);
+        fail(&cb).await.unwrap_err();
+        succeed(&cb).await.unwrap();
+        fail(&cb).await.unwrap_err();
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_non_tripping_errors_do_not_open() {
+        let cb = breaker(1, 10_000);
+        for _ in 0..5 {
+            let result = cb.call(async { Err::<(), _>(TestError::NotFound) }).await;
+            assert_eq!(result.unwrap_err().into_inner(), Some(TestError::NotFound));
+        }
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_recovers_open_half_open_closed() {
+        let cb = breaker(1, 50);
+        fail(&cb).await.unwrap_err();
+        assert_eq!(cb.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(70)).await;
+        assert_eq!(cb.state(), CircuitState::HalfOpen);
+        assert_eq!(succeed(&cb).await.unwrap(), 7);
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_failure_reopens() {
+        let cb = breaker(1, 50);
+        fail(&cb).await.unwrap_err();
+        tokio::time::sleep(Duration::from_millis(70)).await;
+
+        fail(&cb).await.unwrap_err();
+        assert_eq!(cb.state(), CircuitState::Open);
+        assert!(matches!(
+            succeed(&cb).await,
+            Err(CircuitError::CircuitOpen { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_half_open_admits_limited_trials() {
+        let cb = breaker(1, 20);
+        fail(&cb).await.unwrap_err();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let (release, wait) = tokio::sync::oneshot::channel::<()>();
+        let trial = cb.call(async {
+            wait.await.ok();
+            Ok::<_, TestError>(1)
+        });
+        let concurrent = async {
+            // The trial above holds the only half-open slot.
+            let rejected = succeed(&cb).await;
+            release.send(()).unwrap();
+            rejected
+        };
+        let (trial, rejected) = tokio::join!(trial, concurrent);
+        assert_eq!(trial.unwrap(), 1);
+        assert!(matches!(rejected, Err(CircuitError::CircuitOpen { .. })));
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_trial_frees_its_slot() {
+        let cb = breaker(1, 20);
+        fail(&cb).await.unwrap_err();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let pending = cb.call(std::future::pending::<Result<(), TestError>>());
+        let timed_out = tokio::time::timeout(Duration::from_millis(10), pending).await;
+        assert!(timed_out.is_err());
+
+        assert_eq!(succeed(&cb).await.unwrap(), 7);
+        assert_eq!(cb.state(), CircuitState::Closed);
+    }
+
+    #[cfg(any(feature = "web", feature = "full"))]
+    #[tokio::test]
+    async fn test_app_error_classification() {
+        use crate::response::error::AppError;
+
+        let cb = breaker(1, 10_000);
+        let result = cb
+            .call(async { Err::<(), _>(AppError::NotFound("user".into())) })
+            .await;
+        assert!(matches!(
+            result,
+            Err(CircuitError::Failed(AppError::NotFound(_)))
+        ));
+        assert_eq!(cb.state(), CircuitState::Closed);
+
+        let result = cb
+            .call(async { Err::<(), _>(AppError::ExternalError("timeout".into())) })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(cb.state(), CircuitState::Open);
+
+        let err: AppError = cb
+            .call(async { Ok::<_, AppError>(()) })
+            .await
+            .unwrap_err()
+            .into();
+        assert!(matches!(
+            err,
+            AppError::ServiceUnavailable {
+                retry_after: Some(_),
+                ..
+            }
+        ));
+
+        let response = axum::response::IntoResponse::into_response(err);
+        assert_eq!(
+            response.status(),
+            axum::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        let retry_after: u64 = response.headers()[axum::http::header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(retry_after, 10);
+    }
+}
@@ -1,32 +1,475 @@
+//! Bounded pool of reusable "engines" (headless browser handles, template engines, parser
+//! instances, ...): anything expensive to create that is not a database connection.
+//!
+//! [`ResourcePool`] is a lightweight `deadpool`: engines are created lazily by an async
+//! factory up to `max_size`, handed out behind an [`ResourceGuard`] that returns them on drop,
+//! and dropped after sitting idle for `idle_timeout`. When every engine is in use,
+//! [`ResourcePool::acquire`] waits (up to `acquire_timeout`) for one to be released.
+//!
+//! ```rust,ignore
+//! use neocrates::helper::core::engine_pool::{ResourcePool, ResourcePoolConfig};
+//!
+//! let pool = ResourcePool::new(ResourcePoolConfig { max_size: 4, ..Default::default() }, || async {
+//!     Browser::launch().await
+//! });
+//!
+//! let browser = pool.acquire().await?; // waits while 4 are in use
+//! let pdf = browser.print_to_pdf(url).await?;
+//! // `browser` goes back to the pool here; call `browser.discard()` if it is broken.
+//! ```
+//!
+//! For spreading requests over a fixed list of endpoints, see [`RoundRobin`] (formerly the
+//! URL-only [`EnginePool`], kept as a deprecated alias).
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-pub struct EnginePool {
-    urls: Vec<String>,
+use futures::future::BoxFuture;
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type Factory<T> = Box<dyn Fn() -> BoxFuture<'static, Result<T, BoxError>> + Send + Sync>;
+
+/// Configuration for an [`ResourcePool`].
+#[derive(Debug, Clone)]
+pub struct ResourcePoolConfig {
+    /// Maximum number of engines, in use and idle together (at least 1).
+    pub max_size: usize,
+    /// Idle engines older than this are dropped instead of reused. `None` keeps them forever.
+    pub idle_timeout: Option<Duration>,
+    /// How long [`ResourcePool::acquire`] waits for a free slot. `None` waits indefinitely.
+    pub acquire_timeout: Option<Duration>,
+}
+
+impl Default for ResourcePoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 8,
+            idle_timeout: Some(Duration::from_secs(300)),
+            acquire_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// Error returned by [`ResourcePool::acquire`].
+#[derive(Debug, Error)]
+pub enum ResourcePoolError {
+    /// No engine was released within `acquire_timeout`.
+    #[error("timed out after {0:?} waiting for an engine")]
+    Timeout(Duration),
+    /// The factory failed to create a new engine.
+    #[error("failed to create engine: {0}")]
+    Create(#[source] BoxError),
+    /// The pool was closed with [`ResourcePool::close`].
+    #[error("engine pool is closed")]
+    Closed,
+}
+
+/// Point-in-time pool metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourcePoolStatus {
+    pub max_size: usize,
+    /// Engines currently checked out.
+    pub in_use: usize,
+    /// Engines waiting in the pool.
+    pub idle: usize,
+}
+
+/// Bounded, lazily filled pool of `T`. Cheap to clone; clones share the same engines.
+pub struct ResourcePool<T> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for ResourcePool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+struct Inner<T> {
+    config: ResourcePoolConfig,
+    factory: Factory<T>,
+    /// One permit per engine that may be checked out; held by every [`ResourceGuard`].
+    slots: Arc<Semaphore>,
+    /// Most recently returned engines at the back.
+    idle: Mutex<VecDeque<IdleEngine<T>>>,
+    in_use: AtomicUsize,
+}
+
+struct IdleEngine<T> {
+    engine: T,
+    returned_at: Instant,
+}
+
+impl<T: Send + 'static> ResourcePool<T> {
+    /// Create an empty pool; engines are built by `factory` on demand.
+    pub fn new<F, Fut, E>(config: ResourcePoolConfig, factory: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        let config = ResourcePoolConfig {
+            max_size: config.max_size.max(1),
+            ..config
+        };
+        let factory: Factory<T> = Box::new(move || {
+            let fut = factory();
+            Box::pin(async move { fut.await.map_err(Into::into) })
+        });
+        Self {
+            inner: Arc::new(Inner {
+                slots: Arc::new(Semaphore::new(config.max_size)),
+                config,
+                factory,
+                idle: Mutex::new(VecDeque::new()),
+                in_use: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Check out an engine: the most recently used idle one, else a new one from the
+    /// factory. Waits while `max_size` engines are in use.
+    pub async fn acquire(&self) -> Result<ResourceGuard<T>, ResourcePoolError> {
+        let slots = self.inner.slots.clone();
+        let permit = match self.inner.config.acquire_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, slots.acquire_owned())
+                .await
+                .map_err(|_| ResourcePoolError::Timeout(timeout))?,
+            None => slots.acquire_owned().await,
+        }
+        .map_err(|_| ResourcePoolError::Closed)?;
+
+        self.evict_idle();
+        let reused = self.lock_idle().pop_back().map(|idle| idle.engine);
+        let engine = match reused {
+            Some(engine) => engine,
+            // On error the permit is dropped here, freeing the slot for the next caller.
+            None => (self.inner.factory)()
+                .await
+                .map_err(ResourcePoolError::Create)?,
+        };
+
+        self.inner.in_use.fetch_add(1, Ordering::Relaxed);
+        Ok(ResourceGuard {
+            engine: Some(engine),
+            pool: self.inner.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Drop idle engines older than `idle_timeout` and return how many were dropped.
+    ///
+    /// `acquire` already does this lazily; call it periodically to free engines of a pool
+    /// that has gone quiet.
+    pub fn evict_idle(&self) -> usize {
+        let Some(idle_timeout) = self.inner.config.idle_timeout else {
+            return 0;
+        };
+        let expired: Vec<T> = {
+            let mut idle = self.lock_idle();
+            // Oldest first: stop at the first engine that is still fresh.
+            let fresh_from = idle
+                .iter()
+                .position(|e| e.returned_at.elapsed() < idle_timeout)
+                .unwrap_or(idle.len());
+            idle.drain(..fresh_from).map(|e| e.engine).collect()
+        };
+        // Dropped outside the lock, in case dropping an engine is slow.
+        expired.len()
+    }
+
+    /// Current in-use / idle counts.
+    pub fn status(&self) -> ResourcePoolStatus {
+        ResourcePoolStatus {
+            max_size: self.inner.config.max_size,
+            in_use: self.inner.in_use.load(Ordering::Relaxed),
+            idle: self.lock_idle().len(),
+        }
+    }
+
+    /// Drop every idle engine and make pending and future `acquire` calls fail with
+    /// [`ResourcePoolError::Closed`]. Engines still checked out are dropped when released.
+    pub fn close(&self) {
+        self.inner.slots.close();
+        let drained: Vec<IdleEngine<T>> = self.lock_idle().drain(..).collect();
+        drop(drained);
+    }
+
+    fn lock_idle(&self) -> std::sync::MutexGuard<'_, VecDeque<IdleEngine<T>>> {
+        self.inner.lock_idle()
+    }
+}
+
+impl<T> Inner<T> {
+    fn lock_idle(&self) -> std::sync::MutexGuard<'_, VecDeque<IdleEngine<T>>> {
+        // The lock is never held across user code, so a poisoned queue is still consistent.
+        self.idle.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An engine checked out of an [`ResourcePool`]; returned to the pool when dropped.
+pub struct ResourceGuard<T> {
+    engine: Option<T>,
+    pool: Arc<Inner<T>>,
+    // Released after `drop` has put the engine back, so a waiter finds it idle.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl<T> ResourceGuard<T> {
+    /// Return the engine to the pool now (same as dropping the guard).
+    pub fn release(self) {}
+
+    /// Drop the engine instead of returning it, e.g. after it crashed. The pool creates a
+    /// fresh one on a later `acquire`.
+    pub fn discard(mut self) {
+        self.engine = None;
+    }
+}
+
+impl<T> Deref for ResourceGuard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.engine.as_ref().expect("engine present until drop")
+    }
+}
+
+impl<T> DerefMut for ResourceGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.engine.as_mut().expect("engine present until drop")
+    }
+}
+
+impl<T> Drop for ResourceGuard<T> {
+    fn drop(&mut self) {
+        self.pool.in_use.fetch_sub(1, Ordering::Relaxed);
+        let Some(engine) = self.engine.take() else {
+            return;
+        };
+        if self.pool.slots.is_closed() {
+            return;
+        }
+        self.pool.lock_idle().push_back(IdleEngine {
+            engine,
+            returned_at: Instant::now(),
+        });
+    }
+}
+
+/// Hands out items of a fixed list in turn, e.g. to spread requests over engine URLs.
+///
+/// ```rust,ignore
+/// let engines = RoundRobin::new(vec!["http://a".to_string(), "http://b".to_string()]);
+/// let url = engines.pick(); // Some("http://a"), then "http://b", then "http://a", ...
+/// ```
+pub struct RoundRobin<T> {
+    items: Vec<T>,
     idx: AtomicUsize,
 }
 
-impl EnginePool {
-    pub fn new(urls: Vec<String>) -> Self {
+impl<T: Clone> RoundRobin<T> {
+    pub fn new(items: Vec<T>) -> Self {
         Self {
-            urls,
+            items,
             idx: AtomicUsize::new(0),
         }
     }
 
-    pub fn next_url(&self) -> Option<String> {
-        let len = self.urls.len();
+    /// The next item, or `None` if the list is empty.
+    pub fn pick(&self) -> Option<T> {
+        let len = self.items.len();
         if len == 0 {
             return None;
         }
         let i = self.idx.fetch_add(1, Ordering::Relaxed) % len;
-        Some(self.urls[i].clone())
+        Some(self.items[i].clone())
     }
 }
 
-// let pool = Arc::new(EnginePool::new(vec![
-//     "http://a".to_string(),
-//     "http://b".to_string(),
-//     "http://c".to_string(),
-// ]));
+/// The original URL round-robin, kept so `EnginePool::new(urls)` / `next_url()` still compile.
+#[deprecated(note = "use `RoundRobin<String>` (`RoundRobin::new(urls).pick()`)")]
+pub struct EnginePool {
+    urls: RoundRobin<String>,
+}
+
+#[allow(deprecated)]
+impl EnginePool {
+    pub fn new(urls: Vec<String>) -> Self {
+        Self {
+            urls: RoundRobin::new(urls),
+        }
+    }
+
+    pub fn next_url(&self) -> Option<String> {
+        self.urls.pick()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counting_pool(config: ResourcePoolConfig) -> (ResourcePool<usize>, Arc<AtomicUsize>) {
+        let created = Arc::new(AtomicUsize::new(0));
+        let counter = created.clone();
+        let pool = ResourcePool::new(config, move || {
+            let id = counter.fetch_add(1, Ordering::SeqCst);
+            async move { Ok::<_, BoxError>(id) }
+        });
+        (pool, created)
+    }
+
+    fn config(max_size: usize) -> ResourcePoolConfig {
+        ResourcePoolConfig {
+            max_size,
+            idle_timeout: None,
+            acquire_timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn acquire_blocks_when_saturated_and_unblocks_on_release() {
+        let (pool, created) = counting_pool(config(2));
+        let a = pool.acquire().await.unwrap();
+        let b = pool.acquire().await.unwrap();
+        assert_eq!(
+            pool.status(),
+            ResourcePoolStatus {
+                max_size: 2,
+                in_use: 2,
+                idle: 0
+            }
+        );
+
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move { *pool.acquire().await.unwrap() }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+
+        let released = *a;
+        a.release();
+        let got = tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter unblocks after release")
+            .unwrap();
+        assert_eq!(got, released);
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+
+        drop(b);
+        assert_eq!(pool.status().in_use, 0);
+        assert_eq!(pool.status().idle, 2);
+    }
+
+    #[tokio::test]
+    async fn engines_are_reused_most_recent_first() {
+        let (pool, created) = counting_pool(config(4));
+        let a = pool.acquire().await.unwrap();
+        let b = pool.acquire().await.unwrap();
+        let (first, second) = (*a, *b);
+        drop(a);
+        drop(b);
+
+        assert_eq!(*pool.acquire().await.unwrap(), second);
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert_ne!(first, second);
+    }
+
+    #[tokio::test]
+    async fn acquire_times_out() {
+        let (pool, _) = counting_pool(ResourcePoolConfig {
+            acquire_timeout: Some(Duration::from_millis(20)),
+            ..config(1)
+        });
+        let _held = pool.acquire().await.unwrap();
+        assert!(matches!(
+            pool.acquire().await,
+            Err(ResourcePoolError::Timeout(_))
+        ));
+    }
 
-// let url = pool.next_url();
+    #[tokio::test]
+    async fn idle_engines_are_evicted() {
+        let (pool, created) = counting_pool(ResourcePoolConfig {
+            idle_timeout: Some(Duration::from_millis(30)),
+            ..config(2)
+        });
+        drop(pool.acquire().await.unwrap());
+        assert_eq!(pool.status().idle, 1);
+        assert_eq!(pool.evict_idle(), 0);
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(pool.evict_idle(), 1);
+        assert_eq!(pool.status().idle, 0);
+
+        drop(pool.acquire().await.unwrap());
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn discarded_and_failed_engines_free_their_slot() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let pool = ResourcePool::new(config(1), move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if n == 0 {
+                    Err("boom".into())
+                } else {
+                    Ok::<_, BoxError>(n)
+                }
+            }
+        });
+
+        assert!(matches!(
+            pool.acquire().await,
+            Err(ResourcePoolError::Create(_))
+        ));
+        let engine = pool.acquire().await.unwrap();
+        assert_eq!(*engine, 1);
+        engine.discard();
+        assert_eq!(pool.status().idle, 0);
+        assert_eq!(*pool.acquire().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn close_rejects_new_acquires() {
+        let (pool, _) = counting_pool(config(2));
+        let held = pool.acquire().await.unwrap();
+        drop(pool.acquire().await.unwrap());
+        pool.close();
+        assert_eq!(pool.status().idle, 0);
+        assert!(matches!(
+            pool.acquire().await,
+            Err(ResourcePoolError::Closed)
+        ));
+        drop(held);
+        assert_eq!(pool.status().idle, 0);
+    }
+
+    #[test]
+    fn round_robin_cycles() {
+        let rr = RoundRobin::new(vec!["a", "b", "c"]);
+        let picks: Vec<_> = (0..4).map(|_| rr.pick().unwrap()).collect();
+        assert_eq!(picks, ["a", "b", "c", "a"]);
+        assert_eq!(RoundRobin::<String>::new(vec![]).pick(), None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn legacy_engine_pool_still_round_robins() {
+        let pool = EnginePool::new(vec!["http://a".to_string(), "http://b".to_string()]);
+        assert_eq!(pool.next_url().as_deref(), Some("http://a"));
+        assert_eq!(pool.next_url().as_deref(), Some("http://b"));
+        assert_eq!(pool.next_url().as_deref(), Some("http://a"));
+    }
+}
@@ -26,3 +26,247 @@ pub enum OrderEnum {
     #[strum(serialize = "Desc")]
     DESC,
 }
+
+/// String-backed enum: one fixed string per variant, e.g. for `text` columns and JSON.
+///
+/// Usually implemented with [`str_enum!`](crate::str_enum), which also adds `Serialize`,
+/// `Deserialize` and `Display` based on these methods. Implement it by hand and use
+/// [`str_enum_serde`] with `#[serde(with = ...)]` when the macro does not fit.
+pub trait StrEnum: Sized {
+    /// The known string values, in declaration order.
+    const VARIANTS: &'static [&'static str];
+
+    /// The string stored for this value.
+    fn as_str(&self) -> &str;
+
+    /// Parse a stored string. `None` if it is unrecognized; enums declared with a fallback
+    /// variant return that instead and never fail.
+    fn from_str(value: &str) -> Option<Self>;
+}
+
+/// Serde functions for [`StrEnum`] types, for `#[serde(with = "str_enum_serde")]`.
+pub mod str_enum_serde {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use super::StrEnum;
+
+    pub fn serialize<T: StrEnum, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(value.as_str())
+    }
+
+    pub fn deserialize<'de, T: StrEnum, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let value = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        T::from_str(&value).ok_or_else(|| D::Error::unknown_variant(&value, T::VARIANTS))
+    }
+}
+
+/// Declare an enum stored as a string, implementing [`StrEnum`], `Serialize`, `Deserialize`
+/// and `Display` without a derive.
+///
+/// Add `fallback Variant` after the body to get an extra `Variant(String)` that keeps any
+/// unrecognized value, so a value added to the database before the code knows about it
+/// does not fail deserialization (and is written back unchanged). Without a fallback,
+/// unknown strings are an error.
+///
+/// ```rust,ignore
+/// use neocrates::helper::core::enums::StrEnum;
+///
+/// neocrates::str_enum! {
+///     /// Order lifecycle, stored in `orders.status` (text).
+///     #[derive(Debug, Clone, PartialEq, Eq, diesel::AsExpression, diesel::FromSqlRow)]
+///     #[diesel(sql_type = diesel::sql_types::Text)]
+///     pub enum OrderStatus {
+///         Pending => "pending",
+///         Paid => "paid",
+///         Shipped => "shipped",
+///     }
+///     fallback Unknown
+/// }
+/// neocrates::str_enum_diesel!(OrderStatus); // ToSql / FromSql<Text, Pg>
+///
+/// assert_eq!(OrderStatus::Paid.as_str(), "paid");
+/// assert_eq!(OrderStatus::from_str("refunded"), Some(OrderStatus::Unknown("refunded".into())));
+/// ```
+#[macro_export]
+macro_rules! str_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident => $value:literal),+ $(,)?
+        }
+        $(fallback $fallback:ident)?
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $($(#[$vmeta])* $variant,)+
+            $(
+                /// A value this code does not know, kept as stored.
+                $fallback(::std::string::String),
+            )?
+        }
+
+        impl $crate::helper::core::enums::StrEnum for $name {
+            const VARIANTS: &'static [&'static str] = &[$($value),+];
+
+            fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)+
+                    $(Self::$fallback(value) => value.as_str(),)?
+                }
+            }
+
+            fn from_str(value: &str) -> ::std::option::Option<Self> {
+                match value {
+                    $($value => ::std::option::Option::Some(Self::$variant),)+
+                    _ => $crate::str_enum!(@fallback value $(, $fallback)?),
+                }
+            }
+        }
+
+        impl $crate::serde::Serialize for $name {
+            fn serialize<S: $crate::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> {
+                $crate::helper::core::enums::str_enum_serde::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> $crate::serde::Deserialize<'de> for $name {
+            fn deserialize<D: $crate::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> {
+                $crate::helper::core::enums::str_enum_serde::deserialize(deserializer)
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str($crate::helper::core::enums::StrEnum::as_str(self))
+            }
+        }
+    };
+    (@fallback $value:ident) => {
+        ::std::option::Option::None
+    };
+    (@fallback $value:ident, $fallback:ident) => {
+        ::std::option::Option::Some(Self::$fallback(::std::string::ToString::to_string($value)))
+    };
+}
+
+/// Implement Diesel `ToSql` / `FromSql` for `Text` on Postgres for a [`StrEnum`] type.
+///
+/// Pair it with `#[derive(diesel::AsExpression, diesel::FromSqlRow)]` and
+/// `#[diesel(sql_type = diesel::sql_types::Text)]` on the enum (see [`str_enum!`](crate::str_enum)).
+/// Loading an unrecognized value is a deserialization error unless the enum has a fallback.
+#[cfg(any(feature = "diesel", feature = "full"))]
+#[macro_export]
+macro_rules! str_enum_diesel {
+    ($name:ty) => {
+        impl diesel::serialize::ToSql<diesel::sql_types::Text, diesel::pg::Pg> for $name {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut diesel::serialize::Output<'b, '_, diesel::pg::Pg>,
+            ) -> diesel::serialize::Result {
+                <str as diesel::serialize::ToSql<diesel::sql_types::Text, diesel::pg::Pg>>::to_sql(
+                    $crate::helper::core::enums::StrEnum::as_str(self),
+                    out,
+                )
+            }
+        }
+
+        impl diesel::deserialize::FromSql<diesel::sql_types::Text, diesel::pg::Pg> for $name {
+            fn from_sql(bytes: diesel::pg::PgValue<'_>) -> diesel::deserialize::Result<Self> {
+                let value = <::std::string::String as diesel::deserialize::FromSql<
+                    diesel::sql_types::Text,
+                    diesel::pg::Pg,
+                >>::from_sql(bytes)?;
+                <$name as $crate::helper::core::enums::StrEnum>::from_str(&value).ok_or_else(|| {
+                    format!("unrecognized {} value: {:?}", stringify!($name), value).into()
+                })
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StrEnum;
+
+    crate::str_enum! {
+        /// Worked example: order lifecycle with a fallback for values added later.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum OrderStatus {
+            Pending => "pending",
+            Paid => "paid",
+            /// Handed to the carrier
+            Shipped => "shipped",
+        }
+        fallback Unknown
+    }
+
+    crate::str_enum! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        enum Channel {
+            Sms => "sms",
+            Email => "email",
+        }
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Order {
+        status: OrderStatus,
+        channel: Option<Channel>,
+    }
+
+    #[test]
+    fn known_values_round_trip() {
+        assert_eq!(OrderStatus::VARIANTS, ["pending", "paid", "shipped"]);
+        for value in OrderStatus::VARIANTS {
+            assert_eq!(OrderStatus::from_str(value).unwrap().as_str(), *value);
+        }
+        assert_eq!(OrderStatus::Shipped.to_string(), "shipped");
+        assert_eq!(Channel::from_str("email"), Some(Channel::Email));
+    }
+
+    #[test]
+    fn unknown_values_use_the_fallback() {
+        let status = OrderStatus::from_str("refunded").unwrap();
+        assert_eq!(status, OrderStatus::Unknown("refunded".to_string()));
+        assert_eq!(status.as_str(), "refunded");
+        assert_eq!(Channel::from_str("fax"), None);
+    }
+
+    #[test]
+    fn serde_uses_the_strings() {
+        let order = Order {
+            status: OrderStatus::Paid,
+            channel: Some(Channel::Sms),
+        };
+        let json = serde_json::to_string(&order).unwrap();
+        assert_eq!(json, r#"{"status":"paid","channel":"sms"}"#);
+        assert_eq!(serde_json::from_str::<Order>(&json).unwrap(), order);
+
+        let order: Order = serde_json::from_str(r#"{"status":"refunded","channel":null}"#).unwrap();
+        assert_eq!(order.status, OrderStatus::Unknown("refunded".to_string()));
+        assert_eq!(
+            serde_json::to_string(&order).unwrap(),
+            r#"{"status":"refunded","channel":null}"#
+        );
+
+        let err =
+            serde_json::from_str::<Order>(r#"{"status":"paid","channel":"fax"}"#).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unknown variant `fax`, expected `sms` or `email`")
+        );
+    }
+
+    #[cfg(any(feature = "diesel", feature = "full"))]
+    crate::str_enum_diesel!(OrderStatus);
+}
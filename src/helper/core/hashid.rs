@@ -1,3 +1,6 @@
+use once_cell::sync::OnceCell;
+use thiserror::Error;
+
 pub fn encode_u64(n: u64) -> String {
     let mut buf = Vec::with_capacity(13);
     crockford::encode_into(n, &mut buf);
@@ -13,20 +16,183 @@ pub fn encode_i64(n: i64) -> String {
     result.to_string()
 }
 
+/// Why a hashid string could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum DecodeError {
+    #[error("hashid is empty")]
+    Empty,
+    #[error("invalid hashid character {ch:?} at index {index}")]
+    InvalidChar { index: usize, ch: char },
+    #[error("hashid is out of range for a 64-bit id")]
+    OutOfRange,
+}
+
+impl DecodeError {
+    fn from_crockford(err: crockford::Error, input: &str) -> Self {
+        match err {
+            crockford::Error::EmptyString => DecodeError::Empty,
+            crockford::Error::InvalidDigit { index, value }
+            | crockford::Error::CheckDigitUnsupported { index, value } => {
+                // Everything before `index` was ASCII, so it is a char boundary
+                let ch = input
+                    .get(index..)
+                    .and_then(|rest| rest.chars().next())
+                    .unwrap_or(value as char);
+                DecodeError::InvalidChar { index, ch }
+            }
+            crockford::Error::OutOfRange => DecodeError::OutOfRange,
+        }
+    }
+}
+
+/// Decode a hashid, returning an error for malformed input. Use this for anything that
+/// comes from a client.
+pub fn try_decode_u64(s: &str) -> Result<u64, DecodeError> {
+    crockford::decode(s).map_err(|err| DecodeError::from_crockford(err, s))
+}
+
+/// Decode a hashid produced by [`encode_i64`], returning an error for malformed input.
+pub fn try_decode_i64(s: &str) -> Result<i64, DecodeError> {
+    try_decode_u64(s).map(|n| n as i64)
+}
+
+/// Decode a hashid, panicking on malformed input. Only for trusted, internally produced ids;
+/// prefer [`try_decode_u64`].
 pub fn decode_u64(s: &str) -> u64 {
-    crockford::decode(s).expect("Failed to decode string")
+    try_decode_u64(s).expect("Failed to decode string")
 }
 
+/// Decode a hashid, panicking on malformed input. Only for trusted, internally produced ids;
+/// prefer [`try_decode_i64`].
 pub fn decode_i64(s: &str) -> i64 {
     let n = decode_u64(s);
     n as i64
 }
 
+/// Env var read for the process-wide salt when [`init_hashid_salt`] was not called.
+/// Unset or empty keeps the plain, unsalted encoding.
+pub const HASHID_SALT_ENV: &str = "HASHID_SALT";
+
+const FEISTEL_ROUNDS: usize = 6;
+
+/// Round keys derived from a salt.
+type SaltKey = [u64; FEISTEL_ROUNDS];
+
+static CONFIGURED_SALT: OnceCell<Option<SaltKey>> = OnceCell::new();
+
+/// Encode `n` after permuting it with a key derived from `salt`, so sequential ids do not
+/// produce sequential (or decodable) strings without the salt.
+///
+/// This is obfuscation, not encryption: it stops casual enumeration of ids, but do not rely
+/// on it to protect anything that needs real access control.
+///
+/// # Example
+/// ```rust,ignore
+/// let id = encode_with_salt(42, "my-secret-salt");
+/// assert_eq!(decode_with_salt(&id, "my-secret-salt"), Ok(42));
+/// ```
+pub fn encode_with_salt(n: i64, salt: impl AsRef<[u8]>) -> String {
+    encode_u64(permute(n as u64, &salt_key(salt.as_ref())))
+}
+
+/// Decode a string produced by [`encode_with_salt`] with the same salt. A different salt
+/// decodes to a different (wrong) id rather than failing.
+pub fn decode_with_salt(s: &str, salt: impl AsRef<[u8]>) -> Result<i64, DecodeError> {
+    let n = try_decode_u64(s)?;
+    Ok(unpermute(n, &salt_key(salt.as_ref())) as i64)
+}
+
+/// Configure the process-wide salt used by [`encode_id`] / [`try_decode_id`] and therefore by
+/// the serde `serialize_*i64` / `deserialize_*i64` helpers.
+///
+/// Call once at startup, before the first id is encoded or decoded. An empty salt keeps the
+/// unsalted encoding.
+///
+/// # Panics
+/// If the salt was already configured — by an earlier call or by encoding/decoding an id.
+pub fn init_hashid_salt(salt: impl AsRef<[u8]>) {
+    let salt = salt.as_ref();
+    let key = (!salt.is_empty()).then(|| salt_key(salt));
+    if CONFIGURED_SALT.set(key).is_err() {
+        panic!(
+            "init_hashid_salt called after the hashid salt was already configured or used; call it once at startup before encoding ids"
+        );
+    }
+}
+
+fn configured_salt() -> Option<&'static SaltKey> {
+    CONFIGURED_SALT
+        .get_or_init(|| {
+            std::env::var(HASHID_SALT_ENV)
+                .ok()
+                .filter(|salt| !salt.is_empty())
+                .map(|salt| salt_key(salt.as_bytes()))
+        })
+        .as_ref()
+}
+
+/// Encode with the process-wide salt if one is configured, otherwise like [`encode_i64`].
+pub fn encode_id(n: i64) -> String {
+    match configured_salt() {
+        Some(key) => encode_u64(permute(n as u64, key)),
+        None => encode_i64(n),
+    }
+}
+
+/// Decode with the process-wide salt if one is configured, otherwise like [`try_decode_i64`].
+pub fn try_decode_id(s: &str) -> Result<i64, DecodeError> {
+    let n = try_decode_u64(s)?;
+    Ok(match configured_salt() {
+        Some(key) => unpermute(n, key) as i64,
+        None => n as i64,
+    })
+}
+
+/// FNV-1a over the salt, expanded into round keys with SplitMix64.
+fn salt_key(salt: &[u8]) -> SaltKey {
+    let mut state = salt.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    std::array::from_fn(|_| {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        mix64(state)
+    })
+}
+
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn round(half: u32, key: u64) -> u32 {
+    mix64(u64::from(half) ^ key) as u32
+}
+
+/// Keyed bijection on `u64`: a balanced Feistel network over the two 32-bit halves.
+fn permute(n: u64, key: &SaltKey) -> u64 {
+    let (mut left, mut right) = ((n >> 32) as u32, n as u32);
+    for k in key {
+        (left, right) = (right, left ^ round(right, *k));
+    }
+    (u64::from(left) << 32) | u64::from(right)
+}
+
+fn unpermute(n: u64, key: &SaltKey) -> u64 {
+    let (mut left, mut right) = ((n >> 32) as u32, n as u32);
+    for k in key.iter().rev() {
+        (left, right) = (right ^ round(left, *k), left);
+    }
+    (u64::from(left) << 32) | u64::from(right)
+}
+
 // test
 #[cfg(test)]
 mod tests {
     use crate::helper::core::{
-        hashid::{decode_i64, encode_i64},
+        hashid::{
+            DecodeError, decode_i64, decode_with_salt, encode_i64, encode_with_salt, try_decode_i64,
+        },
         snowflake::generate_snowflake_id,
     };
 
@@ -49,4 +215,61 @@ mod tests {
         println!("decoded:{}", decoded);
         assert_eq!(n, decoded);
     }
+
+    #[test]
+    fn try_decode_rejects_garbage() {
+        assert_eq!(try_decode_i64(""), Err(DecodeError::Empty));
+        assert_eq!(
+            try_decode_i64("ab!cd"),
+            Err(DecodeError::InvalidChar { index: 2, ch: '!' })
+        );
+        assert_eq!(
+            try_decode_i64("ZZZZZZZZZZZZZ"),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(
+            try_decode_i64("ZZZZZZZZZZZZZZZZZZZZ"),
+            Err(DecodeError::OutOfRange)
+        );
+        assert_eq!(
+            try_decode_i64("1🦀"),
+            Err(DecodeError::InvalidChar {
+                index: 1, ch: '🦀'
+            })
+        );
+    }
+
+    #[test]
+    fn try_decode_round_trips() {
+        for n in [0, 1, -1, i64::MAX, i64::MIN] {
+            assert_eq!(try_decode_i64(&encode_i64(n)), Ok(n));
+        }
+    }
+
+    #[test]
+    fn salted_round_trip() {
+        let salt = "unit-test-salt";
+        for n in [0, 1, 2, -1, i64::MAX, i64::MIN, generate_snowflake_id()] {
+            let encoded = encode_with_salt(n, salt);
+            assert_eq!(decode_with_salt(&encoded, salt), Ok(n));
+        }
+    }
+
+    #[test]
+    fn different_salts_give_different_encodings() {
+        let n = generate_snowflake_id();
+        let a = encode_with_salt(n, "salt-a");
+        let b = encode_with_salt(n, "salt-b");
+        assert_ne!(a, b);
+        assert_ne!(a, encode_i64(n));
+        assert_ne!(decode_with_salt(&a, "salt-b"), Ok(n));
+    }
+
+    #[test]
+    fn salted_sequential_ids_do_not_look_sequential() {
+        let first = encode_with_salt(1000, "salt");
+        let second = encode_with_salt(1001, "salt");
+        // Unsalted, these share every character but the last
+        assert_ne!(first[..first.len() - 1], second[..second.len() - 1]);
+    }
 }
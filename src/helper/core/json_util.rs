@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use serde_json::Value;
+use thiserror::Error;
 
 /// Validate and convert JSON Value to a specific type
 ///
@@ -37,3 +38,345 @@ where
 {
     serde_json::from_str::<T>(json_str).map_err(|e| format!("JSON Parsing failed: {}", e))
 }
+
+/// Recursively merge `patch` into `base`.
+///
+/// Objects merge key by key; any other value (arrays included) replaces what is in `base`.
+/// Unlike RFC 7396, a `null` in `patch` is stored as `null` rather than deleting the key.
+pub fn merge(base: &mut Value, patch: &Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch.clone(),
+    }
+}
+
+/// One RFC 6902 JSON Patch operation; deserializes from `{"op": "add", "path": ..., ...}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Why a JSON Patch could not be applied.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PatchError {
+    #[error("invalid JSON pointer: {0}")]
+    InvalidPointer(String),
+    #[error("path not found: {0}")]
+    NotFound(String),
+    #[error("path cannot be written: {0}")]
+    InvalidTarget(String),
+    #[error("cannot move {from} into its own child {path}")]
+    MoveIntoChild { from: String, path: String },
+    #[error("test failed at {0}")]
+    TestFailed(String),
+}
+
+/// Apply RFC 6902 operations to `doc`, in order.
+///
+/// The patch is atomic: if any operation fails, `doc` is left unchanged.
+///
+/// ```rust,ignore
+/// let ops: Vec<PatchOp> = serde_json::from_value(body)?;
+/// apply_patch(&mut row.settings, &ops)?;
+/// ```
+pub fn apply_patch(doc: &mut Value, ops: &[PatchOp]) -> Result<(), PatchError> {
+    let mut patched = doc.clone();
+    for op in ops {
+        apply_op(&mut patched, op)?;
+    }
+    *doc = patched;
+    Ok(())
+}
+
+fn apply_op(doc: &mut Value, op: &PatchOp) -> Result<(), PatchError> {
+    match op {
+        PatchOp::Add { path, value } => add(doc, path, value.clone()),
+        PatchOp::Remove { path } => remove(doc, path).map(drop),
+        PatchOp::Replace { path, value } => {
+            *pointer_mut(doc, path)? = value.clone();
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(PatchError::MoveIntoChild {
+                    from: from.clone(),
+                    path: path.clone(),
+                });
+            }
+            let value = remove(doc, from)?;
+            add(doc, path, value)
+        }
+        PatchOp::Copy { from, path } => {
+            let value = pointer(doc, from)?.clone();
+            add(doc, path, value)
+        }
+        PatchOp::Test { path, value } => {
+            if pointer(doc, path)? == value {
+                Ok(())
+            } else {
+                Err(PatchError::TestFailed(path.clone()))
+            }
+        }
+    }
+}
+
+/// Split an RFC 6901 pointer into unescaped reference tokens (`""` is the whole document).
+fn parse_pointer(path: &str) -> Result<Vec<String>, PatchError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = path.strip_prefix('/') else {
+        return Err(PatchError::InvalidPointer(path.to_string()));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Array index token: `0` or digits without a leading zero.
+fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn child<'a>(value: &'a Value, token: &str) -> Option<&'a Value> {
+    match value {
+        Value::Object(map) => map.get(token),
+        Value::Array(items) => items.get(parse_index(token)?),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(value: &'a mut Value, token: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Object(map) => map.get_mut(token),
+        Value::Array(items) => items.get_mut(parse_index(token)?),
+        _ => None,
+    }
+}
+
+fn pointer<'a>(doc: &'a Value, path: &str) -> Result<&'a Value, PatchError> {
+    parse_pointer(path)?
+        .iter()
+        .try_fold(doc, |value, token| child(value, token))
+        .ok_or_else(|| PatchError::NotFound(path.to_string()))
+}
+
+fn pointer_mut<'a>(doc: &'a mut Value, path: &str) -> Result<&'a mut Value, PatchError> {
+    parse_pointer(path)?
+        .iter()
+        .try_fold(doc, |value, token| child_mut(value, token))
+        .ok_or_else(|| PatchError::NotFound(path.to_string()))
+}
+
+/// The container holding the last token of `path`, plus that token. `None` for the root.
+fn parent_mut<'a>(
+    doc: &'a mut Value,
+    path: &str,
+) -> Result<Option<(&'a mut Value, String)>, PatchError> {
+    let mut tokens = parse_pointer(path)?;
+    let Some(last) = tokens.pop() else {
+        return Ok(None);
+    };
+    let parent = tokens
+        .iter()
+        .try_fold(doc, |value, token| child_mut(value, token))
+        .ok_or_else(|| PatchError::NotFound(path.to_string()))?;
+    Ok(Some((parent, last)))
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), PatchError> {
+    let Some((parent, token)) = parent_mut(doc, path)? else {
+        *doc = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(map) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Value::Array(items) if token == "-" => {
+            items.push(value);
+            Ok(())
+        }
+        Value::Array(items) => match parse_index(&token) {
+            Some(index) if index <= items.len() => {
+                items.insert(index, value);
+                Ok(())
+            }
+            _ => Err(PatchError::InvalidTarget(path.to_string())),
+        },
+        _ => Err(PatchError::InvalidTarget(path.to_string())),
+    }
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let Some((parent, token)) = parent_mut(doc, path)? else {
+        return Ok(std::mem::take(doc));
+    };
+    let removed = match parent {
+        Value::Object(map) => map.remove(&token),
+        Value::Array(items) => parse_index(&token)
+            .filter(|index| *index < items.len())
+            .map(|index| items.remove(index)),
+        _ => None,
+    };
+    removed.ok_or_else(|| PatchError::NotFound(path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn merge_recurses_into_objects_and_replaces_arrays() {
+        let mut base = json!({
+            "name": "a",
+            "settings": {"theme": "dark", "notify": {"email": true, "sms": false}},
+            "tags": ["x", "y"]
+        });
+        merge(
+            &mut base,
+            &json!({
+                "settings": {"notify": {"sms": true}, "lang": "zh"},
+                "tags": ["z"],
+                "extra": null
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "name": "a",
+                "settings": {"theme": "dark", "notify": {"email": true, "sms": true}, "lang": "zh"},
+                "tags": ["z"],
+                "extra": null
+            })
+        );
+    }
+
+    #[test]
+    fn merge_non_object_replaces_base() {
+        let mut base = json!({"a": 1});
+        merge(&mut base, &json!([1, 2]));
+        assert_eq!(base, json!([1, 2]));
+    }
+
+    fn ops(value: Value) -> Vec<PatchOp> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn apply_patch_runs_all_operations() {
+        let mut doc = json!({"a": {"b": 1}, "list": [1, 2], "x~y": {"c/d": 0}});
+        apply_patch(
+            &mut doc,
+            &ops(json!([
+                {"op": "test", "path": "/a/b", "value": 1},
+                {"op": "add", "path": "/a/c", "value": 2},
+                {"op": "add", "path": "/list/1", "value": 9},
+                {"op": "add", "path": "/list/-", "value": 3},
+                {"op": "replace", "path": "/a/b", "value": 10},
+                {"op": "remove", "path": "/list/0"},
+                {"op": "copy", "from": "/a", "path": "/copied"},
+                {"op": "move", "from": "/x~0y/c~1d", "path": "/moved"}
+            ])),
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            json!({
+                "a": {"b": 10, "c": 2},
+                "list": [9, 2, 3],
+                "x~y": {},
+                "copied": {"b": 10, "c": 2},
+                "moved": 0
+            })
+        );
+    }
+
+    #[test]
+    fn failing_test_op_errors_and_leaves_doc_unchanged() {
+        let mut doc = json!({"version": 1, "name": "a"});
+        let err = apply_patch(
+            &mut doc,
+            &ops(json!([
+                {"op": "replace", "path": "/name", "value": "b"},
+                {"op": "test", "path": "/version", "value": 2}
+            ])),
+        )
+        .unwrap_err();
+        assert_eq!(err, PatchError::TestFailed("/version".to_string()));
+        assert_eq!(doc, json!({"version": 1, "name": "a"}));
+    }
+
+    #[test]
+    fn invalid_paths_are_rejected() {
+        let mut doc = json!({"a": [1], "n": 1});
+        let cases = [
+            (
+                json!({"op": "remove", "path": "/missing"}),
+                PatchError::NotFound("/missing".into()),
+            ),
+            (
+                json!({"op": "replace", "path": "a", "value": 0}),
+                PatchError::InvalidPointer("a".into()),
+            ),
+            (
+                json!({"op": "add", "path": "/a/5", "value": 0}),
+                PatchError::InvalidTarget("/a/5".into()),
+            ),
+            (
+                json!({"op": "add", "path": "/a/01", "value": 0}),
+                PatchError::InvalidTarget("/a/01".into()),
+            ),
+            (
+                json!({"op": "add", "path": "/n/x", "value": 0}),
+                PatchError::InvalidTarget("/n/x".into()),
+            ),
+            (
+                json!({"op": "move", "from": "/a", "path": "/a/0"}),
+                PatchError::MoveIntoChild {
+                    from: "/a".into(),
+                    path: "/a/0".into(),
+                },
+            ),
+        ];
+        for (op, expected) in cases {
+            let op: PatchOp = serde_json::from_value(op).unwrap();
+            assert_eq!(apply_patch(&mut doc, &[op]), Err(expected));
+        }
+    }
+
+    #[test]
+    fn root_path_replaces_whole_document() {
+        let mut doc = json!({"a": 1});
+        apply_patch(
+            &mut doc,
+            &ops(json!([{"op": "add", "path": "", "value": [1]}])),
+        )
+        .unwrap();
+        assert_eq!(doc, json!([1]));
+    }
+}
@@ -4,19 +4,241 @@ use std::env;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use thiserror::Error;
 
+#[derive(Error, Debug)]
+pub enum LoaderError {
+    #[error("no config file found, tried: {0:?}")]
+    NotFound(Vec<String>),
+
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse {path}: {message}")]
+    Parse { path: PathBuf, message: String },
+
+    #[error("environment variable {0} is not set and has no default")]
+    MissingVar(String),
+
+    #[error("invalid placeholder at byte {position}: {reason}")]
+    InvalidPlaceholder { position: usize, reason: String },
+}
+
+/// What to do with `${VAR}` when `VAR` is not set and no `:-default` is given.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingVarPolicy {
+    /// Fail with `LoaderError::MissingVar`.
+    #[default]
+    Error,
+    /// Leave the placeholder in the text unchanged.
+    KeepLiteral,
+}
+
+/// Expand environment variable placeholders in raw config text.
+///
+/// Supported syntax:
+/// - `${VAR}` — value of `VAR`; if unset, handled according to `missing`
+/// - `${VAR:-default}` — value of `VAR`, or `default` if unset or empty
+/// - `$$` — a literal `$`
+///
+/// Any other `$` is kept as is. This works on a single string; the `*_interpolated` loaders
+/// apply it to each parsed string value (see [`interpolate_value`]), so comments are never
+/// expanded.
+///
+/// # Example
+/// ```rust,ignore
+/// let text = interpolate_env("url: ${DATABASE_URL:-postgres://localhost/dev}", MissingVarPolicy::Error)?;
+/// ```
+pub fn interpolate_env(raw: &str, missing: MissingVarPolicy) -> Result<String, LoaderError> {
+    interpolate_with(raw, missing, |name| env::var(name).ok())
+}
+
+fn interpolate_with<L>(
+    raw: &str,
+    missing: MissingVarPolicy,
+    lookup: L,
+) -> Result<String, LoaderError>
+where
+    L: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        let position = raw.len() - rest.len() + idx;
+        let after = &rest[idx + 1..];
+
+        if let Some(tail) = after.strip_prefix('$') {
+            out.push('$');
+            rest = tail;
+        } else if let Some(body_and_tail) = after.strip_prefix('{') {
+            let end = body_and_tail
+                .find('}')
+                .ok_or_else(|| LoaderError::InvalidPlaceholder {
+                    position,
+                    reason: "missing closing '}'".to_string(),
+                })?;
+            let body = &body_and_tail[..end];
+            let (name, default) = match body.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (body, None),
+            };
+            if !is_valid_var_name(name) {
+                return Err(LoaderError::InvalidPlaceholder {
+                    position,
+                    reason: format!("invalid variable name {:?}", name),
+                });
+            }
+
+            match (lookup(name), default) {
+                (Some(value), Some(default)) if value.is_empty() => out.push_str(default),
+                (Some(value), _) => out.push_str(&value),
+                (None, Some(default)) => out.push_str(default),
+                (None, None) => match missing {
+                    MissingVarPolicy::Error => {
+                        return Err(LoaderError::MissingVar(name.to_string()));
+                    }
+                    MissingVarPolicy::KeepLiteral => {
+                        out.push_str(&rest[idx..idx + 2 + end + 1]);
+                    }
+                },
+            }
+            rest = &body_and_tail[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Expand placeholders in every string of a parsed config value (keys are left alone).
+///
+/// `raw` is the file text `value` was parsed from. For YAML (`retype`), a value that is
+/// exactly one placeholder written unquoted (`port: ${PORT:-8080}`) is resolved like a
+/// plain YAML scalar after expansion, so it can still load into numbers and booleans; a
+/// quoted placeholder (`password: "${DB_PASSWORD}"`) always stays a string.
+fn interpolate_value(
+    value: &mut serde_json::Value,
+    raw: &str,
+    retype: bool,
+    missing: MissingVarPolicy,
+) -> Result<(), LoaderError> {
+    match value {
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                interpolate_value(item, raw, retype, missing)?;
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                interpolate_value(item, raw, retype, missing)?;
+            }
+        }
+        serde_json::Value::String(text) if text.contains('$') => {
+            let expanded = interpolate_env(text, missing)?;
+            *value = if retype && is_bare_placeholder(text, raw) {
+                serde_yaml::from_str::<serde_yaml::Value>(&expanded)
+                    .ok()
+                    .and_then(|scalar| serde_json::to_value(scalar).ok())
+                    .filter(|v| !v.is_object() && !v.is_array())
+                    .unwrap_or(serde_json::Value::String(expanded))
+            } else {
+                serde_json::Value::String(expanded)
+            };
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether `text` is a single `${...}` placeholder that appears unquoted in `raw`.
+fn is_bare_placeholder(text: &str, raw: &str) -> bool {
+    let single = text.starts_with("${") && text.find('}') == Some(text.len() - 1);
+    single && !raw.contains(&format!("\"{}\"", text)) && !raw.contains(&format!("'{}'", text))
+}
+
+fn is_valid_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Extensions tried for each config file name, in search order.
+const CONFIG_EXTENSIONS: [&str; 4] = ["yml", "yaml", "toml", "json"];
+
+/// Config file format, detected from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// `.toml` and `.json` files by extension; anything else is parsed as YAML.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+
+    fn parse<T>(self, raw: &str) -> Result<T, String>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(raw).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(raw).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(raw).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Load a config file, choosing YAML, TOML or JSON by its extension (see [`ConfigFormat`]).
 pub fn load_config_from_file<T, P>(path: P) -> Option<T>
 where
     T: for<'de> Deserialize<'de>,
     P: AsRef<std::path::Path>,
 {
+    let format = ConfigFormat::from_path(path.as_ref());
     let mut file = File::open(path).ok()?;
     let mut contents = String::new();
     file.read_to_string(&mut contents).ok()?;
-    serde_yaml::from_str(&contents).ok()
+    format.parse(&contents).ok()
+}
+
+/// Like `load_config_from_file`, but expands `${VAR}` placeholders (see [`interpolate_env`])
+/// in the parsed string values, and reports why loading failed instead of returning None.
+pub fn load_config_from_file_interpolated<T, P>(
+    path: P,
+    missing: MissingVarPolicy,
+) -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let value = read_value(path, Some(missing))?;
+    serde_json::from_value(value).map_err(|e| LoaderError::Parse {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
 }
 
-// Loads configuration from a specific YAML file path.
+/// Loads configuration from a specific YAML file path.
 ///
 /// This function is similar to `load_config_from_file` but more explicitly accepts
 /// a path parameter and provides better documentation. It is useful when you need
@@ -68,19 +290,16 @@ fn find_config_path(file_name: &str) -> Option<PathBuf> {
     None
 }
 
-/// Loads configuration from environment-specific or default YAML files.
+/// Loads configuration from environment-specific or default config files.
 ///
 /// This function searches for configuration files in the following order:
-/// 1. `application.{ENV}.yml`
-/// 2. `application.{ENV}.yaml`
-/// 3. `config.{ENV}.yml`
-/// 4. `config.{ENV}.yaml`
-/// 5. `application.yml`
-/// 6. `application.yaml`
-/// 7. `config.yml`
-/// 8. `config.yaml`
+/// 1. `application.{ENV}.{yml,yaml,toml,json}`
+/// 2. `config.{ENV}.{yml,yaml,toml,json}`
+/// 3. `application.{yml,yaml,toml,json}`
+/// 4. `config.{yml,yaml,toml,json}`
 ///
-/// Where `ENV` is the value of the environment variable "ENV".
+/// Where `ENV` is the value of the environment variable "ENV". Extensions are tried in the
+/// order shown and the format is picked from the extension of the file found.
 ///
 /// For each candidate filename, the function searches recursively upwards from the
 /// current working directory to the root directory until the file is found.
@@ -112,30 +331,501 @@ pub fn load_config<T>() -> Option<T>
 where
     T: for<'de> Deserialize<'de>,
 {
-    let env_var = env::var("ENV").ok();
-    let mut candidates = Vec::new();
+    let candidates = config_candidates();
 
-    if let Some(env) = env_var.as_deref() {
-        if !env.is_empty() {
-            candidates.push(format!("application.{}.yml", env));
-            candidates.push(format!("application.{}.yaml", env));
-            candidates.push(format!("config.{}.yml", env));
-            candidates.push(format!("config.{}.yaml", env));
+    for file_name in candidates {
+        if let Some(path) = find_config_path(&file_name)
+            && let Some(config) = load_config_from_file::<T, _>(path)
+        {
+            return Some(config);
         }
     }
 
-    candidates.push("application.yml".to_string());
-    candidates.push("application.yaml".to_string());
-    candidates.push("config.yml".to_string());
-    candidates.push("config.yaml".to_string());
+    None
+}
 
-    for file_name in candidates {
-        if let Some(path) = find_config_path(&file_name) {
-            if let Some(config) = load_config_from_file::<T, _>(path) {
-                return Some(config);
+/// Like `load_config`, but expands `${VAR}` placeholders (see [`interpolate_env`]) and
+/// returns the first error instead of None.
+///
+/// The first existing candidate file is used; a placeholder or parse error in it is
+/// returned rather than silently falling through to the next candidate.
+pub fn load_config_interpolated<T>(missing: MissingVarPolicy) -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let candidates = config_candidates();
+    for file_name in &candidates {
+        if let Some(path) = find_config_path(file_name) {
+            return load_config_from_file_interpolated(path, missing);
+        }
+    }
+    Err(LoaderError::NotFound(candidates))
+}
+
+/// Candidate file names in search order, see `load_config`.
+fn config_candidates() -> Vec<String> {
+    let mut candidates = env_candidates();
+    candidates.extend(base_candidates());
+    candidates
+}
+
+/// Environment-specific file names for the current `ENV`, empty if it is unset.
+fn env_candidates() -> Vec<String> {
+    match env::var("ENV") {
+        Ok(env) if !env.is_empty() => {
+            with_extensions(&[format!("application.{}", env), format!("config.{}", env)])
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Shared base file names, used regardless of `ENV`.
+fn base_candidates() -> Vec<String> {
+    with_extensions(&["application".to_string(), "config".to_string()])
+}
+
+fn with_extensions(stems: &[String]) -> Vec<String> {
+    stems
+        .iter()
+        .flat_map(|stem| {
+            CONFIG_EXTENSIONS
+                .iter()
+                .map(move |ext| format!("{}.{}", stem, ext))
+        })
+        .collect()
+}
+
+fn find_first(candidates: &[String]) -> Option<PathBuf> {
+    candidates.iter().find_map(|name| find_config_path(name))
+}
+
+/// Loads the base config and deep-merges the environment-specific file over it.
+///
+/// The base is the first `application.*` or `config.*` file found; the override is the
+/// first `application.{ENV}.*` or `config.{ENV}.*` found (same upward search and extensions
+/// as `load_config`, so the two files may use different formats). The override only needs the keys it changes:
+///
+/// - objects are merged key by key, recursively
+/// - scalars and arrays in the override replace the base value
+///
+/// Either file may be missing; if both are, `LoaderError::NotFound` is returned.
+///
+/// # Example
+/// ```rust,ignore
+/// // application.yml:       server: { host: 0.0.0.0, port: 8080 }
+/// // application.prod.yml:  server: { port: 80 }
+/// // ENV=prod              => server: { host: 0.0.0.0, port: 80 }
+/// let config: AppConfig = load_config_merged()?;
+/// ```
+pub fn load_config_merged<T>() -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    load_merged(None)
+}
+
+/// Like `load_config_merged`, but expands `${VAR}` placeholders (see [`interpolate_env`]) in
+/// both files before they are merged.
+pub fn load_config_merged_interpolated<T>(missing: MissingVarPolicy) -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    load_merged(Some(missing))
+}
+
+fn load_merged<T>(interpolate: Option<MissingVarPolicy>) -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let base = find_first(&base_candidates());
+    let overlay = find_first(&env_candidates());
+    if base.is_none() && overlay.is_none() {
+        return Err(LoaderError::NotFound(config_candidates()));
+    }
+    merge_files(base.as_deref(), overlay.as_deref(), interpolate)
+}
+
+fn merge_files<T>(
+    base: Option<&Path>,
+    overlay: Option<&Path>,
+    interpolate: Option<MissingVarPolicy>,
+) -> Result<T, LoaderError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let mut merged = match base {
+        Some(path) => read_value(path, interpolate)?,
+        None => serde_json::Value::Object(Default::default()),
+    };
+    if let Some(path) = overlay {
+        merge_values(&mut merged, read_value(path, interpolate)?);
+    }
+    serde_json::from_value(merged).map_err(|e| LoaderError::Parse {
+        path: overlay.or(base).map(Path::to_path_buf).unwrap_or_default(),
+        message: format!("merged config: {}", e),
+    })
+}
+
+/// Parse a config file into a format-neutral value, expanding placeholders in its string
+/// values when `interpolate` is set.
+fn read_value(
+    path: &Path,
+    interpolate: Option<MissingVarPolicy>,
+) -> Result<serde_json::Value, LoaderError> {
+    let raw = std::fs::read_to_string(path).map_err(|source| LoaderError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| LoaderError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+    // An empty file means "no keys" in every format
+    if raw.trim().is_empty() {
+        return Ok(serde_json::Value::Object(Default::default()));
+    }
+    let format = ConfigFormat::from_path(path);
+    let mut value: serde_json::Value = match format {
+        ConfigFormat::Yaml => {
+            let yaml: serde_yaml::Value =
+                serde_yaml::from_str(&raw).map_err(|e| parse_error(e.to_string()))?;
+            serde_json::to_value(yaml).map_err(|e| parse_error(e.to_string()))?
+        }
+        ConfigFormat::Toml => {
+            let toml: toml::Value = toml::from_str(&raw).map_err(|e| parse_error(e.to_string()))?;
+            serde_json::to_value(toml).map_err(|e| parse_error(e.to_string()))?
+        }
+        ConfigFormat::Json => serde_json::from_str(&raw).map_err(|e| parse_error(e.to_string()))?,
+    };
+    if value.is_null() {
+        return Ok(serde_json::Value::Object(Default::default()));
+    }
+    if let Some(missing) = interpolate {
+        interpolate_value(&mut value, &raw, format == ConfigFormat::Yaml, missing)?;
+    }
+    Ok(value)
+}
+
+/// Deep-merge `overlay` into `base`: objects merge recursively, anything else in `overlay`
+/// (scalars, arrays, null) replaces the value in `base`.
+pub fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
             }
         }
+        (base, overlay) => *base = overlay,
     }
+}
 
-    None
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "DB_HOST" => Some("db.internal".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn expand(raw: &str, missing: MissingVarPolicy) -> Result<String, LoaderError> {
+        interpolate_with(raw, missing, lookup)
+    }
+
+    #[test]
+    fn expands_vars_and_defaults() {
+        let out = expand(
+            "host: ${DB_HOST}\nport: ${DB_PORT:-5432}\nname: ${EMPTY:-app}",
+            MissingVarPolicy::Error,
+        )
+        .unwrap();
+        assert_eq!(out, "host: db.internal\nport: 5432\nname: app");
+    }
+
+    #[test]
+    fn escapes_and_plain_dollars_are_kept() {
+        let out = expand(
+            "a: $$ {DB_HOST}\nb: $DB_HOST\nc: cost$",
+            MissingVarPolicy::Error,
+        )
+        .unwrap();
+        assert_eq!(out, "a: $ {DB_HOST}\nb: $DB_HOST\nc: cost$");
+        assert_eq!(
+            expand("$${DB_HOST}", MissingVarPolicy::Error).unwrap(),
+            "${DB_HOST}"
+        );
+    }
+
+    #[test]
+    fn missing_var_follows_policy() {
+        let err = expand("key: ${API_KEY}", MissingVarPolicy::Error).unwrap_err();
+        assert!(matches!(err, LoaderError::MissingVar(ref name) if name == "API_KEY"));
+        assert_eq!(
+            err.to_string(),
+            "environment variable API_KEY is not set and has no default"
+        );
+
+        let out = expand(
+            "key: ${API_KEY} host: ${DB_HOST}",
+            MissingVarPolicy::KeepLiteral,
+        )
+        .unwrap();
+        assert_eq!(out, "key: ${API_KEY} host: db.internal");
+    }
+
+    #[test]
+    fn malformed_placeholders_error() {
+        assert!(matches!(
+            expand("a: ${DB_HOST", MissingVarPolicy::Error),
+            Err(LoaderError::InvalidPlaceholder { position: 3, .. })
+        ));
+        assert!(matches!(
+            expand("a: ${1BAD}", MissingVarPolicy::KeepLiteral),
+            Err(LoaderError::InvalidPlaceholder { .. })
+        ));
+    }
+
+    #[test]
+    fn merge_values_overrides_nested_keys_and_replaces_arrays() {
+        let mut base = serde_json::json!({
+            "server": { "host": "0.0.0.0", "port": 8080, "tls": { "enabled": false, "cert": "a.pem" } },
+            "features": ["a", "b"],
+            "name": "svc"
+        });
+        merge_values(
+            &mut base,
+            serde_json::json!({
+                "server": { "port": 80, "tls": { "enabled": true } },
+                "features": ["c"],
+                "extra": 1
+            }),
+        );
+        assert_eq!(
+            base,
+            serde_json::json!({
+                "server": { "host": "0.0.0.0", "port": 80, "tls": { "enabled": true, "cert": "a.pem" } },
+                "features": ["c"],
+                "name": "svc",
+                "extra": 1
+            })
+        );
+    }
+
+    #[test]
+    fn merges_base_and_env_files() {
+        #[derive(Deserialize)]
+        struct Server {
+            host: String,
+            port: u16,
+        }
+        #[derive(Deserialize)]
+        struct Cfg {
+            name: String,
+            server: Server,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("application.yml");
+        let prod = dir.path().join("application.prod.yml");
+        std::fs::write(&base, "name: svc\nserver:\n  host: 0.0.0.0\n  port: 8080\n").unwrap();
+        std::fs::write(&prod, "server:\n  port: 80\n").unwrap();
+
+        let cfg: Cfg = merge_files(Some(&base), Some(&prod), None).unwrap();
+        assert_eq!(cfg.name, "svc");
+        assert_eq!(cfg.server.host, "0.0.0.0");
+        assert_eq!(cfg.server.port, 80);
+
+        let cfg: Cfg = merge_files(Some(&base), None, None).unwrap();
+        assert_eq!(cfg.server.port, 8080);
+
+        std::fs::write(&prod, "").unwrap();
+        let cfg: Cfg = merge_files(Some(&base), Some(&prod), None).unwrap();
+        assert_eq!(cfg.server.port, 8080);
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct FormatCfg {
+        name: String,
+        debug: bool,
+        server: Server,
+    }
+
+    fn expected_format_cfg() -> FormatCfg {
+        FormatCfg {
+            name: "svc".to_string(),
+            debug: true,
+            server: Server {
+                host: "0.0.0.0".to_string(),
+                port: 8080,
+            },
+        }
+    }
+
+    #[test]
+    fn loads_yaml_toml_and_json_into_same_struct() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = [
+            (
+                "application.yml",
+                "name: svc\ndebug: true\nserver:\n  host: 0.0.0.0\n  port: 8080\n",
+            ),
+            (
+                "application.toml",
+                "name = \"svc\"\ndebug = true\n\n[server]\nhost = \"0.0.0.0\"\nport = 8080\n",
+            ),
+            (
+                "application.json",
+                r#"{"name":"svc","debug":true,"server":{"host":"0.0.0.0","port":8080}}"#,
+            ),
+        ];
+
+        for (name, contents) in files {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            assert_eq!(
+                load_config_from_file::<FormatCfg, _>(&path),
+                Some(expected_format_cfg()),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn detects_format_and_keeps_candidate_order() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a/config.TOML")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.yaml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            base_candidates(),
+            vec![
+                "application.yml",
+                "application.yaml",
+                "application.toml",
+                "application.json",
+                "config.yml",
+                "config.yaml",
+                "config.toml",
+                "config.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_across_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("application.toml");
+        let prod = dir.path().join("application.prod.json");
+        std::fs::write(
+            &base,
+            "name = \"svc\"\ndebug = true\n[server]\nhost = \"0.0.0.0\"\nport = 8080\n",
+        )
+        .unwrap();
+        std::fs::write(&prod, r#"{"debug": false, "server": {"port": 80}}"#).unwrap();
+
+        let cfg: FormatCfg = merge_files(Some(&base), Some(&prod), None).unwrap();
+        assert!(!cfg.debug);
+        assert_eq!(cfg.server.host, "0.0.0.0");
+        assert_eq!(cfg.server.port, 80);
+    }
+
+    #[test]
+    fn loads_interpolated_file() {
+        #[derive(Deserialize)]
+        struct Cfg {
+            url: String,
+            port: u16,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("application.yml");
+        std::fs::write(
+            &path,
+            "url: \"${NEOCRATES_TEST_UNSET_URL:-postgres://localhost/dev}\"\nport: ${NEOCRATES_TEST_UNSET_PORT:-8080}\n",
+        )
+        .unwrap();
+
+        let cfg: Cfg = load_config_from_file_interpolated(&path, MissingVarPolicy::Error).unwrap();
+        assert_eq!(cfg.url, "postgres://localhost/dev");
+        assert_eq!(cfg.port, 8080);
+
+        std::fs::write(&path, "url: ${NEOCRATES_TEST_UNSET_URL}\nport: 1\n").unwrap();
+        assert!(matches!(
+            load_config_from_file_interpolated::<Cfg, _>(&path, MissingVarPolicy::Error),
+            Err(LoaderError::MissingVar(_))
+        ));
+    }
+
+    #[test]
+    fn interpolation_skips_comments_and_keeps_quoted_strings() {
+        #[derive(Deserialize)]
+        struct Cfg {
+            password: String,
+            port: u16,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("application.yml");
+        std::fs::write(
+            &path,
+            "# url: ${NEOCRATES_TEST_UNSET_URL}\npassword: \"${NEOCRATES_TEST_UNSET_PW:-12345}\"\nport: ${NEOCRATES_TEST_UNSET_PORT:-8080} # ${NEOCRATES_TEST_UNSET_NOTE}\n",
+        )
+        .unwrap();
+
+        let cfg: Cfg = load_config_from_file_interpolated(&path, MissingVarPolicy::Error).unwrap();
+        assert_eq!(cfg.password, "12345");
+        assert_eq!(cfg.port, 8080);
+    }
+
+    #[test]
+    fn merged_files_are_interpolated() {
+        #[derive(Deserialize)]
+        struct Cfg {
+            url: String,
+            port: u16,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("application.toml");
+        let prod = dir.path().join("application.prod.yml");
+        std::fs::write(
+            &base,
+            "url = \"${NEOCRATES_TEST_UNSET_URL:-postgres://localhost/dev}\"\nport = 1\n",
+        )
+        .unwrap();
+        std::fs::write(&prod, "port: ${NEOCRATES_TEST_UNSET_PORT:-80}\n").unwrap();
+
+        let cfg: Cfg =
+            merge_files(Some(&base), Some(&prod), Some(MissingVarPolicy::Error)).unwrap();
+        assert_eq!(cfg.url, "postgres://localhost/dev");
+        assert_eq!(cfg.port, 80);
+
+        std::fs::write(&prod, "port: ${NEOCRATES_TEST_UNSET_PORT}\n").unwrap();
+        assert!(matches!(
+            merge_files::<Cfg>(Some(&base), Some(&prod), Some(MissingVarPolicy::Error)),
+            Err(LoaderError::MissingVar(_))
+        ));
+    }
 }
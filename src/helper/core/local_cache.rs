@@ -0,0 +1,247 @@
+//! In-process cache with per-entry TTL, built on `moka`.
+//!
+//! Use it as an L1 in front of Redis for hot, rarely-changing data (config, feature flags,
+//! lookup tables): [`LocalCache::get_or_load_tiered`] checks the local cache, then Redis,
+//! then the loader.
+//!
+//! ```rust,ignore
+//! use std::time::Duration;
+//! use neocrates::helper::core::local_cache::LocalCache;
+//!
+//! let cache: LocalCache<String, Settings> = LocalCache::new(1_000);
+//! let settings = cache
+//!     .get_or_load_tiered("settings".into(), &redis, "cfg:settings", Duration::from_secs(60), || async {
+//!         load_settings_from_db().await
+//!     })
+//!     .await?;
+//! ```
+
+use std::{
+    future::Future,
+    hash::Hash,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use moka::{Expiry, future::Cache};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::rediscache::RedisPool;
+
+#[derive(Clone)]
+struct Entry<V> {
+    value: V,
+    ttl: Duration,
+}
+
+/// Expires each entry after the TTL it was inserted with.
+struct PerEntryTtl;
+
+impl<K, V> Expiry<K, Entry<V>> for PerEntryTtl {
+    fn expire_after_create(
+        &self,
+        _key: &K,
+        entry: &Entry<V>,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &K,
+        entry: &Entry<V>,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+}
+
+/// A bounded in-process cache where every entry carries its own TTL.
+///
+/// Cloning is cheap and clones share the same entries.
+#[derive(Clone)]
+pub struct LocalCache<K, V> {
+    inner: Cache<K, Entry<V>>,
+}
+
+impl<K, V> LocalCache<K, V>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Holds at most `max_capacity` entries; the least useful are evicted first.
+    pub fn new(max_capacity: u64) -> Self {
+        Self {
+            inner: Cache::builder()
+                .max_capacity(max_capacity)
+                .expire_after(PerEntryTtl)
+                .build(),
+        }
+    }
+
+    pub async fn get(&self, key: &K) -> Option<V> {
+        self.inner.get(key).await.map(|entry| entry.value)
+    }
+
+    pub async fn insert(&self, key: K, value: V, ttl: Duration) {
+        self.inner.insert(key, Entry { value, ttl }).await;
+    }
+
+    pub async fn invalidate(&self, key: &K) {
+        self.inner.invalidate(key).await;
+    }
+
+    /// Return the cached value, or run `loader` and cache its result for `ttl`.
+    ///
+    /// Concurrent misses for the same key run the loader once; the others wait for it.
+    /// Errors are not cached and are shared between those waiters, hence the `Arc`.
+    pub async fn get_or_insert_with<F, Fut, E>(
+        &self,
+        key: K,
+        ttl: Duration,
+        loader: F,
+    ) -> Result<V, Arc<E>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+        E: Send + Sync + 'static,
+    {
+        self.inner
+            .try_get_with(key, async move {
+                loader().await.map(|value| Entry { value, ttl })
+            })
+            .await
+            .map(|entry| entry.value)
+    }
+}
+
+impl<K, V> LocalCache<K, V>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// L1/L2 lookup: this cache, then `redis_key` in Redis (JSON), then `loader`.
+    ///
+    /// A Redis hit fills the local cache; a loader result is written to both, with `ttl` in
+    /// each. Redis failures are logged and skipped, so a Redis outage falls back to the
+    /// loader instead of failing the read.
+    pub async fn get_or_load_tiered<F, Fut, E>(
+        &self,
+        key: K,
+        redis: &RedisPool,
+        redis_key: &str,
+        ttl: Duration,
+        loader: F,
+    ) -> Result<V, Arc<E>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+        E: Send + Sync + 'static,
+    {
+        self.get_or_insert_with(key, ttl, || async move {
+            if let Some(value) = redis_get_json(redis, redis_key).await {
+                return Ok(value);
+            }
+            let value = loader().await?;
+            redis_set_json(redis, redis_key, &value, ttl).await;
+            Ok(value)
+        })
+        .await
+    }
+}
+
+async fn redis_get_json<V: DeserializeOwned>(redis: &RedisPool, key: &str) -> Option<V> {
+    match redis.get::<_, String>(key).await {
+        Ok(Some(raw)) => match serde_json::from_str(&raw) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                tracing::warn!(
+                    "local_cache: ignoring undecodable Redis value at {}: {}",
+                    key,
+                    e
+                );
+                None
+            }
+        },
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!("local_cache: Redis get {} failed: {}", key, e);
+            None
+        }
+    }
+}
+
+async fn redis_set_json<V: Serialize>(redis: &RedisPool, key: &str, value: &V, ttl: Duration) {
+    let raw = match serde_json::to_string(value) {
+        Ok(raw) => raw,
+        Err(e) => {
+            tracing::warn!("local_cache: cannot serialize value for {}: {}", key, e);
+            return;
+        }
+    };
+    // SETEX rejects 0; round sub-second TTLs up
+    let seconds = ttl.as_secs().max(1);
+    if let Err(e) = redis.setex(key, raw, seconds).await {
+        tracing::warn!("local_cache: Redis set {} failed: {}", key, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn get_or_insert_with_loads_once_then_hits() {
+        let cache: LocalCache<&str, u32> = LocalCache::new(100);
+        let loads = AtomicUsize::new(0);
+        let load = || async {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, String>(7)
+        };
+
+        let ttl = Duration::from_secs(60);
+        assert_eq!(cache.get_or_insert_with("k", ttl, load).await, Ok(7));
+        assert_eq!(cache.get_or_insert_with("k", ttl, load).await, Ok(7));
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&"k").await, Some(7));
+    }
+
+    #[tokio::test]
+    async fn loader_errors_are_not_cached() {
+        let cache: LocalCache<&str, u32> = LocalCache::new(100);
+        let ttl = Duration::from_secs(60);
+
+        let err = cache
+            .get_or_insert_with("k", ttl, || async { Err::<u32, _>("down") })
+            .await
+            .unwrap_err();
+        assert_eq!(*err, "down");
+        assert_eq!(cache.get(&"k").await, None);
+
+        let value = cache
+            .get_or_insert_with("k", ttl, || async { Ok::<_, &str>(3) })
+            .await;
+        assert_eq!(value, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn entries_expire_after_their_ttl() {
+        let cache: LocalCache<&str, u32> = LocalCache::new(100);
+        cache.insert("short", 1, Duration::from_millis(50)).await;
+        cache.insert("long", 2, Duration::from_secs(60)).await;
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(cache.get(&"short").await, None);
+        assert_eq!(cache.get(&"long").await, Some(2));
+
+        let reloaded = cache
+            .get_or_insert_with("short", Duration::from_secs(60), || async {
+                Ok::<_, String>(10)
+            })
+            .await;
+        assert_eq!(reloaded, Ok(10));
+    }
+}
@@ -1,10 +1,14 @@
 #[cfg(any(feature = "web", feature = "full"))]
 pub mod axum_extractor;
+pub mod cast;
+pub mod circuit_breaker;
 pub mod engine_pool;
 pub mod enums;
 pub mod hashid;
 pub mod json_util;
 pub mod loader;
+#[cfg(any(feature = "redis", feature = "full"))]
+pub mod local_cache;
 pub mod page;
 pub mod regex;
 pub mod retry;
@@ -12,4 +16,6 @@ pub mod serde_helpers;
 pub mod snowflake;
 pub mod text_chunks;
 pub mod tools;
+pub mod ulid;
 pub mod utils;
+pub mod validation;
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::serde_helpers::DEFAULT_PAGE_SIZE;
+
 #[derive(Debug, Deserialize)]
 pub struct PageParams {
     pub current: Option<i64>,
@@ -21,3 +23,73 @@ pub fn to_offset_limit(current: usize, size: usize) -> (usize, usize, i64, i64)
     let limit = size as i64;
     (current, size, offset, limit)
 }
+
+/// Paginated list response.
+///
+/// `pages` is `ceil(total / size)`; a non-positive `size` falls back to
+/// [`DEFAULT_PAGE_SIZE`] so it never divides by zero.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub records: Vec<T>,
+    pub total: i64,
+    pub current: i64,
+    pub size: i64,
+    pub pages: i64,
+}
+
+impl<T> Page<T> {
+    pub fn new(records: Vec<T>, total: i64, current: i64, size: i64) -> Self {
+        let size = if size <= 0 { DEFAULT_PAGE_SIZE } else { size };
+        let total = total.max(0);
+        Self {
+            records,
+            total,
+            current,
+            size,
+            pages: (total + size - 1) / size,
+        }
+    }
+
+    /// Transform the records, keeping the pagination metadata.
+    pub fn map<U, F>(self, f: F) -> Page<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        Page {
+            records: self.records.into_iter().map(f).collect(),
+            total: self.total,
+            current: self.current,
+            size: self.size,
+            pages: self.pages,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_computes_pages() {
+        assert_eq!(Page::<i32>::new(vec![], 0, 1, 10).pages, 0);
+        assert_eq!(Page::<i32>::new(vec![], 10, 1, 10).pages, 1);
+        assert_eq!(Page::<i32>::new(vec![], 11, 1, 10).pages, 2);
+    }
+
+    #[test]
+    fn test_page_zero_size_uses_default() {
+        let page = Page::<i32>::new(vec![], 25, 1, 0);
+        assert_eq!(page.size, DEFAULT_PAGE_SIZE);
+        assert_eq!(page.pages, 3);
+    }
+
+    #[test]
+    fn test_page_map_keeps_metadata() {
+        let page = Page::new(vec![1, 2], 12, 2, 10).map(|n| n.to_string());
+        assert_eq!(page.records, vec!["1", "2"]);
+        assert_eq!(
+            (page.total, page.current, page.size, page.pages),
+            (12, 2, 10, 2)
+        );
+    }
+}
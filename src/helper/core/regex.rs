@@ -1,6 +1,153 @@
+//! Precompiled regexes shared across the crate.
+//!
+//! Each pattern compiles once, on first use. Reuse these instead of calling `Regex::new` per
+//! request, or look one up by name with [`lookup`] / [`validate_with`].
+
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 /// Regex for matching English words.
 pub static ENGLISH_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^[a-zA-Z]+$").expect("Failed to compile regex"));
+
+/// Letters and digits, starting with a letter.
+pub static ENGLISH_NUMBER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9]*$").expect("Failed to compile regex"));
+
+// Notes:
+// - These validators are intended for common application validation, not for strict telecom compliance.
+// - Mainland China mobile numbers change over time; keep regex updated if your business needs stricter rules.
+
+/// Mainland China mobile (simple): 11 digits, starts with 1, second digit 3-9.
+/// Examples: 13800138000
+pub static CN_MOBILE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^1[3-9]\d{9}$").expect("Failed to compile CN_MOBILE_REGEX"));
+
+/// China landline (simple):
+/// - With area code: 0xx-xxxxxxx / 0xxx-xxxxxxxx
+/// - Without area code: xxxxxxx / xxxxxxxx
+/// - Optional extension: -xxxx (1-6 digits)
+///
+/// Examples: 010-88886666, 02088886666, 0571-88886666-123, 88886666
+pub static CN_LANDLINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:(?:0\d{2,3}-?)?\d{7,8})(?:-\d{1,6})?$")
+        .expect("Failed to compile CN_LANDLINE_REGEX")
+});
+
+/// Practical email regex (not fully RFC 5322, but good for most cases)
+/// - local part: letters/digits and common symbols
+/// - domain: labels separated by dots, TLD length >= 2
+pub static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)+$")
+        .expect("Failed to compile EMAIL_REGEX")
+});
+
+/// URL slug: lowercase letters and digits in groups joined by single hyphens.
+/// Examples: hello-world, v2
+pub static SLUG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z0-9]+(?:-[a-z0-9]+)*$").expect("Failed to compile SLUG_REGEX"));
+
+/// Hyphenated UUID of any version, either case.
+/// Examples: 550e8400-e29b-41d4-a716-446655440000
+pub static UUID_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$")
+        .expect("Failed to compile UUID_REGEX")
+});
+
+/// `(name, validation error code, regex)` for [`lookup`] and [`validate_with`].
+static NAMED: [(&str, &str, &Lazy<Regex>); 7] = [
+    ("english", "is_not_english", &ENGLISH_REGEX),
+    (
+        "english_number",
+        "is_not_english_number",
+        &ENGLISH_NUMBER_REGEX,
+    ),
+    ("cn_mobile", "is_not_cn_mobile", &CN_MOBILE_REGEX),
+    ("cn_landline", "is_not_cn_landline", &CN_LANDLINE_REGEX),
+    ("email", "is_not_email", &EMAIL_REGEX),
+    ("slug", "is_not_slug", &SLUG_REGEX),
+    ("uuid", "is_not_uuid", &UUID_REGEX),
+];
+
+/// Names accepted by [`lookup`] and [`validate_with`].
+pub fn regex_names() -> impl Iterator<Item = &'static str> {
+    NAMED.iter().map(|(name, _, _)| *name)
+}
+
+/// The shared regex registered under `name`, e.g. `"email"` (see [`regex_names`]).
+pub fn lookup(name: &str) -> Option<&'static Regex> {
+    NAMED
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, _, regex)| Lazy::force(regex))
+}
+
+/// Check `input` against the regex named `name`.
+///
+/// Fails with code `is_not_<name>` on a mismatch and `unknown_regex` if no regex has that
+/// name, so a typo in a name rejects input instead of silently accepting it.
+///
+/// # Example
+/// ```rust,ignore
+/// validate_with("slug", "hello-world")?;
+/// ```
+pub fn validate_with(name: &str, input: &str) -> Result<(), validator::ValidationError> {
+    let Some((_, code, regex)) = NAMED.iter().find(|(n, _, _)| *n == name) else {
+        let mut err = validator::ValidationError::new("unknown_regex");
+        err.add_param("name".into(), &name);
+        return Err(err);
+    };
+    if regex.is_match(input) {
+        Ok(())
+    } else {
+        Err(validator::ValidationError::new(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_name_resolves() {
+        for name in regex_names() {
+            assert!(lookup(name).is_some(), "{name}");
+        }
+        assert!(lookup("nope").is_none());
+    }
+
+    #[test]
+    fn patterns_match_examples() {
+        let cases = [
+            ("english", "Hello", "Hello1"),
+            ("english_number", "abc123", "1abc"),
+            ("cn_mobile", "13800138000", "12800138000"),
+            ("cn_landline", "0571-88886666-123", "0a0-88886666"),
+            ("email", "user@example.com", "user@example"),
+            ("slug", "hello-world-2", "Hello--world"),
+            (
+                "uuid",
+                "550e8400-e29b-41d4-a716-446655440000",
+                "550e8400e29b41d4a716446655440000",
+            ),
+        ];
+        for (name, valid, invalid) in cases {
+            assert!(validate_with(name, valid).is_ok(), "{name}: {valid}");
+            let err = validate_with(name, invalid).unwrap_err();
+            assert_eq!(err.code, format!("is_not_{name}"));
+        }
+    }
+
+    #[test]
+    fn unknown_name_rejects_input() {
+        let err = validate_with("emial", "user@example.com").unwrap_err();
+        assert_eq!(err.code, "unknown_regex");
+    }
+
+    #[test]
+    fn lookup_returns_the_same_compiled_regex() {
+        let a = lookup("email").unwrap() as *const Regex;
+        let b = lookup("email").unwrap() as *const Regex;
+        assert_eq!(a, b);
+    }
+}
@@ -21,7 +21,9 @@
 
 use std::fmt::Display;
 use std::future::Future;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
 
 /// Configuration for retry behavior with exponential backoff.
 #[derive(Debug, Clone)]
@@ -94,6 +96,29 @@ impl RetryPolicy {
     }
 }
 
+/// The final error of a retried operation, with how many attempts were made and how long
+/// they took in total (including backoff sleeps).
+///
+/// Returned by [`retry_async_detailed`] so callers can log e.g.
+/// "download_object failed after 4 attempt(s) over 2.1s: connection reset by peer".
+#[derive(Debug, Error)]
+#[error("failed after {attempts} attempt(s) over {elapsed:?}: {last}")]
+pub struct RetryError<E> {
+    /// Total attempts, including the first one.
+    pub attempts: usize,
+    /// Time from the first attempt to the final failure.
+    pub elapsed: Duration,
+    /// The error returned by the last attempt.
+    pub last: E,
+}
+
+impl<E> RetryError<E> {
+    /// Drop the retry context and keep the last error.
+    pub fn into_inner(self) -> E {
+        self.last
+    }
+}
+
 /// Simple deterministic pseudo-jitter: returns a value in [0.0, 1.0) based on attempt.
 /// Not cryptographically random, but sufficient to spread retry waves.
 fn pseudo_jitter_fraction(attempt: u32) -> f64 {
@@ -166,49 +191,37 @@ where
     F: Fn() -> Fut,
     Fut: Future<Output = Result<T, E>>,
 {
-    let mut attempt: u32 = 0;
-
-    loop {
-        match operation().await {
-            Ok(value) => {
-                if attempt > 0 {
-                    tracing::info!(
-                        "retry succeeded: label={} after {} attempt(s)",
-                        label,
-                        attempt + 1,
-                    );
-                }
-                return Ok(value);
-            }
-            Err(err) => {
-                if attempt < policy.max_retries && is_transient_error(&err) {
-                    attempt += 1;
-                    let delay = policy.backoff_delay(attempt);
-                    tracing::warn!(
-                        "retry transient error: label={} attempt={}/{} delay_ms={} err={}",
-                        label,
-                        attempt,
-                        policy.max_retries,
-                        delay.as_millis(),
-                        err,
-                    );
-                    tokio::time::sleep(delay).await;
-                    continue;
-                }
+    retry_loop(policy, label, is_transient_error, operation)
+        .await
+        .map_err(RetryError::into_inner)
+}
 
-                // Non-transient or retries exhausted
-                if attempt > 0 {
-                    tracing::error!(
-                        "retry exhausted: label={} attempts={} err={}",
-                        label,
-                        attempt + 1,
-                        err,
-                    );
-                }
-                return Err(err);
-            }
-        }
-    }
+/// Like [`retry_async`], but the error carries the attempt count and total elapsed time.
+///
+/// # Returns
+/// The successful result `T`, or a [`RetryError`] wrapping the last error. Errors that are
+/// not retried (non-transient) are wrapped too, with `attempts == 1`.
+///
+/// # Example
+/// ```rust,ignore
+/// let bytes = retry_async_detailed(&policy, "download_object", || storage.download(&path))
+///     .await
+///     .map_err(|e| {
+///         tracing::error!("download_object {}", e); // "failed after 4 attempt(s) over 2.1s: ..."
+///         e.last
+///     })?;
+/// ```
+pub async fn retry_async_detailed<T, E, F, Fut>(
+    policy: &RetryPolicy,
+    label: &str,
+    operation: F,
+) -> Result<T, RetryError<E>>
+where
+    E: Display,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_loop(policy, label, is_transient_error, operation).await
 }
 
 /// Execute an async operation with retries, using a custom predicate to decide retryability.
@@ -233,6 +246,25 @@ where
     Fut: Future<Output = Result<T, E>>,
     P: Fn(&E) -> bool,
 {
+    retry_loop(policy, label, should_retry, operation)
+        .await
+        .map_err(RetryError::into_inner)
+}
+
+/// Shared retry loop behind the public helpers.
+async fn retry_loop<T, E, F, Fut, P>(
+    policy: &RetryPolicy,
+    label: &str,
+    should_retry: P,
+    operation: F,
+) -> Result<T, RetryError<E>>
+where
+    E: Display,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+{
+    let started = Instant::now();
     let mut attempt: u32 = 0;
 
     loop {
@@ -263,15 +295,22 @@ where
                     continue;
                 }
 
+                // Non-transient or retries exhausted
+                let elapsed = started.elapsed();
                 if attempt > 0 {
                     tracing::error!(
-                        "retry exhausted: label={} attempts={} err={}",
+                        "retry exhausted: label={} attempts={} elapsed_ms={} err={}",
                         label,
                         attempt + 1,
+                        elapsed.as_millis(),
                         err,
                     );
                 }
-                return Err(err);
+                return Err(RetryError {
+                    attempts: attempt as usize + 1,
+                    elapsed,
+                    last: err,
+                });
             }
         }
     }
@@ -585,6 +624,48 @@ mod tests {
         assert_eq!(call_count.load(Ordering::SeqCst), 1);
     }
 
+    // ── retry_async_detailed tests ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_retry_async_detailed_reports_attempts_and_elapsed() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            base_delay_ms: 5,
+            max_delay_ms: 20,
+            jitter: 0.0,
+        };
+
+        let err = retry_async_detailed(&policy, "test_detailed", || async {
+            Err::<i32, _>(TestError("connection reset by peer".into()))
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.attempts, 3);
+        // Backoff sleeps of 5ms + 10ms are included
+        assert!(err.elapsed >= Duration::from_millis(15));
+        assert_eq!(err.last.0, "connection reset by peer");
+        assert!(
+            err.to_string()
+                .starts_with("failed after 3 attempt(s) over ")
+        );
+        assert!(err.to_string().ends_with(": connection reset by peer"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_async_detailed_non_transient_is_one_attempt() {
+        let policy = RetryPolicy::default();
+
+        let err = retry_async_detailed(&policy, "test_detailed_permanent", || async {
+            Err::<i32, _>(TestError("not found".into()))
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.attempts, 1);
+        assert_eq!(err.into_inner().0, "not found");
+    }
+
     #[tokio::test]
     async fn test_retry_async_zero_max_retries_no_retry() {
         let call_count = AtomicU32::new(0);
@@ -1,10 +1,9 @@
 use core::fmt;
 use std::str::FromStr;
 
-use chrono::Local;
-use regex::Regex;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat};
 use serde::{
-    Deserialize, Deserializer, Serializer,
+    Deserialize, Deserializer, Serialize, Serializer,
     de::{self, DeserializeOwned},
 };
 use serde_json::Value;
@@ -12,6 +11,7 @@ use std::fmt::Debug;
 
 use super::{
     hashid,
+    regex::{ENGLISH_NUMBER_REGEX, ENGLISH_REGEX},
     snowflake::{generate_snowflake_id, generate_sonyflake_id},
 };
 
@@ -34,10 +34,7 @@ where
         Value::Number(num) => num
             .as_i64()
             .ok_or_else(|| serde::de::Error::custom("Invalid number")),
-        Value::String(s) => hashid::decode_i64(s.as_str())
-            .to_string()
-            .parse::<i64>()
-            .map_err(serde::de::Error::custom),
+        Value::String(s) => hashid::try_decode_id(s.as_str()).map_err(serde::de::Error::custom),
         _ => Err(serde::de::Error::custom("Expected a number or string")),
     }
 }
@@ -58,10 +55,9 @@ where
                     Value::Number(num) => num
                         .as_i64()
                         .ok_or_else(|| serde::de::Error::custom("Invalid number"))?,
-                    Value::String(s) => hashid::decode_i64(s.as_str())
-                        .to_string()
-                        .parse::<i64>()
-                        .map_err(serde::de::Error::custom)?,
+                    Value::String(s) => {
+                        hashid::try_decode_id(s.as_str()).map_err(serde::de::Error::custom)?
+                    }
                     _ => {
                         return Err(serde::de::Error::custom(
                             "Expected a number or string in array",
@@ -97,16 +93,9 @@ where
             if s.is_empty() {
                 return Ok(None);
             }
-            // Decode the string to u64, then convert to i64
-            let decoded = hashid::decode_i64(s.as_str())
-                .to_string()
-                .parse::<i64>()
-                .map_err(|_| serde::de::Error::custom("Failed to decode string"))?;
-
-            // Try to convert u64 to i64
-            i64::try_from(decoded)
+            hashid::try_decode_id(s.as_str())
                 .map(Some)
-                .map_err(|_| serde::de::Error::custom("Decoded value is out of range for i64"))
+                .map_err(serde::de::Error::custom)
         }
         _ => Err(serde::de::Error::custom(
             "Expected a null, number, or string",
@@ -131,10 +120,9 @@ where
                     Value::Number(num) => num
                         .as_i64()
                         .ok_or_else(|| serde::de::Error::custom("Invalid number"))?,
-                    Value::String(s) => hashid::decode_i64(s.as_str())
-                        .to_string()
-                        .parse::<i64>()
-                        .map_err(serde::de::Error::custom)?,
+                    Value::String(s) => {
+                        hashid::try_decode_id(s.as_str()).map_err(serde::de::Error::custom)?
+                    }
                     _ => {
                         return Err(serde::de::Error::custom(
                             "Expected a number or string in array",
@@ -213,7 +201,7 @@ pub fn serialize_i64<S>(x: &i64, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let encode_u64 = hashid::encode_i64(*x);
+    let encode_u64 = hashid::encode_id(*x);
     serializer.serialize_str(&encode_u64)
 }
 
@@ -227,7 +215,7 @@ where
     match x {
         Some(value) => {
             // tracing::error!("serialize_option_i64 value: {}", value);
-            let encode_u64 = hashid::encode_i64(*value);
+            let encode_u64 = hashid::encode_id(*value);
             // tracing::error!("serialize_option_i64: {}", encode_u64);
             serializer.serialize_str(&encode_u64)
         }
@@ -245,7 +233,7 @@ where
     use serde::ser::SerializeSeq;
     let mut seq = serializer.serialize_seq(Some(x.len()))?;
     for element in x {
-        let encode_u64 = hashid::encode_i64(*element);
+        let encode_u64 = hashid::encode_id(*element);
         seq.serialize_element(&encode_u64)?;
     }
     seq.end()
@@ -264,6 +252,18 @@ where
     }
 }
 
+///
+/// Serialize Option<Vec<i64>> type, each element hashid-encoded (`None` -> null)
+///
+/// Counterpart of `deserialize_vec_option_i64`; same output as `serialize_vec_option_i64`.
+///
+pub fn serialize_option_vec_i64<S>(x: &Option<Vec<i64>>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_vec_option_i64(x, serializer)
+}
+
 ///
 /// Generate snowflake id
 ///
@@ -299,6 +299,65 @@ pub fn now_datetime() -> Option<chrono::NaiveDateTime> {
     Some(Local::now().naive_local())
 }
 
+///
+/// Deserialize a timestamp in mixed formats into a UTC `NaiveDateTime`
+///
+/// Accepts:
+/// - RFC3339 strings with offset (`2024-03-01T16:00:00+08:00`), converted to UTC
+/// - `YYYY-MM-DD HH:MM:SS` / `YYYY-MM-DDTHH:MM:SS` (optional fraction), taken as UTC
+/// - a bare date `YYYY-MM-DD`, taken as midnight UTC
+/// - epoch milliseconds as a JSON number
+///
+pub fn deserialize_flexible_datetime<'de, D>(deserializer: D) -> Result<NaiveDateTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::Number(num) => num
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| {
+                de::Error::custom(format!("Invalid epoch milliseconds timestamp: {}", num))
+            }),
+        Value::String(s) => parse_flexible_datetime(s.trim()).ok_or_else(|| {
+            de::Error::custom(format!(
+                "Invalid datetime '{}': expected RFC3339, 'YYYY-MM-DD HH:MM:SS', 'YYYY-MM-DD' or epoch millis",
+                s
+            ))
+        }),
+        other => Err(de::Error::custom(format!(
+            "Expected a datetime string or epoch millis, got {}",
+            other
+        ))),
+    }
+}
+
+fn parse_flexible_datetime(s: &str) -> Option<NaiveDateTime> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.naive_utc());
+    }
+    for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(dt);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+}
+
+///
+/// Serialize a UTC `NaiveDateTime` as RFC3339 (`2024-03-01T08:00:00Z`)
+///
+pub fn serialize_datetime_rfc3339<S>(x: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&x.and_utc().to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
 ///
 /// Default current page
 ///
@@ -362,6 +421,34 @@ where
     }
 }
 
+///
+/// Whitespace-only string to None, otherwise parse the trimmed value
+///
+pub fn trimmed_string_as_none<'de, D, T>(de: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let opt = Option::<String>::deserialize(de)?;
+    match opt.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => FromStr::from_str(s).map_err(de::Error::custom).map(Some),
+    }
+}
+
+///
+/// Empty array to None (elements are kept as-is)
+///
+pub fn empty_vec_as_none<'de, D, T>(de: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let opt = Option::<Vec<T>>::deserialize(de)?;
+    Ok(opt.filter(|v| !v.is_empty()))
+}
+
 ///
 /// Validate and normalize page size
 /// If size is not in the allowed range, return the default value
@@ -414,23 +501,14 @@ where
 ///
 /// Validate and normalize current page number
 /// If current is less than MIN_PAGE_NUMBER, return MIN_PAGE_NUMBER
-/// If current is greater than MAX_PAGE_NUMBER, return MIN_PAGE_NUMBER
+/// If current is greater than MAX_PAGE_NUMBER, return MAX_PAGE_NUMBER
 ///
 pub fn normalize_current<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let current: Option<i64> = Option::deserialize(deserializer)?;
-    Ok(current.map(|c| {
-        if c < MIN_PAGE_NUMBER {
-            MIN_PAGE_NUMBER
-        } else if c > MAX_PAGE_NUMBER {
-            // MAX_PAGE_NUMBER
-            MIN_PAGE_NUMBER
-        } else {
-            c
-        }
-    }))
+    Ok(current.map(|c| c.clamp(MIN_PAGE_NUMBER, MAX_PAGE_NUMBER)))
 }
 
 ///
@@ -494,8 +572,7 @@ where
 /// Validate English-only string
 ///
 pub fn validate_english(input: &str) -> Result<(), validator::ValidationError> {
-    let re = Regex::new(r"^[a-zA-Z]+$").expect("Failed to compile regex");
-    if !re.is_match(input) {
+    if !ENGLISH_REGEX.is_match(input) {
         return Err(validator::ValidationError::new("is_not_english"));
     }
     Ok(())
@@ -505,8 +582,7 @@ pub fn validate_english(input: &str) -> Result<(), validator::ValidationError> {
 /// Validate alphanumeric string (must start with a letter)
 ///
 pub fn validate_english_number(input: &str) -> Result<(), validator::ValidationError> {
-    let re = Regex::new(r"^[A-Za-z][A-Za-z0-9]*$").expect("Failed to compile regex");
-    if !re.is_match(input) {
+    if !ENGLISH_NUMBER_REGEX.is_match(input) {
         return Err(validator::ValidationError::new("is_not_english_number"));
     }
     Ok(())
@@ -545,9 +621,106 @@ where
     })
 }
 
+///
+/// Common list-endpoint query: normalized pagination, sort order, search and date range.
+///
+/// The search key is allowlisted per endpoint in [`ListQuery::to_params`], so the same
+/// struct can be reused everywhere.
+///
+/// # Example
+/// ```rust,ignore
+/// const USER_SEARCH_KEYS: &[&str] = &["name", "mobile"];
+///
+/// async fn list_users(Query(query): Query<ListQuery>) -> AppResult<Json<PageResponse<User>>> {
+///     let params = query.to_params(USER_SEARCH_KEYS);
+///     let (items, total) = UserRepo::list(&params).await?;
+///     ...
+/// }
+/// ```
+///
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListQuery {
+    #[serde(default, deserialize_with = "normalize_current")]
+    pub current: Option<i64>,
+    #[serde(default, deserialize_with = "normalize_page_size")]
+    pub size: Option<i64>,
+    #[serde(default, deserialize_with = "normalize_order")]
+    pub order: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub search_key: Option<String>,
+    #[serde(default, deserialize_with = "normalize_search_value")]
+    pub search_value: Option<String>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "empty_string_as_none")]
+    pub to: Option<NaiveDate>,
+}
+
+///
+/// Clean list parameters produced by [`ListQuery::to_params`], ready for repositories.
+///
+/// `from` is inclusive (start of day) and `to` is exclusive (start of the day after),
+/// so filters can be written as `created_at >= from AND created_at < to`.
+///
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListParams {
+    pub current: i64,
+    pub size: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub order: String,
+    pub search_key: Option<String>,
+    pub search_value: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+impl ListQuery {
+    ///
+    /// Build [`ListParams`], applying defaults and the search-key allowlist.
+    ///
+    /// A search is only kept when both the key is allowed and the value is non-empty.
+    /// An inverted date range is swapped rather than rejected.
+    ///
+    pub fn to_params(&self, allowed_search_keys: &[&str]) -> ListParams {
+        let current = self.current.unwrap_or(MIN_PAGE_NUMBER);
+        let size = self.size.unwrap_or(DEFAULT_PAGE_SIZE);
+
+        let (search_key, search_value) = match (&self.search_key, &self.search_value) {
+            (Some(k), Some(v)) if allowed_search_keys.contains(&k.as_str()) => {
+                (Some(k.clone()), Some(v.clone()))
+            }
+            _ => (None, None),
+        };
+
+        let (from, to) = match (self.from, self.to) {
+            (Some(f), Some(t)) if f > t => (Some(t), Some(f)),
+            range => range,
+        };
+
+        ListParams {
+            current,
+            size,
+            offset: (current - 1) * size,
+            limit: size,
+            order: self
+                .order
+                .clone()
+                .unwrap_or_else(|| DEFAULT_ORDER.to_string()),
+            search_key,
+            search_value,
+            from: from.and_then(|d| d.and_hms_opt(0, 0, 0)),
+            to: to
+                .and_then(|d| d.succ_opt())
+                .and_then(|d| d.and_hms_opt(0, 0, 0)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::helper::core::hashid::{decode_i64, encode_i64};
+    use crate::helper::core::hashid::{decode_i64, encode_i64, encode_id};
 
     #[test]
     fn test_encode() {
@@ -556,6 +729,192 @@ mod tests {
         println!("Encoded value: {}", value);
     }
 
+    #[test]
+    fn test_list_query_defaults() {
+        let query: super::ListQuery = serde_json::from_str("{}").unwrap();
+        let params = query.to_params(&["name"]);
+        assert_eq!(params.current, 1);
+        assert_eq!(params.size, super::DEFAULT_PAGE_SIZE);
+        assert_eq!(params.offset, 0);
+        assert_eq!(params.order, "desc");
+        assert_eq!(params.search_key, None);
+        assert_eq!(params.from, None);
+    }
+
+    #[test]
+    fn test_list_query_normalizes_and_allowlists() {
+        let query: super::ListQuery = serde_json::from_str(
+            r#"{"current":3,"size":33,"order":"ASC","searchKey":"name","searchValue":"  bob; drop ","from":"2024-03-10","to":"2024-03-01"}"#,
+        )
+        .unwrap();
+
+        let params = query.to_params(&["name"]);
+        assert_eq!(params.size, super::DEFAULT_PAGE_SIZE);
+        assert_eq!(params.offset, 20);
+        assert_eq!(params.order, "asc");
+        assert_eq!(params.search_key.as_deref(), Some("name"));
+        assert_eq!(params.search_value.as_deref(), Some("bob drop"));
+        assert_eq!(params.from.unwrap().to_string(), "2024-03-01 00:00:00");
+        assert_eq!(params.to.unwrap().to_string(), "2024-03-11 00:00:00");
+
+        let params = query.to_params(&["mobile"]);
+        assert_eq!(params.search_key, None);
+        assert_eq!(params.search_value, None);
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+    struct Ids {
+        #[serde(
+            serialize_with = "super::serialize_vec_i64",
+            deserialize_with = "super::deserialize_vec_i64"
+        )]
+        ids: Vec<i64>,
+        #[serde(
+            serialize_with = "super::serialize_option_vec_i64",
+            deserialize_with = "super::deserialize_vec_option_i64"
+        )]
+        maybe_ids: Option<Vec<i64>>,
+    }
+
+    #[test]
+    fn test_vec_i64_hashid_round_trip() {
+        let ids = Ids {
+            ids: vec![1, 594031369676525600],
+            maybe_ids: Some(vec![42]),
+        };
+        let json = serde_json::to_value(&ids).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "ids": [encode_id(1), encode_id(594031369676525600)],
+                "maybe_ids": [encode_id(42)],
+            })
+        );
+        let back: Ids = serde_json::from_value(json).unwrap();
+        assert_eq!(back, ids);
+
+        let empty = Ids {
+            ids: vec![],
+            maybe_ids: None,
+        };
+        let json = serde_json::to_value(&empty).unwrap();
+        assert_eq!(json, serde_json::json!({"ids": [], "maybe_ids": null}));
+        assert_eq!(serde_json::from_value::<Ids>(json).unwrap(), empty);
+    }
+
+    #[derive(serde::Deserialize, Debug)]
+    struct OneId {
+        #[serde(deserialize_with = "super::deserialize_i64")]
+        #[allow(dead_code)]
+        id: i64,
+        #[serde(default, deserialize_with = "super::deserialize_option_i64")]
+        #[allow(dead_code)]
+        parent: Option<i64>,
+    }
+
+    #[test]
+    fn test_garbage_hashids_are_errors_not_panics() {
+        for bad in ["!!!", "ZZZZZZZZZZZZZ", "0123456789ABCDEFG", "ü"] {
+            let err = serde_json::from_value::<OneId>(serde_json::json!({ "id": bad }))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("hashid"), "{err}");
+
+            let parent = serde_json::json!({ "id": 1, "parent": bad });
+            assert!(serde_json::from_value::<OneId>(parent).is_err());
+
+            let ids = serde_json::json!({ "ids": [bad], "maybe_ids": [bad] });
+            assert!(serde_json::from_value::<Ids>(ids).is_err());
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    struct At {
+        #[serde(
+            serialize_with = "super::serialize_datetime_rfc3339",
+            deserialize_with = "super::deserialize_flexible_datetime"
+        )]
+        at: chrono::NaiveDateTime,
+    }
+
+    fn parse_at(value: serde_json::Value) -> Result<String, serde_json::Error> {
+        serde_json::from_value::<At>(serde_json::json!({ "at": value })).map(|a| a.at.to_string())
+    }
+
+    #[test]
+    fn test_flexible_datetime_normalizes_to_utc() {
+        let expected = "2024-03-01 08:00:00";
+        assert_eq!(
+            parse_at("2024-03-01T16:00:00+08:00".into()).unwrap(),
+            expected
+        );
+        assert_eq!(parse_at("2024-03-01T08:00:00Z".into()).unwrap(), expected);
+        assert_eq!(parse_at("2024-03-01 08:00:00".into()).unwrap(), expected);
+        assert_eq!(parse_at(1709280000000_i64.into()).unwrap(), expected);
+        assert_eq!(
+            parse_at("2024-03-01".into()).unwrap(),
+            "2024-03-01 00:00:00"
+        );
+
+        let err = parse_at("yesterday".into()).unwrap_err().to_string();
+        assert!(err.contains("Invalid datetime 'yesterday'"), "{}", err);
+        assert!(parse_at(true.into()).is_err());
+    }
+
+    #[test]
+    fn test_serialize_datetime_rfc3339_round_trip() {
+        let at: At = serde_json::from_str(r#"{"at":"2024-03-01T16:00:00+08:00"}"#).unwrap();
+        let json = serde_json::to_string(&at).unwrap();
+        assert_eq!(json, r#"{"at":"2024-03-01T08:00:00Z"}"#);
+        let back: At = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.at, at.at);
+    }
+
+    #[test]
+    fn test_normalize_current_clamps() {
+        #[derive(serde::Deserialize)]
+        struct Q {
+            #[serde(default, deserialize_with = "super::normalize_current")]
+            current: Option<i64>,
+        }
+        let current = |v: i64| {
+            serde_json::from_value::<Q>(serde_json::json!({ "current": v }))
+                .unwrap()
+                .current
+        };
+        assert_eq!(current(5000), Some(super::MAX_PAGE_NUMBER));
+        assert_eq!(current(0), Some(super::MIN_PAGE_NUMBER));
+        assert_eq!(current(-3), Some(super::MIN_PAGE_NUMBER));
+        assert_eq!(current(7), Some(7));
+    }
+
+    #[derive(serde::Deserialize, Debug)]
+    struct Filters {
+        #[serde(default, deserialize_with = "super::trimmed_string_as_none")]
+        name: Option<String>,
+        #[serde(default, deserialize_with = "super::trimmed_string_as_none")]
+        age: Option<i32>,
+        #[serde(default, deserialize_with = "super::empty_vec_as_none")]
+        tags: Option<Vec<String>>,
+    }
+
+    #[test]
+    fn test_trimmed_string_and_empty_vec_as_none() {
+        let f: Filters = serde_json::from_str(r#"{"name":"  ","age":" 18 ","tags":[]}"#).unwrap();
+        assert_eq!(f.name, None);
+        assert_eq!(f.age, Some(18));
+        assert_eq!(f.tags, None);
+
+        let f: Filters = serde_json::from_str(r#"{"name":" bob ","tags":["","x"]}"#).unwrap();
+        assert_eq!(f.name.as_deref(), Some("bob"));
+        assert_eq!(f.age, None);
+        assert_eq!(f.tags, Some(vec!["".to_string(), "x".to_string()]));
+
+        assert!(serde_json::from_str::<Filters>(r#"{"age":"abc"}"#).is_err());
+        let f: Filters = serde_json::from_str(r#"{"name":null,"tags":null}"#).unwrap();
+        assert_eq!((f.name, f.tags), (None, None));
+    }
+
     #[test]
     fn test_decode() {
         let n: &str = "H8Q8WT584400";
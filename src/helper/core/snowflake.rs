@@ -1,6 +1,8 @@
 use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use thiserror::Error;
+
 const EPOCH: u64 = 1609459200000; // 2021-01-01 00:00:00 UTC in milliseconds
 const WORKER_ID_BITS: u64 = 5;
 const DATA_CENTER_ID_BITS: u64 = 5;
@@ -14,11 +16,46 @@ const WORKER_ID_SHIFT: u64 = SEQUENCE_BITS;
 const DATA_CENTER_ID_SHIFT: u64 = SEQUENCE_BITS + WORKER_ID_BITS;
 const TIMESTAMP_SHIFT: u64 = SEQUENCE_BITS + WORKER_ID_BITS + DATA_CENTER_ID_BITS;
 
+/// Fields encoded in a Snowflake id, see [`SnowflakeIdGenerator::decompose`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnowflakeParts {
+    /// Generation time as Unix milliseconds (`EPOCH` already added back).
+    pub timestamp_ms: u64,
+    pub datacenter_id: u64,
+    pub worker_id: u64,
+    pub sequence: u64,
+}
+
+/// What to do when the system clock moves backwards between two ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockPolicy {
+    /// Keep issuing ids from the last seen timestamp until the clock catches up (default).
+    ///
+    /// Never fails, but a large step back burns through the sequence and keeps ids pinned
+    /// to the old timestamp.
+    #[default]
+    WaitForward,
+    /// Return [`SnowflakeError::ClockMovedBackwards`] from [`SnowflakeIdGenerator::try_generate`]
+    /// when the clock moved back by more than `tolerance_ms`; smaller drifts behave like
+    /// `WaitForward`.
+    ErrorOnRollback { tolerance_ms: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SnowflakeError {
+    #[error("clock moved backwards by {drift_ms}ms (last timestamp {last_timestamp_ms})")]
+    ClockMovedBackwards {
+        drift_ms: u64,
+        last_timestamp_ms: u64,
+    },
+}
+
 pub struct SnowflakeIdGenerator {
     worker_id: u64,
     data_center_id: u64,
     sequence: u64,
     last_timestamp: u64,
+    clock_policy: ClockPolicy,
 }
 
 impl SnowflakeIdGenerator {
@@ -37,13 +74,50 @@ impl SnowflakeIdGenerator {
             data_center_id,
             sequence: 0,
             last_timestamp: 0,
+            clock_policy: ClockPolicy::default(),
+        }
+    }
+
+    /// Set how clock rollbacks are handled (default [`ClockPolicy::WaitForward`]).
+    pub fn with_clock_policy(mut self, clock_policy: ClockPolicy) -> Self {
+        self.clock_policy = clock_policy;
+        self
+    }
+
+    /// Split an id into its timestamp, node and sequence parts.
+    pub fn decompose(id: u64) -> SnowflakeParts {
+        SnowflakeParts {
+            timestamp_ms: (id >> TIMESTAMP_SHIFT) + EPOCH,
+            datacenter_id: (id >> DATA_CENTER_ID_SHIFT) & MAX_DATA_CENTER_ID,
+            worker_id: (id >> WORKER_ID_SHIFT) & MAX_WORKER_ID,
+            sequence: id & SEQUENCE_MASK,
         }
     }
 
+    /// Generate the next id.
+    ///
+    /// # Panics
+    /// With [`ClockPolicy::ErrorOnRollback`] when the clock moved back beyond the tolerance;
+    /// use [`try_generate`](Self::try_generate) to handle that case.
     pub fn generate(&mut self) -> u64 {
+        self.try_generate()
+            .unwrap_or_else(|e| panic!("Failed to generate snowflake id: {}", e))
+    }
+
+    /// Generate the next id, reporting clock rollbacks according to the [`ClockPolicy`].
+    pub fn try_generate(&mut self) -> Result<u64, SnowflakeError> {
         let mut timestamp = current_time_millis();
 
         if timestamp < self.last_timestamp {
+            let drift_ms = self.last_timestamp - timestamp;
+            if let ClockPolicy::ErrorOnRollback { tolerance_ms } = self.clock_policy
+                && drift_ms > tolerance_ms
+            {
+                return Err(SnowflakeError::ClockMovedBackwards {
+                    drift_ms,
+                    last_timestamp_ms: self.last_timestamp,
+                });
+            }
             timestamp = self.last_timestamp;
         }
 
@@ -60,10 +134,10 @@ impl SnowflakeIdGenerator {
 
         let time_part = timestamp.saturating_sub(EPOCH);
 
-        (time_part << TIMESTAMP_SHIFT)
+        Ok((time_part << TIMESTAMP_SHIFT)
             | (self.data_center_id << DATA_CENTER_ID_SHIFT)
             | (self.worker_id << WORKER_ID_SHIFT)
-            | self.sequence
+            | self.sequence)
     }
 
     fn wait_for_next_millis(&self, last_timestamp: u64) -> u64 {
@@ -84,17 +158,69 @@ fn current_time_millis() -> u64 {
     since_the_epoch.as_millis() as u64
 }
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 
-static ID_GENERATOR: Lazy<Mutex<SnowflakeIdGenerator>> = Lazy::new(|| {
-    let generator = SnowflakeIdGenerator::new(1, 1);
-    Mutex::new(generator)
-});
+/// Env var read for the worker id when [`init_snowflake`] was not called.
+pub const SNOWFLAKE_WORKER_ID_ENV: &str = "SNOWFLAKE_WORKER_ID";
+/// Env var read for the data center id when [`init_snowflake`] was not called.
+pub const SNOWFLAKE_DATACENTER_ID_ENV: &str = "SNOWFLAKE_DATACENTER_ID";
+
+static ID_GENERATOR: OnceCell<Mutex<SnowflakeIdGenerator>> = OnceCell::new();
+
+/// Configure the node of the global Snowflake generator.
+///
+/// Call once at startup, before the first id is generated, so every process in a cluster
+/// uses a distinct `(worker_id, data_center_id)` pair (each `0..=31`).
+///
+/// # Panics
+/// If the generator was already initialized — by an earlier call or by generating an id.
+///
+/// # Example
+/// ```rust,ignore
+/// use neocrates::helper::core::snowflake::init_snowflake;
+///
+/// init_snowflake(3, 1);
+/// ```
+pub fn init_snowflake(worker_id: u64, data_center_id: u64) {
+    let generator = Mutex::new(SnowflakeIdGenerator::new(worker_id, data_center_id));
+    if ID_GENERATOR.set(generator).is_err() {
+        panic!(
+            "init_snowflake({}, {}) called after the Snowflake generator was already initialized or used; call it once at startup before generating ids",
+            worker_id, data_center_id
+        );
+    }
+}
+
+/// The global generator; lazily built from the env vars (default `1`) when not initialized.
+fn id_generator() -> &'static Mutex<SnowflakeIdGenerator> {
+    ID_GENERATOR.get_or_init(|| {
+        let (worker_id, data_center_id) = node_ids_from_env();
+        Mutex::new(SnowflakeIdGenerator::new(worker_id, data_center_id))
+    })
+}
+
+fn node_ids_from_env() -> (u64, u64) {
+    (
+        env_node_id(SNOWFLAKE_WORKER_ID_ENV),
+        env_node_id(SNOWFLAKE_DATACENTER_ID_ENV),
+    )
+}
+
+fn env_node_id(name: &str) -> u64 {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .unwrap_or_else(|_| panic!("{} must be an integer, got {:?}", name, value)),
+        Err(_) => 1,
+    }
+}
 
 /// Generate a unique ID using the standard Snowflake algorithm.
 ///
 /// This function returns a `u64` identifier.
-/// It uses a static `ID_GENERATOR` initialized with worker_id=1 and data_center_id=1.
+/// The node comes from [`init_snowflake`], or the `SNOWFLAKE_WORKER_ID` /
+/// `SNOWFLAKE_DATACENTER_ID` env vars (default `1`) when it was not called.
 ///
 /// # Returns
 /// - `u64`: A unique snowflake ID.
@@ -107,7 +233,7 @@ static ID_GENERATOR: Lazy<Mutex<SnowflakeIdGenerator>> = Lazy::new(|| {
 /// println!("Generated UID: {}", uid);
 /// ```
 pub fn generate_snowflake_uid() -> u64 {
-    let mut generator = ID_GENERATOR.lock().expect("Failed to lock ID generator");
+    let mut generator = id_generator().lock().expect("Failed to lock ID generator");
     generator.generate()
 }
 
@@ -115,7 +241,8 @@ pub fn generate_snowflake_uid() -> u64 {
 ///
 /// This function returns an `i64` identifier, which is useful for compatibility with systems
 /// that prefer signed 64-bit integers (e.g., some databases or JSON parsers).
-/// It uses a static `ID_GENERATOR` initialized with worker_id=1 and data_center_id=1.
+/// The node comes from [`init_snowflake`], or the `SNOWFLAKE_WORKER_ID` /
+/// `SNOWFLAKE_DATACENTER_ID` env vars (default `1`) when it was not called.
 ///
 /// # Returns
 /// - `i64`: A unique snowflake ID.
@@ -128,10 +255,23 @@ pub fn generate_snowflake_uid() -> u64 {
 /// println!("Generated ID: {}", id);
 /// ```
 pub fn generate_snowflake_id() -> i64 {
-    let mut generator = ID_GENERATOR.lock().expect("Failed to lock ID generator");
+    let mut generator = id_generator().lock().expect("Failed to lock ID generator");
     generator.generate() as i64
 }
 
+/// Decompose an id produced by [`generate_snowflake_id`].
+///
+/// # Example
+/// ```rust
+/// use neocrates::helper::core::snowflake::{decompose_snowflake_id, generate_snowflake_id};
+///
+/// let parts = decompose_snowflake_id(generate_snowflake_id());
+/// println!("generated at {} by worker {}", parts.timestamp_ms, parts.worker_id);
+/// ```
+pub fn decompose_snowflake_id(id: i64) -> SnowflakeParts {
+    SnowflakeIdGenerator::decompose(id as u64)
+}
+
 static SONYFLAKE: Lazy<Mutex<sonyflake::Sonyflake>> = Lazy::new(|| {
     let sf = sonyflake::Sonyflake::new().unwrap();
     Mutex::new(sf)
@@ -221,6 +361,77 @@ mod tests {
         }
     }
 
+    #[test]
+    fn snowflake_decompose_round_trip() {
+        let before = current_time_millis();
+        let mut generator = SnowflakeIdGenerator::new(17, 9);
+        let first = generator.generate();
+        let second = generator.generate();
+        let after = current_time_millis();
+
+        let parts = SnowflakeIdGenerator::decompose(first);
+        assert_eq!(parts.worker_id, 17);
+        assert_eq!(parts.datacenter_id, 9);
+        assert!(parts.timestamp_ms >= before && parts.timestamp_ms <= after);
+
+        let next = decompose_snowflake_id(second as i64);
+        assert_eq!((next.worker_id, next.datacenter_id), (17, 9));
+        if next.timestamp_ms == parts.timestamp_ms {
+            assert_eq!(next.sequence, parts.sequence + 1);
+        }
+    }
+
+    #[test]
+    fn snowflake_clock_rollback_policies() {
+        let ahead = current_time_millis() + 10_000;
+
+        let mut waiting = SnowflakeIdGenerator::new(1, 1);
+        waiting.last_timestamp = ahead;
+        let id = waiting.try_generate().unwrap();
+        assert_eq!(SnowflakeIdGenerator::decompose(id).timestamp_ms, ahead);
+
+        let mut strict = SnowflakeIdGenerator::new(1, 1)
+            .with_clock_policy(ClockPolicy::ErrorOnRollback { tolerance_ms: 5 });
+        strict.last_timestamp = ahead;
+        match strict.try_generate() {
+            Err(SnowflakeError::ClockMovedBackwards { drift_ms, .. }) => {
+                assert!(drift_ms > 5)
+            }
+            other => panic!("expected rollback error, got {:?}", other),
+        }
+
+        let mut tolerant =
+            SnowflakeIdGenerator::new(1, 1).with_clock_policy(ClockPolicy::ErrorOnRollback {
+                tolerance_ms: 60_000,
+            });
+        tolerant.last_timestamp = ahead;
+        assert!(tolerant.try_generate().is_ok());
+    }
+
+    #[test]
+    fn snowflake_node_ids_from_env() {
+        // SAFETY: only this test writes these variables; a concurrent lazy init just picks up
+        // a valid node id.
+        unsafe {
+            std::env::set_var(SNOWFLAKE_WORKER_ID_ENV, " 7 ");
+            std::env::set_var(SNOWFLAKE_DATACENTER_ID_ENV, "3");
+        }
+        assert_eq!(node_ids_from_env(), (7, 3));
+
+        unsafe {
+            std::env::remove_var(SNOWFLAKE_WORKER_ID_ENV);
+            std::env::remove_var(SNOWFLAKE_DATACENTER_ID_ENV);
+        }
+        assert_eq!(node_ids_from_env(), (1, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "already initialized or used")]
+    fn snowflake_init_after_use_panics() {
+        generate_snowflake_uid();
+        init_snowflake(2, 2);
+    }
+
     #[test]
     fn sonyflake_monotonic_and_unique() {
         let mut prev = generate_sonyflake_id();
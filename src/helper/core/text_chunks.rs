@@ -5,21 +5,111 @@ pub struct ParseResult {
     pub typeid: u32,
     pub size: (f32, f32),
     pub text: String,
+    /// Pages the text of a chunk came from, in order and without duplicates. `page`/`bbox`
+    /// describe the first contributing result only, so a chunk crossing a page break lists
+    /// every page here. Empty on parser input, where `page` alone applies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_pages: Vec<u32>,
+}
+
+impl ParseResult {
+    /// `source_pages` if set, otherwise just `page`.
+    fn pages(&self) -> Vec<u32> {
+        if self.source_pages.is_empty() {
+            vec![self.page]
+        } else {
+            self.source_pages.clone()
+        }
+    }
+}
+
+fn push_pages(pages: &mut Vec<u32>, more: &[u32]) {
+    for page in more {
+        if !pages.contains(page) {
+            pages.push(*page);
+        }
+    }
+}
+
+/// Characters after which a chunk may end without cutting a sentence or paragraph.
+const BOUNDARY_CHARS: [char; 7] = ['.', '。', '!', '?', '！', '？', '\n'];
+
+/// Options for [`smart_chunks_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkOptions {
+    /// Maximum characters per chunk, overlap included.
+    pub max_len: usize,
+    /// When a text has to be split, look back up to this many characters from the hard limit
+    /// for a sentence or paragraph end (`.` `。` `!` `?` `\n` ...) and split there instead.
+    /// 0 always cuts at the limit.
+    pub boundary_slack: usize,
+    /// Repeat the last N characters of each chunk at the start of the next one, so context
+    /// carries over between chunks. Must be smaller than `max_len`.
+    pub overlap: usize,
+}
+
+impl ChunkOptions {
+    /// Hard cuts at `max_len`, no overlap: the behavior of [`smart_chunks`].
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            boundary_slack: 0,
+            overlap: 0,
+        }
+    }
+
+    pub fn with_boundary_slack(mut self, slack: usize) -> Self {
+        self.boundary_slack = slack;
+        self
+    }
+
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap;
+        self
+    }
 }
 
 /// Merges ParseResult entries into chunks not exceeding max_len characters.
 pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResult> {
+    smart_chunks_with(results, &ChunkOptions::new(max_len))
+}
+
+/// Like [`smart_chunks`], but can prefer sentence/paragraph boundaries and overlap chunks,
+/// which gives better input for embedding or LLM prompts.
+///
+/// # Example
+/// ```rust,ignore
+/// // Up to 512 chars, split at a sentence end within the last 100 chars if there is one,
+/// // and start each chunk with the last 50 chars of the previous one.
+/// let options = ChunkOptions::new(512).with_boundary_slack(100).with_overlap(50);
+/// let chunks = smart_chunks_with(results, &options);
+/// ```
+pub fn smart_chunks_with(results: Vec<ParseResult>, options: &ChunkOptions) -> Vec<ParseResult> {
+    let overlap = options.overlap.min(options.max_len.saturating_sub(1));
+    // Leave room for the overlap prefix so chunks stay within max_len
+    let content_len = (options.max_len - overlap).max(1);
+    let chunks = merge_chunks(results, content_len, options.boundary_slack);
+    if overlap == 0 {
+        return chunks;
+    }
+    apply_overlap(chunks, overlap)
+}
+
+fn merge_chunks(results: Vec<ParseResult>, max_len: usize, slack: usize) -> Vec<ParseResult> {
     let mut merged_results = Vec::new();
     let mut buffer = String::new();
     let mut last_page = 0;
     let mut last_bbox = (0.0, 0.0, 0.0, 0.0);
     let mut last_typeid = 0;
     let mut last_size = (0.0, 0.0);
+    let mut buffer_pages: Vec<u32> = Vec::new();
 
     for result in results {
+        let result_pages = result.pages();
         let mut text = result.text;
         while text.chars().count() > max_len {
-            let segment: String = text.chars().take(max_len).collect();
+            let cut = split_point(&text, max_len, slack);
+            let segment: String = text.chars().take(cut).collect();
             if !buffer.is_empty() {
                 merged_results.push(ParseResult {
                     page: last_page,
@@ -27,6 +117,7 @@ pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResul
                     typeid: last_typeid,
                     size: last_size,
                     text: buffer.clone(),
+                    source_pages: std::mem::take(&mut buffer_pages),
                 });
                 buffer.clear();
             }
@@ -36,8 +127,9 @@ pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResul
                 typeid: result.typeid,
                 size: result.size,
                 text: segment,
+                source_pages: result_pages.clone(),
             });
-            text = text.chars().skip(max_len).collect();
+            text = text.chars().skip(cut).collect();
         }
 
         if buffer.chars().count() + text.chars().count() > max_len {
@@ -48,6 +140,7 @@ pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResul
                     typeid: last_typeid,
                     size: last_size,
                     text: buffer.clone(),
+                    source_pages: std::mem::take(&mut buffer_pages),
                 });
                 buffer.clear();
             }
@@ -60,6 +153,7 @@ pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResul
                 last_size = result.size;
             }
             buffer.push_str(&text);
+            push_pages(&mut buffer_pages, &result_pages);
         }
     }
 
@@ -70,10 +164,169 @@ pub fn smart_chunks(results: Vec<ParseResult>, max_len: usize) -> Vec<ParseResul
             typeid: last_typeid,
             size: last_size,
             text: buffer,
+            source_pages: buffer_pages,
         });
     }
 
     merged_results
 }
 
+/// Number of characters to take from `text` (longer than `max_len`): just after the last
+/// boundary character within the final `slack` characters before the limit, or `max_len`.
+fn split_point(text: &str, max_len: usize, slack: usize) -> usize {
+    if slack == 0 {
+        return max_len;
+    }
+    let window_start = max_len.saturating_sub(slack);
+    text.chars()
+        .take(max_len)
+        .enumerate()
+        .skip(window_start)
+        .filter(|(_, c)| BOUNDARY_CHARS.contains(c))
+        .last()
+        .map(|(i, _)| i + 1)
+        .unwrap_or(max_len)
+}
+
+/// Prefix each chunk after the first with the last `overlap` characters of the previous one.
+/// The page that tail came from is added to the chunk's `source_pages`.
+fn apply_overlap(chunks: Vec<ParseResult>, overlap: usize) -> Vec<ParseResult> {
+    let mut previous_tail = String::new();
+    let mut previous_page: Option<u32> = None;
+    chunks
+        .into_iter()
+        .map(|mut chunk| {
+            if let Some(page) = previous_page
+                && !chunk.source_pages.contains(&page)
+            {
+                chunk.source_pages.insert(0, page);
+            }
+            previous_page = chunk.source_pages.last().copied();
+            let char_count = chunk.text.chars().count();
+            let tail: String = chunk
+                .text
+                .chars()
+                .skip(char_count.saturating_sub(overlap))
+                .collect();
+            chunk.text = std::mem::replace(&mut previous_tail, tail) + &chunk.text;
+            chunk
+        })
+        .collect()
+}
+
 //let new_vec = smart_merge_parse_results(result, 512);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_result(page: u32, text: &str) -> ParseResult {
+        ParseResult {
+            page,
+            bbox: (0.0, 0.0, 100.0, 20.0),
+            typeid: 1,
+            size: (100.0, 20.0),
+            text: text.to_string(),
+            source_pages: Vec::new(),
+        }
+    }
+
+    fn texts(chunks: &[ParseResult]) -> Vec<&str> {
+        chunks.iter().map(|c| c.text.as_str()).collect()
+    }
+
+    #[test]
+    fn default_options_cut_at_max_len() {
+        let chunks = smart_chunks(vec![parse_result(1, "Hello world. Bye now.")], 8);
+        assert_eq!(texts(&chunks), vec!["Hello wo", "rld. Bye", " now."]);
+    }
+
+    #[test]
+    fn long_paragraph_splits_on_sentence_ends() {
+        let text = "First one here. Second sentence! 第三句话。Tail";
+        let options = ChunkOptions::new(20).with_boundary_slack(10);
+        let chunks = smart_chunks_with(vec![parse_result(1, text)], &options);
+
+        assert_eq!(
+            texts(&chunks),
+            vec!["First one here.", " Second sentence!", " 第三句话。Tail"]
+        );
+        assert!(chunks.iter().all(|c| c.text.chars().count() <= 20));
+    }
+
+    #[test]
+    fn falls_back_to_hard_cut_without_boundary_in_window() {
+        let options = ChunkOptions::new(5).with_boundary_slack(2);
+        let chunks = smart_chunks_with(vec![parse_result(1, "a.bcdefghij")], &options);
+        assert_eq!(texts(&chunks), vec!["a.bcd", "efghi", "j"]);
+    }
+
+    #[test]
+    fn overlap_characters_appear_at_chunk_starts() {
+        let options = ChunkOptions::new(10).with_overlap(3);
+        let chunks = smart_chunks_with(vec![parse_result(1, "abcdefghijklmnopq")], &options);
+
+        assert_eq!(texts(&chunks), vec!["abcdefg", "efghijklmn", "lmnopq"]);
+        for pair in chunks.windows(2) {
+            let prev: String = pair[0].text.chars().rev().take(3).collect();
+            let prev: String = prev.chars().rev().collect();
+            assert!(pair[1].text.starts_with(&prev));
+        }
+        assert!(chunks.iter().all(|c| c.text.chars().count() <= 10));
+    }
+
+    #[test]
+    fn merges_small_results_and_keeps_metadata() {
+        let chunks = smart_chunks(
+            vec![
+                parse_result(1, "aaa"),
+                parse_result(1, "bbb"),
+                parse_result(2, "cccc"),
+            ],
+            7,
+        );
+        assert_eq!(texts(&chunks), vec!["aaabbb", "cccc"]);
+        assert_eq!(chunks[0].page, 1);
+        assert_eq!(chunks[1].page, 2);
+    }
+
+    #[test]
+    fn chunk_spanning_pages_lists_all_source_pages() {
+        let chunks = smart_chunks(
+            vec![
+                parse_result(3, "end of page three. "),
+                parse_result(4, "start of four."),
+                parse_result(4, "x".repeat(40).as_str()),
+            ],
+            40,
+        );
+
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].page, 3);
+        assert_eq!(chunks[0].source_pages, vec![3, 4]);
+        assert_eq!(chunks[1].source_pages, vec![4]);
+    }
+
+    #[test]
+    fn overlap_carries_previous_page() {
+        let options = ChunkOptions::new(10).with_overlap(2);
+        let chunks = smart_chunks_with(
+            vec![parse_result(1, "abcdefgh"), parse_result(2, "ijklmnop")],
+            &options,
+        );
+
+        assert_eq!(texts(&chunks), vec!["abcdefgh", "ghijklmnop"]);
+        assert_eq!(chunks[0].source_pages, vec![1]);
+        assert_eq!(chunks[1].source_pages, vec![1, 2]);
+    }
+
+    #[test]
+    fn source_pages_are_optional_in_json() {
+        let parsed: ParseResult = serde_json::from_str(
+            r#"{"page":1,"bbox":[0,0,1,1],"typeid":0,"size":[1,1],"text":"hi"}"#,
+        )
+        .unwrap();
+        assert!(parsed.source_pages.is_empty());
+        assert_eq!(parsed.pages(), vec![1]);
+    }
+}
@@ -21,26 +21,117 @@ use serde_json::{Number, Value};
 /// }));
 ///
 pub fn normalize_numbers(value: Value, decimals: u32) -> Value {
+    normalize_numbers_with(value, decimals, NumberStyle::Float)
+}
+
+/// How [`normalize_numbers_with`] writes a rounded number back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberStyle {
+    /// A JSON float, e.g. `2.0`, `1.23`.
+    #[default]
+    Float,
+    /// Like `Float`, but whole results become integers, e.g. `2.0` -> `2`.
+    TrimZeros,
+    /// A string with exactly `decimals` digits after the point, e.g. `"12.30"`. Suited to money.
+    FixedString,
+}
+
+/// Like [`normalize_numbers`], with control over the output style.
+///
+/// Numbers that cannot be rounded to a finite value (e.g. `1e308` with 2 decimals overflows)
+/// are kept as they were instead of panicking; `FixedString` still formats them.
+///
+/// # Examples
+/// let data = serde_json::json!({"price": 12.3, "qty": 2.0});
+/// let fixed = normalize_numbers_with(data.clone(), 2, NumberStyle::FixedString);
+/// assert_eq!(fixed, serde_json::json!({"price": "12.30", "qty": "2.00"}));
+/// let trimmed = normalize_numbers_with(data, 2, NumberStyle::TrimZeros);
+/// assert_eq!(trimmed, serde_json::json!({"price": 12.3, "qty": 2}));
+///
+pub fn normalize_numbers_with(value: Value, decimals: u32, style: NumberStyle) -> Value {
     match value {
-        Value::Number(n) => {
-            if let Some(f) = n.as_f64() {
-                let factor = 10f64.powi(decimals as i32);
-                let rounded = (f * factor).round() / factor;
-                Value::Number(Number::from_f64(rounded).unwrap())
-            } else {
-                Value::Number(n)
-            }
-        }
+        Value::Number(n) => match n.as_f64() {
+            Some(f) => format_number(n, round_to(f, decimals), decimals, style),
+            None => Value::Number(n),
+        },
         Value::Array(arr) => Value::Array(
             arr.into_iter()
-                .map(|v| normalize_numbers(v, decimals))
+                .map(|v| normalize_numbers_with(v, decimals, style))
                 .collect(),
         ),
         Value::Object(map) => Value::Object(
             map.into_iter()
-                .map(|(k, v)| (k, normalize_numbers(v, decimals)))
+                .map(|(k, v)| (k, normalize_numbers_with(v, decimals, style)))
                 .collect(),
         ),
         other => other,
     }
 }
+
+/// Rounds `f` to `decimals` places, or returns it unchanged if scaling overflows.
+fn round_to(f: f64, decimals: u32) -> f64 {
+    let factor = 10f64.powi(decimals.min(i32::MAX as u32) as i32);
+    let rounded = (f * factor).round() / factor;
+    if rounded.is_finite() { rounded } else { f }
+}
+
+fn format_number(original: Number, rounded: f64, decimals: u32, style: NumberStyle) -> Value {
+    // Largest integer an f64 holds exactly
+    const MAX_EXACT: f64 = 9_007_199_254_740_992.0;
+    match style {
+        NumberStyle::FixedString => Value::String(format!("{:.*}", decimals as usize, rounded)),
+        NumberStyle::TrimZeros if rounded.fract() == 0.0 && rounded.abs() <= MAX_EXACT => {
+            Value::from(rounded as i64)
+        }
+        _ => Number::from_f64(rounded)
+            .map(Value::Number)
+            .unwrap_or(Value::Number(original)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rounds_nested_numbers() {
+        let data = json!({"a": 1.23456, "b": [2.34567, 3.45678], "c": {"d": 4.56789}});
+        assert_eq!(
+            normalize_numbers(data, 2),
+            json!({"a": 1.23, "b": [2.35, 3.46], "c": {"d": 4.57}})
+        );
+    }
+
+    #[test]
+    fn infinity_and_huge_numbers_do_not_panic() {
+        // serde_json has no non-finite numbers; json! turns infinity into null
+        let data = json!({"inf": f64::INFINITY, "huge": f64::MAX, "big": 1e308});
+        let normalized = normalize_numbers(data, 2);
+        assert_eq!(normalized["inf"], Value::Null);
+        assert_eq!(normalized["huge"], json!(f64::MAX));
+        assert_eq!(normalized["big"], json!(1e308));
+
+        // Large decimals make the scale factor itself infinite
+        assert_eq!(normalize_numbers(json!(1.5), 400), json!(1.5));
+        assert_eq!(normalize_numbers(json!(0.0), 400), json!(0.0));
+    }
+
+    #[test]
+    fn trim_zeros_turns_whole_numbers_into_integers() {
+        let data = json!([2.0, 2.004, 1.25, 1e20]);
+        assert_eq!(
+            normalize_numbers_with(data, 2, NumberStyle::TrimZeros),
+            json!([2, 2, 1.25, 1e20])
+        );
+    }
+
+    #[test]
+    fn fixed_string_keeps_trailing_zeros() {
+        let data = json!({"price": 12.3, "qty": 2, "tax": 0.005});
+        assert_eq!(
+            normalize_numbers_with(data, 2, NumberStyle::FixedString),
+            json!({"price": "12.30", "qty": "2.00", "tax": "0.01"})
+        );
+    }
+}
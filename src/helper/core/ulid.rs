@@ -0,0 +1,126 @@
+//! ULIDs: 26-character, lexicographically sortable string ids.
+//!
+//! A ULID is a 48-bit Unix-millis timestamp followed by 80 random bits, written in Crockford
+//! Base32. Sorting ULIDs as strings sorts them by creation time, which makes them good object
+//! storage keys: unlike plain timestamps, the random part spreads writes across prefixes.
+//!
+//! ```rust,ignore
+//! use neocrates::helper::core::ulid::{generate_ulid, ulid_timestamp};
+//!
+//! let id = generate_ulid(); // e.g. "01J8Z3NDEKTSV4RRFFQ69G5FAV"
+//! let key = format!("uploads/{}.png", id);
+//! let created_at = ulid_timestamp(&id);
+//! ```
+
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Utc};
+
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const ULID_LEN: usize = 26;
+const RANDOM_BITS: u32 = 80;
+const RANDOM_MASK: u128 = (1 << RANDOM_BITS) - 1;
+
+/// `(timestamp_ms, random)` of the last ULID, so ids from the same millisecond still increase.
+static LAST: Mutex<(u64, u128)> = Mutex::new((0, 0));
+
+/// Generate a new ULID.
+///
+/// Ids generated by this process are strictly increasing: within one millisecond (or if the
+/// clock steps back) the random part of the previous id is incremented instead of redrawn.
+pub fn generate_ulid() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let mut last = LAST.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (mut timestamp, mut random) = *last;
+    if now > timestamp {
+        timestamp = now;
+        random = rand::random::<u128>() & RANDOM_MASK;
+    } else if random == RANDOM_MASK {
+        // Random part exhausted for this millisecond: borrow the next one
+        timestamp += 1;
+        random = rand::random::<u128>() & RANDOM_MASK;
+    } else {
+        random += 1;
+    }
+    *last = (timestamp, random);
+    drop(last);
+
+    encode(timestamp, random)
+}
+
+/// Creation time of a ULID, or `None` if `id` is not a valid ULID. Case-insensitive.
+pub fn ulid_timestamp(id: &str) -> Option<DateTime<Utc>> {
+    let value = decode(id)?;
+    let millis = i64::try_from(value >> RANDOM_BITS).ok()?;
+    DateTime::from_timestamp_millis(millis)
+}
+
+fn encode(timestamp_ms: u64, random: u128) -> String {
+    let value = (u128::from(timestamp_ms) << RANDOM_BITS) | (random & RANDOM_MASK);
+    (0..ULID_LEN)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 0x1f) as usize] as char)
+        .collect()
+}
+
+fn decode(id: &str) -> Option<u128> {
+    if id.len() != ULID_LEN {
+        return None;
+    }
+    // 26 chars carry 130 bits; the first may only use the low 3 of its 5
+    id.bytes().enumerate().try_fold(0u128, |acc, (i, byte)| {
+        let digit = ALPHABET
+            .iter()
+            .position(|c| *c == byte.to_ascii_uppercase())? as u128;
+        if i == 0 && digit > 7 {
+            return None;
+        }
+        Some((acc << 5) | digit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulids_sort_in_generation_order() {
+        let ids: Vec<String> = (0..1000).map(|_| generate_ulid()).collect();
+        assert!(ids.iter().all(|id| id.len() == ULID_LEN));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn test_timestamp_round_trips() {
+        let before = Utc::now();
+        let id = generate_ulid();
+        let at = ulid_timestamp(&id).unwrap();
+        // Ids may run slightly ahead of the clock when generated faster than 1/ms
+        let tolerance = chrono::Duration::seconds(1);
+        assert!(at >= before - tolerance && at <= Utc::now() + tolerance);
+    }
+
+    #[test]
+    fn test_known_ulid() {
+        let at = ulid_timestamp("01ARZ3NDEKTSV4RRFFQ69G5FAV").unwrap();
+        assert_eq!(at.timestamp_millis(), 1_469_922_850_259);
+        assert_eq!(ulid_timestamp("01arz3ndektsv4rrffq69g5fav"), Some(at));
+        assert_eq!(encode(1_469_922_850_259, 0), "01ARZ3NDEK0000000000000000");
+    }
+
+    #[test]
+    fn test_invalid_ulids() {
+        assert_eq!(ulid_timestamp(""), None);
+        assert_eq!(ulid_timestamp("01ARZ3NDEK"), None);
+        // I, L, O and U are not in the alphabet
+        assert_eq!(ulid_timestamp("01ARZ3NDEKTSV4RRFFQ69G5FAU"), None);
+        // Overflows 128 bits
+        assert_eq!(ulid_timestamp("81ARZ3NDEKTSV4RRFFQ69G5FAV"), None);
+        assert_eq!(ulid_timestamp("01ARZ3NDEKTSV4RRFFQ69G5FA🦀"), None);
+    }
+}
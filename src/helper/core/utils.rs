@@ -1,42 +1,33 @@
-use once_cell::sync::Lazy;
 use rand::prelude::*;
-use regex::Regex;
+
+use super::regex::{CN_LANDLINE_REGEX, CN_MOBILE_REGEX, EMAIL_REGEX};
 
 pub struct Utils;
 
-// ==================== Common Validators ====================
-//
-// Notes:
-// - These validators are intended for common application validation, not for strict telecom compliance.
-// - Mainland China mobile numbers change over time; keep regex updated if your business needs stricter rules.
-
-static CN_MOBILE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Mainland China mobile (simple): 11 digits, starts with 1, second digit 3-9
-    // Examples: 13800138000
-    Regex::new(r"^1[3-9]\d{9}$").expect("Failed to compile CN_MOBILE_REGEX")
-});
-
-static CN_LANDLINE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // China landline (simple):
-    // - With area code: 0xx-xxxxxxx / 0xxx-xxxxxxxx
-    // - Without area code: xxxxxxx / xxxxxxxx
-    // - Optional extension: -xxxx (1-6 digits)
-    // Examples:
-    // - 010-88886666
-    // - 02088886666
-    // - 0571-88886666-123
-    // - 88886666
-    Regex::new(r"^(?:(?:0\d{2,3}-?)?\d{7,8})(?:-\d{1,6})?$")
-        .expect("Failed to compile CN_LANDLINE_REGEX")
-});
-
-static EMAIL_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Practical email regex (not fully RFC 5322, but good for most cases)
-    // - local part: letters/digits and common symbols
-    // - domain: labels separated by dots, TLD length >= 2
-    Regex::new(r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)+$")
-        .expect("Failed to compile EMAIL_REGEX")
-});
+/// Character sets for [`Utils::generate_random_string`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    /// `A-Z a-z 0-9` (62 symbols, ~5.95 bits per char).
+    Alphanumeric,
+    /// `0-9 a-f` (16 symbols, 4 bits per char).
+    Hex,
+    /// The URL-safe base64 alphabet `A-Z a-z 0-9 - _` (64 symbols, 6 bits per char).
+    UrlSafe,
+}
+
+impl Alphabet {
+    pub fn symbols(self) -> &'static [u8] {
+        match self {
+            Alphabet::Alphanumeric => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
+            }
+            Alphabet::Hex => b"0123456789abcdef",
+            Alphabet::UrlSafe => {
+                b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+            }
+        }
+    }
+}
 
 impl Utils {
     /// Generate a random token using UUIDv4.
@@ -45,6 +36,25 @@ impl Utils {
         uuid.to_string()
     }
 
+    /// Generate a random string of `len` characters from `alphabet`, using the operating
+    /// system's CSPRNG.
+    ///
+    /// Suitable for API keys and other opaque secrets: e.g. 32 `UrlSafe` chars carry 192 bits
+    /// of entropy versus 122 for a UUIDv4. Every character is drawn uniformly (rejection
+    /// sampling, no modulo bias).
+    ///
+    /// Panics if the OS random source fails, which should never happen on supported platforms.
+    ///
+    /// Examples:
+    /// - `Utils::generate_random_string(32, Alphabet::UrlSafe)` -> "q3Vb-0Xz..."
+    pub fn generate_random_string(len: usize, alphabet: Alphabet) -> String {
+        let mut rng = rand::rand_core::UnwrapErr(rand::rngs::SysRng);
+        let symbols = alphabet.symbols();
+        (0..len)
+            .map(|_| symbols[rng.random_range(0..symbols.len())] as char)
+            .collect()
+    }
+
     /// Validate mainland China mobile number (common rule).
     ///
     /// Examples:
@@ -114,6 +124,30 @@ impl Utils {
         }
     }
 
+    /// Mask the local part of an email address, keeping the domain.
+    ///
+    /// Examples:
+    /// - "alice@example.com" -> "a***e@example.com"
+    /// - "ab@example.com" -> "a***@example.com"
+    /// - "a@x.com" -> "*@x.com"
+    /// - "not-an-email" -> "not-an-email" (no `@`, returned unchanged)
+    ///
+    /// The number of `*` is fixed so the local part's length is not revealed.
+    pub fn mask_email(email: &str) -> String {
+        let Some((local, domain)) = email.rsplit_once('@') else {
+            return email.to_string();
+        };
+        let (Some(first), Some(last)) = (local.chars().next(), local.chars().next_back()) else {
+            return email.to_string();
+        };
+        let masked_local = match local.chars().count() {
+            1 => "*".to_string(),
+            2 => format!("{}***", first),
+            _ => format!("{}***{}", first, last),
+        };
+        format!("{}@{}", masked_local, domain)
+    }
+
     // Generate a random username
     // pub fn generate_username() -> String {
     //     let mut rng = rand::thread_rng();
@@ -146,14 +180,28 @@ impl Utils {
     /// # Returns
     /// - `Option<String>`: randomly selected name
     pub fn weighted_random_name(names: &[String], weights: &[usize]) -> Option<String> {
+        Self::weighted_random_name_with_rng(names, weights, &mut rand::rng())
+    }
+
+    /// Same as `weighted_random_name`, but draws from the given RNG.
+    ///
+    /// Pass a seeded `StdRng` to get reproducible selections in tests. Names with weight 0
+    /// are never selected; returns None if all weights are 0, the lengths differ, or the
+    /// weights overflow `usize` when summed.
+    pub fn weighted_random_name_with_rng<R: Rng + ?Sized>(
+        names: &[String],
+        weights: &[usize],
+        rng: &mut R,
+    ) -> Option<String> {
         if names.is_empty() || names.len() != weights.len() {
             return None;
         }
-        let total: usize = weights.iter().sum();
+        let total = weights
+            .iter()
+            .try_fold(0usize, |acc, &w| acc.checked_add(w))?;
         if total == 0 {
             return None;
         }
-        let mut rng = rand::rng();
         let mut target = rng.random_range(0..total);
         for (name, &weight) in names.iter().zip(weights.iter()) {
             if target < weight {
@@ -172,10 +220,12 @@ impl Utils {
     /// # Returns
     /// - `Option<String>`: randomly selected name, or None if the list is empty
     pub fn random_name(names: &[String]) -> Option<String> {
-        // Create a thread-local RNG
-        let mut rng = rand::rng();
-        // Choose a random element and clone as String
-        names.choose(&mut rng).cloned()
+        Self::random_name_with_rng(names, &mut rand::rng())
+    }
+
+    /// Same as `random_name`, but draws from the given RNG (e.g. a seeded `StdRng`).
+    pub fn random_name_with_rng<R: Rng + ?Sized>(names: &[String], rng: &mut R) -> Option<String> {
+        names.choose(rng).cloned()
     }
 
     /// Parse string into usize; return default if parsing fails
@@ -185,3 +235,129 @@ impl Utils {
         s.trim().parse::<usize>().unwrap_or(default)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_random_string() {
+        for alphabet in [Alphabet::Alphanumeric, Alphabet::Hex, Alphabet::UrlSafe] {
+            let symbols = alphabet.symbols();
+            for len in [0, 1, 32, 100] {
+                let s = Utils::generate_random_string(len, alphabet);
+                assert_eq!(s.len(), len);
+                assert!(s.bytes().all(|b| symbols.contains(&b)), "{alphabet:?}: {s}");
+            }
+        }
+        assert_ne!(
+            Utils::generate_random_string(32, Alphabet::UrlSafe),
+            Utils::generate_random_string(32, Alphabet::UrlSafe)
+        );
+    }
+
+    #[test]
+    fn test_generate_random_string_covers_alphabet() {
+        // 4096 hex chars: each of the 16 symbols is expected ~256 times
+        let s = Utils::generate_random_string(4096, Alphabet::Hex);
+        for symbol in Alphabet::Hex.symbols() {
+            let count = s.bytes().filter(|b| b == symbol).count();
+            assert!(
+                (128..=384).contains(&count),
+                "{} seen {count} times",
+                *symbol as char
+            );
+        }
+    }
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_weighted_random_name_with_seeded_rng_is_reproducible() {
+        let names = names(&["Alice", "Bob", "Charlie"]);
+        let weights = [1, 3, 6];
+
+        let picks = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..50)
+                .map(|_| Utils::weighted_random_name_with_rng(&names, &weights, &mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(picks(7), picks(7));
+
+        // A single non-zero weight always wins, whatever the seed
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20 {
+            assert_eq!(
+                Utils::weighted_random_name_with_rng(&names, &[0, 5, 0], &mut rng).as_deref(),
+                Some("Bob")
+            );
+        }
+    }
+
+    #[test]
+    fn test_weighted_random_name_mixed_zero_weights() {
+        let names = names(&["never-first", "a", "never-mid", "b", "never-last"]);
+        let weights = [0, 1, 0, 1, 0];
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let pick = Utils::weighted_random_name_with_rng(&names, &weights, &mut rng).unwrap();
+            assert!(!pick.starts_with("never"), "picked zero-weight {pick}");
+            seen.insert(pick);
+        }
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn test_weighted_random_name_rejects_invalid_input() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let two = names(&["a", "b"]);
+        assert_eq!(
+            Utils::weighted_random_name_with_rng(&two, &[0, 0], &mut rng),
+            None
+        );
+        assert_eq!(
+            Utils::weighted_random_name_with_rng(&two, &[1], &mut rng),
+            None
+        );
+        assert_eq!(
+            Utils::weighted_random_name_with_rng(&[], &[], &mut rng),
+            None
+        );
+        assert_eq!(
+            Utils::weighted_random_name_with_rng(&two, &[usize::MAX, 1], &mut rng),
+            None
+        );
+    }
+
+    #[test]
+    fn test_random_name_with_seeded_rng() {
+        let names = names(&["a", "b", "c"]);
+        let mut first = StdRng::seed_from_u64(3);
+        let mut second = StdRng::seed_from_u64(3);
+        for _ in 0..20 {
+            assert_eq!(
+                Utils::random_name_with_rng(&names, &mut first),
+                Utils::random_name_with_rng(&names, &mut second)
+            );
+        }
+        assert_eq!(Utils::random_name_with_rng(&[], &mut first), None);
+    }
+
+    #[test]
+    fn test_mask_email() {
+        assert_eq!(Utils::mask_email("alice@example.com"), "a***e@example.com");
+        assert_eq!(Utils::mask_email("bob@example.com"), "b***b@example.com");
+        assert_eq!(Utils::mask_email("ab@example.com"), "a***@example.com");
+        assert_eq!(Utils::mask_email("a@x.com"), "*@x.com");
+        assert_eq!(Utils::mask_email("not-an-email"), "not-an-email");
+        assert_eq!(Utils::mask_email("@example.com"), "@example.com");
+        assert_eq!(
+            Utils::mask_email("张三丰@example.com"),
+            "张***丰@example.com"
+        );
+    }
+}
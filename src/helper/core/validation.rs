@@ -0,0 +1,242 @@
+//! Reusable `#[validate(custom(function = ...))]` validators.
+//!
+//! Each function returns a [`validator::ValidationError`] with a fixed code, so clients can
+//! key messages off `code` in the `FieldValidation` payload:
+//!
+//! | Validator | Code |
+//! |---|---|
+//! | [`validate_strong_password`] | `weak_password` |
+//! | [`validate_cn_id_card`] | `invalid_cn_id_card` |
+//! | [`validate_http_url`], [`validate_url_with_schemes`] | `invalid_url` |
+//! | [`validate_no_control_chars`] | `contains_control_chars` |
+//!
+//! # Example
+//! ```rust,ignore
+//! use neocrates::helper::core::validation::{
+//!     validate_cn_id_card, validate_http_url, validate_no_control_chars, validate_strong_password,
+//! };
+//! use validator::Validate;
+//!
+//! #[derive(Deserialize, Validate)]
+//! struct RegisterReq {
+//!     #[validate(length(min = 1, max = 32), custom(function = "validate_no_control_chars"))]
+//!     nickname: String,
+//!     #[validate(custom(function = "validate_strong_password"))]
+//!     password: String,
+//!     #[validate(custom(function = "validate_cn_id_card"))]
+//!     id_card: Option<String>,
+//!     #[validate(custom(function = "validate_http_url"))]
+//!     homepage: Option<String>,
+//! }
+//! ```
+
+use std::borrow::Cow;
+
+use chrono::NaiveDate;
+use validator::{ValidateUrl, ValidationError};
+
+/// Shortest password [`validate_strong_password`] accepts, in characters.
+pub const PASSWORD_MIN_LEN: usize = 8;
+/// Longest password [`validate_strong_password`] accepts, in characters.
+pub const PASSWORD_MAX_LEN: usize = 64;
+
+/// Password of [`PASSWORD_MIN_LEN`]..=[`PASSWORD_MAX_LEN`] characters using at least three
+/// of: lowercase letters, uppercase letters, digits, other symbols.
+///
+/// Fails with code `weak_password`; the `reason` param is `too_short`, `too_long` or
+/// `too_few_char_classes`.
+pub fn validate_strong_password(input: &str) -> Result<(), ValidationError> {
+    let len = input.chars().count();
+    let reason = if len < PASSWORD_MIN_LEN {
+        "too_short"
+    } else if len > PASSWORD_MAX_LEN {
+        "too_long"
+    } else {
+        let has = |f: fn(&char) -> bool| input.chars().any(|c| f(&c));
+        let classes = [
+            has(char::is_ascii_lowercase),
+            has(char::is_ascii_uppercase),
+            has(char::is_ascii_digit),
+            has(|c| !c.is_ascii_alphanumeric() && !c.is_whitespace()),
+        ];
+        if classes.iter().filter(|present| **present).count() >= 3 {
+            return Ok(());
+        }
+        "too_few_char_classes"
+    };
+    let mut err = ValidationError::new("weak_password");
+    err.add_param("reason".into(), &reason);
+    err.add_param("min".into(), &PASSWORD_MIN_LEN);
+    err.add_param("max".into(), &PASSWORD_MAX_LEN);
+    Err(err)
+}
+
+/// Mainland China resident ID card number (18 digits, GB 11643-1999).
+///
+/// Checks the shape, that digits 7-14 are a real birth date, and the ISO 7064 MOD 11-2
+/// check digit (`X` in either case stands for 10). Fails with code `invalid_cn_id_card`.
+pub fn validate_cn_id_card(input: &str) -> Result<(), ValidationError> {
+    if is_cn_id_card(input) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_cn_id_card"))
+    }
+}
+
+fn is_cn_id_card(input: &str) -> bool {
+    const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+    const CHECK_DIGITS: &[u8; 11] = b"10X98765432";
+
+    let bytes = input.as_bytes();
+    if bytes.len() != 18 || !bytes[..17].iter().all(u8::is_ascii_digit) {
+        return false;
+    }
+    if NaiveDate::parse_from_str(&input[6..14], "%Y%m%d").is_err() {
+        return false;
+    }
+    let sum: u32 = bytes[..17]
+        .iter()
+        .zip(WEIGHTS)
+        .map(|(digit, weight)| u32::from(digit - b'0') * weight)
+        .sum();
+    CHECK_DIGITS[(sum % 11) as usize] == bytes[17].to_ascii_uppercase()
+}
+
+/// Absolute `http://` or `https://` URL. Fails with code `invalid_url`.
+pub fn validate_http_url(input: &str) -> Result<(), ValidationError> {
+    validate_url_with_schemes(input, &["http", "https"])
+}
+
+/// Absolute `scheme://...` URL whose scheme (compared case-insensitively) is in `schemes`.
+///
+/// Not usable in `custom(function = ...)` directly; wrap it for other scheme lists:
+/// ```rust,ignore
+/// fn validate_ws_url(input: &str) -> Result<(), ValidationError> {
+///     validate_url_with_schemes(input, &["ws", "wss"])
+/// }
+/// ```
+///
+/// Fails with code `invalid_url`; the `schemes` param lists what was allowed.
+pub fn validate_url_with_schemes(input: &str, schemes: &[&str]) -> Result<(), ValidationError> {
+    let allowed = input.split_once("://").is_some_and(|(scheme, rest)| {
+        !rest.is_empty() && schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme))
+    });
+    if allowed && input.validate_url() && !input.chars().any(char::is_whitespace) {
+        return Ok(());
+    }
+    let mut err = ValidationError::new("invalid_url");
+    err.add_param("schemes".into(), &schemes);
+    Err(err)
+}
+
+/// Rejects any control character (`\0`, `\n`, `\t`, escape sequences, ...), for single-line
+/// fields such as names and titles. Fails with code `contains_control_chars`.
+///
+/// Use [`strip_control_chars`] instead to clean the input rather than reject it.
+pub fn validate_no_control_chars(input: &str) -> Result<(), ValidationError> {
+    if input.chars().any(char::is_control) {
+        Err(ValidationError::new("contains_control_chars"))
+    } else {
+        Ok(())
+    }
+}
+
+/// `input` without control characters; borrows when there is nothing to remove.
+pub fn strip_control_chars(input: &str) -> Cow<'_, str> {
+    if input.chars().any(char::is_control) {
+        Cow::Owned(input.chars().filter(|c| !c.is_control()).collect())
+    } else {
+        Cow::Borrowed(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(result: Result<(), ValidationError>) -> Option<String> {
+        result.err().map(|e| e.code.to_string())
+    }
+
+    #[test]
+    fn strong_password() {
+        for ok in ["Abcdef12", "abcdef1!", "ABCDEF1!", "Pass word 1"] {
+            assert_eq!(validate_strong_password(ok), Ok(()), "{ok}");
+        }
+        for (weak, reason) in [
+            ("Ab1!", "too_short"),
+            ("abcdefgh", "too_few_char_classes"),
+            ("abcd1234", "too_few_char_classes"),
+            (&"Aa1!".repeat(17), "too_long"),
+        ] {
+            let err = validate_strong_password(weak).unwrap_err();
+            assert_eq!(err.code, "weak_password");
+            assert_eq!(err.params["reason"], reason, "{weak}");
+        }
+    }
+
+    #[test]
+    fn cn_id_card() {
+        for ok in [
+            "11010519491231002X",
+            "11010519491231002x",
+            "440524188001010014",
+        ] {
+            assert_eq!(validate_cn_id_card(ok), Ok(()), "{ok}");
+        }
+        for bad in [
+            "110105194912310021",  // wrong check digit
+            "110105194902300028",  // February 30th
+            "11010519491231002",   // too short
+            "1101051949123100２X", // non-ASCII digit
+            "",
+        ] {
+            assert_eq!(
+                code(validate_cn_id_card(bad)).as_deref(),
+                Some("invalid_cn_id_card"),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn urls() {
+        for ok in ["https://example.com/a?b=1", "HTTP://localhost:8080"] {
+            assert_eq!(validate_http_url(ok), Ok(()), "{ok}");
+        }
+        for bad in [
+            "javascript:alert(1)",
+            "ftp://example.com",
+            "example.com",
+            "https://",
+            "https://exa mple.com",
+        ] {
+            assert_eq!(
+                code(validate_http_url(bad)).as_deref(),
+                Some("invalid_url"),
+                "{bad}"
+            );
+        }
+        assert_eq!(
+            validate_url_with_schemes("wss://example.com/socket", &["ws", "wss"]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn control_chars() {
+        assert_eq!(validate_no_control_chars("Zhang San 张三"), Ok(()));
+        for bad in ["a\u{0}b", "line\nbreak", "tab\t", "\u{1b}[31mred"] {
+            assert_eq!(
+                code(validate_no_control_chars(bad)).as_deref(),
+                Some("contains_control_chars"),
+                "{bad:?}"
+            );
+        }
+        assert!(matches!(
+            strip_control_chars("clean"),
+            Cow::Borrowed("clean")
+        ));
+        assert_eq!(strip_control_chars("a\u{0}b\r\n"), "ab");
+    }
+}
@@ -18,6 +18,7 @@
 //! - redis: Redis and caching (redis, bb8, bb8-redis, moka)
 //! - crypto: Cryptography and hashing (argon2, hmac, ring, sha2)
 //! - sms: SMS-related modules (if they depend on HTTP, enable together with "web")
+//! - metrics: Prometheus gauges/counters for pools, caches and the auth interceptor (metrics, metrics-exporter-prometheus)
 //! - full: Enable all features
 //!
 //! Note: Modules are compiled only when their feature is enabled; related dependencies are marked optional in Cargo.toml and aggregated via
@@ -32,11 +33,11 @@
 //! web = ["dep:axum", "dep:tower", "dep:tower-http", "dep:hyper", "dep:reqwest", "dep:url", "dep:urlencoding"]
 //! aws = ["awss3", "awssts", "dep:aws-config", "dep:aws-types"]
 //! awss3 = ["dep:aws-sdk-s3", "dep:aws-config", "dep:aws-types"]
-//! awssts = ["dep:aws-sdk-sts", "dep:aws-config", "dep:aws-types"]
+//! awssts = ["crypto", "dep:aws-sdk-sts", "dep:aws-config", "dep:aws-types"]
 //! diesel = ["dep:diesel", "dep:deadpool", "dep:deadpool-diesel", "dep:diesel_migrations"]
 //! redis = ["dep:redis", "dep:bb8", "dep:bb8-redis", "dep:moka"]
 //! crypto = ["dep:argon2", "dep:hmac", "dep:ring", "dep:sha2"]
-//! sms = [] # If HTTP is needed, enable together with "web"
+//! sms = ["crypto"] # If HTTP is needed, enable together with "web"
 //! full = ["web", "aws", "awss3", "awssts", "diesel", "redis", "crypto", "sms"]
 //!
 //! [dependencies]
@@ -244,3 +245,7 @@ pub mod captcha;
 
 #[cfg(any(feature = "auth", feature = "redis", feature = "full"))]
 pub mod auth;
+
+// Metrics
+#[cfg(any(feature = "metrics", feature = "full"))]
+pub mod metrics;
@@ -1,19 +1,69 @@
-use chrono::Local;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{FixedOffset, Local, Utc};
 use serde::{Deserialize, Serialize};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 pub use tracing::*;
 
-#[derive(Default, Clone, Copy)]
-struct LocalTime;
+/// Default timestamp: milliseconds plus the UTC offset, e.g. `2025-01-31T08:15:02.123+08:00`.
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+/// Default for UTC, which gets a `Z` suffix instead of `+00:00`.
+const DEFAULT_UTC_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
 
-impl tracing_subscriber::fmt::time::FormatTime for LocalTime {
+#[derive(Clone)]
+struct LogTimer {
+    zone: TimeZoneConfig,
+    format: String,
+}
+
+impl LogTimer {
+    /// Build the timer, falling back to the default format when `format` is invalid.
+    /// Returns whether the fallback was used so it can be reported once logging is up.
+    fn new(zone: TimeZoneConfig, format: Option<&str>) -> (Self, bool) {
+        let default = match zone {
+            TimeZoneConfig::Utc => DEFAULT_UTC_TIME_FORMAT,
+            _ => DEFAULT_TIME_FORMAT,
+        };
+        let (format, invalid) = match format {
+            Some(f) if is_valid_time_format(f) => (f, false),
+            Some(_) => (default, true),
+            None => (default, false),
+        };
+        (
+            Self {
+                zone,
+                format: format.to_string(),
+            },
+            invalid,
+        )
+    }
+}
+
+impl tracing_subscriber::fmt::time::FormatTime for LogTimer {
     fn format_time(&self, w: &mut tracing_subscriber::fmt::format::Writer<'_>) -> std::fmt::Result {
-        write!(w, "{}", Local::now().format("%Y-%m-%d %H:%M:%S"))
+        match self.zone {
+            TimeZoneConfig::Utc => write!(w, "{}", Utc::now().format(&self.format)),
+            TimeZoneConfig::Local => write!(w, "{}", Local::now().format(&self.format)),
+            TimeZoneConfig::FixedOffset(seconds) => match FixedOffset::east_opt(seconds) {
+                Some(offset) => write!(
+                    w,
+                    "{}",
+                    Utc::now().with_timezone(&offset).format(&self.format)
+                ),
+                None => write!(w, "{}", Utc::now().format(&self.format)),
+            },
+        }
     }
 }
 
+fn is_valid_time_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| matches!(item, Item::Error))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogConfig {
     #[serde(rename = "rust-log")]
@@ -33,13 +83,125 @@ pub struct LogSettings {
     pub line_number: bool,
     #[serde(default = "default_true")]
     pub file: bool,
+    /// Kept for old configs; ignored when `format` is set.
     #[serde(default = "default_true")]
     pub pretty: bool,
+    #[serde(default)]
+    pub format: Option<LogFormat>,
+    /// Write to rolling files instead of stdout.
+    #[serde(default)]
+    pub file_output: Option<LogFileSettings>,
+    #[serde(default)]
+    pub time_zone: TimeZoneConfig,
+    /// chrono strftime pattern; defaults to RFC 3339 with milliseconds and offset.
+    #[serde(default)]
+    pub time_format: Option<String>,
     // 是否打印 SQL
     #[serde(default)]
     pub sql_log: Option<bool>,
 }
 
+/// Time zone used for log timestamps.
+///
+/// YAML: `time-zone: utc`, `time-zone: local`, or a fixed offset such as `time-zone: "+08:00"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeZoneConfig {
+    Utc,
+    #[default]
+    Local,
+    /// Seconds east of UTC, e.g. 28800 for UTC+8. Out-of-range values fall back to UTC.
+    FixedOffset(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// tracing-subscriber's default single-line format.
+    Full,
+    /// Multi-line, human-friendly output for development.
+    Pretty,
+    /// Shorter single-line output.
+    Compact,
+    /// One JSON object per line, for log shipping in production.
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LogFileSettings {
+    pub directory: String,
+    #[serde(default = "default_file_prefix")]
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: LogRotation,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+impl TryFrom<String> for TimeZoneConfig {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_ascii_lowercase().as_str() {
+            "utc" | "z" => Ok(TimeZoneConfig::Utc),
+            "local" => Ok(TimeZoneConfig::Local),
+            offset => offset
+                .parse::<FixedOffset>()
+                .map(|o| TimeZoneConfig::FixedOffset(o.local_minus_utc()))
+                .map_err(|_| {
+                    format!(
+                        "invalid time-zone {:?}, expected utc, local or an offset like +08:00",
+                        value
+                    )
+                }),
+        }
+    }
+}
+
+impl From<TimeZoneConfig> for String {
+    fn from(zone: TimeZoneConfig) -> Self {
+        match zone {
+            TimeZoneConfig::Utc => "utc".to_string(),
+            TimeZoneConfig::Local => "local".to_string(),
+            TimeZoneConfig::FixedOffset(seconds) => match FixedOffset::east_opt(seconds) {
+                Some(offset) => offset.to_string(),
+                None => "utc".to_string(),
+            },
+        }
+    }
+}
+
+impl LogSettings {
+    /// The output format, falling back to the legacy `pretty` flag.
+    pub fn resolved_format(&self) -> LogFormat {
+        match self.format {
+            Some(format) => format,
+            None if self.pretty => LogFormat::Pretty,
+            None => LogFormat::Full,
+        }
+    }
+}
+
 impl LogConfig {
     pub fn load(path: &str) -> anyhow::Result<Self> {
         let f = std::fs::File::open(path)?;
@@ -65,6 +227,10 @@ impl Default for LogSettings {
             line_number: true,
             file: true,
             pretty: true,
+            format: None,
+            file_output: None,
+            time_zone: TimeZoneConfig::default(),
+            time_format: None,
             sql_log: Some(true),
         }
     }
@@ -74,11 +240,24 @@ fn default_level() -> String {
     "info".to_string()
 }
 
+fn default_file_prefix() -> String {
+    "app.log".to_string()
+}
+
 fn default_true() -> bool {
     true
 }
 
-pub fn init(config: LogConfig) {
+/// Install the global tracing subscriber.
+///
+/// Returns the `WorkerGuard` of the non-blocking file writer when `file_output` is configured.
+/// Keep it alive for the whole program (e.g. `let _guard = init(cfg);` in `main`): dropping
+/// it flushes and stops the writer, so later logs are lost.
+///
+/// If a global subscriber is already installed (a second `init`, a test harness, or a host
+/// application), it is left in place: a warning is logged through it and None is returned.
+#[must_use = "dropping the guard stops the file writer"]
+pub fn init(config: LogConfig) -> Option<WorkerGuard> {
     let config = config.log;
     #[cfg(any(feature = "diesel", feature = "full"))]
     if let Some(on) = config.sql_log {
@@ -87,26 +266,126 @@ pub fn init(config: LogConfig) {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&config.level));
 
+    let (writer, guard) = match &config.file_output {
+        Some(file) => {
+            let appender =
+                RollingFileAppender::new(file.rotation.into(), &file.directory, &file.prefix);
+            let (non_blocking, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(non_blocking), Some(guard))
+        }
+        None => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+
+    let (timer, invalid_time_format) =
+        LogTimer::new(config.time_zone, config.time_format.as_deref());
+
     let builder = FmtSubscriber::builder()
-        .with_timer(LocalTime)
+        .with_timer(timer)
         .with_env_filter(env_filter)
         .with_span_events(FmtSpan::CLOSE)
         .with_target(config.target)
         .with_thread_ids(config.thread_ids)
         .with_line_number(config.line_number)
-        .with_file(config.file);
+        .with_file(config.file)
+        .with_writer(writer)
+        // Colors only make sense on a terminal
+        .with_ansi(guard.is_none());
 
-    if config.pretty {
-        let subscriber = builder.pretty().finish();
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("setting default subscriber failed");
-    } else {
-        let subscriber = builder.finish();
-        tracing::subscriber::set_global_default(subscriber)
-            .expect("setting default subscriber failed");
+    let result = match config.resolved_format() {
+        LogFormat::Full => tracing::subscriber::set_global_default(builder.finish()),
+        LogFormat::Pretty => tracing::subscriber::set_global_default(builder.pretty().finish()),
+        LogFormat::Compact => tracing::subscriber::set_global_default(builder.compact().finish()),
+        LogFormat::Json => tracing::subscriber::set_global_default(builder.json().finish()),
+    };
+    if let Err(err) = result {
+        tracing::warn!(
+            "logger::init skipped, a global subscriber is already set: {}",
+            err
+        );
+        return None;
+    }
+    if invalid_time_format {
+        tracing::warn!(
+            "logger::init invalid time-format {:?}, using the default",
+            config.time_format
+        );
     }
+    guard
 }
 
 pub async fn run() {
-    init(LogConfig::default());
+    // The default config has no `file_output`, so there is no guard to keep.
+    let _ = init(LogConfig::default());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_twice_does_not_panic() {
+        let _first = init(LogConfig::default());
+        let second = init(LogConfig::default());
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn default_timestamps_include_millis_and_offset() {
+        use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
+
+        let render = |timer: &LogTimer| {
+            let mut out = String::new();
+            timer.format_time(&mut Writer::new(&mut out)).unwrap();
+            out
+        };
+
+        let (utc, _) = LogTimer::new(TimeZoneConfig::Utc, None);
+        let out = render(&utc);
+        assert_eq!(out.len(), "2025-01-31T08:15:02.123Z".len(), "{out}");
+        assert!(out.ends_with('Z') && out.contains('.'), "{out}");
+
+        let (fixed, _) = LogTimer::new(TimeZoneConfig::FixedOffset(8 * 3600), None);
+        assert!(render(&fixed).ends_with("+08:00"));
+
+        let (custom, invalid) = LogTimer::new(TimeZoneConfig::Utc, Some("%H:%M"));
+        assert!(!invalid);
+        assert_eq!(render(&custom).len(), 5);
+
+        let (fallback, invalid) = LogTimer::new(TimeZoneConfig::Local, Some("%Q"));
+        assert!(invalid);
+        assert_eq!(fallback.format, DEFAULT_TIME_FORMAT);
+    }
+
+    #[test]
+    fn format_falls_back_to_pretty_flag() {
+        let mut settings = LogSettings::default();
+        assert_eq!(settings.resolved_format(), LogFormat::Pretty);
+        settings.pretty = false;
+        assert_eq!(settings.resolved_format(), LogFormat::Full);
+        settings.format = Some(LogFormat::Json);
+        assert_eq!(settings.resolved_format(), LogFormat::Json);
+    }
+
+    #[test]
+    fn parses_format_and_file_output_from_yaml() {
+        let yaml = r#"
+rust-log:
+  level: warn
+  format: json
+  time-zone: "-05:00"
+  file-output:
+    directory: /var/log/app
+    rotation: hourly
+"#;
+        let config: LogConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.log.resolved_format(), LogFormat::Json);
+        let file = config.log.file_output.unwrap();
+        assert_eq!(file.directory, "/var/log/app");
+        assert_eq!(file.prefix, "app.log");
+        assert_eq!(file.rotation, LogRotation::Hourly);
+        assert!(config.log.file);
+        assert_eq!(config.log.time_zone, TimeZoneConfig::FixedOffset(-18000));
+        assert_eq!(String::from(config.log.time_zone), "-05:00");
+        assert!(TimeZoneConfig::try_from("mars".to_string()).is_err());
+    }
 }
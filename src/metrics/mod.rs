@@ -0,0 +1,209 @@
+//! Prometheus metrics for pools, caches and the auth interceptor.
+//!
+//! Instrumented code records through the [`metrics`](::metrics) facade, so nothing is
+//! collected until a recorder is installed. [`install_prometheus`] installs one and returns
+//! the handle that [`metrics_route`] serves. Without the `metrics` feature the call sites are
+//! compiled out.
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `neocrates_pool_connections` | gauge | `pool`, `state` (`in_use` / `idle`) |
+//! | `neocrates_pool_max_connections` | gauge | `pool` |
+//! | `neocrates_pool_timeouts_total` | counter | `pool` |
+//! | `neocrates_cache_lookups_total` | counter | `cache`, `result` (`hit` / `miss`) |
+//! | `neocrates_auth_requests_total` | counter | `outcome` |
+//!
+//! ```rust,ignore
+//! let handle = neocrates::metrics::install_prometheus()?;
+//! let app = Router::new()
+//!     .route("/metrics", neocrates::metrics::metrics_route(handle))
+//!     .merge(api_routes);
+//! ```
+
+use ::metrics::{Unit, counter, describe_counter, describe_gauge, gauge};
+use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
+
+pub const POOL_CONNECTIONS: &str = "neocrates_pool_connections";
+pub const POOL_MAX_CONNECTIONS: &str = "neocrates_pool_max_connections";
+pub const POOL_TIMEOUTS_TOTAL: &str = "neocrates_pool_timeouts_total";
+pub const CACHE_LOOKUPS_TOTAL: &str = "neocrates_cache_lookups_total";
+pub const AUTH_REQUESTS_TOTAL: &str = "neocrates_auth_requests_total";
+
+/// `pool` label of the shared [`RedisPool`](crate::rediscache::RedisPool).
+pub const POOL_REDIS: &str = "redis";
+/// `pool` label of the `DieselPool` primary.
+pub const POOL_DIESEL_PRIMARY: &str = "diesel_primary";
+/// `pool` label of the `DieselPool` read replica.
+pub const POOL_DIESEL_REPLICA: &str = "diesel_replica";
+
+/// `cache` label of auth token lookups in the interceptor.
+pub const CACHE_TOKEN: &str = "token";
+/// `cache` label of captcha code lookups.
+pub const CACHE_CAPTCHA: &str = "captcha";
+
+/// Outcomes counted under [`AUTH_REQUESTS_TOTAL`].
+pub mod auth_outcome {
+    /// URL on the ignore list; no credentials checked.
+    pub const IGNORED: &str = "ignored";
+    /// Valid PMS BASIC credential.
+    pub const BASIC: &str = "basic";
+    /// Valid bearer token.
+    pub const TOKEN: &str = "token";
+    /// No or unacceptable credentials (401 `Unauthorized`).
+    pub const UNAUTHORIZED: &str = "unauthorized";
+    /// Token not in the store, or the store failed (401 `TokenExpired`).
+    pub const EXPIRED: &str = "expired";
+    /// Rejected by the `authorize` hook.
+    pub const DENIED: &str = "denied";
+}
+
+/// Install a Prometheus recorder as the global `metrics` recorder and describe the crate's
+/// metrics. Call once at startup; a second call fails because a recorder is already set.
+pub fn install_prometheus() -> Result<PrometheusHandle, BuildError> {
+    let handle = PrometheusBuilder::new().install_recorder()?;
+    describe();
+    Ok(handle)
+}
+
+/// Register help text and units for the crate's metrics with the current recorder.
+pub fn describe() {
+    describe_gauge!(
+        POOL_CONNECTIONS,
+        Unit::Count,
+        "Open pool connections by state, sampled at checkout"
+    );
+    describe_gauge!(
+        POOL_MAX_CONNECTIONS,
+        Unit::Count,
+        "Configured maximum pool size"
+    );
+    describe_counter!(
+        POOL_TIMEOUTS_TOTAL,
+        Unit::Count,
+        "Checkouts that timed out waiting for a connection"
+    );
+    describe_counter!(
+        CACHE_LOOKUPS_TOTAL,
+        Unit::Count,
+        "Cache lookups by cache and hit/miss"
+    );
+    describe_counter!(
+        AUTH_REQUESTS_TOTAL,
+        Unit::Count,
+        "Requests seen by the auth interceptor, by outcome"
+    );
+}
+
+/// Record a pool snapshot; `in_use / max` is the saturation to alert on.
+pub fn record_pool(pool: &'static str, in_use: usize, idle: usize, max: usize) {
+    gauge!(POOL_CONNECTIONS, "pool" => pool, "state" => "in_use").set(in_use as f64);
+    gauge!(POOL_CONNECTIONS, "pool" => pool, "state" => "idle").set(idle as f64);
+    gauge!(POOL_MAX_CONNECTIONS, "pool" => pool).set(max as f64);
+}
+
+pub fn record_pool_timeout(pool: &'static str) {
+    counter!(POOL_TIMEOUTS_TOTAL, "pool" => pool).increment(1);
+}
+
+pub fn record_cache_lookup(cache: &'static str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    counter!(CACHE_LOOKUPS_TOTAL, "cache" => cache, "result" => result).increment(1);
+}
+
+/// Count one interceptor request; `outcome` is one of [`auth_outcome`].
+pub fn record_auth(outcome: &'static str) {
+    counter!(AUTH_REQUESTS_TOTAL, "outcome" => outcome).increment(1);
+}
+
+/// `GET` route rendering `handle` in the Prometheus text format.
+#[cfg(any(feature = "web", feature = "full"))]
+pub fn metrics_route<S>(handle: PrometheusHandle) -> axum::routing::MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    axum::routing::get(move || {
+        let handle = handle.clone();
+        async move {
+            handle.run_upkeep();
+            (
+                [(
+                    axum::http::header::CONTENT_TYPE,
+                    "text/plain; version=0.0.4; charset=utf-8",
+                )],
+                handle.render(),
+            )
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(record: impl FnOnce()) -> String {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || {
+            describe();
+            record();
+        });
+        handle.render()
+    }
+
+    #[test]
+    fn cache_lookups_are_labelled_hit_or_miss() {
+        let out = render(|| {
+            record_cache_lookup(CACHE_CAPTCHA, true);
+            record_cache_lookup(CACHE_CAPTCHA, true);
+            record_cache_lookup(CACHE_TOKEN, false);
+        });
+        assert!(out.contains("neocrates_cache_lookups_total{cache=\"captcha\",result=\"hit\"} 2"));
+        assert!(out.contains("neocrates_cache_lookups_total{cache=\"token\",result=\"miss\"} 1"));
+        assert!(out.contains("# HELP neocrates_cache_lookups_total"));
+    }
+
+    #[test]
+    fn pool_snapshot_sets_gauges() {
+        let out = render(|| {
+            record_pool(POOL_REDIS, 3, 1, 10);
+            record_pool(POOL_REDIS, 4, 0, 10);
+            record_pool_timeout(POOL_REDIS);
+        });
+        assert!(out.contains("neocrates_pool_connections{pool=\"redis\",state=\"in_use\"} 4"));
+        assert!(out.contains("neocrates_pool_connections{pool=\"redis\",state=\"idle\"} 0"));
+        assert!(out.contains("neocrates_pool_max_connections{pool=\"redis\"} 10"));
+        assert!(out.contains("neocrates_pool_timeouts_total{pool=\"redis\"} 1"));
+    }
+
+    #[cfg(any(feature = "web", feature = "full"))]
+    #[tokio::test]
+    async fn route_serves_the_text_format() {
+        use tower::ServiceExt;
+
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || record_auth(auth_outcome::TOKEN));
+
+        let app = axum::Router::new().route("/metrics", metrics_route(handle));
+        let response = app
+            .oneshot(
+                axum::http::Request::get("/metrics")
+                    .body(axum::body::Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+        assert!(
+            response.headers()[axum::http::header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("neocrates_auth_requests_total{outcome=\"token\"} 1"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{middlewares::ip::client_key, response::error::AppError};
+
+/// Bounds the number of *concurrent* in-flight requests per client.
+///
+/// Unlike rate limiting (requests per time window) this caps how many requests a single
+/// client may have open at once, so one misbehaving caller cannot saturate the connection
+/// pool. Clients are keyed by [`client_key`]: the authenticated uid when an
+/// [`AuthModel`](crate::middlewares::models::AuthModel) is present in the request
+/// extensions (i.e. the limiter runs inside `interceptor`), otherwise the socket IP.
+/// Requests whose client cannot be identified are not limited.
+///
+/// ```rust,ignore
+/// let limiter = ConcurrencyLimiter::new(16);
+/// let app = Router::new()
+///     .route("/items", get(list_items))
+///     .layer(axum::middleware::from_fn_with_state(limiter, concurrency_limit));
+/// ```
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    max_per_client: usize,
+    trust_proxy: bool,
+    clients: DashMap<String, Arc<Semaphore>>,
+}
+
+impl ConcurrencyLimiter {
+    /// Create a limiter allowing `max_per_client` concurrent requests per key (at least 1).
+    pub fn new(max_per_client: usize) -> Arc<Self> {
+        Self::build(max_per_client, false)
+    }
+
+    /// Like [`Self::new`], but anonymous clients are keyed by `x-real-ip` /
+    /// `x-forwarded-for` when present. Only use it behind a proxy that overwrites them.
+    pub fn behind_proxy(max_per_client: usize) -> Arc<Self> {
+        Self::build(max_per_client, true)
+    }
+
+    fn build(max_per_client: usize, trust_proxy: bool) -> Arc<Self> {
+        Arc::new(Self {
+            max_per_client: max_per_client.max(1),
+            trust_proxy,
+            clients: DashMap::new(),
+        })
+    }
+
+    /// Maximum concurrent requests allowed per client.
+    pub fn max_per_client(&self) -> usize {
+        self.max_per_client
+    }
+
+    /// Number of clients that currently have requests in flight.
+    pub fn active_clients(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Try to take a slot for `key`; `None` when the client is already at its limit.
+    pub fn try_acquire(&self, key: &str) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self
+            .clients
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_client)))
+            .clone();
+        semaphore.try_acquire_owned().ok()
+    }
+
+    /// Drop the client's entry once nothing holds its semaphore, keeping the map bounded.
+    fn release(&self, key: &str) {
+        self.clients
+            .remove_if(key, |_, semaphore| Arc::strong_count(semaphore) == 1);
+    }
+}
+
+/// A taken slot; dropping it frees the slot, also when the request future is cancelled
+/// (e.g. the client disconnects).
+struct Slot {
+    limiter: Arc<ConcurrencyLimiter>,
+    key: String,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        drop(self.permit.take());
+        self.limiter.release(&self.key);
+    }
+}
+
+/// Concurrency limit middleware, see [`ConcurrencyLimiter`].
+///
+/// Rejects with `AppError::RateLimit` (HTTP 429) when the client already has
+/// `max_per_client` requests in flight.
+pub async fn concurrency_limit(
+    State(limiter): State<Arc<ConcurrencyLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = client_key(&request, limiter.trust_proxy) else {
+        return next.run(request).await;
+    };
+    let Some(permit) = limiter.try_acquire(&key) else {
+        tracing::warn!(
+            "「concurrency_limit」 client {} exceeded {} concurrent requests",
+            key,
+            limiter.max_per_client
+        );
+        limiter.release(&key);
+        return AppError::RateLimit(format!(
+            "Too many concurrent requests, max {} per client",
+            limiter.max_per_client
+        ))
+        .into_response();
+    };
+
+    let _slot = Slot {
+        limiter: limiter.clone(),
+        key,
+        permit: Some(permit),
+    };
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, extract::ConnectInfo, http::StatusCode, routing::get};
+    use std::{net::SocketAddr, time::Duration};
+    use tower::ServiceExt;
+
+    fn app(limiter: Arc<ConcurrencyLimiter>) -> Router {
+        Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    "done"
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limiter,
+                concurrency_limit,
+            ))
+    }
+
+    fn request(ip: [u8; 4]) -> Request {
+        let mut request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((ip, 443))));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_limits_concurrent_requests_per_client() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let first = tokio::spawn(app(limiter.clone()).oneshot(request([10, 0, 0, 1])));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let rejected = app(limiter.clone())
+            .oneshot(request([10, 0, 0, 1]))
+            .await
+            .unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let other_client = app(limiter.clone())
+            .oneshot(request([10, 0, 0, 2]))
+            .await
+            .unwrap();
+        assert_eq!(other_client.status(), StatusCode::OK);
+
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(limiter.active_clients(), 0);
+
+        let after = app(limiter).oneshot(request([10, 0, 0, 1])).await.unwrap();
+        assert_eq!(after.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_frees_its_slot() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let first = tokio::spawn(app(limiter.clone()).oneshot(request([10, 0, 0, 1])));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(limiter.active_clients(), 1);
+
+        first.abort();
+        let _ = first.await;
+        assert_eq!(limiter.active_clients(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_headers_only_count_behind_proxy() {
+        let spoofed = |ip: &str| {
+            let mut request = request([10, 0, 0, 1]);
+            request
+                .headers_mut()
+                .insert("x-real-ip", ip.parse().unwrap());
+            request
+        };
+
+        let limiter = ConcurrencyLimiter::new(1);
+        let first = tokio::spawn(app(limiter.clone()).oneshot(spoofed("1.1.1.1")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let rotated = app(limiter).oneshot(spoofed("2.2.2.2")).await.unwrap();
+        assert_eq!(rotated.status(), StatusCode::TOO_MANY_REQUESTS);
+        first.await.unwrap().unwrap();
+
+        let limiter = ConcurrencyLimiter::behind_proxy(1);
+        let first = tokio::spawn(app(limiter.clone()).oneshot(spoofed("1.1.1.1")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let other = app(limiter).oneshot(spoofed("2.2.2.2")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+        first.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,288 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use sha1::{Digest, Sha1};
+use tower_http::compression::CompressionLayer;
+
+/// Maximum body size buffered to compute an ETag; larger responses are passed through
+/// untagged.
+pub const ETAG_MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+/// ETag / conditional GET middleware.
+///
+/// Computes a strong ETag from the response body for `GET`/`HEAD` 200 responses and
+/// answers matching `If-None-Match` requests with `304 Not Modified`.
+///
+/// The validator is encoding-specific: when the response carries a `Content-Encoding`
+/// the encoding is part of the tag (`"<hash>-gzip"`), and `Vary: Accept-Encoding` is always
+/// set, so a cached 304 for a gzip body is never served to a client expecting identity
+/// (or br) and vice versa.
+///
+/// Register it **outside** the compression layer so it sees the encoded body, i.e. add it
+/// after [`compression_layer`]:
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/items", get(list_items))
+///     .layer(compression_layer())
+///     .layer(axum::middleware::from_fn(etag));
+/// ```
+pub async fn etag(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    if method != Method::GET && method != Method::HEAD {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK
+        || is_streaming(response.headers())
+        || content_length(response.headers()).is_some_and(|len| len > ETAG_MAX_BODY_BYTES)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    add_vary_accept_encoding(&mut parts.headers);
+
+    // Respect an ETag chosen by the handler; only answer the conditional request.
+    if let Some(existing) = parts.headers.get(header::ETAG) {
+        if if_none_match_matches(if_none_match.as_ref(), existing) {
+            return not_modified(&parts.headers);
+        }
+        return Response::from_parts(parts, body);
+    }
+
+    if body
+        .size_hint()
+        .upper()
+        .is_some_and(|upper| upper > ETAG_MAX_BODY_BYTES as u64)
+    {
+        return Response::from_parts(parts, body);
+    }
+    // Unsized bodies (chunked, compressed) are only known to fit once read.
+    let bytes = match buffer_body(body, ETAG_MAX_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(body) => return Response::from_parts(parts, body),
+    };
+    let encoding = parts
+        .headers
+        .get(header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok());
+    if let Ok(tag) = HeaderValue::from_str(&compute_etag(&bytes, encoding)) {
+        let matched = if_none_match_matches(if_none_match.as_ref(), &tag);
+        parts.headers.insert(header::ETAG, tag);
+        if matched {
+            return not_modified(&parts.headers);
+        }
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Buffer a body of at most `limit` bytes.
+///
+/// When the body is larger, or fails mid-way, returns a body that yields everything read
+/// so far followed by the rest of the original, so the client still gets the full response.
+pub(crate) async fn buffer_body(body: Body, limit: usize) -> Result<Bytes, Body> {
+    let mut stream = body.into_data_stream();
+    let mut buf = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) if buf.len() + chunk.len() <= limit => buf.extend_from_slice(&chunk),
+            next => {
+                let head = futures::stream::iter([Ok(Bytes::from(buf)), next]);
+                return Err(Body::from_stream(head.chain(stream)));
+            }
+        }
+    }
+    Ok(Bytes::from(buf))
+}
+
+/// Build a 304 carrying the validator and cache headers of the full response.
+fn not_modified(headers: &HeaderMap) -> Response {
+    let mut response = StatusCode::NOT_MODIFIED.into_response();
+    for name in [
+        header::ETAG,
+        header::VARY,
+        header::CACHE_CONTROL,
+        header::CONTENT_ENCODING,
+        header::EXPIRES,
+        header::LAST_MODIFIED,
+    ] {
+        for value in headers.get_all(&name) {
+            response.headers_mut().append(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+/// Compression layer (gzip/br/deflate/zstd negotiated from `Accept-Encoding`).
+///
+/// tower-http already sets `Vary: Accept-Encoding` on compressed responses; combined with
+/// [`etag`] registered outside of it, validators stay encoding-specific.
+pub fn compression_layer() -> CompressionLayer {
+    CompressionLayer::new()
+}
+
+/// Build the ETag value for a body, tagging it with the content encoding when present.
+pub fn compute_etag(body: &[u8], content_encoding: Option<&str>) -> String {
+    let digest = hex::encode(Sha1::digest(body));
+    match content_encoding
+        .map(str::trim)
+        .filter(|e| !e.is_empty() && !e.eq_ignore_ascii_case("identity"))
+    {
+        Some(encoding) => format!("\"{}-{}\"", &digest[..32], encoding.to_ascii_lowercase()),
+        None => format!("\"{}\"", &digest[..32]),
+    }
+}
+
+fn is_streaming(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+fn add_vary_accept_encoding(headers: &mut HeaderMap) {
+    let already_varies = headers.get_all(header::VARY).iter().any(|v| {
+        v.to_str().is_ok_and(|v| {
+            v.split(',')
+                .map(str::trim)
+                .any(|item| item == "*" || item.eq_ignore_ascii_case("accept-encoding"))
+        })
+    });
+    if !already_varies {
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+}
+
+/// Weak comparison as required for `If-None-Match` (RFC 9110 §13.1.2).
+fn if_none_match_matches(if_none_match: Option<&HeaderValue>, etag: &HeaderValue) -> bool {
+    let (Some(candidates), Ok(etag)) = (if_none_match.and_then(|v| v.to_str().ok()), etag.to_str())
+    else {
+        return false;
+    };
+    let etag = etag.trim_start_matches("W/");
+    candidates
+        .split(',')
+        .map(str::trim)
+        .any(|c| c == "*" || c.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::to_bytes, routing::get};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/", get(|| async { "hello etag ".repeat(100) }))
+            .route(
+                "/large",
+                get(|| async {
+                    let chunk = Bytes::from(vec![b'x'; 1024 * 1024]);
+                    let chunks = (0..9).map(move |_| Ok::<_, std::io::Error>(chunk.clone()));
+                    Body::from_stream(futures::stream::iter(chunks))
+                }),
+            )
+            .layer(compression_layer())
+            .layer(axum::middleware::from_fn(etag))
+    }
+
+    async fn call(accept_encoding: Option<&str>, if_none_match: Option<&str>) -> Response {
+        let mut builder = Request::builder().uri("/");
+        if let Some(ae) = accept_encoding {
+            builder = builder.header(header::ACCEPT_ENCODING, ae);
+        }
+        if let Some(inm) = if_none_match {
+            builder = builder.header(header::IF_NONE_MATCH, inm);
+        }
+        app()
+            .oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compute_etag_is_encoding_specific() {
+        let plain = compute_etag(b"body", None);
+        let identity = compute_etag(b"body", Some("identity"));
+        let gzip = compute_etag(b"body", Some("gzip"));
+        assert_eq!(plain, identity);
+        assert_ne!(plain, gzip);
+        assert!(gzip.ends_with("-gzip\""));
+    }
+
+    #[tokio::test]
+    async fn test_etag_differs_per_encoding_and_sets_vary() {
+        let plain = call(None, None).await;
+        let gzip = call(Some("gzip"), None).await;
+
+        let plain_tag = plain.headers().get(header::ETAG).unwrap().clone();
+        let gzip_tag = gzip.headers().get(header::ETAG).unwrap().clone();
+        assert_ne!(plain_tag, gzip_tag);
+        assert_eq!(
+            gzip.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        for resp in [&plain, &gzip] {
+            let vary = resp.headers().get(header::VARY).unwrap().to_str().unwrap();
+            assert!(vary.to_ascii_lowercase().contains("accept-encoding"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_if_none_match_only_matches_same_encoding() {
+        let gzip = call(Some("gzip"), None).await;
+        let gzip_tag = gzip
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let revalidated = call(Some("gzip"), Some(&gzip_tag)).await;
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            revalidated.headers().get(header::ETAG).unwrap(),
+            gzip_tag.as_str()
+        );
+
+        // A client without gzip must not get a 304 for the gzip validator.
+        let identity = call(None, Some(&gzip_tag)).await;
+        assert_eq!(identity.status(), StatusCode::OK);
+        let body = to_bytes(identity.into_body(), usize::MAX).await.unwrap();
+        assert!(body.starts_with(b"hello etag"));
+    }
+
+    #[tokio::test]
+    async fn test_large_unsized_body_passes_through_untagged() {
+        let response = app()
+            .oneshot(
+                Request::builder()
+                    .uri("/large")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::ETAG).is_none());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.len(), 9 * 1024 * 1024);
+    }
+}
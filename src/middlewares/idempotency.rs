@@ -0,0 +1,599 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::{Body, to_bytes},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    helper::core::ulid::generate_ulid,
+    middlewares::{etag::buffer_body, models::AuthModel, token_store::DynTokenStore},
+    response::error::AppError,
+};
+
+/// Request header carrying the client-chosen idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Set to `true` on responses replayed from the cache.
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Longest accepted idempotency key.
+pub const IDEMPOTENCY_KEY_MAX_LEN: usize = 255;
+
+/// Default limit for buffering a response so it can be cached.
+pub const IDEMPOTENCY_MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Default limit for buffering a request body to fingerprint it; same as axum's
+/// `DefaultBodyLimit`.
+pub const IDEMPOTENCY_MAX_REQUEST_BYTES: usize = 2 * 1024 * 1024;
+
+/// Configuration for the [`idempotency`] middleware.
+///
+/// ```rust,ignore
+/// let config = Arc::new(IdempotencyConfig::new(token_store).with_ttl(Duration::from_secs(3600)));
+/// let app = Router::new()
+///     .route("/payments", post(create_payment))
+///     .layer(axum::middleware::from_fn_with_state(config, idempotency));
+/// ```
+#[derive(Clone)]
+pub struct IdempotencyConfig {
+    store: DynTokenStore,
+    prefix: String,
+    ttl: Duration,
+    lock_ttl: Duration,
+    max_body_bytes: usize,
+    max_request_bytes: usize,
+}
+
+impl IdempotencyConfig {
+    /// Cache responses for 24 hours under the `idempotency:` key prefix; an in-flight lock
+    /// expires after 60 seconds.
+    ///
+    /// The store must implement `set_raw_if_absent` and `compare_and_delete` (the in-memory
+    /// and Redis stores do).
+    pub fn new(store: DynTokenStore) -> Self {
+        Self {
+            store,
+            prefix: "idempotency:".to_string(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            lock_ttl: Duration::from_secs(60),
+            max_body_bytes: IDEMPOTENCY_MAX_BODY_BYTES,
+            max_request_bytes: IDEMPOTENCY_MAX_REQUEST_BYTES,
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// How long a completed response is replayed for.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long a request may hold a key before a duplicate may run the handler again.
+    /// Keep it above the slowest handler's runtime.
+    pub fn with_lock_ttl(mut self, lock_ttl: Duration) -> Self {
+        self.lock_ttl = lock_ttl;
+        self
+    }
+
+    /// Responses larger than this are returned but not cached.
+    pub fn with_max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.max_body_bytes = max_body_bytes;
+        self
+    }
+
+    /// Keyed requests with a larger body are rejected with 413, since the body is part of
+    /// the fingerprint.
+    pub fn with_max_request_bytes(mut self, max_request_bytes: usize) -> Self {
+        self.max_request_bytes = max_request_bytes;
+        self
+    }
+}
+
+/// What the store holds for one idempotency key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum Record {
+    /// A request with this key is running; `lock` identifies it.
+    InFlight { lock: String, fingerprint: String },
+    Completed {
+        fingerprint: String,
+        status: u16,
+        headers: Vec<(String, String)>,
+        /// Base64 response body.
+        body: String,
+    },
+    /// The handler ran but its response could not be cached (stream, oversized body).
+    Uncacheable { fingerprint: String },
+}
+
+/// Idempotency-key middleware for retried `POST`/`PUT`/`PATCH`/`DELETE` requests.
+///
+/// The first request with a given `Idempotency-Key` runs the handler and its response
+/// (status, headers, body) is cached; later requests with the same key get that response
+/// back, marked with `idempotent-replayed: true`, without running the handler again.
+///
+/// - A duplicate arriving while the first is still running gets 409.
+/// - Reusing a key for a different method, path or request body gets 422.
+/// - 5xx responses are not cached; the key is released so the client can retry.
+/// - Responses that cannot be buffered (streams, bodies over `max_body_bytes`) are sent
+///   to the client as-is and not cached, but the key stays taken: later duplicates get
+///   409 rather than running the handler a second time.
+///
+/// Keys are scoped per user when an [`AuthModel`] is present (i.e. the middleware runs
+/// inside `interceptor`). Requests without the header, and safe methods, pass through.
+pub async fn idempotency(
+    State(config): State<Arc<IdempotencyConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+    let Some(raw_key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let idempotency_key = match raw_key.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= IDEMPOTENCY_KEY_MAX_LEN => key.to_string(),
+        _ => {
+            return AppError::ValidationError(format!(
+                "Invalid {} header, expected 1-{} visible characters",
+                IDEMPOTENCY_KEY_HEADER, IDEMPOTENCY_KEY_MAX_LEN
+            ))
+            .into_response();
+        }
+    };
+
+    let store_key = store_key(&config.prefix, &request, &idempotency_key);
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, config.max_request_bytes).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("「idempotency」 cannot buffer request body: {}", e);
+            return AppError::PayloadTooLarge(format!(
+                "Request body exceeds {} bytes",
+                config.max_request_bytes
+            ))
+            .into_response();
+        }
+    };
+    let fingerprint = fingerprint(&parts, &body);
+    let request = Request::from_parts(parts, Body::from(body));
+    let lock = Record::InFlight {
+        lock: generate_ulid(),
+        fingerprint: fingerprint.clone(),
+    };
+    let lock_json = match serde_json::to_string(&lock) {
+        Ok(json) => json,
+        Err(e) => return AppError::from(e).into_response(),
+    };
+
+    match config
+        .store
+        .set_raw_if_absent(&store_key, &lock_json, Some(ttl_secs(config.lock_ttl)))
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => return existing_response(&config, &store_key, &fingerprint).await,
+        Err(e) => {
+            tracing::error!("「idempotency」 cannot lock {}: {}", store_key, e);
+            return AppError::Internal("Idempotency store unavailable".to_string()).into_response();
+        }
+    }
+
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        release(&config, &store_key, &lock_json).await;
+        return response;
+    }
+    if is_streaming(&response)
+        || content_length(&response).is_some_and(|len| len > config.max_body_bytes)
+    {
+        tracing::warn!(
+            "「idempotency」 response for {} is not cacheable",
+            store_key
+        );
+        complete(
+            &config,
+            &store_key,
+            &lock_json,
+            &Record::Uncacheable { fingerprint },
+        )
+        .await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match buffer_body(body, config.max_body_bytes).await {
+        Ok(bytes) => bytes,
+        Err(body) => {
+            tracing::warn!(
+                "「idempotency」 response body for {} exceeds {} bytes or failed, not cached",
+                store_key,
+                config.max_body_bytes
+            );
+            complete(
+                &config,
+                &store_key,
+                &lock_json,
+                &Record::Uncacheable { fingerprint },
+            )
+            .await;
+            return Response::from_parts(parts, body);
+        }
+    };
+
+    let completed = Record::Completed {
+        fingerprint,
+        status: parts.status.as_u16(),
+        headers: parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        body: STANDARD.encode(&bytes),
+    };
+    complete(&config, &store_key, &lock_json, &completed).await;
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Replace our lock with the final record for the key.
+async fn complete(config: &IdempotencyConfig, store_key: &str, lock_json: &str, record: &Record) {
+    let stored = match serde_json::to_string(record) {
+        Ok(json) => {
+            config
+                .store
+                .set_raw(store_key, &json, Some(ttl_secs(config.ttl)))
+                .await
+        }
+        Err(e) => Err(e.into()),
+    };
+    if let Err(e) = stored {
+        // The handler already ran: return its response, but let a retry run again rather
+        // than keep answering 409 until the lock expires.
+        tracing::error!(
+            "「idempotency」 cannot store response for {}: {}",
+            store_key,
+            e
+        );
+        release(config, store_key, lock_json).await;
+    }
+}
+
+/// Answer a request whose key is already taken.
+async fn existing_response(
+    config: &IdempotencyConfig,
+    store_key: &str,
+    fingerprint: &str,
+) -> Response {
+    let record = match config.store.get_raw(store_key).await {
+        Ok(Some(json)) => serde_json::from_str::<Record>(&json).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            tracing::error!("「idempotency」 cannot read {}: {}", store_key, e);
+            return AppError::Internal("Idempotency store unavailable".to_string()).into_response();
+        }
+    };
+    match record {
+        Some(Record::Completed {
+            fingerprint: stored,
+            ..
+        })
+        | Some(Record::InFlight {
+            fingerprint: stored,
+            ..
+        })
+        | Some(Record::Uncacheable {
+            fingerprint: stored,
+        }) if stored != fingerprint => AppError::UnprocessableEntity(format!(
+            "{} was already used for a different request",
+            IDEMPOTENCY_KEY_HEADER
+        ))
+        .into_response(),
+        Some(Record::Completed {
+            status,
+            headers,
+            body,
+            ..
+        }) => replay(status, &headers, &body),
+        Some(Record::Uncacheable { .. }) => AppError::Conflict(
+            "A request with this idempotency key already completed; its response was not cached"
+                .to_string(),
+        )
+        .into_response(),
+        // In flight, or released between our attempt and this read: ask the client to retry.
+        _ => AppError::Conflict("A request with this idempotency key is in progress".to_string())
+            .into_response(),
+    }
+}
+
+fn replay(status: u16, headers: &[(String, String)], body: &str) -> Response {
+    let (Ok(status), Ok(body)) = (StatusCode::from_u16(status), STANDARD.decode(body)) else {
+        return AppError::Internal("Corrupt idempotency record".to_string()).into_response();
+    };
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Drop our lock, unless it already expired and another request took the key.
+async fn release(config: &IdempotencyConfig, store_key: &str, lock_json: &str) {
+    if let Err(e) = config.store.compare_and_delete(store_key, lock_json).await {
+        tracing::warn!("「idempotency」 cannot release {}: {}", store_key, e);
+    }
+}
+
+/// `<prefix>uid:<uid>:<key>` when authenticated, otherwise `<prefix><key>`.
+fn store_key(prefix: &str, request: &Request, idempotency_key: &str) -> String {
+    match request.extensions().get::<AuthModel>() {
+        Some(auth) => format!("{}uid:{}:{}", prefix, auth.uid, idempotency_key),
+        None => format!("{}{}", prefix, idempotency_key),
+    }
+}
+
+/// Method, path and a SHA-1 of the request body.
+fn fingerprint(parts: &axum::http::request::Parts, body: &[u8]) -> String {
+    format!(
+        "{} {} {}",
+        parts.method,
+        parts.uri.path(),
+        hex::encode(Sha1::digest(body))
+    )
+}
+
+fn ttl_secs(ttl: Duration) -> u64 {
+    ttl.as_secs().max(1)
+}
+
+fn is_streaming(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/event-stream"))
+}
+
+fn content_length(response: &Response) -> Option<usize> {
+    response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middlewares::token_store::default_in_memory_store;
+    use axum::{Router, body::Bytes, routing::post};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn app(config: Arc<IdempotencyConfig>, calls: Arc<AtomicUsize>) -> Router {
+        let ok_calls = calls.clone();
+        Router::new()
+            .route(
+                "/pay",
+                post(move || {
+                    let calls = ok_calls.clone();
+                    async move {
+                        let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        (
+                            StatusCode::CREATED,
+                            [("x-charge", n.to_string())],
+                            format!("charge {}", n),
+                        )
+                    }
+                }),
+            )
+            .route(
+                "/stream",
+                post({
+                    let calls = calls.clone();
+                    move || {
+                        let calls = calls.clone();
+                        async move {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            let chunks = ["abcdefgh", "ijklmnop", "qrstuvwx"]
+                                .map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk)));
+                            Body::from_stream(futures::stream::iter(chunks))
+                        }
+                    }
+                }),
+            )
+            .route(
+                "/fail",
+                post(move || {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(config, idempotency))
+    }
+
+    fn request(path: &str, key: Option<&str>) -> Request {
+        request_with_body(path, key, "")
+    }
+
+    fn request_with_body(path: &str, key: Option<&str>, body: &'static str) -> Request {
+        let mut builder = Request::builder().method(Method::POST).uri(path);
+        if let Some(key) = key {
+            builder = builder.header(IDEMPOTENCY_KEY_HEADER, key);
+        }
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn setup() -> (Arc<IdempotencyConfig>, Arc<AtomicUsize>) {
+        (
+            Arc::new(IdempotencyConfig::new(default_in_memory_store())),
+            Arc::new(AtomicUsize::new(0)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_replays_cached_response() {
+        let (config, calls) = setup();
+        let first = app(config.clone(), calls.clone())
+            .oneshot(request("/pay", Some("k1")))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(first.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+        assert_eq!(body_string(first).await, "charge 1");
+
+        let replayed = app(config.clone(), calls.clone())
+            .oneshot(request("/pay", Some("k1")))
+            .await
+            .unwrap();
+        assert_eq!(replayed.status(), StatusCode::CREATED);
+        assert_eq!(replayed.headers()["x-charge"], "1");
+        assert_eq!(replayed.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+        assert_eq!(body_string(replayed).await, "charge 1");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let other_key = app(config.clone(), calls.clone())
+            .oneshot(request("/pay", Some("k2")))
+            .await
+            .unwrap();
+        assert_eq!(body_string(other_key).await, "charge 2");
+
+        let no_key = app(config, calls.clone())
+            .oneshot(request("/pay", None))
+            .await
+            .unwrap();
+        assert_eq!(body_string(no_key).await, "charge 3");
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_duplicate_gets_conflict() {
+        let (config, calls) = setup();
+        let first =
+            tokio::spawn(app(config.clone(), calls.clone()).oneshot(request("/pay", Some("k"))));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        let duplicate = app(config.clone(), calls.clone())
+            .oneshot(request("/pay", Some("k")))
+            .await
+            .unwrap();
+        assert_eq!(duplicate.status(), StatusCode::CONFLICT);
+
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::CREATED);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_for_other_endpoint_is_rejected() {
+        let (config, calls) = setup();
+        app(config.clone(), calls.clone())
+            .oneshot(request("/pay", Some("k")))
+            .await
+            .unwrap();
+        let reused = app(config, calls)
+            .oneshot(request("/fail", Some("k")))
+            .await
+            .unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_other_body_is_rejected() {
+        let (config, calls) = setup();
+        let first = app(config.clone(), calls.clone())
+            .oneshot(request_with_body("/pay", Some("k"), r#"{"amount":10}"#))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+
+        let same = app(config.clone(), calls.clone())
+            .oneshot(request_with_body("/pay", Some("k"), r#"{"amount":10}"#))
+            .await
+            .unwrap();
+        assert_eq!(same.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+
+        let other = app(config, calls.clone())
+            .oneshot(request_with_body("/pay", Some("k"), r#"{"amount":99}"#))
+            .await
+            .unwrap();
+        assert_eq!(other.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_unsized_response_is_streamed_and_key_kept() {
+        let config =
+            Arc::new(IdempotencyConfig::new(default_in_memory_store()).with_max_body_bytes(10));
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let first = app(config.clone(), calls.clone())
+            .oneshot(request("/stream", Some("k")))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(body_string(first).await, "abcdefghijklmnopqrstuvwx");
+
+        let retry = app(config, calls.clone())
+            .oneshot(request("/stream", Some("k")))
+            .await
+            .unwrap();
+        assert_eq!(retry.status(), StatusCode::CONFLICT);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_not_cached() {
+        let (config, calls) = setup();
+        for _ in 0..2 {
+            let response = app(config.clone(), calls.clone())
+                .oneshot(request("/fail", Some("k")))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_key_is_rejected() {
+        let (config, calls) = setup();
+        let long_key = "k".repeat(IDEMPOTENCY_KEY_MAX_LEN + 1);
+        let response = app(config, calls.clone())
+            .oneshot(request("/pay", Some(&long_key)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
@@ -1,18 +1,25 @@
 use axum::{
     body::{Body, Bytes},
     extract::{Request, State},
+    http::{
+        HeaderMap, Method,
+        header::{CONTENT_LENGTH, CONTENT_TYPE, COOKIE},
+    },
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use serde_json::{Value, json};
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 use url::form_urlencoded;
 
 use crate::{
     crypto::core::Crypto,
     middlewares::{
         ip::get_request_host,
-        models::{AUTHORIZATION, AuthModel, BASIC, BEARER, CACHE_AUTH_TOKEN, MiddlewareConfig},
+        models::{
+            AUTHORIZATION, AuditFieldNames, AuthModel, BASIC, CACHE_AUTH_TOKEN, MiddlewareConfig,
+            TokenSource,
+        },
     },
     response::error::{AppError, AppResult},
 };
@@ -50,6 +57,8 @@ pub async fn interceptor(
         .find(|ignore_url| uri.starts_with(ignore_url.as_str()))
     {
         tracing::info!("Middleware Authorization Ignore Urls :{}", ignore_url);
+        #[cfg(any(feature = "metrics", feature = "full"))]
+        crate::metrics::record_auth(crate::metrics::auth_outcome::IGNORED);
         return next.run(request).await;
     }
     // PMS (Permission Management System) ignore URLs
@@ -62,7 +71,8 @@ pub async fn interceptor(
             .get(AUTHORIZATION)
             .and_then(|value| value.to_str().ok())
             .filter(|auth_str| auth_str.starts_with(BASIC))
-            .map(|auth_str| auth_str[(BASIC.len() + 1)..].trim());
+            .and_then(|auth_str| auth_str.get((BASIC.len() + 1)..))
+            .map(str::trim);
 
         tracing::info!(
             "Middleware Authorization PMS Ignore Urls: {:?} auth_basics:{:?} auth_str:{:?}",
@@ -71,54 +81,32 @@ pub async fn interceptor(
             auth_str
         );
         if let Some(auth_str) = auth_str {
-            // Check if auth_str is in auth_basics
-            if let Some(matched_basic) = auth_basics.iter().find(|basic| basic.as_str() == auth_str)
-            {
-                let basic = Crypto::decode_basic_auth_key(matched_basic).map_err(|e| {
+            match basic_auth_principal(auth_str, auth_basics, config.basic_auth_secret.as_deref()) {
+                Some(principal) => tracing::info!(
+                    "Middleware Authorization BASIC Success principal:{}",
+                    principal
+                ),
+                None => {
                     tracing::warn!(
-                        "Middleware Authorization BASIC failed: auth_str:{:?} error{:?}",
-                        auth_str,
-                        e
+                        "Middleware Authorization BASIC not allowed auth_str:{:?}",
+                        auth_str
                     );
-                    AppError::Unauthorized.into_response()
-                });
-                tracing::info!(
-                    "Middleware Authorization BASIC Success auth_str:{} basic:{:?}",
-                    auth_str,
-                    basic
-                );
-            } else {
-                tracing::warn!(
-                    "Middleware Authorization BASIC not allowed auth_str:{:?}",
-                    auth_str
-                );
-                return AppError::Unauthorized.into_response();
+                    #[cfg(any(feature = "metrics", feature = "full"))]
+                    crate::metrics::record_auth(crate::metrics::auth_outcome::UNAUTHORIZED);
+                    return AppError::Unauthorized.into_response();
+                }
             }
         } else {
             tracing::warn!("Middleware Missing or Invalid Authorization BASIC header");
+            #[cfg(any(feature = "metrics", feature = "full"))]
+            crate::metrics::record_auth(crate::metrics::auth_outcome::UNAUTHORIZED);
             return AppError::Unauthorized.into_response();
         }
+        #[cfg(any(feature = "metrics", feature = "full"))]
+        crate::metrics::record_auth(crate::metrics::auth_outcome::BASIC);
         return next.run(request).await;
     }
-    // Support two token sources: Authorization header and accessToken query param
-    let mut token_opt: Option<String> = None;
-    if let Some(auth_header) = request.headers().get(AUTHORIZATION) {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with(BEARER) {
-                token_opt = Some(auth_str[(BEARER.len() + 1)..].to_string());
-            }
-        }
-    }
-    if token_opt.is_none() {
-        if let Some(query) = request.uri().query() {
-            let params: HashMap<_, _> = form_urlencoded::parse(query.as_bytes())
-                .into_owned()
-                .collect();
-            if let Some(token) = params.get("accessToken") {
-                token_opt = Some(token.clone());
-            }
-        }
-    }
+    let token_opt = extract_token(&request, &config.token_sources);
     if let Some(token) = token_opt {
         let store_key = format!("{}{}{}", prefix, CACHE_AUTH_TOKEN, token);
         let auth_model: AuthModel = match crate::middlewares::token_store::store_get::<AuthModel>(
@@ -127,8 +115,17 @@ pub async fn interceptor(
         )
         .await
         {
-            Ok(Some(m)) => m,
+            Ok(Some(m)) => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_cache_lookup(crate::metrics::CACHE_TOKEN, true);
+                m
+            }
             Ok(None) => {
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                {
+                    crate::metrics::record_cache_lookup(crate::metrics::CACHE_TOKEN, false);
+                    crate::metrics::record_auth(crate::metrics::auth_outcome::EXPIRED);
+                }
                 return {
                     tracing::warn!(
                         "Middleware token expired: store_key:{} token:{}",
@@ -140,51 +137,185 @@ pub async fn interceptor(
             }
             Err(e) => {
                 tracing::warn!("Middleware failed to fetch token from store: {}", e);
+                #[cfg(any(feature = "metrics", feature = "full"))]
+                crate::metrics::record_auth(crate::metrics::auth_outcome::EXPIRED);
                 return AppError::TokenExpired.into_response();
             }
         };
         tracing::warn!("Middleware extracted cache_token: {:?}", &auth_model);
-        // TODO: Load role permission
-        let uid = auth_model.uid;
-        let tid = auth_model.tid;
-        let ouid = auth_model.ouid;
+        if let Some(authorize) = &config.authorize
+            && let Err(e) = authorize(&auth_model, request.uri(), request.method()).await
+        {
+            tracing::warn!(
+                "Middleware authorization denied: uid:{} uri:{} error:{}",
+                auth_model.uid,
+                uri,
+                e
+            );
+            #[cfg(any(feature = "metrics", feature = "full"))]
+            crate::metrics::record_auth(crate::metrics::auth_outcome::DENIED);
+            return e.into_response();
+        }
 
         // Rewrite auth model into request extensions
         request.extensions_mut().insert(auth_model);
+        #[cfg(any(feature = "metrics", feature = "full"))]
+        crate::metrics::record_auth(crate::metrics::auth_outcome::TOKEN);
     } else {
         tracing::warn!(
-            "Middleware Missing Authorization BEARER header and accessToken query param"
+            "Middleware Missing access token, tried sources: {:?}",
+            config.token_sources
         );
+        #[cfg(any(feature = "metrics", feature = "full"))]
+        crate::metrics::record_auth(crate::metrics::auth_outcome::UNAUTHORIZED);
         return AppError::Unauthorized.into_response();
     }
-    // Read and modify the body
-    let body_bytes = match read_and_print_body(&mut request).await {
-        Ok(b) => b,
-        Err(e) => return e.into_response(),
-    };
-    let modified_bytes = match modify_body(body_bytes, &mut request).await {
-        Ok(b) => b,
-        Err(e) => return e.into_response(),
-    };
-    *request.body_mut() = Body::from(modified_bytes);
+    // Read and modify the body only when audit fields apply; GET/DELETE, empty and non-JSON
+    // bodies (multipart uploads, binary) pass through without being buffered
+    if needs_body_rewrite(&request, &config.audit_fields) {
+        let body_bytes = match read_and_print_body(&mut request, config.max_body_bytes).await {
+            Ok(b) => b,
+            Err(e) => return e.into_response(),
+        };
+        let modified_bytes = match modify_body(body_bytes, &mut request, &config.audit_fields).await
+        {
+            Ok(b) => b,
+            Err(e) => return e.into_response(),
+        };
+        *request.body_mut() = Body::from(modified_bytes);
+    }
 
     // next response
     let response = next.run(request).await;
     response
 }
 
+/// Resolve a PMS BASIC credential to the principal it was issued for.
+///
+/// # Arguments
+/// auth_str - Credential from the `Authorization: Basic` header.
+/// auth_basics - Legacy allow-list of double-base64 keys.
+/// secret - Secret for keyed tokens from `Crypto::sign_basic_auth_key`, if configured.
+///
+/// Returns
+/// The principal, or None if the credential is not accepted. Keyed tokens are checked
+/// first; allow-listed legacy keys are still accepted so existing clients keep working
+/// while they move to keyed tokens.
+fn basic_auth_principal(
+    auth_str: &str,
+    auth_basics: &[String],
+    secret: Option<&str>,
+) -> Option<String> {
+    if let Some(principal) = secret.and_then(|s| Crypto::verify_basic_auth_key(auth_str, s)) {
+        return Some(principal);
+    }
+    if auth_basics.iter().any(|basic| basic.as_str() == auth_str) {
+        tracing::warn!("Middleware Authorization BASIC accepted legacy double-base64 key");
+        #[allow(deprecated)]
+        let principal = Crypto::decode_basic_auth_key(auth_str)
+            .unwrap_or_else(|_| "<undecodable legacy key>".to_string());
+        return Some(principal);
+    }
+    None
+}
+
+/// Extract the access token from the first matching source.
+///
+/// # Arguments
+/// request - The incoming HTTP request.
+/// sources - Token sources, tried in order.
+///
+/// Returns
+/// The first non-empty token found, or None.
+fn extract_token(request: &Request, sources: &[TokenSource]) -> Option<String> {
+    sources.iter().find_map(|source| {
+        let token = match source {
+            TokenSource::Header { name, prefix } => {
+                let value = request.headers().get(name.as_str())?.to_str().ok()?.trim();
+                match prefix {
+                    Some(prefix) => {
+                        let (scheme, rest) = value.split_once(' ')?;
+                        if !scheme.eq_ignore_ascii_case(prefix) {
+                            return None;
+                        }
+                        rest.trim().to_string()
+                    }
+                    None => value.to_string(),
+                }
+            }
+            TokenSource::Cookie(name) => request
+                .headers()
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .flat_map(|v| v.split(';'))
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .map(|(_, value)| value.trim().trim_matches('"').to_string())?,
+            TokenSource::Query(name) => form_urlencoded::parse(request.uri().query()?.as_bytes())
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.into_owned())?,
+        };
+        (!token.is_empty()).then_some(token)
+    })
+}
+
+/// Whether the interceptor has to buffer the body to inject audit fields: the method is one
+/// audit fields apply to, the body is JSON and it is not declared empty.
+fn needs_body_rewrite(request: &Request, fields: &AuditFieldNames) -> bool {
+    let declared_empty = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "0");
+    fields.applies_to(request.method()) && !declared_empty && is_json_request(request.headers())
+}
+
+/// Whether the request carries a JSON body (`application/json` or `application/*+json`).
+fn is_json_request(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|mime| {
+            let mime = mime.trim().to_ascii_lowercase();
+            mime == "application/json"
+                || (mime.starts_with("application/") && mime.ends_with("+json"))
+        })
+        .unwrap_or(false)
+}
+
 /// Read and print the request body
 /// # Arguments
 /// request - The incoming HTTP request.
+/// max_body_bytes - Maximum number of bytes to buffer.
 ///
 /// Returns
-/// The request body as Bytes.
-async fn read_and_print_body(request: &mut Request) -> AppResult<Bytes> {
+/// The request body as Bytes, or `AppError::PayloadTooLarge` (413) when it exceeds `max_body_bytes`.
+async fn read_and_print_body(request: &mut Request, max_body_bytes: usize) -> AppResult<Bytes> {
+    let declared_len = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if declared_len.is_some_and(|len| len > max_body_bytes) {
+        return Err(too_large(max_body_bytes));
+    }
+
     let body = std::mem::replace(request.body_mut(), Body::empty());
 
-    let bytes = axum::body::to_bytes(body, usize::MAX)
+    // Chunked bodies have no Content-Length; `to_bytes` stops at the limit instead.
+    let bytes = axum::body::to_bytes(body, max_body_bytes)
         .await
-        .map_err(|_| AppError::ClientError("Middleware Invalid request body".into()))?;
+        .map_err(|e| {
+            tracing::warn!("Middleware failed to read request body: {}", e);
+            let e = e.into_inner();
+            if e.is::<http_body_util::LengthLimitError>() {
+                too_large(max_body_bytes)
+            } else {
+                AppError::ClientError(format!("Middleware Invalid request body: {}", e))
+            }
+        })?;
 
     // if let Ok(body_str) = String::from_utf8(bytes.to_vec()) {
     //     warn!("「read_and_print_body」Request body: {}", body_str);
@@ -193,43 +324,58 @@ async fn read_and_print_body(request: &mut Request) -> AppResult<Bytes> {
     Ok(bytes)
 }
 
+fn too_large(max_body_bytes: usize) -> AppError {
+    AppError::PayloadTooLarge(format!(
+        "Middleware request body too large, limit is {} bytes",
+        max_body_bytes
+    ))
+}
+
 /// Modify the request body.
 ///
 /// # Arguments
 /// bytes - The original request body as Bytes.
 /// request - The incoming HTTP request.
+/// fields - Audit field names to inject.
 ///
 /// Returns
 /// The modified request body as Bytes.
-async fn modify_body(bytes: Bytes, request: &mut Request) -> AppResult<Bytes> {
+async fn modify_body(
+    bytes: Bytes,
+    request: &mut Request,
+    fields: &AuditFieldNames,
+) -> AppResult<Bytes> {
     if bytes.is_empty() {
         return Ok(bytes);
     }
-    if let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) {
-        match &mut json {
-            Value::Object(obj) => {
-                insert_auth_fields(obj, request);
-            }
-            Value::Array(arr) => {
-                for item in arr.iter_mut() {
-                    if let Value::Object(obj) = item {
-                        insert_auth_fields(obj, request);
-                    }
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        tracing::debug!("Middleware Interceptor body is not valid JSON, passing through");
+        return Ok(bytes);
+    };
+    let mut modified = false;
+    match &mut json {
+        Value::Object(obj) => {
+            insert_auth_fields(obj, request, fields);
+            modified = true;
+        }
+        Value::Array(arr) => {
+            for item in arr.iter_mut() {
+                if let Value::Object(obj) = item {
+                    insert_auth_fields(obj, request, fields);
+                    modified = true;
                 }
             }
-            _ => {
-                tracing::warn!("Middleware Interceptor json is not object or array");
-            }
         }
-
-        let modified_bytes = serde_json::to_vec(&json)
-            .map_err(|_| AppError::Internal("Middleware Interceptor JSON encode error".into()))?;
-        return Ok(Bytes::from(modified_bytes));
-    } else {
-        tracing::warn!("Middleware Interceptor json parse failed");
+        _ => {}
+    }
+    if !modified {
+        tracing::debug!("Middleware Interceptor JSON has no objects to inject into");
+        return Ok(bytes);
     }
 
-    Ok(bytes)
+    let modified_bytes = serde_json::to_vec(&json)
+        .map_err(|_| AppError::Internal("Middleware Interceptor JSON encode error".into()))?;
+    Ok(Bytes::from(modified_bytes))
 }
 
 /// Insert authentication fields into the JSON object.
@@ -237,33 +383,335 @@ async fn modify_body(bytes: Bytes, request: &mut Request) -> AppResult<Bytes> {
 /// # Arguments
 /// obj - The JSON object to modify.
 /// request - The incoming HTTP request.
+/// fields - Audit field names and the methods they apply to.
 ///
 /// Returns
-/// Nothing. The function modifies the JSON object in place.
-fn insert_auth_fields(obj: &mut serde_json::Map<String, Value>, request: &mut Request) {
-    match request.method().as_str() {
-        "POST" => {
-            if let Some(auth_model) = request.extensions().get::<AuthModel>() {
-                obj.insert("creator".to_string(), json!(auth_model.uid));
-                obj.insert("creator_by".to_string(), json!(auth_model.nickname));
-                obj.insert("updater".to_string(), json!(auth_model.uid));
-                obj.insert("updater_by".to_string(), json!(auth_model.nickname));
-            } else {
-                obj.insert("creator".to_string(), json!(0));
-                obj.insert("creator_by".to_string(), json!("anonymous"));
-                obj.insert("updater".to_string(), json!(0));
-                obj.insert("updater_by".to_string(), json!("anonymous"));
-            }
+/// Nothing. The function modifies the JSON object in place; fields the client already
+/// sent are kept as-is.
+fn insert_auth_fields(
+    obj: &mut serde_json::Map<String, Value>,
+    request: &mut Request,
+    fields: &AuditFieldNames,
+) {
+    if !fields.applies_to(request.method()) {
+        return;
+    }
+    let with_creator = request.method() == Method::POST;
+    let (uid, nickname) = match request.extensions().get::<AuthModel>() {
+        Some(auth_model) => (json!(auth_model.uid), json!(auth_model.nickname)),
+        None => (json!(0), json!("anonymous")),
+    };
+    let mut insert = |name: &str, value: &Value| {
+        if !obj.contains_key(name) {
+            obj.insert(name.to_string(), value.clone());
         }
-        "PUT" => {
-            if let Some(auth_model) = request.extensions().get::<AuthModel>() {
-                obj.insert("updater".to_string(), json!(auth_model.uid));
-                obj.insert("updater_by".to_string(), json!(auth_model.nickname));
-            } else {
-                obj.insert("updater".to_string(), json!(0));
-                obj.insert("updater_by".to_string(), json!("anonymous"));
-            }
+    };
+    if with_creator {
+        insert(&fields.creator, &uid);
+        insert(&fields.creator_by, &nickname);
+    }
+    insert(&fields.updater, &uid);
+    insert(&fields.updater_by, &nickname);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middlewares::models::{AuthorizeFn, DEFAULT_MAX_BODY_BYTES};
+
+    fn json_request(body: &'static str, content_length: Option<usize>) -> Request {
+        let mut builder = Request::builder()
+            .method("POST")
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(len) = content_length {
+            builder = builder.header(CONTENT_LENGTH, len);
         }
-        _ => {}
+        builder.body(Body::from(body)).unwrap()
+    }
+
+    fn auth_request(method: &str) -> Request {
+        let mut request = Request::builder()
+            .method(method)
+            .body(Body::empty())
+            .unwrap();
+        let auth: AuthModel = serde_json::from_value(json!({
+            "uid": 7, "nickname": "neo", "tid": 1, "ouid": 1
+        }))
+        .unwrap();
+        request.extensions_mut().insert(auth);
+        request
+    }
+
+    fn inject(method: &str, body: Value, fields: &AuditFieldNames) -> Value {
+        let Value::Object(mut obj) = body else {
+            panic!("expected object")
+        };
+        insert_auth_fields(&mut obj, &mut auth_request(method), fields);
+        Value::Object(obj)
+    }
+
+    #[test]
+    fn test_audit_fields_per_method() {
+        let fields = AuditFieldNames::default();
+        assert_eq!(
+            inject("POST", json!({"name": "a"}), &fields),
+            json!({"name": "a", "creator": 7, "creator_by": "neo", "updater": 7, "updater_by": "neo"})
+        );
+        assert_eq!(
+            inject("PUT", json!({"name": "a"}), &fields),
+            json!({"name": "a", "updater": 7, "updater_by": "neo"})
+        );
+        assert_eq!(
+            inject("PATCH", json!({"name": "a"}), &fields),
+            json!({"name": "a"})
+        );
+        assert_eq!(
+            inject("POST", json!({}), &AuditFieldNames::disabled()),
+            json!({})
+        );
+    }
+
+    #[test]
+    fn test_audit_fields_renamed_and_not_clobbered() {
+        let fields = AuditFieldNames::camel_case();
+        assert_eq!(
+            inject("POST", json!({"creator": 99}), &fields),
+            json!({"creator": 99, "creatorBy": "neo", "updater": 7, "updaterBy": "neo"})
+        );
+    }
+
+    fn token_request(uri: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_extract_token_default_chain() {
+        let sources = TokenSource::default_chain();
+        let req = token_request("/x?accessToken=q1", &[("Authorization", "Bearer h1")]);
+        assert_eq!(extract_token(&req, &sources).as_deref(), Some("h1"));
+        let req = token_request("/x?accessToken=q1", &[("Authorization", "Basic abc")]);
+        assert_eq!(extract_token(&req, &sources).as_deref(), Some("q1"));
+        let req = token_request("/x", &[("Authorization", "Bearer")]);
+        assert_eq!(extract_token(&req, &sources), None);
+    }
+
+    #[test]
+    fn test_extract_token_from_cookie_and_custom_header() {
+        let sources = vec![
+            TokenSource::header("X-Auth-Token"),
+            TokenSource::cookie("access_token"),
+        ];
+        let req = token_request("/x", &[("Cookie", "theme=dark; access_token=c1; lang=en")]);
+        assert_eq!(extract_token(&req, &sources).as_deref(), Some("c1"));
+        let req = token_request(
+            "/x",
+            &[("X-Auth-Token", "x1"), ("Cookie", "access_token=c1")],
+        );
+        assert_eq!(extract_token(&req, &sources).as_deref(), Some("x1"));
+        let req = token_request("/x", &[("Cookie", "my_access_token=c1")]);
+        assert_eq!(extract_token(&req, &sources), None);
+
+        let prefixed = [TokenSource::header_with_prefix("Authorization", "Token")];
+        let req = token_request("/x", &[("Authorization", "token t1")]);
+        assert_eq!(extract_token(&req, &prefixed).as_deref(), Some("t1"));
+    }
+
+    async fn authorize_app(authorize: Option<AuthorizeFn>) -> axum::Router {
+        let token_store = crate::middlewares::token_store::default_in_memory_store();
+        let auth: AuthModel = serde_json::from_value(json!({
+            "uid": 7, "tid": 1, "ouid": 1
+        }))
+        .unwrap();
+        let key = format!("{}{}", CACHE_AUTH_TOKEN, "t1");
+        crate::middlewares::token_store::store_set(token_store.as_ref(), &key, &auth, None)
+            .await
+            .unwrap();
+        let config = Arc::new(MiddlewareConfig {
+            token_store,
+            ignore_urls: vec![],
+            pms_ignore_urls: vec![],
+            prefix: String::new(),
+            auth_basics: vec![],
+            basic_auth_secret: None,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+            audit_fields: AuditFieldNames::default(),
+            token_sources: TokenSource::default_chain(),
+            authorize,
+        });
+        axum::Router::new()
+            .route("/admin/users", axum::routing::get(|| async { "ok" }))
+            .route("/items", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(config, interceptor))
+    }
+
+    async fn status_of(app: axum::Router, uri: &str) -> axum::http::StatusCode {
+        use tower::ServiceExt;
+        let request = token_request(
+            uri,
+            &[("Authorization", "Bearer t1"), ("x-real-ip", "10.0.0.1")],
+        );
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_authorize_hook_denies_with_returned_error() {
+        let authorize: AuthorizeFn = Arc::new(|auth, uri, method| {
+            let allowed = auth.uid == 7 && !uri.path().starts_with("/admin");
+            assert_eq!(method, axum::http::Method::GET);
+            Box::pin(async move {
+                if allowed {
+                    Ok(())
+                } else {
+                    Err(AppError::Forbidden)
+                }
+            })
+        });
+        let app = authorize_app(Some(authorize)).await;
+        assert_eq!(status_of(app.clone(), "/items").await, 200);
+        assert_eq!(status_of(app, "/admin/users").await, 403);
+
+        let open = authorize_app(None).await;
+        assert_eq!(status_of(open, "/admin/users").await, 200);
+    }
+
+    #[test]
+    fn test_needs_body_rewrite_skips_reads() {
+        let fields = AuditFieldNames::default();
+        let request = |method: &str, content_type: &str, len: &str| {
+            Request::builder()
+                .method(method)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, len)
+                .body(Body::empty())
+                .unwrap()
+        };
+        assert!(needs_body_rewrite(
+            &request("POST", "application/json", "2"),
+            &fields
+        ));
+        assert!(needs_body_rewrite(
+            &request("PUT", "application/json", "2"),
+            &fields
+        ));
+        assert!(!needs_body_rewrite(
+            &request("GET", "application/json", "2"),
+            &fields
+        ));
+        assert!(!needs_body_rewrite(
+            &request("DELETE", "application/json", "2"),
+            &fields
+        ));
+        assert!(!needs_body_rewrite(
+            &request("POST", "application/json", "0"),
+            &fields
+        ));
+        assert!(!needs_body_rewrite(
+            &request("POST", "multipart/form-data", "2"),
+            &fields
+        ));
+        assert!(!needs_body_rewrite(
+            &request("POST", "application/json", "2"),
+            &AuditFieldNames::disabled()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_body_is_not_read() {
+        use tower::ServiceExt;
+        // Declared length is above the limit: reading would be rejected, skipping passes.
+        let oversized = |method: &str| {
+            Request::builder()
+                .method(method)
+                .uri("/items")
+                .header("Authorization", "Bearer t1")
+                .header("x-real-ip", "10.0.0.1")
+                .header(CONTENT_TYPE, "application/json")
+                .header(CONTENT_LENGTH, DEFAULT_MAX_BODY_BYTES + 1)
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+        let app = authorize_app(None).await;
+        let get = app.clone().oneshot(oversized("GET")).await.unwrap();
+        assert_eq!(get.status(), 200);
+        let post = app.oneshot(oversized("POST")).await.unwrap();
+        assert_ne!(post.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_modify_body_keeps_original_bytes_when_untouched() {
+        let fields = AuditFieldNames::default();
+        for body in ["not json", "[1, 2, 3]", "\"text\""] {
+            let bytes = Bytes::from(body);
+            let out = modify_body(bytes.clone(), &mut auth_request("POST"), &fields)
+                .await
+                .unwrap();
+            assert_eq!(out, bytes);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_basic_auth_accepts_keyed_and_legacy_credentials() {
+        let legacy = Crypto::generate_basic_auth_key("legacy-service");
+        let keyed = Crypto::sign_basic_auth_key("billing", "s3cret");
+        let auth_basics = vec![legacy.clone()];
+
+        assert_eq!(
+            basic_auth_principal(&keyed, &auth_basics, Some("s3cret")).as_deref(),
+            Some("billing")
+        );
+        assert_eq!(
+            basic_auth_principal(&legacy, &auth_basics, Some("s3cret")).as_deref(),
+            Some("legacy-service")
+        );
+        assert_eq!(basic_auth_principal(&keyed, &auth_basics, None), None);
+        assert_eq!(basic_auth_principal(&keyed, &[], Some("rotated")), None);
+        assert_eq!(
+            basic_auth_principal("bogus", &auth_basics, Some("s3cret")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_json_request() {
+        let headers = |ct: &str| {
+            let mut h = HeaderMap::new();
+            h.insert(CONTENT_TYPE, ct.parse().unwrap());
+            h
+        };
+        assert!(is_json_request(&headers("application/json")));
+        assert!(is_json_request(&headers("Application/JSON; charset=utf-8")));
+        assert!(is_json_request(&headers("application/merge-patch+json")));
+        assert!(!is_json_request(&headers(
+            "multipart/form-data; boundary=x"
+        )));
+        assert!(!is_json_request(&headers("application/octet-stream")));
+        assert!(!is_json_request(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn test_read_body_respects_limit() {
+        let mut ok = json_request(r#"{"a":1}"#, None);
+        assert_eq!(
+            read_and_print_body(&mut ok, 64).await.unwrap(),
+            Bytes::from_static(br#"{"a":1}"#)
+        );
+
+        // Declared length over the limit is rejected before reading.
+        let mut declared = json_request(r#"{"a":1}"#, Some(1_000));
+        let err = read_and_print_body(&mut declared, 64).await.unwrap_err();
+        assert!(matches!(err, AppError::PayloadTooLarge(_)));
+        assert!(err.message().contains("too large"), "{}", err.message());
+
+        // Without Content-Length the read itself stops at the limit.
+        let mut streamed = json_request(r#"{"name":"a long body"}"#, None);
+        assert!(matches!(
+            read_and_print_body(&mut streamed, 8).await,
+            Err(AppError::PayloadTooLarge(_))
+        ));
     }
 }
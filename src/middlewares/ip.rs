@@ -1,24 +1,118 @@
-use axum::extract::Request;
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, Request};
+
+use crate::middlewares::models::AuthModel;
 
 /// Get the client IP and request URI
+///
+/// The IP comes from `x-real-ip`, then the first `x-forwarded-for` entry, then the socket
+/// address (`ConnectInfo`); it is empty when none is available. The headers are client
+/// controlled, so use this for logging only; limits should key on [`client_key`].
 pub fn get_request_host(request: &Request) -> (String, String) {
-    let real_ip = request.headers().get("x-real-ip");
-    let mut request_ip = if real_ip.is_some() {
-        real_ip
-            .expect("Failed to get real IP")
-            .to_str()
-            .expect("Failed to convert real IP to string")
-    } else {
-        ""
-    };
-    if request_ip.is_empty() {
-        request_ip = request
-            .headers()
-            .get("x-forwarded-for")
-            .expect("Failed to get forwarded IP")
-            .to_str()
-            .expect("Failed to convert forwarded IP to string");
-    }
+    let request_ip = client_ip(request, true).unwrap_or_default();
     let uri = request.uri().path();
-    (request_ip.to_string(), uri.to_string())
+    (request_ip, uri.to_string())
+}
+
+/// Client IP of the request
+///
+/// Uses the socket address (`ConnectInfo`, present when the app is served with
+/// `into_make_service_with_connect_info::<SocketAddr>()`). With `trust_proxy`,
+/// `x-real-ip` and then the first `x-forwarded-for` entry take precedence; only enable it
+/// when every request arrives through a proxy that overwrites those headers, since
+/// clients can set them to anything.
+pub fn client_ip(request: &Request, trust_proxy: bool) -> Option<String> {
+    let headers = request.headers();
+    let forwarded = trust_proxy
+        .then(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .or_else(|| {
+                    headers
+                        .get("x-forwarded-for")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.split(',').next())
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                })
+        })
+        .flatten();
+    match forwarded {
+        Some(ip) => Some(ip.to_string()),
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string()),
+    }
+}
+
+/// Per-client key for limiters: `uid:<uid>` when an [`AuthModel`] is present (the
+/// middleware runs inside `interceptor`), otherwise `ip:<addr>` from [`client_ip`].
+///
+/// `None` when neither is known, so unidentifiable requests are not all limited as one
+/// client.
+pub fn client_key(request: &Request, trust_proxy: bool) -> Option<String> {
+    if let Some(auth) = request.extensions().get::<AuthModel>() {
+        return Some(format!("uid:{}", auth.uid));
+    }
+    client_ip(request, trust_proxy).map(|ip| format!("ip:{}", ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(headers: &[(&str, &str)]) -> Request {
+        let mut builder = Request::builder().uri("/a/b?x=1");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_request_host_header_order() {
+        let req = request(&[("x-real-ip", "1.1.1.1"), ("x-forwarded-for", "2.2.2.2")]);
+        assert_eq!(
+            get_request_host(&req),
+            ("1.1.1.1".to_string(), "/a/b".to_string())
+        );
+
+        let req = request(&[("x-forwarded-for", "2.2.2.2, 10.0.0.1")]);
+        assert_eq!(get_request_host(&req).0, "2.2.2.2");
+    }
+
+    #[test]
+    fn test_request_host_without_headers_does_not_panic() {
+        let mut req = request(&[]);
+        assert_eq!(get_request_host(&req).0, "");
+
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 8080))));
+        assert_eq!(get_request_host(&req).0, "127.0.0.1");
+    }
+
+    #[test]
+    fn test_client_key_ignores_forwarded_headers_unless_trusted() {
+        let mut req = request(&[("x-real-ip", "1.1.1.1")]);
+        assert_eq!(client_key(&req, false), None);
+        assert_eq!(client_key(&req, true).as_deref(), Some("ip:1.1.1.1"));
+
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 7], 443))));
+        assert_eq!(client_key(&req, false).as_deref(), Some("ip:10.0.0.7"));
+        assert_eq!(client_key(&req, true).as_deref(), Some("ip:1.1.1.1"));
+
+        let auth: AuthModel = serde_json::from_value(serde_json::json!({
+            "uid": 42, "tid": 1, "ouid": 1
+        }))
+        .unwrap();
+        req.extensions_mut().insert(auth);
+        assert_eq!(client_key(&req, false).as_deref(), Some("uid:42"));
+    }
 }
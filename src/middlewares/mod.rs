@@ -1,4 +1,9 @@
+pub mod concurrency;
+pub mod etag;
+pub mod idempotency;
 pub mod interceptor;
 pub mod ip;
 pub mod models;
+pub mod rate_limit;
+pub mod request_id;
 pub mod token_store;
@@ -1,4 +1,9 @@
-use crate::middlewares::token_store::DynTokenStore;
+use std::sync::Arc;
+
+use axum::http::{Method, Uri};
+use futures::future::BoxFuture;
+
+use crate::{middlewares::token_store::DynTokenStore, response::error::AppError};
 
 pub const AUTHORIZATION: &str = "Authorization";
 pub const BEARER: &str = "Bearer";
@@ -8,7 +13,10 @@ pub const TOKEN_KEY: &str = ":auth_user:";
 
 // Cache key segments
 pub const CACHE_USER_INFO: &str = ":userinfo:uid:";
+// Legacy single-session slot, superseded by CACHE_AUTH_SESSIONS
 pub const CACHE_AUTH_UID: &str = ":auth:uid:";
+// Sorted set of a user's sessions (AuthTokenResult JSON, scored by issue time in millis)
+pub const CACHE_AUTH_SESSIONS: &str = ":auth:sessions:";
 pub const CACHE_AUTH_TOKEN: &str = ":auth:token:";
 pub const CACHE_AUTH_REFRESH_TOKEN: &str = ":auth:refresh_token:";
 pub const CACHE_ADMIN_PERMS: &str = ":perms:admin:";
@@ -21,6 +29,9 @@ pub const CACHE_PERMS_RID: &str = ":perms:roleid:";
 // set role menus cache key
 pub const CACHE_MENUS_RID: &str = ":menus:roleid:";
 
+// Default limit for request bodies buffered by the interceptor (2 MiB)
+pub const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
 // Token expiration (seconds)
 // pub const EXPIRES_AT: u64 = 60 * 30;
 // pub const REFRESH_EXPIRES_AT: u64 = 60 * 60 * 24 * 15;
@@ -72,10 +83,166 @@ pub struct AuthModel {
 /// ignore_urls - URL prefixes that bypass the middleware
 /// pms_ignore_urls - Permission system URL prefixes that bypass the middleware
 /// prefix - Key prefix/namespace for caching, logging, or identification
+/// max_body_bytes - Largest JSON body the interceptor buffers; bigger bodies are rejected
+/// audit_fields - Names of the injected audit fields and the methods they are injected on
+/// token_sources - Where to look for the access token, tried in order; the first match wins
+/// basic_auth_secret - Secret for keyed BASIC tokens (`Crypto::sign_basic_auth_key`) on PMS routes;
+///                     `auth_basics` entries keep working alongside it during migration
+/// authorize - Optional permission check run after the token resolves; `None` allows every
+///             authenticated request
 pub struct MiddlewareConfig {
     pub token_store: DynTokenStore,
     pub ignore_urls: Vec<String>,
     pub pms_ignore_urls: Vec<String>,
     pub prefix: String,
     pub auth_basics: Vec<String>,
+    pub basic_auth_secret: Option<String>,
+    pub max_body_bytes: usize,
+    pub audit_fields: AuditFieldNames,
+    pub token_sources: Vec<TokenSource>,
+    pub authorize: Option<AuthorizeFn>,
+}
+
+/// Permission check invoked by the interceptor with the resolved user, request URI and method.
+///
+/// Return `Ok(())` to let the request through, or an error (typically `AppError::Forbidden`)
+/// that is sent back as the response. The future is `'static`, so copy what you need out of
+/// the arguments before the `async` block.
+///
+/// # Example
+/// ```rust,ignore
+/// let authorize: AuthorizeFn = Arc::new(|auth, uri, _method| {
+///     let is_admin_route = uri.path().starts_with("/admin");
+///     let rids = auth.rids.clone();
+///     Box::pin(async move {
+///         if is_admin_route && !rids.contains(&ADMIN_ROLE_ID) {
+///             return Err(AppError::Forbidden);
+///         }
+///         Ok(())
+///     })
+/// });
+/// ```
+pub type AuthorizeFn = Arc<
+    dyn Fn(&AuthModel, &Uri, &Method) -> BoxFuture<'static, Result<(), AppError>> + Send + Sync,
+>;
+
+/// A place the interceptor looks for the access token.
+///
+/// # Example
+/// ```rust,ignore
+/// let token_sources = vec![
+///     TokenSource::bearer(),
+///     TokenSource::header("X-Auth-Token"),
+///     TokenSource::cookie("access_token"),
+///     TokenSource::query("accessToken"),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    /// Header value, with an optional scheme prefix (e.g. `Bearer`) that must be present
+    /// and is stripped. Prefix matching is case-insensitive.
+    Header {
+        name: String,
+        prefix: Option<String>,
+    },
+    /// Cookie by name.
+    Cookie(String),
+    /// Query parameter by name.
+    Query(String),
+}
+
+impl TokenSource {
+    /// `Authorization: Bearer <token>`
+    pub fn bearer() -> Self {
+        Self::header_with_prefix(AUTHORIZATION, BEARER)
+    }
+
+    /// Raw header value, e.g. `X-Auth-Token: <token>`.
+    pub fn header(name: impl Into<String>) -> Self {
+        Self::Header {
+            name: name.into(),
+            prefix: None,
+        }
+    }
+
+    /// Header value with a scheme prefix, e.g. `Authorization: Token <token>`.
+    pub fn header_with_prefix(name: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self::Header {
+            name: name.into(),
+            prefix: Some(prefix.into()),
+        }
+    }
+
+    pub fn cookie(name: impl Into<String>) -> Self {
+        Self::Cookie(name.into())
+    }
+
+    pub fn query(name: impl Into<String>) -> Self {
+        Self::Query(name.into())
+    }
+
+    /// The default chain: `Authorization: Bearer` header, then the `accessToken` query param.
+    pub fn default_chain() -> Vec<Self> {
+        vec![Self::bearer(), Self::query("accessToken")]
+    }
+}
+
+/// Audit fields the interceptor injects into JSON bodies.
+///
+/// POST gets creator + updater fields, PUT/PATCH get updater fields. Fields already present
+/// in the body are left untouched.
+#[derive(Debug, Clone)]
+pub struct AuditFieldNames {
+    pub creator: String,
+    pub creator_by: String,
+    pub updater: String,
+    pub updater_by: String,
+    pub on_post: bool,
+    pub on_put: bool,
+    pub on_patch: bool,
+}
+
+impl Default for AuditFieldNames {
+    fn default() -> Self {
+        Self {
+            creator: "creator".to_string(),
+            creator_by: "creator_by".to_string(),
+            updater: "updater".to_string(),
+            updater_by: "updater_by".to_string(),
+            on_post: true,
+            on_put: true,
+            on_patch: false,
+        }
+    }
+}
+
+impl AuditFieldNames {
+    /// camelCase names: `creator`, `creatorBy`, `updater`, `updaterBy`.
+    pub fn camel_case() -> Self {
+        Self {
+            creator_by: "creatorBy".to_string(),
+            updater_by: "updaterBy".to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether any audit field is injected for `method`.
+    pub fn applies_to(&self, method: &Method) -> bool {
+        match *method {
+            Method::POST => self.on_post,
+            Method::PUT => self.on_put,
+            Method::PATCH => self.on_patch,
+            _ => false,
+        }
+    }
+
+    /// Disable injection entirely.
+    pub fn disabled() -> Self {
+        Self {
+            on_post: false,
+            on_put: false,
+            on_patch: false,
+            ..Self::default()
+        }
+    }
 }
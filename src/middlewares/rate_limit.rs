@@ -0,0 +1,377 @@
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::Request,
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+
+pub use crate::middlewares::ip::client_key;
+use crate::response::error::AppError;
+
+pub type RateLimitError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Fixed-window hit counter behind [`RateLimitLayer`].
+#[async_trait]
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Count one hit for `key` in a window of length `window` starting at the key's first
+    /// hit. Returns the hits in the current window and the time until it resets.
+    async fn hit(&self, key: &str, window: Duration) -> Result<(u64, Duration), RateLimitError>;
+}
+
+/// Redis-backed counters shared by every instance (`INCR` + `PEXPIRE` in one script).
+#[cfg(any(feature = "redis", feature = "full"))]
+#[async_trait]
+impl RateLimitStore for crate::rediscache::RedisPool {
+    async fn hit(&self, key: &str, window: Duration) -> Result<(u64, Duration), RateLimitError> {
+        self.incr_with_ttl(key, window).await
+    }
+}
+
+/// Per-process counters, for tests and single-instance deployments.
+///
+/// Expired windows are reset on the next hit; call [`Self::sweep_expired`] periodically to
+/// drop clients that never come back.
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    windows: crate::dashmap::DashMap<String, (u64, Instant)>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove every expired window and return how many were removed.
+    pub fn sweep_expired(&self) -> usize {
+        let before = self.windows.len();
+        let now = Instant::now();
+        self.windows.retain(|_, (_, reset_at)| *reset_at > now);
+        before.saturating_sub(self.windows.len())
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn hit(&self, key: &str, window: Duration) -> Result<(u64, Duration), RateLimitError> {
+        let now = Instant::now();
+        let mut entry = self
+            .windows
+            .entry(key.to_string())
+            .or_insert((0, now + window));
+        let (count, reset_at) = entry.value_mut();
+        if *reset_at <= now {
+            *count = 0;
+            *reset_at = now + window;
+        }
+        *count += 1;
+        Ok((*count, reset_at.saturating_duration_since(now)))
+    }
+}
+
+/// Maps a request to its rate-limit bucket; `None` exempts the request.
+pub type RateLimitKeyFn = Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>;
+
+/// Tower layer allowing at most `limit` requests per `window` for each key.
+///
+/// Keys default to [`client_key`] (authenticated uid, else the socket IP; forwarded headers
+/// only after [`Self::behind_proxy`]); plug in another with [`Self::with_key_fn`]. Over the limit the request gets `AppError::RateLimit` (HTTP 429)
+/// with `Retry-After` set to the seconds until the window resets. If the store fails the
+/// request is let through, so a Redis outage does not take the API down.
+///
+/// ```rust,ignore
+/// let limit = RateLimitLayer::new(Arc::new(redis_pool), 100, Duration::from_secs(60))
+///     .with_key_fn(|req| Some(format!("{}:{}", client_key(req, false)?, req.uri().path())));
+/// let app = Router::new()
+///     .route("/login", post(login))
+///     .layer(limit);
+/// ```
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    config: RateLimitConfig,
+}
+
+#[derive(Clone)]
+struct RateLimitConfig {
+    store: Arc<dyn RateLimitStore>,
+    limit: u64,
+    window: Duration,
+    prefix: String,
+    trust_proxy: bool,
+    /// `None`: [`client_key`] with `trust_proxy`.
+    key_fn: Option<RateLimitKeyFn>,
+}
+
+impl RateLimitLayer {
+    /// `limit` is clamped to at least 1; keys are stored under the `rate_limit:` prefix.
+    pub fn new(store: Arc<dyn RateLimitStore>, limit: u64, window: Duration) -> Self {
+        Self {
+            config: RateLimitConfig {
+                store,
+                limit: limit.max(1),
+                window,
+                prefix: "rate_limit:".to_string(),
+                trust_proxy: false,
+                key_fn: None,
+            },
+        }
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.prefix = prefix.into();
+        self
+    }
+
+    pub fn with_key_fn<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        self.config.key_fn = Some(Arc::new(key_fn));
+        self
+    }
+
+    /// Key anonymous clients by `x-real-ip` / `x-forwarded-for` instead of the socket IP.
+    ///
+    /// Only enable it when every request passes through a proxy that overwrites these
+    /// headers; otherwise a client can rotate them to dodge the limit. Has no effect on a
+    /// custom [`Self::with_key_fn`].
+    pub fn behind_proxy(mut self) -> Self {
+        self.config.trust_proxy = true;
+        self
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            config: Arc::new(self.config.clone()),
+        }
+    }
+}
+
+/// Service produced by [`RateLimitLayer`].
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    config: Arc<RateLimitConfig>,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // Take the service that was polled ready and leave a fresh clone in its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let key = match &config.key_fn {
+                Some(key_fn) => key_fn(&request),
+                None => client_key(&request, config.trust_proxy),
+            };
+            let Some(key) = key else {
+                return inner.call(request).await;
+            };
+            let key = format!("{}{}", config.prefix, key);
+            match config.store.hit(&key, config.window).await {
+                Ok((count, reset_after)) if count > config.limit => {
+                    tracing::warn!(
+                        "「rate_limit」 {} exceeded {} requests per {:?}",
+                        key,
+                        config.limit,
+                        config.window
+                    );
+                    return Ok(too_many_requests(config.limit, reset_after));
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("「rate_limit」 store failed for {}, allowing: {}", key, e)
+                }
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+fn too_many_requests(limit: u64, reset_after: Duration) -> Response {
+    // Round up so clients never retry before the window has reset.
+    let retry_after = (reset_after.as_secs() + u64::from(reset_after.subsec_nanos() > 0)).max(1);
+    let mut response =
+        AppError::RateLimit(format!("Rate limit of {} requests exceeded", limit)).into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, extract::ConnectInfo, http::StatusCode, routing::get};
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    fn app(layer: RateLimitLayer) -> Router {
+        Router::new()
+            .route("/ping", get(|| async { "pong" }))
+            .layer(layer)
+    }
+
+    fn request(ip: &str) -> Request {
+        let mut request = Request::builder().uri("/ping").body(Body::empty()).unwrap();
+        let addr: std::net::IpAddr = ip.parse().unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(addr, 443)));
+        request
+    }
+
+    fn spoofed(forwarded_for: &str) -> Request {
+        let mut request = request("10.0.0.1");
+        request
+            .headers_mut()
+            .insert("x-forwarded-for", forwarded_for.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_rejects_requests_past_the_limit() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let app = app(RateLimitLayer::new(store, 5, Duration::from_secs(30)));
+
+        let mut statuses = Vec::new();
+        for _ in 0..8 {
+            let response = app.clone().oneshot(request("10.0.0.1")).await.unwrap();
+            statuses.push(response.status());
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+                    .to_str()
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+                assert!((1..=30).contains(&retry_after));
+            }
+        }
+        assert_eq!(statuses.iter().filter(|s| **s == StatusCode::OK).count(), 5);
+        assert_eq!(statuses[5..], [StatusCode::TOO_MANY_REQUESTS; 3]);
+
+        let other = app.oneshot(request("10.0.0.2")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_window_resets() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let app = app(RateLimitLayer::new(
+            store.clone(),
+            1,
+            Duration::from_millis(50),
+        ));
+        assert_eq!(
+            app.clone()
+                .oneshot(request("10.0.0.1"))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.clone()
+                .oneshot(request("10.0.0.1"))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        assert_eq!(store.sweep_expired(), 1);
+        assert_eq!(
+            app.oneshot(request("10.0.0.1")).await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_key_fn_can_exempt_requests() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let layer = RateLimitLayer::new(store, 1, Duration::from_secs(30)).with_key_fn(|req| {
+            req.headers()
+                .get("x-api-key")
+                .and_then(|v| v.to_str().ok())
+                .map(|key| format!("key:{}", key))
+        });
+        let app = app(layer);
+        for _ in 0..3 {
+            let response = app.clone().oneshot(request("10.0.0.1")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rotating_forwarded_header_does_not_bypass_limit() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let app = app(RateLimitLayer::new(store, 1, Duration::from_secs(30)));
+        assert_eq!(
+            app.clone()
+                .oneshot(spoofed("1.1.1.1"))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.oneshot(spoofed("2.2.2.2")).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_behind_proxy_keys_by_forwarded_ip() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let app = app(RateLimitLayer::new(store, 1, Duration::from_secs(30)).behind_proxy());
+        for ip in ["1.1.1.1", "2.2.2.2"] {
+            assert_eq!(
+                app.clone().oneshot(spoofed(ip)).await.unwrap().status(),
+                StatusCode::OK
+            );
+        }
+        assert_eq!(
+            app.oneshot(spoofed("1.1.1.1")).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unidentified_clients_are_not_limited() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let app = app(RateLimitLayer::new(store, 1, Duration::from_secs(30)));
+        for _ in 0..3 {
+            let anonymous = Request::builder().uri("/ping").body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(anonymous).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+}
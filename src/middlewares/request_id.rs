@@ -0,0 +1,203 @@
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderName, HeaderValue, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+use crate::response::error::{AppError, REQUEST_ID_HEADER, scope_request_id};
+
+/// Request id stored in the request extensions by [`request_id`].
+///
+/// Also an extractor: `async fn handler(RequestId(id): RequestId)`. Extraction fails with
+/// `AppError::Internal` when the [`request_id`] middleware is not installed on the route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<RequestId>().cloned().ok_or_else(|| {
+            AppError::Internal("RequestId extractor used without request_id middleware".to_string())
+        })
+    }
+}
+
+/// Request correlation id middleware.
+///
+/// Reuses an incoming `x-request-id` header (e.g. set by a gateway) or generates a UUID v4,
+/// then:
+/// - inserts it into the request extensions as [`RequestId`],
+/// - runs the rest of the stack inside a `request` tracing span carrying `request_id`,
+///   `method` and `path`, so every log line below it (interceptor included) shows the id,
+/// - runs the rest of the stack inside [`scope_request_id`], so `AppError` responses
+///   include it,
+/// - echoes it back in the `x-request-id` response header.
+///
+/// Register it outermost (last `.layer`) so the span covers the other middlewares.
+///
+/// ```rust,ignore
+/// let app = Router::new()
+///     .route("/items", get(list_items))
+///     .layer(axum::middleware::from_fn_with_state(config, interceptor))
+///     .layer(axum::middleware::from_fn(request_id));
+/// ```
+pub async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    request.extensions_mut().insert(RequestId(id.clone()));
+    let mut response = scope_request_id(id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Body, http::StatusCode, response::IntoResponse, routing::get};
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/fail",
+                get(|| async { Err::<(), _>(AppError::Internal("boom".to_string())) }),
+            )
+            .route(
+                "/id",
+                get(|RequestId(id): RequestId| async move {
+                    tracing::info!("handling request");
+                    id
+                }),
+            )
+            .layer(axum::middleware::from_fn(request_id))
+    }
+
+    async fn body_json(response: Response) -> serde_json::Value {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_body_carries_incoming_request_id() {
+        let request = Request::builder()
+            .uri("/fail")
+            .header(REQUEST_ID_HEADER, "req-123")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers().get(REQUEST_ID_HEADER).unwrap(),
+            "req-123"
+        );
+        assert_eq!(body_json(response).await["request_id"], "req-123");
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_generated_when_missing() {
+        let request = Request::builder().uri("/fail").body(Body::empty()).unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let header = response
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(uuid::Uuid::parse_str(&header).is_ok());
+        assert_eq!(body_json(response).await["request_id"], header.as_str());
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_extractor_and_span_carry_request_id() {
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let request = Request::builder()
+            .uri("/id")
+            .header(REQUEST_ID_HEADER, "req-456")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"req-456");
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let line = logs
+            .lines()
+            .find(|line| line.contains("handling request"))
+            .unwrap();
+        assert!(line.contains("request_id=req-456"), "{line}");
+        assert!(line.contains("path=/id"), "{line}");
+    }
+
+    #[tokio::test]
+    async fn test_extractor_without_middleware_is_rejected() {
+        let app = Router::new().route("/id", get(|RequestId(id): RequestId| async move { id }));
+        let request = Request::builder().uri("/id").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_request_id_omitted_outside_scope() {
+        let response = AppError::Unauthorized.into_response();
+        let json = body_json(response).await;
+        assert!(json.get("request_id").is_none());
+
+        let response = AppError::Unauthorized.with_request_id("explicit");
+        assert_eq!(body_json(response).await["request_id"], "explicit");
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use serde::{Serialize, de::DeserializeOwned};
@@ -10,6 +10,8 @@ pub enum TokenStoreError {
     Backend(String),
     #[error("JSON error: {0}")]
     Json(String),
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
 }
 
 impl From<serde_json::Error> for TokenStoreError {
@@ -37,6 +39,78 @@ pub trait TokenStore: Send + Sync + 'static {
 
     /// Delete a key. Returns true if the key existed and was deleted.
     async fn delete(&self, key: &str) -> Result<bool, TokenStoreError>;
+
+    /// Set `key` only if it does not exist (or has expired). Returns true when this call
+    /// stored the value, so of several concurrent callers exactly one wins.
+    ///
+    /// The default implementation returns `TokenStoreError::Unsupported`, since a
+    /// get-then-set fallback would not be atomic.
+    async fn set_raw_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl_secs: Option<u64>,
+    ) -> Result<bool, TokenStoreError> {
+        let _ = (key, value, ttl_secs);
+        Err(TokenStoreError::Unsupported(
+            "set_raw_if_absent".to_string(),
+        ))
+    }
+
+    /// Delete every key starting with `prefix` (e.g. all sessions of one user) and return
+    /// how many were removed.
+    ///
+    /// The default implementation returns `TokenStoreError::Unsupported`; stores that can
+    /// enumerate keys override it.
+    async fn delete_prefix(&self, prefix: &str) -> Result<u64, TokenStoreError> {
+        let _ = prefix;
+        Err(TokenStoreError::Unsupported("delete_prefix".to_string()))
+    }
+
+    /// Atomically delete `key` only if its stored payload equals `expected_json`.
+    ///
+    /// Returns true when this call deleted the key, so of several concurrent callers holding
+    /// the same payload exactly one wins (single-use refresh tokens, safe logout). The default
+    /// implementation returns `TokenStoreError::Unsupported`, since a get-then-delete fallback
+    /// would not be atomic.
+    async fn compare_and_delete(
+        &self,
+        key: &str,
+        expected_json: &str,
+    ) -> Result<bool, TokenStoreError> {
+        let _ = (key, expected_json);
+        Err(TokenStoreError::Unsupported(
+            "compare_and_delete".to_string(),
+        ))
+    }
+
+    /// Remaining time to live of `key`. Returns None if the key does not exist or never
+    /// expires.
+    async fn ttl(&self, key: &str) -> Result<Option<Duration>, TokenStoreError> {
+        let _ = key;
+        Err(TokenStoreError::Unsupported("ttl".to_string()))
+    }
+
+    /// Reset the time to live of an existing `key` to `ttl` (sliding sessions). Returns false
+    /// if the key does not exist.
+    async fn touch(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> {
+        let _ = (key, ttl);
+        Err(TokenStoreError::Unsupported("touch".to_string()))
+    }
+
+    /// Atomically increment the counter at `key`, starting a `window` TTL when this call
+    /// created it (fixed-window counting). Returns the new count and the remaining TTL.
+    ///
+    /// The default implementation returns `TokenStoreError::Unsupported`, since a
+    /// get-then-set fallback would lose concurrent increments.
+    async fn incr_with_ttl(
+        &self,
+        key: &str,
+        window: Duration,
+    ) -> Result<(u64, Duration), TokenStoreError> {
+        let _ = (key, window);
+        Err(TokenStoreError::Unsupported("incr_with_ttl".to_string()))
+    }
 }
 
 /// Deserialize JSON value from a TokenStore into type T.
@@ -67,7 +141,7 @@ where
 /// In-memory token store (fallback when Redis is not available).
 ///
 /// - Thread-safe and lock-free via DashMap
-/// - Optional TTL support (checked lazily on read)
+/// - Optional TTL support (checked lazily on read, or periodically via `spawn_sweeper`)
 /// - Intended for tests and non-distributed setups
 pub struct InMemoryTokenStore {
     map: crate::dashmap::DashMap<String, Entry>,
@@ -87,6 +161,60 @@ impl InMemoryTokenStore {
         }
     }
 
+    /// Remove every expired entry and return how many were removed.
+    ///
+    /// Expired keys are collected first and removed one by one afterwards, so no shard lock
+    /// is held across the whole scan.
+    pub fn sweep_expired(&self) -> usize {
+        let expired: Vec<String> = self
+            .map
+            .iter()
+            .filter(|entry| Self::is_expired(entry.expires_at))
+            .map(|entry| entry.key().clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter(|key| {
+                // Re-check: the key may have been rewritten or touched since the scan.
+                self.map
+                    .remove_if(key, |_, entry| Self::is_expired(entry.expires_at))
+                    .is_some()
+            })
+            .count()
+    }
+
+    /// Spawn a background task calling [`Self::sweep_expired`] every `interval`.
+    ///
+    /// Without it, keys that are written once and never read again stay in memory forever.
+    /// The task holds only a weak reference and exits once the store is dropped; abort the
+    /// returned handle to stop it earlier. Must be called within a Tokio runtime.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// let store = Arc::new(InMemoryTokenStore::new());
+    /// let _sweeper = store.spawn_sweeper(Duration::from_secs(60));
+    /// let token_store: DynTokenStore = store;
+    /// ```
+    pub fn spawn_sweeper(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let store = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately; skip it so the first sweep runs after `interval`.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(store) = store.upgrade() else {
+                    break;
+                };
+                let removed = store.sweep_expired();
+                if removed > 0 {
+                    tracing::debug!("「spawn_sweeper」removed {} expired tokens", removed);
+                }
+            }
+        })
+    }
+
     fn is_expired(expires_at: Option<std::time::Instant>) -> bool {
         match expires_at {
             Some(deadline) => std::time::Instant::now() >= deadline,
@@ -132,6 +260,109 @@ impl TokenStore for InMemoryTokenStore {
     async fn delete(&self, key: &str) -> Result<bool, TokenStoreError> {
         Ok(self.map.remove(key).is_some())
     }
+
+    async fn set_raw_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl_secs: Option<u64>,
+    ) -> Result<bool, TokenStoreError> {
+        use crate::dashmap::mapref::entry::Entry as MapEntry;
+
+        let entry = Entry {
+            json: value.to_string(),
+            expires_at: ttl_secs.map(|s| std::time::Instant::now() + Duration::from_secs(s)),
+        };
+        // The entry API holds the shard's write lock between the check and the insert.
+        match self.map.entry(key.to_string()) {
+            MapEntry::Occupied(mut occupied) => {
+                if !Self::is_expired(occupied.get().expires_at) {
+                    return Ok(false);
+                }
+                occupied.insert(entry);
+            }
+            MapEntry::Vacant(vacant) => {
+                vacant.insert(entry);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<u64, TokenStoreError> {
+        let mut deleted = 0u64;
+        self.map.retain(|key, entry| {
+            if !key.starts_with(prefix) {
+                return true;
+            }
+            // Expired entries are dropped too but not counted, matching `get_raw`.
+            if !Self::is_expired(entry.expires_at) {
+                deleted += 1;
+            }
+            false
+        });
+        Ok(deleted)
+    }
+
+    async fn compare_and_delete(
+        &self,
+        key: &str,
+        expected_json: &str,
+    ) -> Result<bool, TokenStoreError> {
+        // `remove_if` evaluates the predicate under the shard's write lock.
+        let removed = self.map.remove_if(key, |_, entry| {
+            !Self::is_expired(entry.expires_at) && entry.json == expected_json
+        });
+        Ok(removed.is_some())
+    }
+
+    async fn ttl(&self, key: &str) -> Result<Option<Duration>, TokenStoreError> {
+        let Some(entry) = self.map.get(key) else {
+            return Ok(None);
+        };
+        match entry.expires_at {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                Ok((!remaining.is_zero()).then_some(remaining))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn touch(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> {
+        match self.map.get_mut(key) {
+            Some(mut entry) if !Self::is_expired(entry.expires_at) => {
+                entry.expires_at = Some(std::time::Instant::now() + ttl);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn incr_with_ttl(
+        &self,
+        key: &str,
+        window: Duration,
+    ) -> Result<(u64, Duration), TokenStoreError> {
+        let now = std::time::Instant::now();
+        // The entry guard holds the shard's write lock across the read and the update.
+        let mut entry = self.map.entry(key.to_string()).or_insert_with(|| Entry {
+            json: "0".to_string(),
+            expires_at: None,
+        });
+        let (count, deadline) = match entry.expires_at {
+            Some(deadline) if deadline > now => {
+                let count = entry.json.parse::<u64>().map_err(|_| {
+                    TokenStoreError::Backend(format!("value at {} is not a counter", key))
+                })?;
+                (count + 1, deadline)
+            }
+            // New, expired, or a counter that lost its TTL: start a fresh window.
+            _ => (1, now + window),
+        };
+        entry.json = count.to_string();
+        entry.expires_at = Some(deadline);
+        Ok((count, deadline - now))
+    }
 }
 
 /// Redis-backed token store (enabled when the `redis` feature is active).
@@ -165,6 +396,19 @@ impl RedisTokenStore {
     }
 }
 
+/// Escape Redis glob metacharacters so `key` matches literally in `SCAN MATCH`.
+#[cfg(any(feature = "redis", feature = "full"))]
+fn escape_glob(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[cfg(any(feature = "redis", feature = "full"))]
 #[async_trait]
 impl TokenStore for RedisTokenStore {
@@ -204,6 +448,83 @@ impl TokenStore for RedisTokenStore {
             .await
             .map_err(|e| TokenStoreError::Backend(e.to_string()))
     }
+
+    async fn set_raw_if_absent(
+        &self,
+        key: &str,
+        value: &str,
+        ttl_secs: Option<u64>,
+    ) -> Result<bool, TokenStoreError> {
+        // `acquire_lock` is `SET NX PX`, which always needs an expiry.
+        let Some(ttl_secs) = ttl_secs else {
+            return Err(TokenStoreError::Unsupported(
+                "set_raw_if_absent without a TTL".to_string(),
+            ));
+        };
+        let redis_key = self.build_key(key);
+        self.pool
+            .acquire_lock(
+                &redis_key,
+                Duration::from_secs(ttl_secs.max(1)),
+                Some(value),
+            )
+            .await
+            .map(|lock| lock.is_some())
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))
+    }
+
+    async fn delete_prefix(&self, prefix: &str) -> Result<u64, TokenStoreError> {
+        let pattern = format!("{}*", escape_glob(&self.build_key(prefix)));
+        self.pool
+            .del_by_pattern(&pattern)
+            .await
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))
+    }
+
+    async fn compare_and_delete(
+        &self,
+        key: &str,
+        expected_json: &str,
+    ) -> Result<bool, TokenStoreError> {
+        let redis_key = self.build_key(key);
+        self.pool
+            .compare_and_delete(&redis_key, expected_json)
+            .await
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))
+    }
+
+    async fn ttl(&self, key: &str) -> Result<Option<Duration>, TokenStoreError> {
+        let redis_key = self.build_key(key);
+        let secs = self
+            .pool
+            .ttl(redis_key)
+            .await
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))?;
+        // -2: key does not exist, -1: key has no expiry
+        Ok(u64::try_from(secs).ok().map(Duration::from_secs))
+    }
+
+    async fn touch(&self, key: &str, ttl: Duration) -> Result<bool, TokenStoreError> {
+        let redis_key = self.build_key(key);
+        // EXPIRE has second granularity; round up so a sub-second TTL does not delete the key.
+        let secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+        self.pool
+            .expire(redis_key, secs.max(1))
+            .await
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))
+    }
+
+    async fn incr_with_ttl(
+        &self,
+        key: &str,
+        window: Duration,
+    ) -> Result<(u64, Duration), TokenStoreError> {
+        let redis_key = self.build_key(key);
+        self.pool
+            .incr_with_ttl(&redis_key, window)
+            .await
+            .map_err(|e| TokenStoreError::Backend(e.to_string()))
+    }
 }
 
 /// A boxed trait object alias for dynamic dispatch.
@@ -227,3 +548,226 @@ pub fn redis_store(
 ) -> DynTokenStore {
     Arc::new(RedisTokenStore::new(pool, prefix))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_delete_prefix() {
+        let store = InMemoryTokenStore::new();
+        store.set_raw("uid:1:a", "{}", None).await.unwrap();
+        store.set_raw("uid:1:b", "{}", Some(60)).await.unwrap();
+        store.set_raw("uid:10:a", "{}", None).await.unwrap();
+        store.set_raw("uid:2:a", "{}", None).await.unwrap();
+
+        assert_eq!(store.delete_prefix("uid:1:").await.unwrap(), 2);
+        assert_eq!(store.get_raw("uid:1:a").await.unwrap(), None);
+        assert!(store.get_raw("uid:10:a").await.unwrap().is_some());
+        assert!(store.get_raw("uid:2:a").await.unwrap().is_some());
+        assert_eq!(store.delete_prefix("uid:1:").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_compare_and_delete() {
+        let store = InMemoryTokenStore::new();
+        store.set_raw("rt:1", r#"{"uid":1}"#, None).await.unwrap();
+        assert!(
+            !store
+                .compare_and_delete("rt:1", r#"{"uid":2}"#)
+                .await
+                .unwrap()
+        );
+        assert!(store.get_raw("rt:1").await.unwrap().is_some());
+        assert!(
+            store
+                .compare_and_delete("rt:1", r#"{"uid":1}"#)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .compare_and_delete("rt:1", r#"{"uid":1}"#)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_set_if_absent() {
+        let store = InMemoryTokenStore::new();
+        assert!(store.set_raw_if_absent("k", "first", None).await.unwrap());
+        assert!(!store.set_raw_if_absent("k", "second", None).await.unwrap());
+        assert_eq!(store.get_raw("k").await.unwrap().as_deref(), Some("first"));
+
+        store.set_raw("expired", "old", Some(0)).await.unwrap();
+        assert!(
+            store
+                .set_raw_if_absent("expired", "new", Some(60))
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            store.get_raw("expired").await.unwrap().as_deref(),
+            Some("new")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_ttl_and_touch() {
+        let store = InMemoryTokenStore::new();
+        store.set_raw("session", "{}", Some(10)).await.unwrap();
+        store.set_raw("forever", "{}", None).await.unwrap();
+
+        let ttl = store.ttl("session").await.unwrap().unwrap();
+        assert!(ttl <= Duration::from_secs(10) && ttl > Duration::from_secs(9));
+        assert_eq!(store.ttl("forever").await.unwrap(), None);
+        assert_eq!(store.ttl("missing").await.unwrap(), None);
+
+        assert!(
+            store
+                .touch("session", Duration::from_secs(60))
+                .await
+                .unwrap()
+        );
+        assert!(store.ttl("session").await.unwrap().unwrap() > Duration::from_secs(59));
+        assert!(
+            store
+                .touch("forever", Duration::from_secs(5))
+                .await
+                .unwrap()
+        );
+        assert!(store.ttl("forever").await.unwrap().is_some());
+        assert!(
+            !store
+                .touch("missing", Duration::from_secs(5))
+                .await
+                .unwrap()
+        );
+
+        store.set_raw("gone", "{}", Some(0)).await.unwrap();
+        assert!(!store.touch("gone", Duration::from_secs(5)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_incr_with_ttl() {
+        let store = InMemoryTokenStore::new();
+        let window = Duration::from_secs(60);
+        assert_eq!(store.incr_with_ttl("count", window).await.unwrap().0, 1);
+        let (count, ttl) = store.incr_with_ttl("count", window).await.unwrap();
+        assert_eq!(count, 2);
+        assert!(ttl <= window && ttl > Duration::from_secs(59));
+        assert_eq!(store.get_raw("count").await.unwrap().as_deref(), Some("2"));
+
+        // An expired counter starts a new window.
+        store.set_raw("count", "7", Some(0)).await.unwrap();
+        assert_eq!(store.incr_with_ttl("count", window).await.unwrap().0, 1);
+
+        store.set_raw("json", "{}", Some(60)).await.unwrap();
+        assert!(matches!(
+            store.incr_with_ttl("json", window).await,
+            Err(TokenStoreError::Backend(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_expired_removes_unread_entries() {
+        let store = Arc::new(InMemoryTokenStore::new());
+        store.set_raw("expired", "{}", Some(0)).await.unwrap();
+        store.set_raw("live", "{}", Some(60)).await.unwrap();
+        store.set_raw("forever", "{}", None).await.unwrap();
+
+        assert_eq!(store.sweep_expired(), 1);
+        assert_eq!(store.map.len(), 2);
+
+        store.set_raw("expired", "{}", Some(0)).await.unwrap();
+        let sweeper = store.spawn_sweeper(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!store.map.contains_key("expired"));
+        assert_eq!(store.map.len(), 2);
+
+        drop(store);
+        tokio::time::timeout(Duration::from_secs(1), sweeper)
+            .await
+            .expect("sweeper exits after the store is dropped")
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_compare_and_delete_single_winner_under_race() {
+        for _ in 0..50 {
+            let store = Arc::new(InMemoryTokenStore::new());
+            store.set_raw("rt:race", "payload", None).await.unwrap();
+            let barrier = Arc::new(tokio::sync::Barrier::new(2));
+            let racers: Vec<_> = (0..2)
+                .map(|_| {
+                    let store = store.clone();
+                    let barrier = barrier.clone();
+                    tokio::spawn(async move {
+                        barrier.wait().await;
+                        store
+                            .compare_and_delete("rt:race", "payload")
+                            .await
+                            .unwrap()
+                    })
+                })
+                .collect();
+            let mut wins = 0;
+            for racer in racers {
+                wins += racer.await.unwrap() as usize;
+            }
+            assert_eq!(wins, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optional_operations_default_to_unsupported() {
+        struct GetOnly;
+
+        #[async_trait]
+        impl TokenStore for GetOnly {
+            async fn get_raw(&self, _key: &str) -> Result<Option<String>, TokenStoreError> {
+                Ok(None)
+            }
+            async fn set_raw(
+                &self,
+                _key: &str,
+                _value: &str,
+                _ttl_secs: Option<u64>,
+            ) -> Result<(), TokenStoreError> {
+                Ok(())
+            }
+            async fn delete(&self, _key: &str) -> Result<bool, TokenStoreError> {
+                Ok(false)
+            }
+        }
+
+        assert!(matches!(
+            GetOnly.delete_prefix("uid:1:").await,
+            Err(TokenStoreError::Unsupported(_))
+        ));
+        assert!(matches!(
+            GetOnly.compare_and_delete("uid:1:a", "{}").await,
+            Err(TokenStoreError::Unsupported(_))
+        ));
+        assert!(matches!(
+            GetOnly.set_raw_if_absent("uid:1:a", "{}", None).await,
+            Err(TokenStoreError::Unsupported(_))
+        ));
+        assert!(matches!(
+            GetOnly.touch("uid:1:a", Duration::from_secs(1)).await,
+            Err(TokenStoreError::Unsupported(_))
+        ));
+        assert!(matches!(
+            GetOnly.incr_with_ttl("count", Duration::from_secs(1)).await,
+            Err(TokenStoreError::Unsupported(_))
+        ));
+    }
+
+    #[cfg(any(feature = "redis", feature = "full"))]
+    #[test]
+    fn test_escape_glob() {
+        assert_eq!(escape_glob("auth:uid:1:"), "auth:uid:1:");
+        assert_eq!(escape_glob("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+}
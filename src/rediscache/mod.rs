@@ -1,11 +1,18 @@
+use crate::helper::core::cast::{to_i64, to_u64};
 use bb8::Pool;
 use bb8_redis::{RedisConnectionManager, bb8::RunError};
-use redis::{AsyncCommands, RedisError, Script};
+use once_cell::sync::Lazy;
+use redis::{AsyncCommands, FromRedisValue, RedisError, Script, ToRedisArgs};
 use std::{env, sync::Arc};
 use tokio::sync::OnceCell;
 use tracing::info;
 
+/// Pool settings for [`RedisPool::new`].
+///
+/// Marked `#[non_exhaustive]` so new options do not break callers: outside this crate,
+/// build it with [`RedisConfig::new`] and the `with_*` setters (or assign the pub fields).
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct RedisConfig {
     pub url: String,
     pub max_size: u32,
@@ -13,43 +20,228 @@ pub struct RedisConfig {
     pub connection_timeout: std::time::Duration,
     pub idle_timeout: Option<std::time::Duration>,
     pub max_lifetime: Option<std::time::Duration>,
+    /// Sent as `CLIENT SETNAME` on every new connection, so `CLIENT LIST` on a shared
+    /// server shows which service owns it. Must be printable ASCII without spaces.
+    pub client_name: Option<String>,
+    /// Also send `CLIENT SETINFO LIB-NAME neocrates` / `LIB-VER <crate version>`.
+    /// Servers older than Redis 7.2 reject it; that is logged and ignored.
+    pub client_lib_info: bool,
 }
 
 impl Default for RedisConfig {
     fn default() -> Self {
         let url = env::var("REDIS_URL")
             .unwrap_or_else(|_| panic!("REDIS_URL environment variable must be set!"));
+        Self::new(url)
+    }
+}
+
+impl RedisConfig {
+    /// Config for `url` with the default pool settings (10 connections, 1 idle, 5s connect
+    /// timeout, 10min idle timeout, 1h max lifetime, no client name).
+    pub fn new(url: impl Into<String>) -> Self {
         Self {
-            url,
+            url: url.into(),
             max_size: 10,
             min_idle: Some(1),
             connection_timeout: std::time::Duration::from_secs(5),
             idle_timeout: Some(std::time::Duration::from_secs(600)),
             max_lifetime: Some(std::time::Duration::from_secs(3600)),
+            client_name: None,
+            client_lib_info: false,
         }
     }
+
+    pub fn with_max_size(mut self, max_size: u32) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn with_min_idle(mut self, min_idle: Option<u32>) -> Self {
+        self.min_idle = min_idle;
+        self
+    }
+
+    pub fn with_connection_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    pub fn with_idle_timeout(mut self, timeout: Option<std::time::Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    pub fn with_max_lifetime(mut self, lifetime: Option<std::time::Duration>) -> Self {
+        self.max_lifetime = lifetime;
+        self
+    }
+
+    /// See [`RedisConfig::client_name`].
+    pub fn with_client_name(mut self, name: impl Into<String>) -> Self {
+        self.client_name = Some(name.into());
+        self
+    }
+
+    /// See [`RedisConfig::client_lib_info`].
+    pub fn with_client_lib_info(mut self, enabled: bool) -> Self {
+        self.client_lib_info = enabled;
+        self
+    }
+}
+
+/// Labels each new pooled connection via `CLIENT SETNAME` / `CLIENT SETINFO`.
+#[derive(Debug)]
+struct ClientIdentity {
+    name: Option<String>,
+    lib_info: bool,
+}
+
+impl bb8::CustomizeConnection<redis::aio::MultiplexedConnection, RedisError> for ClientIdentity {
+    fn on_acquire<'a>(
+        &'a self,
+        conn: &'a mut redis::aio::MultiplexedConnection,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), RedisError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            if let Some(name) = &self.name {
+                redis::cmd("CLIENT")
+                    .arg("SETNAME")
+                    .arg(name)
+                    .query_async::<()>(conn)
+                    .await?;
+            }
+            if self.lib_info {
+                for (attr, value) in [
+                    ("LIB-NAME", "neocrates"),
+                    ("LIB-VER", env!("CARGO_PKG_VERSION")),
+                ] {
+                    if let Err(e) = redis::cmd("CLIENT")
+                        .arg("SETINFO")
+                        .arg(attr)
+                        .arg(value)
+                        .query_async::<()>(conn)
+                        .await
+                    {
+                        tracing::debug!("CLIENT SETINFO {} not supported: {}", attr, e);
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// `CLIENT SETNAME` only accepts printable ASCII without spaces.
+fn valid_client_name(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| (b'!'..=b'~').contains(&b))
 }
 
 #[derive(Clone)]
 pub struct RedisPool {
     pool: Arc<Pool<RedisConnectionManager>>,
     max_size: u32,
+    scripts: Arc<dashmap::DashMap<String, ScriptHandle>>,
+}
+
+/// A Lua script identified by its SHA1, see [`RedisPool::register_script`].
+///
+/// Cheap to clone; keep it around and pass it to [`RedisPool::eval`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptHandle {
+    name: Arc<str>,
+    sha: Arc<str>,
+    body: Arc<str>,
 }
 
+impl ScriptHandle {
+    pub fn new(name: &str, body: &str) -> Self {
+        Self {
+            name: name.into(),
+            sha: Script::new(body).get_hash().into(),
+            body: body.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Lowercase hex SHA1 of the body, as used by `EVALSHA`.
+    pub fn sha(&self) -> &str {
+        &self.sha
+    }
+}
+
+static RELEASE_LOCK_SCRIPT: Lazy<ScriptHandle> = Lazy::new(|| {
+    ScriptHandle::new(
+        "release_lock",
+        r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end"#,
+    )
+});
+
+static INCR_WITH_TTL_SCRIPT: Lazy<ScriptHandle> = Lazy::new(|| {
+    ScriptHandle::new(
+        "incr_with_ttl",
+        r#"local count = redis.call("INCR", KEYS[1])
+local ttl = redis.call("PTTL", KEYS[1])
+if count == 1 or ttl < 0 then
+    redis.call("PEXPIRE", KEYS[1], ARGV[1])
+    ttl = tonumber(ARGV[1])
+end
+return {count, ttl}"#,
+    )
+});
+
+static GET_OR_SET_SCRIPT: Lazy<ScriptHandle> = Lazy::new(|| {
+    ScriptHandle::new(
+        "get_or_set",
+        r#"local current = redis.call("GET", KEYS[1])
+if current then
+    return current
+end
+if tonumber(ARGV[2]) > 0 then
+    redis.call("SET", KEYS[1], ARGV[1], "EX", ARGV[2])
+else
+    redis.call("SET", KEYS[1], ARGV[1])
+end
+return false"#,
+    )
+});
+
 impl RedisPool {
     pub async fn new(
         config: RedisConfig,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let manager = RedisConnectionManager::new(config.url.clone())?;
+        if let Some(name) = &config.client_name
+            && !valid_client_name(name)
+        {
+            return Err(format!(
+                "invalid Redis client_name {:?}: use printable ASCII without spaces",
+                name
+            )
+            .into());
+        }
 
-        let pool = Pool::builder()
+        let mut builder = Pool::builder()
             .max_size(config.max_size)
             .min_idle(config.min_idle)
             .connection_timeout(config.connection_timeout)
             .idle_timeout(config.idle_timeout)
-            .max_lifetime(config.max_lifetime)
-            .build(manager)
-            .await?;
+            .max_lifetime(config.max_lifetime);
+        if config.client_name.is_some() || config.client_lib_info {
+            builder = builder.connection_customizer(Box::new(ClientIdentity {
+                name: config.client_name.clone(),
+                lib_info: config.client_lib_info,
+            }));
+        }
+        let pool = builder.build(manager).await?;
         {
             let mut conn = pool.get().await?;
             let _: String = conn.ping().await?;
@@ -63,6 +255,7 @@ impl RedisPool {
         Ok(Self {
             pool: Arc::new(pool),
             max_size: config.max_size,
+            scripts: Arc::new(dashmap::DashMap::new()),
         })
     }
 
@@ -97,6 +290,11 @@ impl RedisPool {
                     .and_then(|s| s.parse().ok())
                     .unwrap_or(3600),
             )),
+            client_name: std::env::var("REDIS_CLIENT_NAME")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            client_lib_info: std::env::var("REDIS_CLIENT_LIB_INFO")
+                .is_ok_and(|s| s == "1" || s.eq_ignore_ascii_case("true")),
         };
 
         Self::new(config).await
@@ -105,7 +303,25 @@ impl RedisPool {
     pub async fn get_connection(
         &self,
     ) -> Result<bb8::PooledConnection<'_, RedisConnectionManager>, RunError<RedisError>> {
-        self.pool.get().await
+        let conn = self.pool.get().await;
+        #[cfg(any(feature = "metrics", feature = "full"))]
+        self.record_metrics(matches!(conn, Err(RunError::TimedOut)));
+        conn
+    }
+
+    #[cfg(any(feature = "metrics", feature = "full"))]
+    fn record_metrics(&self, timed_out: bool) {
+        use crate::metrics::{POOL_REDIS, record_pool, record_pool_timeout};
+        if timed_out {
+            record_pool_timeout(POOL_REDIS);
+        }
+        let state = self.pool.state();
+        record_pool(
+            POOL_REDIS,
+            state.connections.saturating_sub(state.idle_connections) as usize,
+            state.idle_connections as usize,
+            self.max_size as usize,
+        );
     }
 
     pub async fn set<K, V>(
@@ -142,6 +358,64 @@ impl RedisPool {
         Ok(())
     }
 
+    /// `GETSET key value`: store `value` and return the previous value, if any.
+    ///
+    /// Like a plain `SET`, this clears any TTL on the key.
+    pub async fn getset<K, V, R>(
+        &self,
+        key: K,
+        value: V,
+    ) -> Result<Option<R>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+        V: redis::ToRedisArgs + Send + Sync,
+        R: redis::FromRedisValue,
+    {
+        let mut conn = self.get_connection().await?;
+        let previous: Option<R> = redis::cmd("GETSET")
+            .arg(&key)
+            .arg(&value)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(previous)
+    }
+
+    /// Return the value at `key`, or store `init()` there (with `seconds` TTL) and return it.
+    ///
+    /// The check and the write run as one script, so concurrent callers all get the same
+    /// value: the first one stored. `init` only runs when the key looked missing, but two
+    /// callers racing on a missing key may both run it, and only one result is kept. Keep it
+    /// free of side effects, or guard it with [`Self::acquire_lock`] if it must run once.
+    ///
+    /// ```rust,ignore
+    /// let enabled: String = pool.get_or_set_with("flags:new_checkout", None, || "off".to_string()).await?;
+    /// ```
+    pub async fn get_or_set_with<V>(
+        &self,
+        key: &str,
+        seconds: Option<u64>,
+        init: impl FnOnce() -> V,
+    ) -> Result<V, Box<dyn std::error::Error + Send + Sync>>
+    where
+        V: redis::ToRedisArgs + redis::FromRedisValue + Send + Sync,
+    {
+        if let Some(current) = self.get::<_, V>(key).await? {
+            return Ok(current);
+        }
+        let value = init();
+        let mut args = value.to_redis_args();
+        if args.len() != 1 {
+            return Err(format!(
+                "get_or_set_with needs a single Redis value, got {} arguments",
+                args.len()
+            )
+            .into());
+        }
+        args.push(seconds.unwrap_or(0).to_string().into_bytes());
+        let existing: Option<V> = self.eval(&GET_OR_SET_SCRIPT, &[key], &args).await?;
+        Ok(existing.unwrap_or(value))
+    }
+
     pub async fn get<K, V>(
         &self,
         key: K,
@@ -182,7 +456,7 @@ impl RedisPool {
         K: redis::ToRedisArgs + Send + Sync,
     {
         let mut conn = self.get_connection().await?;
-        let result: bool = conn.expire(&key, seconds as i64).await?;
+        let result: bool = conn.expire(&key, to_i64(seconds)?).await?;
         Ok(result)
     }
 
@@ -218,6 +492,115 @@ impl RedisPool {
         Ok(result)
     }
 
+    /// `ZADD key score member`. Returns true if `member` is new (false if only its score changed).
+    pub async fn zadd<K, M>(
+        &self,
+        key: K,
+        member: M,
+        score: f64,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+        M: redis::ToRedisArgs + Send + Sync,
+    {
+        let mut conn = self.get_connection().await?;
+        let added: u64 = redis::cmd("ZADD")
+            .arg(&key)
+            .arg(score)
+            .arg(&member)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(added > 0)
+    }
+
+    /// `ZRANGE key start stop WITHSCORES`, lowest score first. Negative indexes count from
+    /// the end: `(0, -1)` is the whole set, `(-3, -1)` the three highest scores.
+    pub async fn zrange_with_scores<K, M>(
+        &self,
+        key: K,
+        start: isize,
+        stop: isize,
+    ) -> Result<Vec<(M, f64)>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+        M: redis::FromRedisValue,
+    {
+        let mut conn = self.get_connection().await?;
+        let members: Vec<(M, f64)> = redis::cmd("ZRANGE")
+            .arg(&key)
+            .arg(start)
+            .arg(stop)
+            .arg("WITHSCORES")
+            .query_async(&mut *conn)
+            .await?;
+        Ok(members)
+    }
+
+    /// `ZREMRANGEBYSCORE key min max` (both inclusive). Returns how many members were removed.
+    pub async fn zrem_range_by_score<K>(
+        &self,
+        key: K,
+        min: f64,
+        max: f64,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+    {
+        let mut conn = self.get_connection().await?;
+        let removed: u64 = redis::cmd("ZREMRANGEBYSCORE")
+            .arg(&key)
+            .arg(min)
+            .arg(max)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(removed)
+    }
+
+    /// `ZCARD key`: number of members, 0 if the key does not exist.
+    pub async fn zcard<K>(&self, key: K) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+    {
+        let mut conn = self.get_connection().await?;
+        let count: u64 = redis::cmd("ZCARD")
+            .arg(&key)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(count)
+    }
+
+    /// Count members of a sliding-window sorted set whose score (Unix millis) is within
+    /// `window` of `now_ms`, after removing the older ones.
+    ///
+    /// Add events with `zadd(key, unique_member, now_ms as f64)`; the trim and the count run
+    /// in one `MULTI` so concurrent callers see a consistent count.
+    pub async fn sliding_window_count<K>(
+        &self,
+        key: K,
+        window: std::time::Duration,
+        now_ms: i64,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>>
+    where
+        K: redis::ToRedisArgs + Send + Sync,
+    {
+        let window_ms = to_i64(u64::try_from(window.as_millis())?)?;
+        // Exclusive bound: an event exactly `window` old is already outside it
+        let cutoff = format!("({}", now_ms.saturating_sub(window_ms));
+        let mut conn = self.get_connection().await?;
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .cmd("ZREMRANGEBYSCORE")
+            .arg(&key)
+            .arg("-inf")
+            .arg(cutoff)
+            .ignore()
+            .cmd("ZCARD")
+            .arg(&key)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(count)
+    }
+
     pub async fn del_by_pattern(
         &self,
         pattern: &str,
@@ -256,7 +639,7 @@ impl RedisPool {
 
                         match unlink_res {
                             Ok(n) => {
-                                total_deleted += n as u64;
+                                total_deleted += to_u64(n)?;
                                 continue;
                             }
                             Err(_) => {
@@ -268,7 +651,7 @@ impl RedisPool {
 
                     // Backup plan: use DEL if UNLINK is not supported
                     let n: i64 = redis::cmd("DEL").arg(chunk).query_async(&mut *conn).await?;
-                    total_deleted += n as u64;
+                    total_deleted += to_u64(n)?;
                 }
             }
 
@@ -311,7 +694,7 @@ impl RedisPool {
                 format!("{}:{}:{}", pid, thread_name, ts)
             }
         };
-        let ttl_ms = ttl.as_millis() as u64;
+        let ttl_ms = u64::try_from(ttl.as_millis())?;
         let mut cmd = redis::cmd("SET");
         cmd.arg(key)
             .arg(lock_value.clone())
@@ -334,18 +717,84 @@ impl RedisPool {
         key: &str,
         token: &str,
     ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
-        let mut conn = self.get_connection().await?;
-        let script = Script::new(
-            r#"if redis.call("GET", KEYS[1]) == ARGV[1] then
-    return redis.call("DEL", KEYS[1])
-else
-    return 0
-end"#,
-        );
-        let deleted: i32 = script.key(key).arg(token).invoke_async(&mut *conn).await?;
+        let deleted: i32 = self.eval(&RELEASE_LOCK_SCRIPT, &[key], &[token]).await?;
         Ok(deleted > 0)
     }
 
+    /// Register a Lua script under `name` and return its handle.
+    ///
+    /// Only the SHA1 is computed here; the body is sent to Redis the first time [`Self::eval`]
+    /// meets `NOSCRIPT`. Registering a name again replaces its script. Clones of the pool
+    /// share the registry.
+    pub fn register_script(&self, name: &str, body: &str) -> ScriptHandle {
+        let handle = ScriptHandle::new(name, body);
+        self.scripts.insert(name.to_string(), handle.clone());
+        handle
+    }
+
+    /// The script registered under `name`, if any.
+    pub fn script(&self, name: &str) -> Option<ScriptHandle> {
+        self.scripts.get(name).map(|entry| entry.clone())
+    }
+
+    /// Run a script with `EVALSHA`, falling back to `EVAL` (which also caches it server-side)
+    /// when Redis answers `NOSCRIPT`, e.g. after a restart or `SCRIPT FLUSH`.
+    pub async fn eval<T, K, A>(
+        &self,
+        script: &ScriptHandle,
+        keys: &[K],
+        args: &[A],
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>>
+    where
+        T: FromRedisValue,
+        K: ToRedisArgs + Send + Sync,
+        A: ToRedisArgs + Send + Sync,
+    {
+        let mut conn = self.get_connection().await?;
+        let mut cmd = redis::cmd("EVALSHA");
+        cmd.arg(script.sha()).arg(keys.len()).arg(keys).arg(args);
+        match cmd.query_async(&mut *conn).await {
+            Ok(value) => Ok(value),
+            Err(e) if e.code() == Some("NOSCRIPT") => {
+                tracing::debug!("Redis script {} not cached, sending body", script.name());
+                let mut cmd = redis::cmd("EVAL");
+                cmd.arg(&*script.body).arg(keys.len()).arg(keys).arg(args);
+                Ok(cmd.query_async(&mut *conn).await?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Increment the counter at `key`, starting a `window` TTL when this call created it
+    /// (fixed-window rate limiting).
+    ///
+    /// Returns the new count and the time until the key expires. A counter that somehow lost
+    /// its TTL gets `window` again, so it cannot block a client forever.
+    pub async fn incr_with_ttl(
+        &self,
+        key: &str,
+        window: std::time::Duration,
+    ) -> Result<(u64, std::time::Duration), Box<dyn std::error::Error + Send + Sync>> {
+        let window_ms = u64::try_from(window.as_millis())?.max(1);
+        let (count, ttl_ms): (u64, u64) = self
+            .eval(&INCR_WITH_TTL_SCRIPT, &[key], &[window_ms])
+            .await?;
+        Ok((count, std::time::Duration::from_millis(ttl_ms)))
+    }
+
+    /// Atomically delete `key` only if its current value equals `expected`.
+    ///
+    /// Returns true when this call deleted the key. Use it for single-use tokens: of several
+    /// concurrent callers holding the same value, exactly one gets `true`.
+    pub async fn compare_and_delete(
+        &self,
+        key: &str,
+        expected: &str,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        // Same script as `release_lock`, kept separate so the intent stays clear at call sites.
+        self.release_lock(key, expected).await
+    }
+
     /// Build a namespaced lock key.
     pub fn lock_key(namespace: &str, resource: &str) -> String {
         format!("lock:{}:{}", namespace, resource)
@@ -425,6 +874,194 @@ impl RedisUtils {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_handle_sha_matches_redis() {
+        // redis-cli SCRIPT LOAD "return 1"
+        let handle = ScriptHandle::new("one", "return 1");
+        assert_eq!(handle.sha(), "e0e1f9fabfc9d4800c877a703b823ac0578ff8db");
+        assert_eq!(handle.name(), "one");
+    }
+
+    #[test]
+    fn test_client_name_validation() {
+        assert!(valid_client_name("billing-api:prod"));
+        assert!(!valid_client_name(""));
+        assert!(!valid_client_name("billing api"));
+        assert!(!valid_client_name("计费"));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_client_name_is_set_on_every_connection() {
+        let config = RedisConfig::new(
+            env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+        )
+        .with_max_size(2)
+        .with_min_idle(Some(2))
+        .with_idle_timeout(None)
+        .with_max_lifetime(None)
+        .with_client_name("neocrates-test")
+        .with_client_lib_info(true);
+        let pool = RedisPool::new(config.clone()).await.unwrap();
+        let (mut a, mut b) = (
+            pool.get_connection().await.unwrap(),
+            pool.get_connection().await.unwrap(),
+        );
+        for conn in [&mut a, &mut b] {
+            let name: Option<String> = redis::cmd("CLIENT")
+                .arg("GETNAME")
+                .query_async(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(name.as_deref(), Some("neocrates-test"));
+        }
+
+        let bad = config.with_client_name("has space");
+        assert!(RedisPool::new(bad).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_get_or_set_with_single_winner() {
+        let pool = RedisPool::from_env()
+            .await
+            .expect("Failed to create Redis pool");
+        let key = "neocrates:test:get_or_set";
+        for _ in 0..20 {
+            pool.del(key).await.unwrap();
+            let barrier = Arc::new(tokio::sync::Barrier::new(2));
+            let racers: Vec<_> = ["a", "b"]
+                .into_iter()
+                .map(|candidate| {
+                    let pool = pool.clone();
+                    let barrier = barrier.clone();
+                    tokio::spawn(async move {
+                        barrier.wait().await;
+                        pool.get_or_set_with(key, Some(60), || candidate.to_string())
+                            .await
+                            .unwrap()
+                    })
+                })
+                .collect();
+            let mut seen = Vec::new();
+            for racer in racers {
+                seen.push(racer.await.unwrap());
+            }
+            let stored: String = pool.get(key).await.unwrap().unwrap();
+            assert_eq!(seen, [stored.clone(), stored]);
+        }
+        assert!(pool.ttl(key).await.unwrap() > 0);
+
+        let kept = pool
+            .get_or_set_with(key, None, || -> String { unreachable!("key exists") })
+            .await
+            .unwrap();
+        assert!(kept == "a" || kept == "b");
+        pool.del(key).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_getset_returns_previous_value() {
+        let pool = RedisPool::from_env()
+            .await
+            .expect("Failed to create Redis pool");
+        let key = "neocrates:test:getset";
+        pool.del(key).await.unwrap();
+
+        assert_eq!(pool.getset::<_, _, u64>(key, 1).await.unwrap(), None);
+        assert_eq!(pool.getset::<_, _, u64>(key, 2).await.unwrap(), Some(1));
+        assert_eq!(pool.get::<_, u64>(key).await.unwrap(), Some(2));
+        pool.del(key).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_sorted_set_helpers() {
+        let pool = RedisPool::from_env()
+            .await
+            .expect("Failed to create Redis pool");
+        let key = "neocrates:test:zset";
+        pool.del(key).await.unwrap();
+
+        assert!(pool.zadd(key, "alice", 30.0).await.unwrap());
+        assert!(pool.zadd(key, "bob", 10.0).await.unwrap());
+        assert!(pool.zadd(key, "carol", 20.0).await.unwrap());
+        assert!(!pool.zadd(key, "bob", 40.0).await.unwrap());
+        assert_eq!(pool.zcard(key).await.unwrap(), 3);
+
+        let ranked: Vec<(String, f64)> = pool.zrange_with_scores(key, 0, -1).await.unwrap();
+        assert_eq!(
+            ranked,
+            vec![
+                ("carol".to_string(), 20.0),
+                ("alice".to_string(), 30.0),
+                ("bob".to_string(), 40.0)
+            ]
+        );
+        let top: Vec<(String, f64)> = pool.zrange_with_scores(key, -1, -1).await.unwrap();
+        assert_eq!(top, vec![("bob".to_string(), 40.0)]);
+
+        assert_eq!(pool.zrem_range_by_score(key, 0.0, 30.0).await.unwrap(), 2);
+        assert_eq!(pool.zcard(key).await.unwrap(), 1);
+        pool.del(key).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_sliding_window_count() {
+        let pool = RedisPool::from_env()
+            .await
+            .expect("Failed to create Redis pool");
+        let key = "neocrates:test:window";
+        pool.del(key).await.unwrap();
+
+        let now = 1_000_000i64;
+        for (member, at) in [
+            ("a", now - 90_000),
+            ("b", now - 60_000),
+            ("c", now - 1_000),
+            ("d", now),
+        ] {
+            pool.zadd(key, member, at as f64).await.unwrap();
+        }
+        let window = std::time::Duration::from_secs(60);
+        assert_eq!(
+            pool.sliding_window_count(key, window, now).await.unwrap(),
+            2
+        );
+        assert_eq!(pool.zcard(key).await.unwrap(), 2);
+        pool.del(key).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a Redis server at REDIS_URL"]
+    async fn test_eval_falls_back_after_script_flush() {
+        let pool = RedisPool::from_env()
+            .await
+            .expect("Failed to create Redis pool");
+        let handle = pool.register_script("echo_arg", "return ARGV[1]");
+        assert_eq!(pool.script("echo_arg"), Some(handle.clone()));
+
+        for round in 0..3 {
+            let _: () = redis::cmd("SCRIPT")
+                .arg("FLUSH")
+                .query_async(&mut *pool.get_connection().await.unwrap())
+                .await
+                .unwrap();
+            let first: String = pool.eval(&handle, &["unused"], &[round]).await.unwrap();
+            // Cached by the EVAL fallback, so this one is a plain EVALSHA hit.
+            let second: String = pool.eval(&handle, &["unused"], &[round]).await.unwrap();
+            assert_eq!(first, round.to_string());
+            assert_eq!(second, first);
+        }
+    }
+}
+
 // #[tokio::test]
 // async fn test_redis_pool_basic_ops() {
 //     let pool = RedisPool::new(RedisConfig::default())
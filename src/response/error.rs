@@ -1,8 +1,9 @@
-use std::{fmt::Display, panic::Location};
+use std::{fmt::Display, panic::Location, time::Duration};
 
 use axum::{
     Json,
-    http::StatusCode,
+    extract::rejection::{BytesRejection, FailedToBufferBody, JsonRejection},
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
@@ -76,6 +77,8 @@ pub enum AppError {
     // Client errors (4xx)
     #[error("{0}")]
     ValidationError(String), // Parameter validation failure
+    #[error("Parameter validation failed: {}", join_field_errors(.0))]
+    FieldValidation(Vec<FieldError>), // 422: Per-field validation failures, returned under `data`
     #[error("Unauthorized")]
     Unauthorized, // Not logged in or invalid token
     #[error("Token Expired")]
@@ -95,18 +98,31 @@ pub enum AppError {
     UnprocessableEntity(String), // 422: Business rule validation
     #[error("Rate limit exceeded: {0}")]
     RateLimit(String), // 429: Rate limit exceeded
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String), // 413: Request body exceeds the configured limit
     #[error("{0}")]
     EasterEgg(String), // 418: Fun easter egg responses
 
     // Server errors (5xx)
-    #[error("Database error: {0}")]
-    DbError(String), // Database error
+    #[error("Database error: {msg}")]
+    DbError {
+        msg: String,
+        // Original error, kept so `source()` / anyhow chains reach the driver error
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    }, // Database error
     #[error("Redis error: {0}")]
     RedisError(String), // Redis error
     #[error("Message queue error: {0}")]
     MqError(String), // Message queue error
     #[error("External service error: {0}")]
     ExternalError(String), // External service call error
+    #[error("Service unavailable: {msg}")]
+    ServiceUnavailable {
+        msg: String,
+        // Sent as `Retry-After` (whole seconds, rounded up) when known
+        retry_after: Option<Duration>,
+    }, // 503: Dependency temporarily refused, e.g. an open circuit breaker
     #[error("Internal server error")]
     Internal(String), // Other internal errors
 
@@ -117,12 +133,105 @@ pub enum AppError {
     JsonError(String), // JSON serialization error
 }
 
+/// A single field validation failure, serialized into `ApiResponse.data` so clients
+/// can highlight the offending inputs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldError {
+    pub field: String,   // Field name (as declared on the validated struct)
+    pub code: String,    // Validator code, e.g. "length", "email"
+    pub message: String, // Human readable message (falls back to the code)
+}
+
+impl FieldError {
+    pub fn new(
+        field: impl Into<String>,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+fn join_field_errors(errors: &[FieldError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
 // API response structure
 #[derive(Serialize)]
 pub struct ApiResponse<T> {
     pub code: u32,       // Business status code
     pub message: String, // Error message
     pub data: Option<T>, // Response data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>, // Correlation id, omitted when absent
+}
+
+impl<T> ApiResponse<T> {
+    /// Business code for a successful response (HTTP 200).
+    pub const CODE_OK: u32 = 200000;
+    /// Business code for a successful creation (HTTP 201).
+    pub const CODE_CREATED: u32 = 201000;
+
+    /// Success response: HTTP 200, code `200000`.
+    pub fn ok(data: T) -> Self {
+        Self::success(Self::CODE_OK, data)
+    }
+
+    /// Creation response: HTTP 201, code `201000`.
+    pub fn created(data: T) -> Self {
+        Self::success(Self::CODE_CREATED, data)
+    }
+
+    fn success(code: u32, data: T) -> Self {
+        Self {
+            code,
+            message: "success".to_string(),
+            data: Some(data),
+            request_id: current_request_id(),
+        }
+    }
+
+    /// HTTP status derived from the business code prefix (`201000` -> 201), 200 otherwise.
+    pub fn status_code(&self) -> StatusCode {
+        u16::try_from(self.code / 1000)
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::OK)
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        (self.status_code(), Json(self)).into_response()
+    }
+}
+
+/// Header used to read/propagate the request correlation id.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Run `fut` with `request_id` as the current request id.
+///
+/// Errors converted into responses inside the scope (`AppError::into_response`) pick the id
+/// up automatically. Usually installed by `middlewares::request_id::request_id`.
+pub async fn scope_request_id<F: std::future::Future>(request_id: String, fut: F) -> F::Output {
+    REQUEST_ID.scope(request_id, fut).await
+}
+
+/// The request id of the current task, if running inside [`scope_request_id`].
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
 }
 
 // Error code and HTTP status code mapping
@@ -139,6 +248,7 @@ impl AppError {
     const HTTP_IM_A_TEAPOT: StatusCode = StatusCode::IM_A_TEAPOT; // 418
     const EXPECTATION_FAILED: StatusCode = StatusCode::EXPECTATION_FAILED; // 417
     const HTTP_INTERNAL_ERROR: StatusCode = StatusCode::INTERNAL_SERVER_ERROR; // 500
+    const HTTP_SERVICE_UNAVAILABLE: StatusCode = StatusCode::SERVICE_UNAVAILABLE; // 503
 
     // Business error code constants
     const BIZ_VALIDATION_ERROR: u32 = 400001;
@@ -153,10 +263,12 @@ impl AppError {
     const BIZ_REDIS_ERROR: u32 = 500002;
     const BIZ_MQ_ERROR: u32 = 500003;
     const BIZ_EXTERNAL_ERROR: u32 = 500004;
+    const BIZ_SERVICE_UNAVAILABLE: u32 = 500005;
     const BIZ_INTERNAL_ERROR: u32 = 500000;
     const BIZ_UNPROCESSABLE_ENTITY: u32 = 400100; // Business validation errors
     const BIZ_RATE_LIMIT: u32 = 400101; // Rate limiting errors
     const BIZ_EASTER_EGG: u32 = 400102; // Easter egg responses
+    const BIZ_PAYLOAD_TOO_LARGE: u32 = 400103; // Request body too large
 
     // Business data errors - Expanded categories
     // 410000-410099: Data existence errors
@@ -175,6 +287,7 @@ impl AppError {
         match self {
             // 4xx Client Errors
             Self::ValidationError(_) => Self::HTTP_BAD_REQUEST,
+            Self::FieldValidation(_) => Self::HTTP_UNPROCESSABLE_ENTITY,
             Self::Unauthorized => Self::HTTP_UNAUTHORIZED,
             Self::TokenExpired => Self::HTTP_UNAUTHORIZED,
             Self::Forbidden => Self::HTTP_FORBIDDEN,
@@ -182,20 +295,41 @@ impl AppError {
             Self::Conflict(_) => Self::HTTP_CONFLICT,
             Self::UnprocessableEntity(_) => Self::HTTP_UNPROCESSABLE_ENTITY,
             Self::RateLimit(_) => Self::HTTP_TOO_MANY_REQUESTS,
+            Self::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
             Self::EasterEgg(_) => Self::HTTP_IM_A_TEAPOT,
             Self::Internal(_) => Self::HTTP_INTERNAL_ERROR,
+            Self::ServiceUnavailable { .. } => Self::HTTP_SERVICE_UNAVAILABLE,
             Self::ClientError(_) => Self::EXPECTATION_FAILED,
-            Self::DataError(_, _) => Self::HTTP_CONFLICT, // All data errors use HTTP 409
+            Self::DataError(code, _) => Self::data_error_status(*code),
             // 4xx HTTP_BAD_REQUEST - Return 400 for all
             _ => Self::HTTP_BAD_REQUEST,
         }
     }
 
+    /// Maps `DataError` business codes to HTTP status codes.
+    ///
+    /// | Code | HTTP |
+    /// |---|---|
+    /// | `BIZ_DATA_NOT_FOUND` | 404 |
+    /// | `BIZ_DATA_DELETED` | 410 |
+    /// | `BIZ_JSON_ERROR` | 400 |
+    /// | `BIZ_DATA_EXISTS`, `BIZ_DATA_DUPLICATE`, `BIZ_DATA_ARCHIVED`, `BIZ_DATA_OUTDATED` | 409 |
+    /// | anything else | 409 |
+    fn data_error_status(code: u32) -> StatusCode {
+        match code {
+            Self::BIZ_DATA_NOT_FOUND => Self::HTTP_NOT_FOUND,
+            Self::BIZ_DATA_DELETED => StatusCode::GONE,
+            Self::BIZ_JSON_ERROR => Self::HTTP_BAD_REQUEST,
+            _ => Self::HTTP_CONFLICT,
+        }
+    }
+
     /// Maps application errors to business error codes
     pub fn business_code(&self) -> u32 {
         match self {
             // 4xx Client Errors
             Self::ValidationError(_) => Self::BIZ_VALIDATION_ERROR,
+            Self::FieldValidation(_) => Self::BIZ_VALIDATION_ERROR,
             Self::Unauthorized => Self::BIZ_UNAUTHORIZED,
             Self::TokenExpired => Self::BIZ_TOKEN_EXPIRED,
             Self::Forbidden => Self::BIZ_FORBIDDEN,
@@ -203,15 +337,17 @@ impl AppError {
             Self::Conflict(_) => Self::BIZ_CONFLICT,
             Self::UnprocessableEntity(_) => Self::BIZ_UNPROCESSABLE_ENTITY,
             Self::RateLimit(_) => Self::BIZ_RATE_LIMIT,
+            Self::PayloadTooLarge(_) => Self::BIZ_PAYLOAD_TOO_LARGE,
             Self::EasterEgg(_) => Self::BIZ_EASTER_EGG,
             Self::ClientError(_) => Self::BIZ_CLIENT_ERROR,
             Self::ClientDataError(_) => Self::BIZ_DATA_ERROR,
             Self::DataError(code, _) => *code, // Use the custom business code from DataError
             // 5xx Server Errors
-            Self::DbError(_) => Self::BIZ_DB_ERROR,
+            Self::DbError { .. } => Self::BIZ_DB_ERROR,
             Self::RedisError(_) => Self::BIZ_REDIS_ERROR,
             Self::MqError(_) => Self::BIZ_MQ_ERROR,
             Self::ExternalError(_) => Self::BIZ_EXTERNAL_ERROR,
+            Self::ServiceUnavailable { .. } => Self::BIZ_SERVICE_UNAVAILABLE,
             Self::Internal(_) => Self::BIZ_INTERNAL_ERROR,
             // Business data errors
             // Self::DataExtis(_) => Self::BIZ_DATA_EXTIS,
@@ -220,22 +356,64 @@ impl AppError {
         }
     }
 
+    /// Whether the failed operation is worth retrying (pairs with `helper::core::retry`).
+    ///
+    /// | Variant | Retryable |
+    /// |---|---|
+    /// | `DbError`, `RedisError`, `MqError`, `ExternalError`, `Internal` | yes |
+    /// | `ValidationError`, `FieldValidation`, `Unauthorized`, `TokenExpired`, `Forbidden` | no |
+    /// | `NotFound`, `Conflict`, `ClientError`, `ClientDataError`, `UnprocessableEntity` | no |
+    /// | `RateLimit`, `PayloadTooLarge`, `EasterEgg`, `DataError`, `JsonError` | no |
+    /// | `ServiceUnavailable` (wait for `retry_after` instead) | no |
+    ///
+    /// The match is exhaustive on purpose: a new variant must choose its classification.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::DbError { .. }
+            | Self::RedisError(_)
+            | Self::MqError(_)
+            | Self::ExternalError(_)
+            | Self::Internal(_) => true,
+            Self::ValidationError(_)
+            | Self::FieldValidation(_)
+            | Self::Unauthorized
+            | Self::TokenExpired
+            | Self::Forbidden
+            | Self::NotFound(_)
+            | Self::Conflict(_)
+            | Self::ClientError(_)
+            | Self::ClientDataError(_)
+            | Self::UnprocessableEntity(_)
+            | Self::RateLimit(_)
+            | Self::PayloadTooLarge(_)
+            | Self::EasterEgg(_)
+            | Self::DataError(_, _)
+            | Self::JsonError(_)
+            | Self::ServiceUnavailable { .. } => false,
+        }
+    }
+
     /// Returns a user-friendly error message
     pub fn message(&self) -> String {
         match self {
             Self::UnprocessableEntity(msg) => msg.to_string(),
             Self::RateLimit(msg) => format!("Rate limit exceeded: {}", msg),
+            Self::PayloadTooLarge(msg) => format!("Payload too large: {}", msg),
             Self::EasterEgg(msg) => format!("Easter egg: {}", msg),
             Self::ValidationError(msg) => msg.to_string(),
+            Self::FieldValidation(errors) => {
+                format!("Parameter validation failed: {}", join_field_errors(errors))
+            }
             Self::Unauthorized => "Unauthorized access".to_string(),
             Self::TokenExpired => "Token expired".to_string(),
             Self::Forbidden => "Access forbidden".to_string(),
             Self::NotFound(msg) => msg.to_string(),
             Self::Conflict(msg) => msg.to_string(),
-            Self::DbError(e) => format!("Database error: {}", e),
+            Self::DbError { msg, .. } => format!("Database error: {}", msg),
             Self::RedisError(e) => format!("Cache error: {}", e),
             Self::MqError(e) => format!("Message queue error: {}", e),
             Self::ExternalError(e) => format!("External service error: {}", e),
+            Self::ServiceUnavailable { msg, .. } => format!("Service unavailable: {}", msg),
             Self::Internal(e) => format!("Internal server error: {}", e),
             Self::ClientError(msg) => msg.to_string(),
             Self::ClientDataError(msg) => msg.to_string(),
@@ -248,40 +426,149 @@ impl AppError {
 // Implement response conversion
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let status = self.status_code();
-        let response = ApiResponse {
+        let request_id = current_request_id();
+        self.into_response_with(request_id)
+    }
+}
+
+impl AppError {
+    /// Convert into a response carrying an explicit request id, for code paths that run
+    /// outside the task-local scope set by the request id middleware.
+    pub fn with_request_id(self, request_id: impl Into<String>) -> Response {
+        self.into_response_with(Some(request_id.into()))
+    }
+
+    /// The `{ code, message, data, request_id }` envelope sent for this error.
+    pub(crate) fn api_response(&self, request_id: Option<String>) -> ApiResponse<Vec<FieldError>> {
+        let data = match self {
+            Self::FieldValidation(errors) => Some(errors.clone()),
+            _ => None,
+        };
+        ApiResponse {
             code: self.business_code(),
             message: self.to_string(),
-            data: None::<()>,
+            data,
+            request_id,
+        }
+    }
+
+    fn into_response_with(self, request_id: Option<String>) -> Response {
+        let status = self.status_code();
+        let retry_after = match &self {
+            Self::ServiceUnavailable { retry_after, .. } => *retry_after,
+            _ => None,
         };
+        let response = self.api_response(request_id);
         // Log the response
         tracing::error!(
-            "...App Error...: code:{:?} message:{:?} self:{:?}",
+            "...App Error...: request_id:{} code:{:?} message:{:?} self:{:?}",
+            response.request_id.as_deref().unwrap_or("-"),
             response.code,
             response.message,
             self
         );
-        (status, Json(response)).into_response()
+        let mut response = (status, Json(response)).into_response();
+        if let Some(retry_after) = retry_after {
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs.max(1)));
+        }
+        response
     }
 }
 
 impl From<ValidationErrors> for AppError {
     fn from(err: ValidationErrors) -> Self {
         tracing::warn!("Parameter validation failed: {:?}", err);
-        let message = err
+        let mut errors: Vec<FieldError> = err
             .field_errors()
             .iter()
-            .map(|(field, errors)| {
-                let error_messages: Vec<String> = errors
-                    .iter()
-                    .filter_map(|error| error.message.as_ref().map(|m| m.to_string()))
-                    .collect();
-                format!("{}: {}", field, error_messages.join(", "))
+            .flat_map(|(field, errors)| {
+                errors.iter().map(move |error| {
+                    let message = error
+                        .message
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| error.code.to_string());
+                    FieldError::new(field.to_string(), error.code.to_string(), message)
+                })
             })
-            .collect::<Vec<String>>()
-            .join("; ");
+            .collect();
+        // HashMap iteration order is random; keep the payload stable for clients.
+        errors.sort_by(|a, b| a.field.cmp(&b.field));
+
+        AppError::FieldValidation(errors)
+    }
+}
+
+/// JSON body rejections, used by `AppJson` so extractor failures share the `AppError` envelope.
+///
+/// | Rejection | Variant | HTTP |
+/// |---|---|---|
+/// | `JsonSyntaxError` | `JsonError` | 400 |
+/// | `JsonDataError`, `MissingJsonContentType` | `ValidationError` | 400 |
+/// | `BytesRejection` over the body limit | `PayloadTooLarge` | 413 |
+/// | other `BytesRejection`s | `ClientError` | 417 |
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        tracing::warn!("JSON extraction failed: {}", rejection.body_text());
+        match rejection {
+            JsonRejection::JsonSyntaxError(err) => {
+                AppError::JsonError(format!("JSON syntax error: {}", err.body_text()))
+            }
+            JsonRejection::JsonDataError(err) => {
+                AppError::ValidationError(format!("Invalid JSON data: {}", err.body_text()))
+            }
+            JsonRejection::MissingJsonContentType(_) => AppError::ValidationError(
+                "Missing Content-Type: application/json header".to_string(),
+            ),
+            JsonRejection::BytesRejection(BytesRejection::FailedToBufferBody(
+                FailedToBufferBody::LengthLimitError(err),
+            )) => AppError::PayloadTooLarge(err.body_text()),
+            other => AppError::ClientError(format!(
+                "Failed to read request body: {}",
+                other.body_text()
+            )),
+        }
+    }
+}
 
-        AppError::ValidationError(format!("Parameter validation failed: {}", message))
+/// Lets handlers returning `AppResult` use `?` on `anyhow::Result`. The full context chain
+/// is logged here; the client only sees the generic 500 message.
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        tracing::error!("{:?}", err);
+        AppError::Internal(err.to_string())
+    }
+}
+
+/// Outgoing HTTP failures become `ExternalError`, with the message saying what went wrong:
+/// `gateway timeout: ...` for timeouts, `connection failed: ...` for connect errors, and
+/// reqwest's own message (which names the upstream status and URL) otherwise.
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        tracing::error!("External request error: {:?}", err);
+        let kind = if err.is_timeout() {
+            Some("gateway timeout")
+        } else if err.is_connect() {
+            Some("connection failed")
+        } else if err.is_decode() {
+            Some("invalid response body")
+        } else {
+            None
+        };
+        match kind {
+            Some(kind) => AppError::ExternalError(format!("{}: {}", kind, err)),
+            None => AppError::ExternalError(err.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        tracing::warn!("JSON error: {}", err);
+        AppError::JsonError(err.to_string())
     }
 }
 
@@ -289,7 +576,7 @@ impl From<ValidationErrors> for AppError {
 impl From<diesel::result::Error> for AppError {
     fn from(err: diesel::result::Error) -> Self {
         tracing::error!("Database error: {}", err);
-        AppError::DbError(err.to_string())
+        AppError::db_with_source(err.to_string(), err)
     }
 }
 
@@ -297,7 +584,7 @@ impl From<diesel::result::Error> for AppError {
 impl From<deadpool_diesel::PoolError> for AppError {
     fn from(err: deadpool_diesel::PoolError) -> Self {
         tracing::error!("Deadpool_diesel Database error: {}", err);
-        AppError::DbError(err.to_string())
+        AppError::db_with_source(err.to_string(), err)
     }
 }
 
@@ -308,6 +595,25 @@ pub fn msg_with_location<M: Display>(msg: M) -> String {
 }
 
 impl AppError {
+    /// Database error without an underlying source.
+    pub fn db(msg: impl Into<String>) -> Self {
+        AppError::DbError {
+            msg: msg.into(),
+            source: None,
+        }
+    }
+
+    /// Database error that keeps `source` in the `std::error::Error::source()` chain.
+    pub fn db_with_source<E>(msg: impl Into<String>, source: E) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        AppError::DbError {
+            msg: msg.into(),
+            source: Some(Box::new(source)),
+        }
+    }
+
     #[track_caller]
     pub fn client_here<M: Display>(msg: M) -> Self {
         AppError::ClientError(msg_with_location(msg))
@@ -448,3 +754,241 @@ impl<T, E> AppResultExt<T, E> for Result<T, E> {
 //         false
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::ValidationError;
+
+    #[test]
+    fn test_validation_errors_become_field_errors() {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "name",
+            ValidationError::new("length").with_message("name is too long".into()),
+        );
+        errors.add("email", ValidationError::new("email"));
+
+        let err = AppError::from(errors);
+        assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+        let AppError::FieldValidation(fields) = &err else {
+            panic!("expected FieldValidation, got {:?}", err);
+        };
+        assert_eq!(
+            fields,
+            &vec![
+                FieldError::new("email", "email", "email"),
+                FieldError::new("name", "length", "name is too long"),
+            ]
+        );
+        assert_eq!(
+            err.message(),
+            "Parameter validation failed: email: email; name: name is too long"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_field_validation_response_carries_fields_in_data() {
+        let err =
+            AppError::FieldValidation(vec![FieldError::new("mobile", "regex", "invalid mobile")]);
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["code"], 400001);
+        assert_eq!(json["data"][0]["field"], "mobile");
+        assert_eq!(json["data"][0]["code"], "regex");
+        assert_eq!(json["data"][0]["message"], "invalid mobile");
+    }
+
+    #[test]
+    fn test_is_retryable_classification() {
+        let s = || "x".to_string();
+        let cases = vec![
+            (AppError::db(s()), true),
+            (AppError::RedisError(s()), true),
+            (AppError::MqError(s()), true),
+            (AppError::ExternalError(s()), true),
+            (AppError::Internal(s()), true),
+            (AppError::ValidationError(s()), false),
+            (AppError::FieldValidation(vec![]), false),
+            (AppError::Unauthorized, false),
+            (AppError::TokenExpired, false),
+            (AppError::Forbidden, false),
+            (AppError::NotFound(s()), false),
+            (AppError::Conflict(s()), false),
+            (AppError::ClientError(s()), false),
+            (AppError::ClientDataError(s()), false),
+            (AppError::UnprocessableEntity(s()), false),
+            (AppError::RateLimit(s()), false),
+            (AppError::PayloadTooLarge(s()), false),
+            (AppError::EasterEgg(s()), false),
+            (AppError::DataError(AppError::BIZ_DATA_EXISTS, s()), false),
+            (AppError::JsonError(s()), false),
+            (
+                AppError::ServiceUnavailable {
+                    msg: s(),
+                    retry_after: None,
+                },
+                false,
+            ),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(err.is_retryable(), expected, "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_db_error_keeps_source_chain() {
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "pool timed out");
+        let err = AppError::db_with_source("connection failed", io);
+        assert_eq!(err.to_string(), "Database error: connection failed");
+        assert_eq!(err.message(), "Database error: connection failed");
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "pool timed out"
+        );
+
+        let chain: Vec<String> = anyhow::Error::from(err)
+            .chain()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            chain,
+            vec!["Database error: connection failed", "pool timed out"]
+        );
+        assert!(std::error::Error::source(&AppError::db("plain")).is_none());
+    }
+
+    #[cfg(any(feature = "diesel", feature = "full"))]
+    #[test]
+    fn test_diesel_error_conversion_keeps_source() {
+        let err = AppError::from(diesel::result::Error::NotFound);
+        assert_eq!(err.message(), "Database error: Record not found");
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_api_response_success_constructors() {
+        let response = ApiResponse::ok(vec![1, 2]).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"code": 200000, "message": "success", "data": [1, 2]})
+        );
+
+        let created = ApiResponse::created("id-1");
+        assert_eq!(created.code, ApiResponse::<()>::CODE_CREATED);
+        assert_eq!(created.into_response().status(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn test_data_error_status_follows_business_code() {
+        let status = |code| AppError::DataError(code, "x".to_string()).status_code();
+        assert_eq!(status(AppError::BIZ_DATA_NOT_FOUND), StatusCode::NOT_FOUND);
+        assert_eq!(status(AppError::BIZ_DATA_DELETED), StatusCode::GONE);
+        assert_eq!(status(AppError::BIZ_JSON_ERROR), StatusCode::BAD_REQUEST);
+        assert_eq!(status(AppError::BIZ_DATA_EXISTS), StatusCode::CONFLICT);
+        assert_eq!(status(AppError::BIZ_DATA_DUPLICATE), StatusCode::CONFLICT);
+        assert_eq!(status(AppError::BIZ_DATA_ARCHIVED), StatusCode::CONFLICT);
+        assert_eq!(status(AppError::BIZ_DATA_OUTDATED), StatusCode::CONFLICT);
+        assert_eq!(status(410099), StatusCode::CONFLICT);
+        // The business code itself is passed through untouched.
+        let err = AppError::DataError(AppError::BIZ_DATA_NOT_FOUND, "x".to_string());
+        assert_eq!(err.business_code(), AppError::BIZ_DATA_NOT_FOUND);
+    }
+
+    #[test]
+    fn test_anyhow_error_becomes_internal() {
+        fn handler() -> AppResult<()> {
+            Err(anyhow::anyhow!("boom")).map_err(|e| e.context("loading config"))?;
+            Ok(())
+        }
+        let err = handler().unwrap_err();
+        assert!(matches!(&err, AppError::Internal(msg) if msg == "loading config"));
+        assert_eq!(err.business_code(), 500000);
+
+        let response = AppError::from(anyhow::anyhow!("boom")).into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_errors_become_external_errors() {
+        // Nothing listens on the port once the listener is dropped.
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let err = reqwest::get(format!("http://{}", addr)).await.unwrap_err();
+        let err = AppError::from(err);
+        assert!(
+            matches!(&err, AppError::ExternalError(msg) if msg.starts_with("connection failed: ")),
+            "{:?}",
+            err
+        );
+        assert!(err.is_retryable());
+
+        // Accepts the connection but never answers.
+        let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = silent.local_addr().unwrap();
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let err = client
+            .get(format!("http://{}", addr))
+            .send()
+            .await
+            .unwrap_err();
+        let err = AppError::from(err);
+        assert!(
+            matches!(&err, AppError::ExternalError(msg) if msg.starts_with("gateway timeout: ")),
+            "{:?}",
+            err
+        );
+
+        let upstream = axum::http::Response::builder()
+            .status(503)
+            .body("down")
+            .unwrap();
+        let err = reqwest::Response::from(upstream)
+            .error_for_status()
+            .unwrap_err();
+        let err = AppError::from(err);
+        assert!(
+            matches!(&err, AppError::ExternalError(msg) if msg.contains("503")),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.business_code(), AppError::BIZ_EXTERNAL_ERROR);
+    }
+
+    #[test]
+    fn test_serde_json_error_becomes_json_error() {
+        fn parse(body: &str) -> AppResult<serde_json::Value> {
+            Ok(serde_json::from_str(body)?)
+        }
+        let err = parse("{\"a\":").unwrap_err();
+        assert!(
+            matches!(&err, AppError::JsonError(msg) if msg.contains("EOF")),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.business_code(), AppError::BIZ_JSON_ERROR);
+        assert!(parse("{\"a\":1}").is_ok());
+    }
+
+    #[test]
+    fn test_plain_validation_error_is_unchanged() {
+        let err = AppError::ValidationError("name is required".to_string());
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.message(), "name is required");
+    }
+}
@@ -0,0 +1,261 @@
+//! Server-Sent Events in the app envelope.
+//!
+//! Every data frame carries the same `{ code, message, data }` JSON as a regular
+//! [`ApiResponse`]; an [`AppError`] ends the stream with an `event: error` frame holding the
+//! error envelope and its business code.
+
+use std::{convert::Infallible, time::Duration};
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::{Stream, StreamExt, future};
+use serde::Serialize;
+
+use crate::response::error::{ApiResponse, AppError, AppResult, current_request_id};
+
+/// Default interval between heartbeat comment frames.
+pub const DEFAULT_HEARTBEAT: Duration = Duration::from_secs(15);
+
+/// Builder for a single SSE frame.
+///
+/// ```rust,ignore
+/// let frame = SseEvent::json(&chunk)?.event("delta").id(seq.to_string());
+/// let done = SseEvent::raw("[DONE]").event("done");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl SseEvent {
+    /// Frame whose data is `data` in the success envelope,
+    /// `{"code":200000,"message":"success","data":...}`.
+    pub fn json<T: Serialize>(data: T) -> AppResult<Self> {
+        Self::envelope(&ApiResponse::ok(data))
+    }
+
+    /// Frame with `data` sent as-is, e.g. a `[DONE]` marker.
+    pub fn raw(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            event: None,
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Terminal `event: error` frame carrying the error envelope.
+    pub fn error(err: &AppError) -> Self {
+        Self::error_with(err, current_request_id())
+    }
+
+    /// Event name (`event:` field); clients subscribe with `addEventListener(name, ...)`.
+    ///
+    /// Line breaks would end the field early, so `\r` and `\n` are removed.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(strip_field(event.into(), &['\r', '\n']));
+        self
+    }
+
+    /// Event id (`id:` field), echoed back by browsers as `Last-Event-ID` on reconnect.
+    ///
+    /// `\r`, `\n` and NUL are not allowed in an id and are removed.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(strip_field(id.into(), &['\r', '\n', '\0']));
+        self
+    }
+
+    /// Reconnection delay hint (`retry:` field).
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    pub fn into_event(self) -> Event {
+        let mut event = Event::default();
+        if let Some(name) = self.event {
+            event = event.event(name);
+        }
+        if let Some(id) = self.id {
+            event = event.id(id);
+        }
+        if let Some(retry) = self.retry {
+            event = event.retry(retry);
+        }
+        event.data(self.data)
+    }
+
+    fn envelope<T: Serialize>(response: &ApiResponse<T>) -> AppResult<Self> {
+        Ok(Self::raw(serde_json::to_string(response)?))
+    }
+
+    fn error_with(err: &AppError, request_id: Option<String>) -> Self {
+        let response = err.api_response(request_id);
+        tracing::error!(
+            "...SSE Error...: request_id:{} code:{:?} message:{:?} self:{:?}",
+            response.request_id.as_deref().unwrap_or("-"),
+            response.code,
+            response.message,
+            err
+        );
+        // An envelope of plain strings and numbers always serializes.
+        Self::envelope(&response)
+            .unwrap_or_else(|_| Self::raw(response.message))
+            .event("error")
+    }
+}
+
+/// Drop `forbidden` characters from a field value; axum's `Event` panics on them.
+fn strip_field(mut value: String, forbidden: &[char]) -> String {
+    if value.contains(forbidden) {
+        value.retain(|c| !forbidden.contains(&c));
+    }
+    value
+}
+
+/// Stream `items` as SSE, each `Ok` item as a data frame in the success envelope.
+///
+/// The first `Err` is sent as an `event: error` frame and ends the stream. A comment frame
+/// is sent after `heartbeat` of silence so proxies keep the connection open. Call it from
+/// the handler: the request id is captured there, since the stream is polled outside the
+/// request id middleware's scope.
+///
+/// ```rust,ignore
+/// async fn chat(Json(req): Json<ChatRequest>) -> impl IntoResponse {
+///     let tokens = llm.stream(req).map(|r| r.map_err(|e| AppError::ExternalError(e.to_string())));
+///     sse(tokens, DEFAULT_HEARTBEAT)
+/// }
+/// ```
+pub fn sse<S, T>(
+    items: S,
+    heartbeat: Duration,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static>
+where
+    S: Stream<Item = AppResult<T>> + Send + 'static,
+    T: Serialize,
+{
+    let request_id = current_request_id();
+    let events = items.map(move |item| {
+        item.and_then(|data| {
+            let mut response = ApiResponse::ok(data);
+            response.request_id = response.request_id.or_else(|| request_id.clone());
+            SseEvent::envelope(&response)
+        })
+    });
+    sse_events(events, heartbeat)
+}
+
+/// Like [`sse`], for streams that build their own frames (event names, ids, raw data).
+pub fn sse_events<S>(
+    events: S,
+    heartbeat: Duration,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static>
+where
+    S: Stream<Item = AppResult<SseEvent>> + Send + 'static,
+{
+    let request_id = current_request_id();
+    let frames = events.scan(false, move |failed, item| {
+        if *failed {
+            return future::ready(None);
+        }
+        let frame = item.unwrap_or_else(|err| {
+            *failed = true;
+            SseEvent::error_with(&err, request_id.clone())
+        });
+        future::ready(Some(Ok(frame.into_event())))
+    });
+    Sse::new(frames).keep_alive(KeepAlive::new().interval(heartbeat))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    async fn body_of(response: impl IntoResponse) -> String {
+        let response = response.into_response();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn items_are_wrapped_in_the_envelope() {
+        let items = futures::stream::iter(vec![Ok::<_, AppError>("a"), Ok("b")]);
+        let body = body_of(sse(items, DEFAULT_HEARTBEAT)).await;
+        assert_eq!(
+            body,
+            "data: {\"code\":200000,\"message\":\"success\",\"data\":\"a\"}\n\n\
+             data: {\"code\":200000,\"message\":\"success\",\"data\":\"b\"}\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn error_ends_the_stream_with_an_error_frame() {
+        let items = futures::stream::iter(vec![
+            Ok(1),
+            Err(AppError::RateLimit("slow down".into())),
+            Ok(2),
+        ]);
+        let body = body_of(sse(items, DEFAULT_HEARTBEAT)).await;
+        let frames: Vec<&str> = body.split_terminator("\n\n").collect();
+        assert_eq!(frames.len(), 2);
+        let error = frames[1].strip_prefix("event: error\ndata: ").unwrap();
+        let json: serde_json::Value = serde_json::from_str(error).unwrap();
+        assert_eq!(
+            json["code"],
+            AppError::RateLimit(String::new()).business_code()
+        );
+        assert_eq!(json["message"], "Rate limit exceeded: slow down");
+    }
+
+    #[tokio::test]
+    async fn request_id_is_captured_when_the_response_is_built() {
+        let response = crate::response::error::scope_request_id("req-1".into(), async {
+            let items = futures::stream::iter(vec![Ok(1), Err(AppError::Forbidden)]);
+            sse(items, DEFAULT_HEARTBEAT)
+        })
+        .await;
+        let body = body_of(response).await;
+        assert_eq!(body.matches("\"request_id\":\"req-1\"").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn custom_frames_keep_their_fields() {
+        let events = futures::stream::iter(vec![
+            SseEvent::json(7).map(|e| e.event("delta").id("1")),
+            Ok(SseEvent::raw("[DONE]")
+                .event("done")
+                .retry(Duration::from_secs(3))),
+        ]);
+        let body = body_of(sse_events(events, DEFAULT_HEARTBEAT)).await;
+        assert!(body.contains("event: delta\n"));
+        assert!(body.contains("id: 1\n"));
+        assert!(body.contains("event: done\nretry: 3000\ndata: [DONE]\n"));
+    }
+
+    #[tokio::test]
+    async fn line_breaks_are_stripped_from_event_and_id() {
+        let frame = SseEvent::raw("x").event("del\nta\r").id("4\r\n2\0");
+        let events = futures::stream::iter(vec![Ok::<_, AppError>(frame)]);
+        let body = body_of(sse_events(events, DEFAULT_HEARTBEAT)).await;
+        assert!(body.contains("event: delta\n"), "{body:?}");
+        assert!(body.contains("id: 42\n"), "{body:?}");
+    }
+
+    #[tokio::test]
+    async fn heartbeat_is_sent_while_the_stream_is_idle() {
+        let items = futures::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(120)).await;
+            Ok::<_, AppError>("late")
+        });
+        let body = body_of(sse(items, Duration::from_millis(20))).await;
+        let (before, after) = body.split_once("data: ").unwrap();
+        assert!(before.matches(":\n\n").count() >= 2, "{before:?}");
+        assert!(after.contains("\"late\""));
+    }
+}
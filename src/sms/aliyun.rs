@@ -8,8 +8,8 @@
 
 use base64::{Engine, engine::general_purpose::STANDARD};
 use chrono::{SecondsFormat, Utc};
-use ring::hmac;
-use std::collections::HashMap;
+use ring::{digest, hmac};
+use std::collections::{BTreeMap, HashMap};
 
 /// The version of the SMS API. Currently a fixed value `2017-05-25`.
 const SMS_VERSION: &str = "2017-05-25";
@@ -23,10 +23,19 @@ const SIGNATURE_METHOD: &str = "HMAC-SHA1";
 /// The format of the response data. You can choose either `JSON` or `XML`. The default is `XML`.
 const FORMAT: &str = "json";
 
+/// The SMS API endpoint.
+const SMS_HOST: &str = "dysmsapi.aliyuncs.com";
+
+/// The V3 signature algorithm (`ACS3-HMAC-SHA256`).
+///
+/// link: https://help.aliyun.com/zh/sdk/product-overview/v3-request-structure-and-signature
+const ACS3_SIGNATURE_ALGORITHM: &str = "ACS3-HMAC-SHA256";
+
 /// aliyun sms
 pub struct Aliyun<'a> {
     access_key_id: &'a str,
     access_secret: &'a str,
+    acs3: bool,
 }
 
 impl<'a> Aliyun<'a> {
@@ -42,9 +51,23 @@ impl<'a> Aliyun<'a> {
         Self {
             access_key_id,
             access_secret,
+            acs3: false,
         }
     }
 
+    /// Sign requests with the V3 `ACS3-HMAC-SHA256` POST signature instead of the legacy
+    /// `HMAC-SHA1` GET signature.
+    ///
+    /// ```rust,no_run
+    /// use crate::sms::aliyun::Aliyun;
+    ///
+    /// let aliyun = Aliyun::new("xxxx", "xxxx").with_acs3_signature(true);
+    /// ```
+    pub fn with_acs3_signature(mut self, enabled: bool) -> Self {
+        self.acs3 = enabled;
+        self
+    }
+
     /// send_sms
     ///
     /// ```rust,no_run
@@ -80,6 +103,11 @@ impl<'a> Aliyun<'a> {
         params.insert("TemplateCode", template_code);
         params.insert("RegionId", "cn-hangzhou");
         params.insert("TemplateParam", template_param);
+
+        if self.acs3 {
+            return self.send_sms_acs3("SendSms", &params).await;
+        }
+
         params.insert("Action", "SendSms");
         params.insert("Version", SMS_VERSION);
 
@@ -94,8 +122,8 @@ impl<'a> Aliyun<'a> {
         );
 
         let url = format!(
-            "https://dysmsapi.aliyuncs.com/?{}&Signature={}",
-            canonicalize_query_string, signature
+            "https://{}/?{}&Signature={}",
+            SMS_HOST, canonicalize_query_string, signature
         );
 
         let resp = reqwest::get(url)
@@ -150,6 +178,128 @@ impl<'a> Aliyun<'a> {
 
         STANDARD.encode(sign.as_ref())
     }
+
+    /// Send a request signed with `ACS3-HMAC-SHA256`.
+    ///
+    /// Business parameters go into the query string; `Action`/`Version` are sent as
+    /// `x-acs-*` headers and the body is empty.
+    async fn send_sms_acs3(
+        &self,
+        action: &str,
+        params: &HashMap<&str, &'a str>,
+    ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+        let query: BTreeMap<&str, &str> = params.iter().map(|(&k, &v)| (k, v)).collect();
+
+        let now = Utc::now();
+        let date = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let nonce = uuid::Uuid::new_v4().to_string();
+
+        let headers = Self::acs3_headers(action, &date, &nonce, b"");
+        let authorization = self.acs3_authorization("POST", "/", &query, &headers);
+
+        let mut request = reqwest::Client::new().post(format!(
+            "https://{}/?{}",
+            SMS_HOST,
+            Self::acs3_canonical_query(&query)
+        ));
+        for (name, value) in &headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        let resp = request
+            .header("Authorization", authorization)
+            .send()
+            .await?
+            .json::<HashMap<String, String>>()
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// The headers that take part in the V3 signature (lowercase names, sorted).
+    fn acs3_headers(
+        action: &str,
+        date: &str,
+        nonce: &str,
+        payload: &[u8],
+    ) -> BTreeMap<String, String> {
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), SMS_HOST.to_string());
+        headers.insert("x-acs-action".to_string(), action.to_string());
+        headers.insert("x-acs-content-sha256".to_string(), sha256_hex(payload));
+        headers.insert("x-acs-date".to_string(), date.to_string());
+        headers.insert("x-acs-signature-nonce".to_string(), nonce.to_string());
+        headers.insert("x-acs-version".to_string(), SMS_VERSION.to_string());
+        headers
+    }
+
+    /// Build the canonicalized query string for the V3 signature
+    /// (RFC 3986 encoded keys and values, sorted by key).
+    fn acs3_canonical_query(query: &BTreeMap<&str, &str>) -> String {
+        query
+            .iter()
+            .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+            .collect::<Vec<String>>()
+            .join("&")
+    }
+
+    /// Build the canonical request for the V3 signature.
+    fn acs3_canonical_request(
+        method: &str,
+        uri: &str,
+        query: &BTreeMap<&str, &str>,
+        headers: &BTreeMap<String, String>,
+    ) -> String {
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let signed_headers = headers.keys().cloned().collect::<Vec<String>>().join(";");
+        let hashed_payload = headers
+            .get("x-acs-content-sha256")
+            .cloned()
+            .unwrap_or_else(|| sha256_hex(b""));
+
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            uri,
+            Self::acs3_canonical_query(query),
+            canonical_headers,
+            signed_headers,
+            hashed_payload
+        )
+    }
+
+    /// Build the `Authorization` header value for the V3 signature.
+    fn acs3_authorization(
+        &self,
+        method: &str,
+        uri: &str,
+        query: &BTreeMap<&str, &str>,
+        headers: &BTreeMap<String, String>,
+    ) -> String {
+        let canonical_request = Self::acs3_canonical_request(method, uri, query, headers);
+        let string_to_sign = format!(
+            "{}\n{}",
+            ACS3_SIGNATURE_ALGORITHM,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let key = hmac::Key::new(hmac::HMAC_SHA256, self.access_secret.as_bytes());
+        let signature = hex::encode(hmac::sign(&key, string_to_sign.as_bytes()).as_ref());
+        let signed_headers = headers.keys().cloned().collect::<Vec<String>>().join(";");
+
+        format!(
+            "{} Credential={},SignedHeaders={},Signature={}",
+            ACS3_SIGNATURE_ALGORITHM, self.access_key_id, signed_headers, signature
+        )
+    }
+}
+
+/// Lowercase hex SHA-256 digest.
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data).as_ref())
 }
 
 #[cfg(test)]
@@ -158,6 +308,76 @@ mod tests {
 
     use super::*;
 
+    /// The worked example from Aliyun's V3 signature guide
+    /// (https://help.aliyun.com/zh/sdk/product-overview/v3-request-structure-and-signature):
+    /// `RunInstances` against ECS with the guide's fixed date, nonce and credentials.
+    fn acs3_doc_fixture() -> (
+        BTreeMap<&'static str, &'static str>,
+        BTreeMap<String, String>,
+    ) {
+        let mut query = BTreeMap::new();
+        query.insert(
+            "ImageId",
+            "win2019_1809_x64_dtc_zh-cn_40G_alibase_20230811.vhd",
+        );
+        query.insert("RegionId", "cn-beijing");
+
+        let headers = [
+            ("host", "ecs.cn-beijing.aliyuncs.com"),
+            ("x-acs-action", "RunInstances"),
+            ("x-acs-content-sha256", sha256_hex(b"").as_str()),
+            ("x-acs-date", "2023-10-26T10:22:32Z"),
+            ("x-acs-signature-nonce", "3156853299f313e23d1673dc12e1703d"),
+            ("x-acs-version", "2014-05-26"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        (query, headers)
+    }
+
+    #[test]
+    fn test_acs3_canonical_request() {
+        let (query, headers) = acs3_doc_fixture();
+        let canonical = Aliyun::acs3_canonical_request("POST", "/", &query, &headers);
+
+        let expected = "POST\n/\n\
+ImageId=win2019_1809_x64_dtc_zh-cn_40G_alibase_20230811.vhd&RegionId=cn-beijing\n\
+host:ecs.cn-beijing.aliyuncs.com\n\
+x-acs-action:RunInstances\n\
+x-acs-content-sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n\
+x-acs-date:2023-10-26T10:22:32Z\n\
+x-acs-signature-nonce:3156853299f313e23d1673dc12e1703d\n\
+x-acs-version:2014-05-26\n\n\
+host;x-acs-action;x-acs-content-sha256;x-acs-date;x-acs-signature-nonce;x-acs-version\n\
+e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(canonical, expected);
+    }
+
+    #[test]
+    fn test_acs3_authorization_known_vector() {
+        let (query, headers) = acs3_doc_fixture();
+        let aliyun =
+            Aliyun::new("YourAccessKeyId", "YourAccessKeySecret").with_acs3_signature(true);
+
+        let authorization = aliyun.acs3_authorization("POST", "/", &query, &headers);
+        assert_eq!(
+            authorization,
+            "ACS3-HMAC-SHA256 Credential=YourAccessKeyId,\
+SignedHeaders=host;x-acs-action;x-acs-content-sha256;x-acs-date;x-acs-signature-nonce;x-acs-version,\
+Signature=e9eede46fdb0315f85b035fd95099e805ef0e2c6ff82efd02357bb6b9b02742a"
+        );
+    }
+
+    #[test]
+    fn test_acs3_headers_target_sms() {
+        let headers = Aliyun::acs3_headers("SendSms", "2024-01-01T00:00:00Z", "nonce", b"");
+        assert_eq!(headers["host"], SMS_HOST);
+        assert_eq!(headers["x-acs-action"], "SendSms");
+        assert_eq!(headers["x-acs-version"], SMS_VERSION);
+        assert_eq!(headers["x-acs-content-sha256"], sha256_hex(b""));
+    }
+
     #[tokio::test]
     async fn test_send_sms() {
         let aliyun = Aliyun::new("LTAI5t9WtXXXXXXX", "63HhssAIfRNPXXXXXXXX");
@@ -1,9 +1,20 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use futures::StreamExt;
+use rand::RngExt;
+use serde::Serialize;
+
+use crate::middlewares::token_store::{RedisTokenStore, TokenStore};
 use crate::rediscache::RedisPool;
 use crate::response::error::{AppError, AppResult};
 use crate::sms::aliyun::Aliyun;
-use crate::sms::tencent::{Region, Tencent};
+use crate::sms::tencent::{Region, SendStatus, Tencent};
+
+/// 批量发送时（Aliyun 单号码接口）的最大并发数
+pub const SMS_BULK_CONCURRENCY: usize = 8;
+
+/// 腾讯云单次 SendSms 最多支持的手机号数量
+pub const TENCENT_MAX_PHONES_PER_REQUEST: usize = 200;
 
 /// 发送验证码所需的短信模板变量。
 ///
@@ -25,6 +36,53 @@ impl CaptchaTemplate {
     }
 }
 
+/// 通用短信模板参数，用于订单通知、告警等非验证码短信。
+///
+/// - `template`: 覆盖配置中的模板（Aliyun `TemplateCode` / Tencent `TemplateId`），为 `None` 时使用配置里的模板
+/// - `params`: 模板变量，按模板参数顺序保存；Aliyun 以变量名作为 JSON key，Tencent 只按顺序取值
+#[derive(Debug, Clone, Default)]
+pub struct SmsTemplateParams {
+    pub template: Option<String>,
+    pub params: Vec<(String, String)>,
+}
+
+impl SmsTemplateParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 指定本次发送使用的模板
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// 追加一个模板变量
+    pub fn param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn to_aliyun_template_param_json(&self) -> String {
+        let map = self
+            .params
+            .iter()
+            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+            .collect::<serde_json::Map<String, serde_json::Value>>();
+        serde_json::Value::Object(map).to_string()
+    }
+
+    pub fn to_tencent_template_param_vec(&self) -> Vec<String> {
+        self.params.iter().map(|(_, v)| v.clone()).collect()
+    }
+}
+
+impl From<&CaptchaTemplate> for SmsTemplateParams {
+    fn from(template: &CaptchaTemplate) -> Self {
+        SmsTemplateParams::new().param("code", template.code.clone())
+    }
+}
+
 /// 可扩展的短信提供商配置。
 ///
 /// - `Aliyun`: 走阿里云短信
@@ -35,6 +93,16 @@ pub enum SmsProviderConfig {
     Tencent(TencentSmsConfig),
 }
 
+impl SmsProviderConfig {
+    /// 服务商名称（与 `SmsSendResult.provider` 一致）
+    pub fn name(&self) -> &'static str {
+        match self {
+            SmsProviderConfig::Aliyun(_) => "aliyun",
+            SmsProviderConfig::Tencent(_) => "tencent",
+        }
+    }
+}
+
 /// 阿里云短信配置（SendSms）。
 #[derive(Debug, Clone)]
 pub struct AliyunSmsConfig {
@@ -55,23 +123,141 @@ pub struct TencentSmsConfig {
     pub template_id: String,
 }
 
+/// 验证码生成配置。
+///
+/// - `length`: 验证码位数（默认 6，最少 1 位）
+/// - `allow_leading_zero`: 是否允许首位为 0（默认 false，即 6 位时范围为 100000..=999999）
+///
+/// 逐位生成，任意长度都是均匀分布，不受 `u32` 范围限制。
+#[derive(Debug, Clone)]
+pub struct CaptchaCodeConfig {
+    pub length: usize,
+    pub allow_leading_zero: bool,
+}
+
+impl Default for CaptchaCodeConfig {
+    fn default() -> Self {
+        Self {
+            length: 6,
+            allow_leading_zero: false,
+        }
+    }
+}
+
+impl CaptchaCodeConfig {
+    /// 生成一个数字验证码
+    pub fn generate(&self) -> String {
+        let mut rng = rand::rng();
+        (0..self.length.max(1))
+            .map(|i| {
+                let digit = if i == 0 && !self.allow_leading_zero {
+                    rng.random_range(1..10u8)
+                } else {
+                    rng.random_range(0..10u8)
+                };
+                char::from(b'0' + digit)
+            })
+            .collect()
+    }
+}
+
+/// 验证码短信防刷配置（按手机号计算）。
+///
+/// - `cooldown`: 两次发送的最小间隔（默认 60 秒），为 0 时不限制
+/// - `max_per_window`: `window` 内最多发送次数（默认 10），为 0 时不限制
+/// - `window`: 发送次数的统计窗口（默认 24 小时），从窗口内第一次发送开始计时
+///
+/// 冷却与计数使用独立的 key，验证码校验成功后依然有效。
+#[derive(Debug, Clone)]
+pub struct SmsLimitConfig {
+    pub cooldown: Duration,
+    pub max_per_window: u32,
+    pub window: Duration,
+}
+
+impl Default for SmsLimitConfig {
+    fn default() -> Self {
+        Self {
+            cooldown: Duration::from_secs(60),
+            max_per_window: 10,
+            window: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
 /// SmsService 运行配置。
 ///
 /// `provider` 决定使用哪个短信服务商；
-/// `debug` 为 true 时不发短信，只把验证码写入 Redis（便于联调/测试）。
+/// `debug` 为 true 时不发短信，只把验证码写入 Redis（便于联调/测试）；
+/// `code` 控制验证码位数/是否允许前导 0；
+/// `limit` 控制同一手机号的发送冷却与次数上限（debug 模式同样生效）。
 #[derive(Debug, Clone)]
 pub struct SmsConfig {
     pub debug: bool,
     pub provider: SmsProviderConfig,
+    pub code: CaptchaCodeConfig,
+    pub limit: SmsLimitConfig,
 }
 
 /// 发送结果（便于日志/调用方排查）。
-#[derive(Debug, Clone)]
+///
+/// 单条发送失败时直接返回 `Err`；批量发送时失败的号码以 `success == false` 体现。
+///
+/// `serial_no` / `fee` / `iso_code` 仅腾讯云返回（用于计费对账、回执匹配），阿里云为 `None`。
+#[derive(Debug, Clone, Serialize)]
 pub struct SmsSendResult {
     pub provider: &'static str,
+    pub success: bool,
     pub request_id: Option<String>,
     pub raw_code: Option<String>,
     pub raw_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_no: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iso_code: Option<String>,
+}
+
+impl SmsSendResult {
+    fn debug() -> Self {
+        SmsSendResult {
+            provider: "debug",
+            success: true,
+            request_id: None,
+            raw_code: Some("OK".to_string()),
+            raw_message: Some("debug mode".to_string()),
+            serial_no: None,
+            fee: None,
+            iso_code: None,
+        }
+    }
+
+    fn failed(provider: &'static str, message: impl Into<String>) -> Self {
+        SmsSendResult {
+            provider,
+            success: false,
+            request_id: None,
+            raw_code: None,
+            raw_message: Some(message.into()),
+            serial_no: None,
+            fee: None,
+            iso_code: None,
+        }
+    }
+
+    fn from_tencent_status(request_id: &str, status: SendStatus) -> Self {
+        SmsSendResult {
+            provider: "tencent",
+            success: status.code.eq_ignore_ascii_case("Ok"),
+            request_id: Some(request_id.to_string()),
+            raw_code: Some(status.code),
+            raw_message: Some(status.message),
+            serial_no: Some(status.serial_no).filter(|s| !s.is_empty()),
+            fee: Some(status.fee),
+            iso_code: Some(status.iso_code).filter(|s| !s.is_empty()),
+        }
+    }
 }
 
 /// 验证码短信服务
@@ -85,9 +271,10 @@ impl SmsService {
     ///
     /// 行为：
     /// 1. 校验手机号
-    /// 2. 生成 6 位验证码
-    /// 3. debug 模式：只存 Redis，不发短信
-    /// 4. 正常模式：发短信成功后存 Redis；失败则返回错误
+    /// 2. 按 `config.limit` 检查发送冷却与次数上限，超出返回 `AppError::RateLimit`
+    /// 3. 按 `config.code` 生成验证码（默认 6 位数字）
+    /// 4. debug 模式：只存 Redis，不发短信
+    /// 5. 正常模式：发短信成功后存 Redis；失败则返回错误（并释放冷却，允许立即重试）
     pub async fn send_captcha(
         config: &Arc<SmsConfig>,
         redis_pool: &Arc<RedisPool>,
@@ -125,10 +312,43 @@ impl SmsService {
             return Err(AppError::ClientError("手机号码格式不正确".to_string()));
         }
 
-        let code_num: u32 = rand::random::<u32>() % 900000 + 100000;
+        let limit_store = RedisTokenStore::new(redis_pool.clone(), redis_key_prefix);
+        Self::acquire_send_slot(&limit_store, &config.limit, mobile).await?;
+
         let template = CaptchaTemplate {
-            code: code_num.to_string(),
+            code: config.code.generate(),
+        };
+
+        // 发送（debug 模式下不发短信）
+        let send_result = match Self::send_template(
+            config,
+            mobile,
+            mobile_regex,
+            SmsTemplateParams::from(&template),
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                // 没发出去就不占用冷却
+                if let Err(release) = limit_store.delete(&Self::cooldown_key(mobile)).await {
+                    tracing::warn!(
+                        "「send_captcha」 failed to release cooldown, mobile: {}, error: {}",
+                        mobile,
+                        release
+                    );
+                }
+                return Err(e);
+            }
         };
+        // 短信已发出：计数失败只记日志，验证码仍要入 Redis
+        if let Err(e) = Self::record_send(&limit_store, &config.limit, mobile).await {
+            tracing::warn!(
+                "「send_captcha」 failed to record send count, mobile: {}, error: {}",
+                mobile,
+                e
+            );
+        }
 
         tracing::info!(
             "「send_captcha」 mobile: {}, code: {}",
@@ -136,58 +356,297 @@ impl SmsService {
             template.code
         );
 
-        // debug 模式：不发短信，只入库
-        if config.debug {
-            Self::store_captcha_code_with_options(
-                redis_pool,
-                mobile,
-                code_num,
-                expire_seconds,
-                redis_key_prefix,
-            )
-            .await?;
-
-            tracing::warn!("「send_captcha」 Debug mode: SMS not sent, code stored in Redis");
-
-            return Ok(SmsSendResult {
-                provider: "debug",
-                request_id: None,
-                raw_code: Some("OK".to_string()),
-                raw_message: Some("debug mode".to_string()),
-            });
-        }
-
-        let send_result = Self::send_via_provider(config, mobile, &template).await?;
-
         // 只有发送成功才入 Redis（避免用户收不到但能用验证码登录）
         Self::store_captcha_code_with_options(
             redis_pool,
             mobile,
-            code_num,
+            &template.code,
             expire_seconds,
             redis_key_prefix,
         )
         .await?;
 
-        tracing::info!("「send_captcha」 SMS sent and code stored successfully");
+        if config.debug {
+            tracing::warn!("「send_captcha」 Debug mode: SMS not sent, code stored in Redis");
+        } else {
+            tracing::info!("「send_captcha」 SMS sent and code stored successfully");
+        }
         Ok(send_result)
     }
 
+    /// 验证码 key：`{prefix}{mobile}`，与限流 key 共用同一前缀
+    fn captcha_code_key(key_prefix: &str, mobile: &str) -> String {
+        format!("{}{}", key_prefix, mobile)
+    }
+
+    fn cooldown_key(mobile: &str) -> String {
+        format!("cooldown:{}", mobile)
+    }
+
+    fn send_count_key(mobile: &str) -> String {
+        format!("send_count:{}", mobile)
+    }
+
+    /// 检查次数上限并占用冷却。
+    ///
+    /// 冷却通过 `set_raw_if_absent` 原子占用，同一手机号并发请求只有一个能通过；
+    /// 计数在发送成功后由 [`Self::record_send`] 原子累加。
+    async fn acquire_send_slot(
+        store: &dyn TokenStore,
+        limit: &SmsLimitConfig,
+        mobile: &str,
+    ) -> AppResult<()> {
+        let store_err = |e: crate::middlewares::token_store::TokenStoreError| {
+            AppError::RedisError(e.to_string())
+        };
+
+        if limit.max_per_window > 0 {
+            let sent = store
+                .get_raw(&Self::send_count_key(mobile))
+                .await
+                .map_err(store_err)?
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(0);
+            if sent >= limit.max_per_window {
+                tracing::warn!(
+                    "「send_captcha」 send cap reached, mobile: {}, sent: {}",
+                    mobile,
+                    sent
+                );
+                return Err(AppError::RateLimit(
+                    "验证码发送次数已达上限，请稍后再试".to_string(),
+                ));
+            }
+        }
+
+        let cooldown_secs = Self::ceil_secs(limit.cooldown);
+        if cooldown_secs > 0 {
+            let key = Self::cooldown_key(mobile);
+            let acquired = store
+                .set_raw_if_absent(&key, "1", Some(cooldown_secs))
+                .await
+                .map_err(store_err)?;
+            if !acquired {
+                let remaining = store
+                    .ttl(&key)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map_or(cooldown_secs, |ttl| Self::ceil_secs(ttl).max(1));
+                return Err(AppError::RateLimit(format!(
+                    "验证码发送过于频繁，请 {} 秒后再试",
+                    remaining
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 发送成功后原子累加计数（`incr_with_ttl`），窗口从第一次发送开始计时。
+    async fn record_send(
+        store: &dyn TokenStore,
+        limit: &SmsLimitConfig,
+        mobile: &str,
+    ) -> AppResult<()> {
+        if limit.max_per_window == 0 {
+            return Ok(());
+        }
+        let window = Duration::from_secs(Self::ceil_secs(limit.window).max(1));
+        store
+            .incr_with_ttl(&Self::send_count_key(mobile), window)
+            .await
+            .map(|_| ())
+            .map_err(|e| AppError::RedisError(e.to_string()))
+    }
+
+    fn ceil_secs(duration: Duration) -> u64 {
+        duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+    }
+
+    /// Send an arbitrary template SMS (order notifications, alerts, ...).
+    ///
+    /// 与验证码无关，不会读写 Redis。
+    ///
+    /// - `mobile_regex`: 由调用方注入的手机号正则
+    /// - `params`: 模板及模板变量，见 [`SmsTemplateParams`]
+    ///
+    /// debug 模式下只校验手机号，不真正发送短信。
+    pub async fn send_template(
+        config: &Arc<SmsConfig>,
+        mobile: &str,
+        mobile_regex: &regex::Regex,
+        params: SmsTemplateParams,
+    ) -> AppResult<SmsSendResult> {
+        if !mobile_regex.is_match(mobile) {
+            return Err(AppError::ClientError("手机号码格式不正确".to_string()));
+        }
+
+        if config.debug {
+            tracing::warn!(
+                "「send_template」 Debug mode: SMS not sent, mobile: {}, params: {:?}",
+                mobile,
+                params.params
+            );
+            return Ok(SmsSendResult::debug());
+        }
+
+        Self::send_via_provider(config, mobile, &params).await
+    }
+
+    /// Send the same template SMS to multiple recipients.
+    ///
+    /// 按手机号逐个返回发送结果，单个号码失败（格式错误、被运营商拒绝等）不会导致整批失败，
+    /// 调用方通过 `SmsSendResult.success` 判断每个号码的结果。
+    ///
+    /// - Tencent: 整批号码一次请求提交（每次最多 `TENCENT_MAX_PHONES_PER_REQUEST` 个），按 `PhoneNumber` 对应回结果
+    /// - Aliyun: 单号码接口，最多 `SMS_BULK_CONCURRENCY` 个并发逐个发送
+    ///
+    /// 返回结果的顺序不保证与 `mobiles` 一致。
+    pub async fn send_template_bulk(
+        config: &Arc<SmsConfig>,
+        mobiles: &[&str],
+        mobile_regex: &regex::Regex,
+        params: SmsTemplateParams,
+    ) -> AppResult<Vec<(String, SmsSendResult)>> {
+        let provider = config.provider.name();
+        let (valid, mut results) = Self::split_valid_mobiles(mobiles, mobile_regex, provider);
+
+        if config.debug {
+            tracing::warn!(
+                "「send_template_bulk」 Debug mode: SMS not sent, mobiles: {:?}, params: {:?}",
+                valid,
+                params.params
+            );
+            results.extend(
+                valid
+                    .into_iter()
+                    .map(|m| (m.to_string(), SmsSendResult::debug())),
+            );
+            return Ok(results);
+        }
+
+        match &config.provider {
+            SmsProviderConfig::Aliyun(_) => {
+                let params = &params;
+                let sent = futures::stream::iter(valid)
+                    .map(|mobile| async move {
+                        let result = Self::send_via_provider(config, mobile, params)
+                            .await
+                            .unwrap_or_else(|e| SmsSendResult::failed(provider, e.message()));
+                        (mobile.to_string(), result)
+                    })
+                    .buffer_unordered(SMS_BULK_CONCURRENCY)
+                    .collect::<Vec<_>>()
+                    .await;
+                results.extend(sent);
+            }
+            SmsProviderConfig::Tencent(tencent_cfg) => {
+                for (chunk, phones) in Self::tencent_batches(&valid) {
+                    let outcome = Self::send_tencent(tencent_cfg, &phones, &params)
+                        .await
+                        .map_err(|e| e.message());
+                    results.extend(Self::tencent_chunk_results(chunk, &phones, outcome));
+                }
+            }
+        }
+
+        let failed = results.iter().filter(|(_, r)| !r.success).count();
+        tracing::info!(
+            "「send_template_bulk」 provider: {}, total: {}, failed: {}",
+            provider,
+            results.len(),
+            failed
+        );
+        Ok(results)
+    }
+
+    /// 按 `mobile_regex` 拆分号码：返回合法号码，以及不合法号码的失败结果。
+    fn split_valid_mobiles<'a>(
+        mobiles: &[&'a str],
+        mobile_regex: &regex::Regex,
+        provider: &'static str,
+    ) -> (Vec<&'a str>, Vec<(String, SmsSendResult)>) {
+        let mut valid = Vec::with_capacity(mobiles.len());
+        let mut invalid = Vec::new();
+        for mobile in mobiles {
+            if mobile_regex.is_match(mobile) {
+                valid.push(*mobile);
+            } else {
+                invalid.push((
+                    mobile.to_string(),
+                    SmsSendResult::failed(provider, "手机号码格式不正确"),
+                ));
+            }
+        }
+        (valid, invalid)
+    }
+
+    /// Tencent 单次请求的号码批次（每批最多 `TENCENT_MAX_PHONES_PER_REQUEST` 个）及其 E.164 格式。
+    fn tencent_batches<'a>(
+        valid: &'a [&'a str],
+    ) -> impl Iterator<Item = (&'a [&'a str], Vec<String>)> + 'a {
+        valid.chunks(TENCENT_MAX_PHONES_PER_REQUEST).map(|chunk| {
+            let phones = chunk.iter().map(|m| Self::tencent_phone(m)).collect();
+            (chunk, phones)
+        })
+    }
+
+    /// 把一批 Tencent 请求的结果按 `PhoneNumber` 对应回原始号码。
+    ///
+    /// `outcome` 为 `(request_id, statuses)` 或请求失败的错误信息；请求失败时整批记为失败，
+    /// 响应中缺少某个号码的状态时该号码记为失败。
+    fn tencent_chunk_results(
+        chunk: &[&str],
+        phones: &[String],
+        outcome: Result<(String, Vec<SendStatus>), String>,
+    ) -> Vec<(String, SmsSendResult)> {
+        match outcome {
+            Ok((request_id, statuses)) => chunk
+                .iter()
+                .zip(phones)
+                .map(|(mobile, phone)| {
+                    let result = statuses
+                        .iter()
+                        .find(|s| &s.phone_number == phone)
+                        .cloned()
+                        .map(|s| SmsSendResult::from_tencent_status(&request_id, s))
+                        .unwrap_or_else(|| {
+                            SmsSendResult::failed("tencent", "no send status returned")
+                        });
+                    (mobile.to_string(), result)
+                })
+                .collect(),
+            Err(message) => chunk
+                .iter()
+                .map(|m| {
+                    (
+                        m.to_string(),
+                        SmsSendResult::failed("tencent", message.clone()),
+                    )
+                })
+                .collect(),
+        }
+    }
+
     async fn send_via_provider(
         config: &Arc<SmsConfig>,
         mobile: &str,
-        template: &CaptchaTemplate,
+        params: &SmsTemplateParams,
     ) -> AppResult<SmsSendResult> {
         match &config.provider {
             SmsProviderConfig::Aliyun(aliyun_cfg) => {
                 let aliyun = Aliyun::new(&aliyun_cfg.access_key_id, &aliyun_cfg.access_key_secret);
+                let template_code = params
+                    .template
+                    .as_deref()
+                    .unwrap_or(&aliyun_cfg.template_code);
 
                 let resp: HashMap<String, String> = aliyun
                     .send_sms(
                         mobile,
                         &aliyun_cfg.sign_name,
-                        &aliyun_cfg.template_code,
-                        &template.to_aliyun_template_param_json(),
+                        template_code,
+                        &params.to_aliyun_template_param_json(),
                     )
                     .await
                     .map_err(|e| AppError::ClientError(format!("短信发送失败(Aliyun): {}", e)))?;
@@ -196,9 +655,13 @@ impl SmsService {
                 match resp.get("Code").map(|s| s.as_str()) {
                     Some("OK") => Ok(SmsSendResult {
                         provider: "aliyun",
+                        success: true,
                         request_id: resp.get("RequestId").cloned(),
                         raw_code: resp.get("Code").cloned(),
                         raw_message: resp.get("Message").cloned(),
+                        serial_no: None,
+                        fee: None,
+                        iso_code: None,
                     }),
                     _ => Err(AppError::ClientError(format!(
                         "发送短信失败(Aliyun): {}",
@@ -209,67 +672,72 @@ impl SmsService {
                 }
             }
             SmsProviderConfig::Tencent(tencent_cfg) => {
-                let tencent = Tencent::new(
-                    tencent_cfg.secret_id.clone(),
-                    tencent_cfg.secret_key.clone(),
-                    tencent_cfg.sms_app_id.clone(),
-                );
-
-                // Tencent phone number 需要带国家码（例如 +86xxxxxxxxxxx）
-                // 这里保持最小侵入：如果调用方没带 +，默认按 +86 拼接。
-                let phone = if mobile.starts_with('+') {
-                    mobile.to_string()
-                } else {
-                    format!("+86{}", mobile)
-                };
-
-                let params = template
-                    .to_tencent_template_param_vec()
-                    .into_iter()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>();
-
-                let params_ref = params.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
-
-                let resp = tencent
-                    .send_sms(
-                        tencent_cfg.region.clone(),
-                        &tencent_cfg.sign_name,
-                        vec![phone.as_str()],
-                        tencent_cfg.template_id.clone(),
-                        params_ref,
-                    )
-                    .await
-                    .map_err(|e| AppError::ClientError(format!("短信发送失败(Tencent): {}", e)))?;
+                let (request_id, statuses) =
+                    Self::send_tencent(tencent_cfg, &[Self::tencent_phone(mobile)], params).await?;
 
                 // 腾讯云返回结构：
                 // resp.response.send_status_set[0].code == "Ok" 表示成功
-                let status = resp
-                    .response
-                    .send_status_set
-                    .get(0)
-                    .cloned()
-                    .ok_or_else(|| {
-                        AppError::ClientError("发送短信失败(Tencent): empty response".to_string())
-                    })?;
-
-                if status.code.eq_ignore_ascii_case("Ok") {
-                    Ok(SmsSendResult {
-                        provider: "tencent",
-                        request_id: Some(resp.response.request_id),
-                        raw_code: Some(status.code),
-                        raw_message: Some(status.message),
-                    })
+                let status = statuses.into_iter().next().ok_or_else(|| {
+                    AppError::ClientError("发送短信失败(Tencent): empty response".to_string())
+                })?;
+
+                let result = SmsSendResult::from_tencent_status(&request_id, status);
+                if result.success {
+                    Ok(result)
                 } else {
                     Err(AppError::ClientError(format!(
                         "发送短信失败(Tencent): {}",
-                        status.message
+                        result.raw_message.unwrap_or_default()
                     )))
                 }
             }
         }
     }
 
+    /// Tencent phone number 需要带国家码（例如 +86xxxxxxxxxxx）
+    /// 这里保持最小侵入：如果调用方没带 +，默认按 +86 拼接。
+    fn tencent_phone(mobile: &str) -> String {
+        if mobile.starts_with('+') {
+            mobile.to_string()
+        } else {
+            format!("+86{}", mobile)
+        }
+    }
+
+    /// 调用腾讯云 SendSms，返回 `(RequestId, SendStatusSet)`
+    async fn send_tencent(
+        tencent_cfg: &TencentSmsConfig,
+        phones: &[String],
+        params: &SmsTemplateParams,
+    ) -> AppResult<(String, Vec<SendStatus>)> {
+        let tencent = Tencent::new(
+            tencent_cfg.secret_id.clone(),
+            tencent_cfg.secret_key.clone(),
+            tencent_cfg.sms_app_id.clone(),
+        );
+
+        let template_id = params
+            .template
+            .clone()
+            .unwrap_or_else(|| tencent_cfg.template_id.clone());
+        let params = params.to_tencent_template_param_vec();
+        let params_ref = params.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+        let phones_ref = phones.iter().map(|s| s.as_str()).collect::<Vec<&str>>();
+
+        let resp = tencent
+            .send_sms(
+                tencent_cfg.region.clone(),
+                &tencent_cfg.sign_name,
+                phones_ref,
+                template_id,
+                params_ref,
+            )
+            .await
+            .map_err(|e| AppError::ClientError(format!("短信发送失败(Tencent): {}", e)))?;
+
+        Ok((resp.response.request_id, resp.response.send_status_set))
+    }
+
     /// Validate authentication captcha
     pub async fn valid_auth_captcha(
         redis_pool: &Arc<RedisPool>,
@@ -308,10 +776,12 @@ impl SmsService {
     }
 
     /// Store captcha code in Redis (default 5 minutes)
+    ///
+    /// `code` 可以是数字或字符串；带前导 0 的验证码请传字符串。
     pub async fn store_captcha_code(
         redis_pool: &Arc<RedisPool>,
         mobile: &str,
-        code: u32,
+        code: impl ToString,
         redis_key_prefix: &str,
     ) -> AppResult<()> {
         Self::store_captcha_code_with_options(redis_pool, mobile, code, 60 * 5, redis_key_prefix)
@@ -322,11 +792,11 @@ impl SmsService {
     pub async fn store_captcha_code_with_options(
         redis_pool: &Arc<RedisPool>,
         mobile: &str,
-        code: u32,
+        code: impl ToString,
         expire_seconds: u64,
         key_prefix: &str,
     ) -> AppResult<()> {
-        let key = format!("{}{}", key_prefix, mobile);
+        let key = Self::captcha_code_key(key_prefix, mobile);
         let value = code.to_string();
 
         redis_pool
@@ -348,7 +818,7 @@ impl SmsService {
         mobile: &str,
         redis_key_prefix: &str,
     ) -> AppResult<Option<String>> {
-        let key = format!("{}{}", redis_key_prefix, mobile);
+        let key = Self::captcha_code_key(redis_key_prefix, mobile);
 
         match redis_pool.get(&key).await {
             Ok(Some(value)) => Ok(Some(value)),
@@ -363,7 +833,7 @@ impl SmsService {
         mobile: &str,
         redis_key_prefix: &str,
     ) -> AppResult<()> {
-        let key = format!("{}{}", redis_key_prefix, mobile);
+        let key = Self::captcha_code_key(redis_key_prefix, mobile);
 
         redis_pool
             .del(&key)
@@ -374,3 +844,344 @@ impl SmsService {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middlewares::token_store::TokenStoreError;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// TokenStore with a manual clock, so tests can step past cooldowns instantly.
+    #[derive(Default)]
+    struct ClockStore {
+        now: Mutex<u64>,
+        entries: Mutex<HashMap<String, (String, Option<u64>)>>,
+    }
+
+    impl ClockStore {
+        fn advance(&self, secs: u64) {
+            *self.now.lock().unwrap() += secs;
+        }
+
+        fn live(&self, key: &str) -> Option<(String, Option<u64>)> {
+            let now = *self.now.lock().unwrap();
+            self.entries
+                .lock()
+                .unwrap()
+                .get(key)
+                .filter(|(_, expires_at)| expires_at.is_none_or(|at| at > now))
+                .cloned()
+        }
+    }
+
+    #[async_trait]
+    impl TokenStore for ClockStore {
+        async fn get_raw(&self, key: &str) -> Result<Option<String>, TokenStoreError> {
+            Ok(self.live(key).map(|(value, _)| value))
+        }
+
+        async fn set_raw(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_secs: Option<u64>,
+        ) -> Result<(), TokenStoreError> {
+            let now = *self.now.lock().unwrap();
+            self.entries.lock().unwrap().insert(
+                key.to_string(),
+                (value.to_string(), ttl_secs.map(|ttl| now + ttl)),
+            );
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> Result<bool, TokenStoreError> {
+            Ok(self.entries.lock().unwrap().remove(key).is_some())
+        }
+
+        async fn set_raw_if_absent(
+            &self,
+            key: &str,
+            value: &str,
+            ttl_secs: Option<u64>,
+        ) -> Result<bool, TokenStoreError> {
+            if self.live(key).is_some() {
+                return Ok(false);
+            }
+            self.set_raw(key, value, ttl_secs).await?;
+            Ok(true)
+        }
+
+        async fn ttl(&self, key: &str) -> Result<Option<Duration>, TokenStoreError> {
+            let now = *self.now.lock().unwrap();
+            Ok(self
+                .live(key)
+                .and_then(|(_, expires_at)| expires_at)
+                .map(|at| Duration::from_secs(at - now)))
+        }
+
+        async fn incr_with_ttl(
+            &self,
+            key: &str,
+            window: Duration,
+        ) -> Result<(u64, Duration), TokenStoreError> {
+            let now = *self.now.lock().unwrap();
+            let (count, expires_at) = match self.live(key) {
+                Some((value, Some(at))) => (value.parse::<u64>().unwrap() + 1, at),
+                _ => (1, now + window.as_secs()),
+            };
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), (count.to_string(), Some(expires_at)));
+            Ok((count, Duration::from_secs(expires_at - now)))
+        }
+    }
+
+    async fn try_send(store: &ClockStore, limit: &SmsLimitConfig, mobile: &str) -> AppResult<()> {
+        SmsService::acquire_send_slot(store, limit, mobile).await?;
+        SmsService::record_send(store, limit, mobile).await
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_blocks_resend_until_it_expires() {
+        let store = ClockStore::default();
+        let limit = SmsLimitConfig::default();
+
+        try_send(&store, &limit, "13800138000").await.unwrap();
+        store.advance(30);
+        let err = try_send(&store, &limit, "13800138000").await.unwrap_err();
+        assert!(matches!(&err, AppError::RateLimit(msg) if msg.contains("30")));
+
+        // Other numbers are not affected
+        try_send(&store, &limit, "13900139000").await.unwrap();
+
+        // Validation deletes the code key, not the cooldown. The store plays the
+        // prefixed RedisTokenStore, so the code key is the unprefixed one here.
+        let code_key = SmsService::captcha_code_key("", "13800138000");
+        store.set_raw(&code_key, "123456", Some(300)).await.unwrap();
+        assert!(store.delete(&code_key).await.unwrap());
+        assert!(store.get_raw(&code_key).await.unwrap().is_none());
+        assert!(try_send(&store, &limit, "13800138000").await.is_err());
+
+        store.advance(30);
+        try_send(&store, &limit, "13800138000").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_cap_per_window() {
+        let store = ClockStore::default();
+        let limit = SmsLimitConfig {
+            cooldown: Duration::from_secs(60),
+            max_per_window: 3,
+            window: Duration::from_secs(3600),
+        };
+
+        for _ in 0..3 {
+            try_send(&store, &limit, "13800138000").await.unwrap();
+            store.advance(60);
+        }
+        let err = try_send(&store, &limit, "13800138000").await.unwrap_err();
+        assert!(matches!(&err, AppError::RateLimit(msg) if msg.contains("上限")));
+
+        // The window counts from the first send (t=0), so it reopens at t=3600
+        store.advance(3600 - 180 - 1);
+        assert!(try_send(&store, &limit, "13800138000").await.is_err());
+        store.advance(1);
+        try_send(&store, &limit, "13800138000").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_zero_limits_disable_checks() {
+        let store = ClockStore::default();
+        let limit = SmsLimitConfig {
+            cooldown: Duration::ZERO,
+            max_per_window: 0,
+            window: Duration::from_secs(3600),
+        };
+        for _ in 0..20 {
+            try_send(&store, &limit, "13800138000").await.unwrap();
+        }
+        assert!(store.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_default_code_is_six_digits_without_leading_zero() {
+        let config = CaptchaCodeConfig::default();
+        for _ in 0..200 {
+            let code = config.generate();
+            assert_eq!(code.len(), 6);
+            assert!(code.chars().all(|c| c.is_ascii_digit()));
+            assert_ne!(code.as_bytes()[0], b'0');
+        }
+    }
+
+    #[test]
+    fn test_tencent_status_fields_are_kept() {
+        let status = SendStatus {
+            serial_no: "2028:f825e6d3a4d6b2b4a1b5".to_string(),
+            phone_number: "+8613800138000".to_string(),
+            fee: 2,
+            session_context: String::new(),
+            code: "Ok".to_string(),
+            message: "send success".to_string(),
+            iso_code: "CN".to_string(),
+        };
+
+        let result = SmsSendResult::from_tencent_status("req-1", status);
+        assert!(result.success);
+        assert_eq!(
+            result.serial_no.as_deref(),
+            Some("2028:f825e6d3a4d6b2b4a1b5")
+        );
+        assert_eq!(result.fee, Some(2));
+        assert_eq!(result.iso_code.as_deref(), Some("CN"));
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["fee"], 2);
+        assert_eq!(json["provider"], "tencent");
+
+        let json = serde_json::to_value(SmsSendResult::failed("aliyun", "boom")).unwrap();
+        assert!(json.get("serial_no").is_none());
+    }
+
+    fn send_status(phone: &str, code: &str) -> SendStatus {
+        SendStatus {
+            serial_no: format!("serial-{}", phone),
+            phone_number: phone.to_string(),
+            fee: 1,
+            session_context: String::new(),
+            code: code.to_string(),
+            message: code.to_lowercase(),
+            iso_code: "CN".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tencent_chunk_results_match_statuses_by_phone() {
+        let chunk = ["13800138000", "+447700900123", "13900139000"];
+        let phones: Vec<String> = chunk.iter().map(|m| SmsService::tencent_phone(m)).collect();
+        // Out of order, and nothing for 13900139000
+        let statuses = vec![
+            send_status("+447700900123", "LimitExceeded.PhoneNumberDailyLimit"),
+            send_status("+8613800138000", "Ok"),
+        ];
+
+        let results =
+            SmsService::tencent_chunk_results(&chunk, &phones, Ok(("req-1".to_string(), statuses)));
+        assert_eq!(results.len(), 3);
+
+        let (mobile, ok) = &results[0];
+        assert_eq!(mobile, "13800138000");
+        assert!(ok.success);
+        assert_eq!(ok.serial_no.as_deref(), Some("serial-+8613800138000"));
+        assert_eq!(ok.request_id.as_deref(), Some("req-1"));
+
+        let (mobile, limited) = &results[1];
+        assert_eq!(mobile, "+447700900123");
+        assert!(!limited.success);
+        assert_eq!(
+            limited.raw_code.as_deref(),
+            Some("LimitExceeded.PhoneNumberDailyLimit")
+        );
+
+        let (mobile, missing) = &results[2];
+        assert_eq!(mobile, "13900139000");
+        assert!(!missing.success);
+        assert_eq!(missing.provider, "tencent");
+        assert_eq!(
+            missing.raw_message.as_deref(),
+            Some("no send status returned")
+        );
+        assert!(missing.request_id.is_none());
+    }
+
+    #[test]
+    fn test_tencent_chunk_results_fail_whole_chunk_on_request_error() {
+        let chunk = ["13800138000", "13900139000"];
+        let phones: Vec<String> = chunk.iter().map(|m| SmsService::tencent_phone(m)).collect();
+        let results =
+            SmsService::tencent_chunk_results(&chunk, &phones, Err("timeout".to_string()));
+        assert_eq!(
+            results.iter().map(|(m, _)| m.as_str()).collect::<Vec<_>>(),
+            chunk
+        );
+        assert!(
+            results
+                .iter()
+                .all(|(_, r)| !r.success && r.raw_message.as_deref() == Some("timeout"))
+        );
+    }
+
+    #[test]
+    fn test_split_valid_mobiles_fails_numbers_not_matching_regex() {
+        let regex = regex::Regex::new(r"^1\d{10}$").unwrap();
+        let (valid, invalid) = SmsService::split_valid_mobiles(
+            &["13800138000", "123", "1380013800a", "13900139000"],
+            &regex,
+            "tencent",
+        );
+        assert_eq!(valid, ["13800138000", "13900139000"]);
+        assert_eq!(
+            invalid.iter().map(|(m, _)| m.as_str()).collect::<Vec<_>>(),
+            ["123", "1380013800a"]
+        );
+        assert!(
+            invalid
+                .iter()
+                .all(|(_, r)| !r.success && r.provider == "tencent")
+        );
+    }
+
+    #[test]
+    fn test_tencent_batches_split_at_request_limit() {
+        let mobiles: Vec<String> = (0..450).map(|i| format!("138{:08}", i)).collect();
+        let mut valid: Vec<&str> = mobiles.iter().map(String::as_str).collect();
+        valid.push("+447700900123");
+
+        let batches: Vec<_> = SmsService::tencent_batches(&valid).collect();
+        assert_eq!(
+            batches.iter().map(|(c, _)| c.len()).collect::<Vec<_>>(),
+            [
+                TENCENT_MAX_PHONES_PER_REQUEST,
+                TENCENT_MAX_PHONES_PER_REQUEST,
+                51
+            ]
+        );
+        for (chunk, phones) in &batches {
+            assert_eq!(chunk.len(), phones.len());
+        }
+        assert_eq!(batches[0].1[0], "+8613800000000");
+        assert_eq!(batches[2].1.last().unwrap(), "+447700900123");
+    }
+
+    #[test]
+    fn test_long_code_does_not_overflow() {
+        let config = CaptchaCodeConfig {
+            length: 12,
+            allow_leading_zero: true,
+        };
+        let code = config.generate();
+        assert_eq!(code.len(), 12);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn test_leading_zero_allowed() {
+        let config = CaptchaCodeConfig {
+            length: 1,
+            allow_leading_zero: true,
+        };
+        let seen_zero = (0..1000).any(|_| config.generate() == "0");
+        assert!(seen_zero);
+    }
+
+    #[test]
+    fn test_zero_length_falls_back_to_one_digit() {
+        let config = CaptchaCodeConfig {
+            length: 0,
+            allow_leading_zero: false,
+        };
+        assert_eq!(config.generate().len(), 1);
+    }
+}
@@ -1,14 +1,17 @@
 use anyhow::{Error, Result, anyhow};
 use chrono::prelude::*;
 use hmac::digest::CtOutput;
-use hmac::{Hmac, Mac};
+use hmac::{Hmac, KeyInit, Mac};
 use reqwest::header;
 use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sha2::{Digest, Sha256};
 
+use crate::crypto::core::Crypto;
+
 type HmacSha256 = Hmac<Sha256>;
+#[deprecated(note = "use `crypto::core::Crypto::hmac_sha256` instead")]
 pub fn hmac_sha256(key: &[u8], data: &[u8]) -> CtOutput<Hmac<Sha256>> {
     let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take key of any size");
     mac.update(data);
@@ -146,15 +149,13 @@ impl Tencent {
     }
 
     fn signature(&self, time_date: String, string_to_sign: String) -> String {
-        let secret_date = hmac_sha256(
+        let secret_date = Crypto::hmac_sha256(
             format!("TC3{}", self.secret_key).as_bytes(),
             time_date.as_bytes(),
         );
-        let secret_service = hmac_sha256(&secret_date.into_bytes(), SERVICE.as_bytes());
-        let secret_signing = hmac_sha256(&secret_service.into_bytes(), b"tc3_request");
-        let signature = hmac_sha256(&secret_signing.into_bytes(), string_to_sign.as_bytes());
-        let signature_str = hex::encode(signature.into_bytes());
-        signature_str
+        let secret_service = Crypto::hmac_sha256(&secret_date, SERVICE.as_bytes());
+        let secret_signing = Crypto::hmac_sha256(&secret_service, b"tc3_request");
+        Crypto::hmac_sha256_hex(&secret_signing, string_to_sign.as_bytes())
     }
 
     fn builder_headers(